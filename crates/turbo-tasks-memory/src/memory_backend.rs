@@ -555,6 +555,14 @@ impl Backend for MemoryBackend {
         self.with_task(task, |task| task.mark_as_finished(self, turbo_tasks))
     }
 
+    fn is_own_task_cancelled(
+        &self,
+        task: TaskId,
+        _turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) -> bool {
+        self.with_task(task, |task| task.is_cancelled())
+    }
+
     fn create_transient_task(
         &self,
         task_type: TransientTaskType,
@@ -1385,6 +1385,17 @@ impl Task {
         }
     }
 
+    /// Returns `true` when the task is being executed, but that execution has
+    /// been invalidated in the meantime. Its result will be discarded, so the
+    /// execution can be aborted early.
+    pub fn is_cancelled(&self) -> bool {
+        if let TaskMetaStateReadGuard::Full(state) = self.state() {
+            matches!(state.state_type, TaskStateType::InProgressDirty { .. })
+        } else {
+            false
+        }
+    }
+
     pub fn reset_stats(&self) {
         if let TaskMetaStateWriteGuard::Full(mut state) = self.state_mut() {
             state.stats.reset();
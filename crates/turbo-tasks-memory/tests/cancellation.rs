@@ -0,0 +1,158 @@
+#![feature(arbitrary_self_types)]
+
+use std::{sync::Mutex, time::Duration};
+
+use anyhow::Result;
+use tokio::sync::oneshot;
+use turbo_tasks::{get_invalidator, is_cancelled, Invalidator, Vc};
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+#[tokio::test]
+async fn observes_invalidation_while_running() {
+    run! {
+        let (started_tx, started_rx) = oneshot::channel();
+        let source = Source::cell(Source { value: Mutex::new((0, None)) });
+        let probe = Probe::cell(Probe {
+            started: Mutex::new(Some(started_tx)),
+            observations: Mutex::new(vec![]),
+        });
+
+        let observed = observe(source, probe);
+
+        // Invalidate the first execution while it is still running
+        started_rx.await?;
+        source.await?.incr();
+
+        assert_eq!(*observed.strongly_consistent().await?, 1);
+        // The invalidated execution saw that it was cancelled, the one that
+        // replaced it wasn't
+        assert_eq!(
+            *probe.await?.observations.lock().unwrap(),
+            vec![(0, true), (1, false)]
+        );
+    }
+}
+
+#[tokio::test]
+async fn subtasks_observe_parent_invalidation() {
+    run! {
+        let (started_tx, started_rx) = oneshot::channel();
+        let source = Source::cell(Source { value: Mutex::new((0, None)) });
+        let probe = Probe::cell(Probe {
+            started: Mutex::new(Some(started_tx)),
+            observations: Mutex::new(vec![]),
+        });
+
+        let observed = observe_in_subtask(source, probe);
+
+        // Invalidate the parent while the subtask is still running
+        started_rx.await?;
+        source.await?.incr();
+
+        assert_eq!(*observed.strongly_consistent().await?, 1);
+        // The subtask saw that the execution of its parent was cancelled. Its
+        // result was discarded along with the parent's, so the execution that
+        // replaced the parent's doesn't see it.
+        let observations = probe.await?.observations.lock().unwrap().clone();
+        assert_eq!(observations.first(), Some(&(0, true)));
+        assert_eq!(observations.last(), Some(&(1, false)));
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+struct Observed(usize);
+
+#[turbo_tasks::value(transparent)]
+struct Cancelled(bool);
+
+#[turbo_tasks::function]
+async fn observe_in_subtask(source: Vc<Source>, probe: Vc<Probe>) -> Result<Vc<Observed>> {
+    let value = *source.get_value().await?;
+    let cancelled = *wait_for_cancellation(probe).await?;
+    probe
+        .await?
+        .observations
+        .lock()
+        .unwrap()
+        .push((value, cancelled));
+    Ok(Vc::cell(value))
+}
+
+#[turbo_tasks::function]
+async fn wait_for_cancellation(probe: Vc<Probe>) -> Result<Vc<Cancelled>> {
+    let probe = probe.await?;
+    let started = probe.started.lock().unwrap().take();
+    let Some(started) = started else {
+        return Ok(Vc::cell(is_cancelled()));
+    };
+    started.send(()).unwrap();
+    for _ in 0..1000 {
+        if is_cancelled() {
+            return Ok(Vc::cell(true));
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    Ok(Vc::cell(false))
+}
+
+#[turbo_tasks::function]
+async fn observe(source: Vc<Source>, probe: Vc<Probe>) -> Result<Vc<Observed>> {
+    let value = *source.get_value().await?;
+    let probe = probe.await?;
+    let started = probe.started.lock().unwrap().take();
+    let cancelled = if let Some(started) = started {
+        started.send(()).unwrap();
+        let mut cancelled = false;
+        for _ in 0..1000 {
+            if is_cancelled() {
+                cancelled = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        cancelled
+    } else {
+        is_cancelled()
+    };
+    probe.observations.lock().unwrap().push((value, cancelled));
+    Ok(Vc::cell(value))
+}
+
+#[turbo_tasks::value(serialization = "none", cell = "new", eq = "manual")]
+struct Probe {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    started: Mutex<Option<oneshot::Sender<()>>>,
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    observations: Mutex<Vec<(usize, bool)>>,
+}
+
+#[turbo_tasks::value(transparent)]
+struct SourceValue(usize);
+
+#[turbo_tasks::value(serialization = "none", cell = "new", eq = "manual")]
+struct Source {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    value: Mutex<(usize, Option<Invalidator>)>,
+}
+
+impl Source {
+    fn incr(&self) {
+        let mut lock = self.value.lock().unwrap();
+        lock.0 += 1;
+        if let Some(i) = lock.1.take() {
+            i.invalidate();
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Source {
+    #[turbo_tasks::function]
+    async fn get_value(&self) -> Result<Vc<SourceValue>> {
+        let mut lock = self.value.lock().unwrap();
+        lock.1 = Some(get_invalidator());
+        Ok(Vc::cell(lock.0))
+    }
+}
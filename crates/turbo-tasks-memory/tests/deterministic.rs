@@ -0,0 +1,68 @@
+#![feature(arbitrary_self_types)]
+
+use anyhow::Result;
+use turbo_tasks::{TaskInputRecording, TurboTasks, Vc};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+
+register!();
+
+/// Runs `sum(8)` with deterministic scheduling and returns the order tasks
+/// were started in and the recorded task inputs.
+async fn run_with_seed(seed: u64) -> (Vec<String>, TaskInputRecording) {
+    *REGISTER;
+    let tt = TurboTasks::new_deterministic(MemoryBackend::default(), seed);
+    tt.run_once(async {
+        assert_eq!(*sum(8).strongly_consistent().await?, 36);
+        Ok(())
+    })
+    .await
+    .unwrap();
+    (tt.execution_log(), tt.recorded_task_inputs().unwrap())
+}
+
+#[tokio::test]
+async fn same_seed_same_execution_log() {
+    let (first, _) = run_with_seed(42).await;
+    let (second, _) = run_with_seed(42).await;
+    assert_eq!(first, second);
+    // the once task, sum and a task for every number
+    assert_eq!(first.len(), 10);
+}
+
+#[tokio::test]
+async fn replay_recorded_inputs() {
+    let (_, recording) = run_with_seed(42).await;
+    assert_eq!(recording.seed, 42);
+    assert_eq!(recording.calls.len(), 9);
+    assert_eq!(recording.skipped, 0);
+
+    let serialized = serde_json::to_string(&recording).unwrap();
+    let recording: TaskInputRecording = serde_json::from_str(&serialized).unwrap();
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async move {
+        let outputs = recording.replay()?;
+        // the first call was sum(8), made by the once task
+        let sum: Vc<u32> = outputs[0].into();
+        assert_eq!(*sum.strongly_consistent().await?, 36);
+        Ok(())
+    })
+    .await
+    .unwrap();
+}
+
+#[turbo_tasks::function]
+async fn sum(count: u32) -> Result<Vc<u32>> {
+    let numbers = (1..=count).map(number).collect::<Vec<_>>();
+    let mut sum = 0;
+    for number in numbers {
+        sum += *number.await?;
+    }
+    Ok(Vc::cell(sum))
+}
+
+#[turbo_tasks::function]
+fn number(value: u32) -> Vc<u32> {
+    Vc::cell(value)
+}
@@ -246,6 +246,10 @@ impl TurboTasksApi for VcStorage {
         // no-op
     }
 
+    fn is_own_task_cancelled(&self, _task: TaskId) -> bool {
+        false
+    }
+
     fn detached(
         &self,
         _f: std::pin::Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>,
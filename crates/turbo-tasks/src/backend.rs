@@ -341,6 +341,17 @@ pub trait Backend: Sync + Send {
         // Do nothing by default
     }
 
+    /// Returns `true` when the in-progress execution of `task` has been
+    /// invalidated, so its result will be discarded and the task will be
+    /// executed again.
+    fn is_own_task_cancelled(
+        &self,
+        _task: TaskId,
+        _turbo_tasks: &dyn TurboTasksBackendApi<Self>,
+    ) -> bool {
+        false
+    }
+
     fn create_transient_task(
         &self,
         task_type: TransientTaskType,
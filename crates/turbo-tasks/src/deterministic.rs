@@ -0,0 +1,344 @@
+//! Support for executing tasks in a reproducible order.
+//!
+//! Normally every scheduled task is spawned onto the tokio runtime right away,
+//! so the order in which tasks start depends on timing. When debugging a task
+//! graph that only misbehaves under certain interleavings, that makes the bug
+//! hard to reproduce. The [DeterministicScheduler] instead collects all tasks
+//! that are scheduled in the same tick and spawns them in an order derived from
+//! a seed. They run on a dedicated current-thread runtime, so only one task
+//! makes progress at a time no matter which runtime the caller uses. The same
+//! seed produces the same execution order on every run, and different seeds
+//! can be used to explore other orders.
+
+use std::{
+    borrow::Cow, collections::HashMap, future::Future, mem::take, pin::Pin, sync::Mutex, thread,
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tokio::{runtime::Handle, sync::oneshot};
+
+use crate::{
+    backend::PersistentTaskType, dynamic_call, trait_call, CellId, ConcreteTaskInput, FunctionId,
+    RawVc, TaskId, TraitTypeId,
+};
+
+pub(crate) type ScheduledFuture = Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>;
+
+pub(crate) struct DeterministicScheduler {
+    seed: u64,
+    inner: Mutex<Inner>,
+    // the runtime that all futures are spawned onto, which is driven by its
+    // own thread until the scheduler is dropped
+    handle: Handle,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+struct Inner {
+    rng_state: u64,
+    pending: Vec<(String, ScheduledFuture)>,
+    log: Vec<String>,
+    recording: TaskInputRecording,
+    // the index of the call in `recording` that created each task
+    recorded_tasks: HashMap<TaskId, usize>,
+}
+
+impl DeterministicScheduler {
+    pub fn new(seed: u64) -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to create the deterministic runtime");
+        let handle = runtime.handle().clone();
+        let (shutdown, stopped) = oneshot::channel();
+        thread::Builder::new()
+            .name("turbo-tasks deterministic".to_string())
+            .spawn(move || {
+                let _ = runtime.block_on(stopped);
+            })
+            .expect("failed to spawn the deterministic runtime thread");
+        Self {
+            seed,
+            inner: Mutex::new(Inner {
+                rng_state: seed,
+                pending: Vec::new(),
+                log: Vec::new(),
+                recording: TaskInputRecording {
+                    seed,
+                    calls: Vec::new(),
+                    skipped: 0,
+                },
+                recorded_tasks: HashMap::new(),
+            }),
+            handle,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Runs `future` on the scheduler's runtime.
+    pub fn spawn(&self, future: impl Future<Output = ()> + Send + 'static) {
+        self.handle.spawn(future);
+    }
+
+    /// Queues the execution future of a task. Returns `true` if the queue was
+    /// empty before, in which case the caller is responsible for arranging a
+    /// call to [DeterministicScheduler::take_batch].
+    pub fn enqueue(&self, description: String, future: ScheduledFuture) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        inner.pending.push((description, future));
+        inner.pending.len() == 1
+    }
+
+    /// Takes all queued futures in a seeded order and records that order in
+    /// the execution log.
+    pub fn take_batch(&self) -> Vec<ScheduledFuture> {
+        let mut inner = self.inner.lock().unwrap();
+        let mut batch = take(&mut inner.pending);
+        // Fisher-Yates shuffle, driven by our own seeded generator so the order
+        // doesn't depend on anything but the seed and the order of scheduling.
+        for i in (1..batch.len()).rev() {
+            let j = (splitmix64(&mut inner.rng_state) % (i as u64 + 1)) as usize;
+            batch.swap(i, j);
+        }
+        batch
+            .into_iter()
+            .map(|(description, future)| {
+                inner.log.push(description);
+                future
+            })
+            .collect()
+    }
+
+    /// Descriptions of all tasks that were started so far, in start order.
+    pub fn log(&self) -> Vec<String> {
+        self.inner.lock().unwrap().log.clone()
+    }
+
+    /// Records the call that `task_id` was looked up or created for. Only the
+    /// first call for each task is recorded.
+    pub fn record(&self, task_type: &PersistentTaskType, task_id: TaskId) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.recorded_tasks.contains_key(&task_id) {
+            return;
+        }
+        let call = {
+            let record_inputs = |inputs: &[ConcreteTaskInput]| {
+                inputs
+                    .iter()
+                    .map(|input| RecordedInput::new(input, &inner.recorded_tasks))
+                    .collect::<Option<Vec<_>>>()
+            };
+            match task_type {
+                PersistentTaskType::Native(function, inputs)
+                | PersistentTaskType::ResolveNative(function, inputs) => {
+                    record_inputs(inputs).map(|inputs| RecordedCall::Function(*function, inputs))
+                }
+                PersistentTaskType::ResolveTrait(trait_type, name, inputs) => record_inputs(inputs)
+                    .map(|inputs| RecordedCall::TraitMethod(*trait_type, name.clone(), inputs)),
+            }
+        };
+        match call {
+            Some(call) => {
+                let index = inner.recording.calls.len();
+                inner.recording.calls.push(call);
+                inner.recorded_tasks.insert(task_id, index);
+            }
+            // e.g. a call with a cell of a root task, which has no call that
+            // could be replayed
+            None => inner.recording.skipped += 1,
+        }
+    }
+
+    /// The inputs of all tasks created so far.
+    pub fn recording(&self) -> TaskInputRecording {
+        self.inner.lock().unwrap().recording.clone()
+    }
+}
+
+/// The inputs of the tasks created by a deterministic [crate::TurboTasks]
+/// instance, in the order they were created.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskInputRecording {
+    /// The seed of the recorded instance. Replaying in an instance with the
+    /// same seed reproduces its scheduling as closely as possible.
+    pub seed: u64,
+    pub calls: Vec<RecordedCall>,
+    /// The number of calls that couldn't be recorded because they refer to
+    /// tasks that weren't created by a call, like root and once tasks.
+    pub skipped: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordedCall {
+    Function(FunctionId, Vec<RecordedInput>),
+    TraitMethod(TraitTypeId, Cow<'static, str>, Vec<RecordedInput>),
+}
+
+/// A task input as recorded in a [TaskInputRecording].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordedInput {
+    /// The output of the task created by the call at this index.
+    Output(usize),
+    /// A cell of the task created by the call at this index.
+    Cell(usize, CellId),
+    List(Vec<RecordedInput>),
+    /// An input that doesn't refer to any task. Transient values are kept as
+    /// is, but can't be serialized.
+    Value(ConcreteTaskInput),
+}
+
+impl RecordedInput {
+    fn new(input: &ConcreteTaskInput, recorded_tasks: &HashMap<TaskId, usize>) -> Option<Self> {
+        Some(match input {
+            ConcreteTaskInput::TaskOutput(task) => Self::Output(*recorded_tasks.get(task)?),
+            ConcreteTaskInput::TaskCell(task, cell) => {
+                Self::Cell(*recorded_tasks.get(task)?, *cell)
+            }
+            ConcreteTaskInput::List(list) => Self::List(
+                list.iter()
+                    .map(|input| Self::new(input, recorded_tasks))
+                    .collect::<Option<_>>()?,
+            ),
+            value => Self::Value(value.clone()),
+        })
+    }
+
+    fn replay(&self, tasks: &[TaskId]) -> Result<ConcreteTaskInput> {
+        let task = |index: usize| {
+            tasks
+                .get(index)
+                .copied()
+                .ok_or_else(|| anyhow!("input refers to call {index}, which comes after it"))
+        };
+        Ok(match self {
+            Self::Output(index) => ConcreteTaskInput::TaskOutput(task(*index)?),
+            Self::Cell(index, cell) => ConcreteTaskInput::TaskCell(task(*index)?, *cell),
+            Self::List(list) => ConcreteTaskInput::List(replay_inputs(list, tasks)?),
+            Self::Value(value) => value.clone(),
+        })
+    }
+}
+
+impl TaskInputRecording {
+    /// Makes all recorded calls again, in order, and returns the output of
+    /// each. This has to be called from within a turbo tasks context, e.g. in
+    /// [crate::TurboTasks::run_once].
+    pub fn replay(&self) -> Result<Vec<RawVc>> {
+        let mut tasks = Vec::with_capacity(self.calls.len());
+        let mut outputs = Vec::with_capacity(self.calls.len());
+        for call in &self.calls {
+            let output = match call {
+                RecordedCall::Function(function, inputs) => {
+                    dynamic_call(*function, replay_inputs(inputs, &tasks)?)
+                }
+                RecordedCall::TraitMethod(trait_type, name, inputs) => {
+                    trait_call(*trait_type, name.clone(), replay_inputs(inputs, &tasks)?)
+                }
+            };
+            let RawVc::TaskOutput(task) = output else {
+                unreachable!("calls always return the output of a task")
+            };
+            tasks.push(task);
+            outputs.push(output);
+        }
+        Ok(outputs)
+    }
+}
+
+fn replay_inputs(inputs: &[RecordedInput], tasks: &[TaskId]) -> Result<Vec<ConcreteTaskInput>> {
+    inputs.iter().map(|input| input.replay(tasks)).collect()
+}
+
+impl Drop for DeterministicScheduler {
+    fn drop(&mut self) {
+        // Tasks that haven't finished are dropped along with the runtime
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(seed: u64) -> Vec<String> {
+        let scheduler = DeterministicScheduler::new(seed);
+        for i in 0..16 {
+            scheduler.enqueue(format!("task {i}"), Box::pin(async { Ok(()) }));
+        }
+        scheduler.take_batch();
+        scheduler.log()
+    }
+
+    #[test]
+    fn same_seed_same_order() {
+        assert_eq!(order(42), order(42));
+        assert_ne!(order(1), order(2));
+    }
+
+    #[test]
+    fn runs_on_one_dedicated_thread() {
+        let scheduler = DeterministicScheduler::new(42);
+        let (tx, rx) = std::sync::mpsc::channel();
+        for _ in 0..8 {
+            let tx = tx.clone();
+            scheduler.spawn(async move {
+                tokio::task::yield_now().await;
+                tx.send(thread::current().id()).unwrap();
+            });
+        }
+        drop(tx);
+        let threads = rx.iter().collect::<Vec<_>>();
+        assert_eq!(threads.len(), 8);
+        assert!(threads.iter().all(|id| *id == threads[0]));
+        assert_ne!(threads[0], thread::current().id());
+    }
+
+    #[test]
+    fn record_and_replay_inputs() {
+        let recorded_tasks = HashMap::from([(TaskId::from(7), 0), (TaskId::from(9), 1)]);
+        let input = ConcreteTaskInput::List(vec![
+            ConcreteTaskInput::TaskOutput(TaskId::from(9)),
+            ConcreteTaskInput::Usize(3),
+        ]);
+
+        let recorded = RecordedInput::new(&input, &recorded_tasks).unwrap();
+        assert_eq!(
+            recorded,
+            RecordedInput::List(vec![
+                RecordedInput::Output(1),
+                RecordedInput::Value(ConcreteTaskInput::Usize(3)),
+            ])
+        );
+
+        // The calls create different tasks when they are replayed
+        let replayed = recorded
+            .replay(&[TaskId::from(20), TaskId::from(21)])
+            .unwrap();
+        assert_eq!(
+            replayed,
+            ConcreteTaskInput::List(vec![
+                ConcreteTaskInput::TaskOutput(TaskId::from(21)),
+                ConcreteTaskInput::Usize(3),
+            ])
+        );
+        assert!(recorded.replay(&[TaskId::from(20)]).is_err());
+
+        // Tasks that weren't created by a recorded call can't be referred to
+        let unrecorded = ConcreteTaskInput::TaskOutput(TaskId::from(8));
+        assert_eq!(RecordedInput::new(&unrecorded, &recorded_tasks), None);
+    }
+}
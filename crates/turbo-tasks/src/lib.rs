@@ -38,6 +38,7 @@ pub mod backend;
 mod collectibles;
 mod completion;
 pub mod debug;
+mod deterministic;
 mod display;
 pub mod duration_span;
 pub mod event;
@@ -76,6 +77,7 @@ pub use anyhow::{Error, Result};
 use auto_hash_map::AutoSet;
 pub use collectibles::CollectiblesSource;
 pub use completion::{Completion, Completions};
+pub use deterministic::{RecordedCall, RecordedInput, TaskInputRecording};
 pub use display::ValueToString;
 pub use id::{
     with_task_id_mapping, without_task_id_mapping, FunctionId, IdMapping, TaskId, TraitTypeId,
@@ -87,10 +89,10 @@ pub use invalidation::{
 pub use join_iter_ext::{JoinIterExt, TryFlatJoinIterExt, TryJoinIterExt};
 pub use keyed_cell::{global_keyed_cell, keyed_cell};
 pub use manager::{
-    dynamic_call, emit, get_invalidator, mark_finished, mark_stateful, prevent_gc, run_once,
-    run_once_with_reason, spawn_blocking, spawn_thread, trait_call, turbo_tasks, CurrentCellRef,
-    Invalidator, StatsType, TaskIdProvider, TurboTasks, TurboTasksApi, TurboTasksBackendApi,
-    TurboTasksCallApi, Unused, UpdateInfo,
+    dynamic_call, emit, get_invalidator, is_cancelled, mark_finished, mark_stateful, prevent_gc,
+    run_once, run_once_with_reason, spawn_blocking, spawn_thread, trait_call, turbo_tasks,
+    CurrentCellRef, Invalidator, StatsType, TaskIdProvider, TurboTasks, TurboTasksApi,
+    TurboTasksBackendApi, TurboTasksCallApi, Unused, UpdateInfo,
 };
 pub use native_function::NativeFunction;
 use nohash_hasher::BuildNoHashHasher;
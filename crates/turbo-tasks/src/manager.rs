@@ -24,6 +24,7 @@ use tracing::{info_span, instrument, trace_span, Instrument, Level};
 
 use crate::{
     backend::{Backend, CellContent, PersistentTaskType, TaskExecutionSpec, TransientTaskType},
+    deterministic::{DeterministicScheduler, TaskInputRecording},
     event::{Event, EventListener},
     id::{BackendJobId, FunctionId, TraitTypeId},
     id_factory::IdFactory,
@@ -118,6 +119,7 @@ pub trait TurboTasksApi: TurboTasksCallApi + Sync + Send {
     fn read_own_task_cell(&self, task: TaskId, index: CellId) -> Result<CellContent>;
     fn update_own_task_cell(&self, task: TaskId, index: CellId, content: CellContent);
     fn mark_own_task_as_finished(&self, task: TaskId);
+    fn is_own_task_cancelled(&self, task: TaskId) -> bool;
 
     fn connect_task(&self, task: TaskId);
 
@@ -278,6 +280,9 @@ pub struct TurboTasks<B: Backend + 'static> {
     // locking overhead.
     enable_full_stats: AtomicBool,
     program_start: Instant,
+    /// When set, tasks are started in a seeded, reproducible order instead of
+    /// being spawned immediately. See [TurboTasks::new_deterministic].
+    deterministic: Option<DeterministicScheduler>,
 }
 
 #[derive(Default)]
@@ -289,6 +294,18 @@ struct CurrentTaskState {
 
     // true, if the current task has state in cells
     stateful: bool,
+
+    // true, if the execution was cancelled along with a task in its
+    // cancellation scope
+    cancelled_by_scope: bool,
+}
+
+/// The chain of tasks whose executions scheduled the current task, innermost
+/// first. When one of them is cancelled, the current execution is cancelled
+/// as well.
+struct CancellationScope {
+    task: TaskId,
+    parent: Option<Arc<CancellationScope>>,
 }
 
 // TODO implement our own thread pool and make these thread locals instead
@@ -301,6 +318,8 @@ task_local! {
     static CURRENT_TASK_ID: TaskId;
 
     static CURRENT_TASK_STATE: RefCell<CurrentTaskState>;
+
+    static CANCELLATION_SCOPE: Option<Arc<CancellationScope>>;
 }
 
 impl<B: Backend + 'static> TurboTasks<B> {
@@ -309,7 +328,29 @@ impl<B: Backend + 'static> TurboTasks<B> {
     // that should be safe as long tasks can't outlife turbo task
     // so we probably want to make sure that all tasks are joined
     // when trying to drop turbo tasks
-    pub fn new(mut backend: B) -> Arc<Self> {
+    pub fn new(backend: B) -> Arc<Self> {
+        Self::new_with_scheduler(backend, None)
+    }
+
+    /// Creates a TurboTasks instance that starts tasks in a reproducible
+    /// order derived from `seed`. Tasks scheduled in the same tick are
+    /// collected and spawned in a shuffled order, and the order is recorded so
+    /// it can be inspected with [TurboTasks::execution_log].
+    ///
+    /// This is meant for debugging ordering-dependent bugs. Tasks and backend
+    /// jobs run on a dedicated current-thread runtime, so only one of them
+    /// makes progress at a time. `spawn_blocking`/`spawn_thread` inside tasks
+    /// still introduce nondeterminism. Running again with the same seed
+    /// replays the same order. The inputs of all tasks are recorded as well,
+    /// see [TurboTasks::recorded_task_inputs].
+    pub fn new_deterministic(backend: B, seed: u64) -> Arc<Self> {
+        Self::new_with_scheduler(backend, Some(DeterministicScheduler::new(seed)))
+    }
+
+    fn new_with_scheduler(
+        mut backend: B,
+        deterministic: Option<DeterministicScheduler>,
+    ) -> Arc<Self> {
         let task_id_factory = IdFactory::new();
         backend.initialize(&task_id_factory);
         let this = Arc::new_cyclic(|this| Self {
@@ -329,6 +370,7 @@ impl<B: Backend + 'static> TurboTasks<B> {
             event_background: Event::new(|| "TurboTasks::event_background".to_string()),
             enable_full_stats: AtomicBool::new(false),
             program_start: Instant::now(),
+            deterministic,
         });
         this.backend.startup(&*this);
         this
@@ -338,6 +380,28 @@ impl<B: Backend + 'static> TurboTasks<B> {
         self.this.upgrade().unwrap()
     }
 
+    /// The seed used for scheduling, if this instance was created with
+    /// [TurboTasks::new_deterministic].
+    pub fn deterministic_seed(&self) -> Option<u64> {
+        self.deterministic.as_ref().map(|d| d.seed())
+    }
+
+    /// Descriptions of all tasks started so far, in the order they were
+    /// started. Always empty unless deterministic scheduling is enabled.
+    pub fn execution_log(&self) -> Vec<String> {
+        self.deterministic
+            .as_ref()
+            .map(|d| d.log())
+            .unwrap_or_default()
+    }
+
+    /// The inputs of all tasks created so far, which can be replayed in another
+    /// instance with [TaskInputRecording::replay]. Only available when
+    /// deterministic scheduling is enabled.
+    pub fn recorded_task_inputs(&self) -> Option<TaskInputRecording> {
+        self.deterministic.as_ref().map(|d| d.recording())
+    }
+
     /// Creates a new root task
     pub fn spawn_root_task<T, F, Fut>(&self, functor: F) -> TaskId
     where
@@ -401,11 +465,9 @@ impl<B: Backend + 'static> TurboTasks<B> {
     /// Call a native function with arguments.
     /// All inputs must be resolved.
     pub(crate) fn native_call(&self, func: FunctionId, inputs: Vec<ConcreteTaskInput>) -> RawVc {
-        RawVc::TaskOutput(self.backend.get_or_create_persistent_task(
-            PersistentTaskType::Native(func, inputs),
-            current_task("turbo_function calls"),
-            self,
-        ))
+        RawVc::TaskOutput(
+            self.get_or_create_persistent_task(PersistentTaskType::Native(func, inputs)),
+        )
     }
 
     /// Calls a native function with arguments. Resolves arguments when needed
@@ -414,11 +476,9 @@ impl<B: Backend + 'static> TurboTasks<B> {
         if inputs.iter().all(|i| i.is_resolved()) {
             self.native_call(func, inputs)
         } else {
-            RawVc::TaskOutput(self.backend.get_or_create_persistent_task(
-                PersistentTaskType::ResolveNative(func, inputs),
-                current_task("turbo_function calls"),
-                self,
-            ))
+            RawVc::TaskOutput(
+                self.get_or_create_persistent_task(PersistentTaskType::ResolveNative(func, inputs)),
+            )
         }
     }
 
@@ -444,11 +504,30 @@ impl<B: Backend + 'static> TurboTasks<B> {
         }
 
         // create a wrapper task to resolve all inputs
-        RawVc::TaskOutput(self.backend.get_or_create_persistent_task(
-            PersistentTaskType::ResolveTrait(trait_type, trait_fn_name, inputs),
+        RawVc::TaskOutput(
+            self.get_or_create_persistent_task(PersistentTaskType::ResolveTrait(
+                trait_type,
+                trait_fn_name,
+                inputs,
+            )),
+        )
+    }
+
+    fn get_or_create_persistent_task(&self, task_type: PersistentTaskType) -> TaskId {
+        let Some(deterministic) = &self.deterministic else {
+            return self.backend.get_or_create_persistent_task(
+                task_type,
+                current_task("turbo_function calls"),
+                self,
+            );
+        };
+        let task_id = self.backend.get_or_create_persistent_task(
+            task_type.clone(),
             current_task("turbo_function calls"),
             self,
-        ))
+        );
+        deterministic.record(&task_type, task_id);
+        task_id
     }
 
     #[track_caller]
@@ -459,6 +538,16 @@ impl<B: Backend + 'static> TurboTasks<B> {
         #[cfg(feature = "tokio_tracing")]
         let description = self.backend.get_task_description(task_id);
 
+        // Tasks scheduled from within another task are cancelled along with it
+        let cancellation_scope = CURRENT_TASK_ID
+            .try_with(|parent| {
+                Arc::new(CancellationScope {
+                    task: *parent,
+                    parent: CANCELLATION_SCOPE.try_with(Clone::clone).ok().flatten(),
+                })
+            })
+            .ok();
+
         let this = self.pin();
         let future = async move {
             #[allow(clippy::blocks_in_conditions)]
@@ -490,9 +579,19 @@ impl<B: Backend + 'static> TurboTasks<B> {
                                 });
                                 this.backend.task_execution_result(task_id, result, &*this);
                                 let stateful = this.finish_current_task_state();
-                                this.backend.task_execution_completed(
+                                let cancelled_by_scope = CURRENT_TASK_STATE
+                                    .with(|cell| cell.borrow().cancelled_by_scope);
+                                let reschedule = this.backend.task_execution_completed(
                                     task_id, duration, instant, stateful, &*this,
-                                )
+                                );
+                                if cancelled_by_scope && !reschedule {
+                                    // What the execution returned after bailing out must not
+                                    // be kept. The next execution isn't part of the cancelled
+                                    // scope anymore.
+                                    CANCELLATION_SCOPE
+                                        .sync_scope(None, || this.invalidate(task_id));
+                                }
+                                reschedule
                             }
                             .instrument(span)
                             .await
@@ -508,10 +607,34 @@ impl<B: Backend + 'static> TurboTasks<B> {
         let future = TURBO_TASKS
             .scope(
                 self.pin(),
-                CURRENT_TASK_ID.scope(task_id, self.backend.execution_scope(task_id, future)),
+                CURRENT_TASK_ID.scope(
+                    task_id,
+                    CANCELLATION_SCOPE.scope(
+                        cancellation_scope,
+                        self.backend.execution_scope(task_id, future),
+                    ),
+                ),
             )
             .in_current_span();
 
+        if let Some(deterministic) = &self.deterministic {
+            let description = self.backend.get_task_description(task_id);
+            if deterministic.enqueue(description, Box::pin(future)) {
+                let this = self.pin();
+                deterministic.spawn(async move {
+                    // Give every task scheduled in the same tick the chance to
+                    // join this batch before picking an order.
+                    tokio::task::yield_now().await;
+                    if let Some(deterministic) = &this.deterministic {
+                        for future in deterministic.take_batch() {
+                            tokio::task::spawn(future);
+                        }
+                    }
+                });
+            }
+            return;
+        }
+
         #[cfg(feature = "tokio_tracing")]
         tokio::task::Builder::new()
             .name(&description)
@@ -738,6 +861,17 @@ impl<B: Backend + 'static> TurboTasks<B> {
         self.backend.stop(self);
     }
 
+    /// Spawns a job onto the deterministic runtime if there is one, and onto
+    /// the current runtime otherwise.
+    fn spawn(&self, future: impl Future<Output = ()> + Send + 'static) {
+        match &self.deterministic {
+            Some(deterministic) => deterministic.spawn(future),
+            None => {
+                tokio::spawn(future);
+            }
+        }
+    }
+
     #[track_caller]
     pub(crate) fn schedule_background_job<
         T: FnOnce(Arc<TurboTasks<B>>) -> F + Send + 'static,
@@ -749,7 +883,7 @@ impl<B: Backend + 'static> TurboTasks<B> {
         let this = self.pin();
         self.currently_scheduled_background_jobs
             .fetch_add(1, Ordering::AcqRel);
-        tokio::spawn(
+        self.spawn(
             TURBO_TASKS
                 .scope(this.clone(), async move {
                     while this.currently_scheduled_tasks.load(Ordering::Acquire) != 0 {
@@ -786,7 +920,7 @@ impl<B: Backend + 'static> TurboTasks<B> {
     ) {
         let this = self.pin();
         this.begin_foreground_job();
-        tokio::spawn(
+        self.spawn(
             TURBO_TASKS
                 .scope(this.clone(), async move {
                     if !this.stopped.load(Ordering::Acquire) {
@@ -803,6 +937,7 @@ impl<B: Backend + 'static> TurboTasks<B> {
             let CurrentTaskState {
                 tasks_to_notify,
                 stateful,
+                ..
             } = &mut *cell.borrow_mut();
             (*stateful, take(tasks_to_notify))
         });
@@ -1016,18 +1151,26 @@ impl<B: Backend + 'static> TurboTasksApi for TurboTasks<B> {
         self.backend.mark_own_task_as_finished(task, self);
     }
 
+    fn is_own_task_cancelled(&self, task: TaskId) -> bool {
+        self.backend.is_own_task_cancelled(task, self)
+    }
+
     fn detached(
         &self,
         f: Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>,
     ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>> {
         let current_task_id = CURRENT_TASK_ID.get();
+        let cancellation_scope = CANCELLATION_SCOPE.try_with(Clone::clone).ok().flatten();
         Box::pin(TURBO_TASKS.scope(
             turbo_tasks(),
             CURRENT_TASK_ID.scope(
                 current_task_id,
-                CELL_COUNTERS.scope(
-                    Default::default(),
-                    self.backend.execution_scope(current_task_id, f),
+                CANCELLATION_SCOPE.scope(
+                    cancellation_scope,
+                    CELL_COUNTERS.scope(
+                        Default::default(),
+                        self.backend.execution_scope(current_task_id, f),
+                    ),
                 ),
             ),
         ))
@@ -1389,6 +1532,35 @@ pub fn mark_finished() {
     });
 }
 
+/// Returns `true` when the execution of the current task has been invalidated
+/// while it was running, e.g. because an input changed again mid-build. The
+/// result of such an execution is discarded and the task is executed again,
+/// so long-running tasks can check this between units of work and bail out
+/// early instead of finishing work that nobody will read.
+///
+/// Executions scheduled by a cancelled task are cancelled as well. As other
+/// tasks might depend on them too, such a task is invalidated once its
+/// execution completes, so whatever it returned after bailing out is never
+/// kept.
+pub fn is_cancelled() -> bool {
+    with_turbo_tasks(|tt| {
+        let task = current_task("turbo_tasks::is_cancelled()");
+        if tt.is_own_task_cancelled(task) {
+            return true;
+        }
+        let cancellation_scope = CANCELLATION_SCOPE.try_with(Clone::clone).ok().flatten();
+        let mut scope = cancellation_scope.as_deref();
+        while let Some(current) = scope {
+            if tt.is_own_task_cancelled(current.task) {
+                CURRENT_TASK_STATE.with(|cell| cell.borrow_mut().cancelled_by_scope = true);
+                return true;
+            }
+            scope = current.parent.as_deref();
+        }
+        false
+    })
+}
+
 /// Marks the current task as stateful. This prevents the tasks from being
 /// dropped without persisting the state.
 pub fn mark_stateful() {
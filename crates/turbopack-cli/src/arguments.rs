@@ -89,6 +89,17 @@ pub struct DevArguments {
     #[clap(long)]
     pub no_open: bool,
 
+    /// Serve the module graph of the current session as JSON at
+    /// `/__turbopack__/graph.json`.
+    #[clap(long)]
+    pub introspect_graph: bool,
+
+    /// Serve over HTTPS (and HTTP/2) with a certificate issued by a local
+    /// certificate authority. The authority is shared with mkcert: run
+    /// `mkcert -install` once to make browsers trust it.
+    #[clap(long)]
+    pub https: bool,
+
     // ==
     // = Inherited options from next-dev, need revisit later.
     // ==
@@ -107,4 +118,9 @@ pub struct BuildArguments {
     /// Don't minify build output.
     #[clap(long)]
     pub no_minify: bool,
+
+    /// Entrypoints to build for Node.js into the `server` output directory.
+    /// Resolved relative to the project's directory (`--dir`).
+    #[clap(long = "server-entry", value_parser)]
+    pub server_entries: Vec<String>,
 }
@@ -5,23 +5,25 @@ use std::{
     sync::Arc,
 };
 
-use anyhow::{bail, Context, Result};
-use turbo_tasks::{TransientInstance, TryJoinIterExt, TurboTasks, Value, Vc};
-use turbo_tasks_fs::FileSystem;
+use anyhow::{anyhow, bail, Context, Result};
+use turbo_tasks::{ReadRef, TransientInstance, TryJoinIterExt, TurboTasks, Value, Vc};
+use turbo_tasks_env::{CustomProcessEnv, EnvMap, ProcessEnv};
+use turbo_tasks_fs::{FileSystem, FileSystemPath};
 use turbo_tasks_memory::MemoryBackend;
 use turbopack::ecmascript::EcmascriptModuleAsset;
 use turbopack_cli_utils::issue::{ConsoleUi, LogOptions};
 use turbopack_core::{
     asset::Asset,
     chunk::{
-        availability_info::AvailabilityInfo, ChunkableModule, ChunkingContextExt,
+        availability_info::AvailabilityInfo, ChunkableModule, ChunkingContext, ChunkingContextExt,
         EvaluatableAssets, MinifyType,
     },
+    context::AssetContext,
     environment::{BrowserEnvironment, Environment, ExecutionEnvironment},
-    issue::{handle_issues, IssueReporter, IssueSeverity},
+    issue::{handle_issues, IssueDescriptionExt, IssueReporter, IssueSeverity, PlainIssue},
     module::Module,
-    output::OutputAsset,
-    reference::all_assets_from_entries,
+    output::{OutputAsset, OutputAssets},
+    reference::all_assets_from_target_entries,
     reference_type::{EntryReferenceSubType, ReferenceType},
     resolve::{
         origin::{PlainResolveOrigin, ResolveOriginExt},
@@ -33,12 +35,15 @@ use turbopack_env::dotenv::load_env;
 use turbopack_node::execution_context::ExecutionContext;
 use turbopack_nodejs::NodeJsChunkingContext;
 
+pub use crate::util::EntryRequest;
 use crate::{
     arguments::BuildArguments,
-    contexts::{get_client_asset_context, get_client_compile_time_info, NodeEnv},
+    contexts::{
+        get_client_asset_context, get_client_compile_time_info, get_server_asset_context,
+        get_server_compile_time_info, NodeEnv,
+    },
     util::{
-        normalize_dirs, normalize_entries, output_fs, project_fs, EntryRequest, EntryRequests,
-        NormalizedDirs,
+        normalize_dirs, normalize_entries, output_fs, project_fs, EntryRequests, NormalizedDirs,
     },
 };
 
@@ -47,11 +52,24 @@ pub fn register() {
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
 }
 
+/// The outcome of a successful [`TurbopackBuildBuilder::build`].
+#[derive(Debug)]
+pub struct BuildResult {
+    /// Paths of every emitted output asset, relative to the output directory
+    /// and sorted.
+    pub output_assets: Vec<String>,
+    /// All non-fatal issues raised during the build, ordered by severity.
+    pub issues: Vec<ReadRef<PlainIssue>>,
+}
+
 pub struct TurbopackBuildBuilder {
     turbo_tasks: Arc<TurboTasks<MemoryBackend>>,
     project_dir: String,
     root_dir: String,
+    output_dir: String,
     entry_requests: Vec<EntryRequest>,
+    server_entry_requests: Vec<EntryRequest>,
+    env: Vec<(String, String)>,
     browserslist_query: String,
     log_level: IssueSeverity,
     show_all: bool,
@@ -69,7 +87,10 @@ impl TurbopackBuildBuilder {
             turbo_tasks,
             project_dir,
             root_dir,
+            output_dir: "dist".to_owned(),
             entry_requests: vec![],
+            server_entry_requests: vec![],
+            env: vec![],
             browserslist_query: "chrome 64, edge 79, firefox 67, opera 51, safari 12".to_owned(),
             log_level: IssueSeverity::Warning,
             show_all: false,
@@ -83,6 +104,28 @@ impl TurbopackBuildBuilder {
         self
     }
 
+    /// Adds an entry that is built to run in Node.js. Its chunks are written to
+    /// the `server` directory inside of the output directory, and assets that
+    /// only server entries reference are kept out of the browser output.
+    pub fn server_entry_request(mut self, entry_asset_path: EntryRequest) -> Self {
+        self.server_entry_requests.push(entry_asset_path);
+        self
+    }
+
+    /// Sets the directory, relative to the project directory, that output
+    /// assets are written to. Defaults to `dist`.
+    pub fn output_dir(mut self, output_dir: String) -> Self {
+        self.output_dir = output_dir;
+        self
+    }
+
+    /// Adds an environment variable that is visible to the build, taking
+    /// precedence over the process environment and any `.env` files.
+    pub fn env(mut self, name: String, value: String) -> Self {
+        self.env.push((name, value));
+        self
+    }
+
     pub fn browserslist_query(mut self, browserslist_query: String) -> Self {
         self.browserslist_query = browserslist_query;
         self
@@ -108,11 +151,22 @@ impl TurbopackBuildBuilder {
         self
     }
 
-    pub async fn build(self) -> Result<()> {
-        let task = self.turbo_tasks.spawn_once_task::<(), _>(async move {
+    /// Runs the build to completion, writing all output assets to disk.
+    ///
+    /// Issues are reported to the console according to the configured log
+    /// options. If any of them are fatal, an error is returned, otherwise
+    /// they are included in the [`BuildResult`].
+    pub async fn build(self) -> Result<BuildResult> {
+        // Once tasks can only return cells, so the result is sent back
+        // separately
+        let (result_tx, result_rx) = tokio::sync::oneshot::channel();
+        let turbo_tasks = self.turbo_tasks.clone();
+        let task = turbo_tasks.spawn_once_task::<(), _>(async move {
+            let env: Vc<EnvMap> = Vc::cell(self.env.into_iter().collect());
             let build_result = build_internal(
                 self.project_dir.clone(),
                 self.root_dir,
+                self.output_dir,
                 EntryRequests(
                     self.entry_requests
                         .iter()
@@ -121,12 +175,21 @@ impl TurbopackBuildBuilder {
                         .collect(),
                 )
                 .cell(),
+                EntryRequests(
+                    self.server_entry_requests
+                        .iter()
+                        .cloned()
+                        .map(EntryRequest::cell)
+                        .collect(),
+                )
+                .cell(),
+                env,
                 self.browserslist_query,
                 self.minify_type,
             );
 
             // Await the result to propagate any errors.
-            build_result.await?;
+            let output_assets = build_result.await?.clone_value();
 
             let issue_reporter: Vc<Box<dyn IssueReporter>> =
                 Vc::upcast(ConsoleUi::new(TransientInstance::new(LogOptions {
@@ -146,90 +209,59 @@ impl TurbopackBuildBuilder {
             )
             .await?;
 
+            let issues = build_result
+                .peek_issues_with_path()
+                .await?
+                .get_plain_issues()
+                .await?;
+
+            result_tx
+                .send(BuildResult {
+                    output_assets,
+                    issues,
+                })
+                .map_err(|_| anyhow!("unable to send the build result"))?;
+
             Ok(Default::default())
         });
 
-        self.turbo_tasks.wait_task_completion(task, true).await?;
+        turbo_tasks.wait_task_completion(task, true).await?;
 
-        Ok(())
+        Ok(result_rx.await?)
     }
 }
 
+/// The environment visible to the build: the process environment and `.env`
+/// files, overridden by `env_vars`.
 #[turbo_tasks::function]
-async fn build_internal(
-    project_dir: String,
-    root_dir: String,
-    entry_requests: Vc<EntryRequests>,
-    browserslist_query: String,
-    minify_type: MinifyType,
-) -> Result<Vc<()>> {
-    let env = Environment::new(Value::new(ExecutionEnvironment::Browser(
-        BrowserEnvironment {
-            dom: true,
-            web_worker: false,
-            service_worker: false,
-            browserslist_query: browserslist_query.clone(),
-        }
-        .into(),
-    )));
-    let output_fs = output_fs(project_dir.clone());
-    let project_fs = project_fs(root_dir.clone());
-    let project_relative = project_dir.strip_prefix(&root_dir).unwrap();
-    let project_relative = project_relative
-        .strip_prefix(MAIN_SEPARATOR)
-        .unwrap_or(project_relative)
-        .replace(MAIN_SEPARATOR, "/");
-    let project_path = project_fs.root().join(project_relative);
-    let build_output_root = output_fs.root().join("dist".to_string());
-
-    let node_env = NodeEnv::Production.cell();
-
-    let chunking_context = Vc::upcast(
-        NodeJsChunkingContext::builder(
-            project_path,
-            build_output_root,
-            build_output_root,
-            build_output_root,
-            build_output_root,
-            env,
-            match *node_env.await? {
-                NodeEnv::Development => RuntimeType::Development,
-                NodeEnv::Production => RuntimeType::Production,
-            },
-        )
-        .minify_type(minify_type)
-        .build(),
-    );
-
-    let compile_time_info = get_client_compile_time_info(browserslist_query, node_env);
-    let execution_context =
-        ExecutionContext::new(project_path, chunking_context, load_env(project_path));
-    let asset_context =
-        get_client_asset_context(project_path, execution_context, compile_time_info, node_env);
+fn build_process_env(
+    project_path: Vc<FileSystemPath>,
+    env_vars: Vc<EnvMap>,
+) -> Vc<Box<dyn ProcessEnv>> {
+    Vc::upcast(CustomProcessEnv::new(load_env(project_path), env_vars))
+}
 
-    let entry_requests = (*entry_requests
+/// Resolves entry requests against an asset context.
+async fn resolve_entries(
+    asset_context: Vc<Box<dyn AssetContext>>,
+    origin_path: Vc<FileSystemPath>,
+    entry_requests: Vc<EntryRequests>,
+    project_dir: &str,
+) -> Result<Vec<Vc<Box<dyn Module>>>> {
+    let origin = PlainResolveOrigin::new(asset_context, origin_path);
+    entry_requests
         .await?
         .iter()
-        .cloned()
+        .copied()
         .map(|r| async move {
-            Ok(match &*r.await? {
+            let request_vc = match &*r.await? {
                 EntryRequest::Relative(p) => {
                     Request::relative(Value::new(p.clone().into()), Default::default(), false)
                 }
                 EntryRequest::Module(m, p) => {
                     Request::module(m.clone(), Value::new(p.clone().into()), Default::default())
                 }
-            })
-        })
-        .try_join()
-        .await?)
-        .to_vec();
-
-    let origin = PlainResolveOrigin::new(asset_context, output_fs.root().join("_".to_string()));
-    let project_dir = &project_dir;
-    let entries = entry_requests
-        .into_iter()
-        .map(|request_vc| async move {
+            };
             let ty = Value::new(ReferenceType::Entry(EntryReferenceSubType::Undefined));
             let request = request_vc.await?;
             origin
@@ -245,9 +277,16 @@ async fn build_internal(
                 })
         })
         .try_join()
-        .await?;
+        .await
+}
 
-    let entry_chunk_groups = entries
+/// Chunks entry modules into chunk groups placed in `output_root`.
+async fn entry_chunk_groups(
+    chunking_context: Vc<NodeJsChunkingContext>,
+    output_root: Vc<FileSystemPath>,
+    entries: Vec<Vc<Box<dyn Module>>>,
+) -> Result<Vec<Vc<OutputAssets>>> {
+    entries
         .into_iter()
         .map(|entry_module| async move {
             Ok(
@@ -255,11 +294,9 @@ async fn build_internal(
                     Vc::try_resolve_downcast_type::<EcmascriptModuleAsset>(entry_module).await?
                 {
                     Vc::cell(vec![
-                        Vc::try_resolve_downcast_type::<NodeJsChunkingContext>(chunking_context)
-                            .await?
-                            .unwrap()
+                        chunking_context
                             .entry_chunk_group(
-                                build_output_root
+                                output_root
                                     .join(
                                         ecmascript
                                             .ident()
@@ -281,7 +318,8 @@ async fn build_internal(
                 } else if let Some(chunkable) =
                     Vc::try_resolve_sidecast::<Box<dyn ChunkableModule>>(entry_module).await?
                 {
-                    chunking_context.root_chunk_group_assets(chunkable)
+                    Vc::upcast::<Box<dyn ChunkingContext>>(chunking_context)
+                        .root_chunk_group_assets(chunkable)
                 } else {
                     // TODO convert into a serve-able asset
                     bail!(
@@ -292,20 +330,144 @@ async fn build_internal(
             )
         })
         .try_join()
-        .await?;
+        .await
+}
+
+#[turbo_tasks::function]
+#[allow(clippy::too_many_arguments)]
+async fn build_internal(
+    project_dir: String,
+    root_dir: String,
+    output_dir: String,
+    entry_requests: Vc<EntryRequests>,
+    server_entry_requests: Vc<EntryRequests>,
+    env_vars: Vc<EnvMap>,
+    browserslist_query: String,
+    minify_type: MinifyType,
+) -> Result<Vc<Vec<String>>> {
+    let env = Environment::new(Value::new(ExecutionEnvironment::Browser(
+        BrowserEnvironment {
+            dom: true,
+            web_worker: false,
+            service_worker: false,
+            browserslist_query: browserslist_query.clone(),
+        }
+        .into(),
+    )));
+    let output_fs = output_fs(project_dir.clone());
+    let project_fs = project_fs(root_dir.clone());
+    let project_relative = project_dir.strip_prefix(&root_dir).unwrap();
+    let project_relative = project_relative
+        .strip_prefix(MAIN_SEPARATOR)
+        .unwrap_or(project_relative)
+        .replace(MAIN_SEPARATOR, "/");
+    let project_path = project_fs.root().join(project_relative);
+    let build_output_root = output_fs.root().join(output_dir);
+    let server_output_root = build_output_root.join("server".to_string());
+
+    let node_env = NodeEnv::Production.cell();
+    let runtime_type = match *node_env.await? {
+        NodeEnv::Development => RuntimeType::Development,
+        NodeEnv::Production => RuntimeType::Production,
+    };
+
+    let chunking_context = NodeJsChunkingContext::builder(
+        project_path,
+        build_output_root,
+        build_output_root,
+        build_output_root,
+        build_output_root,
+        env,
+        runtime_type,
+    )
+    .minify_type(minify_type)
+    .build();
 
-    let mut chunks: HashSet<Vc<Box<dyn OutputAsset>>> = HashSet::new();
-    for chunk_group in entry_chunk_groups {
-        chunks.extend(&*all_assets_from_entries(chunk_group).await?);
+    let compile_time_info = get_client_compile_time_info(browserslist_query, node_env);
+    let execution_context = ExecutionContext::new(
+        project_path,
+        Vc::upcast(chunking_context),
+        build_process_env(project_path, env_vars),
+    );
+    let asset_context =
+        get_client_asset_context(project_path, execution_context, compile_time_info, node_env);
+
+    // Static assets of the server target are placed in the browser output root
+    // like any other static asset. The target-aware walk below moves the ones
+    // only the server references into the server output root.
+    let server_compile_time_info = get_server_compile_time_info(node_env);
+    let server_chunking_context = NodeJsChunkingContext::builder(
+        project_path,
+        server_output_root,
+        build_output_root,
+        server_output_root,
+        build_output_root,
+        server_compile_time_info.environment(),
+        runtime_type,
+    )
+    .minify_type(minify_type)
+    .build();
+    let server_asset_context = get_server_asset_context(
+        project_path,
+        execution_context,
+        server_compile_time_info,
+        node_env,
+    );
+
+    let origin_path = output_fs.root().join("_".to_string());
+    let entries = resolve_entries(asset_context, origin_path, entry_requests, &project_dir).await?;
+    let server_entries = resolve_entries(
+        server_asset_context,
+        origin_path,
+        server_entry_requests,
+        &project_dir,
+    )
+    .await?;
+
+    let mut browser_assets = Vec::new();
+    for chunk_group in entry_chunk_groups(chunking_context, build_output_root, entries).await? {
+        browser_assets.extend(chunk_group.await?.iter().copied());
+    }
+    let mut server_assets = Vec::new();
+    for chunk_group in
+        entry_chunk_groups(server_chunking_context, server_output_root, server_entries).await?
+    {
+        server_assets.extend(chunk_group.await?.iter().copied());
     }
 
+    let chunks: HashSet<Vc<Box<dyn OutputAsset>>> = all_assets_from_target_entries(
+        Vc::cell(server_assets),
+        server_output_root,
+        Vc::cell(browser_assets),
+        build_output_root,
+    )
+    .await?
+    .iter()
+    .copied()
+    .collect();
+
     chunks
         .iter()
         .map(|c| c.content().write(c.ident().path()))
         .try_join()
         .await?;
 
-    Ok(Default::default())
+    let build_output_root = build_output_root.await?;
+    let build_output_root = &*build_output_root;
+    let mut output_assets = chunks
+        .iter()
+        .map(|c| async move {
+            let path = c.ident().path().await?;
+            Ok(build_output_root
+                .get_path_to(&path)
+                .unwrap_or(&path.path)
+                .to_string())
+        })
+        .try_join()
+        .await?;
+    output_assets.sort();
+
+    Ok(Vc::cell(output_assets))
 }
 
 pub async fn build(args: &BuildArguments) -> Result<()> {
@@ -338,7 +500,65 @@ pub async fn build(args: &BuildArguments) -> Result<()> {
         builder = builder.entry_request(EntryRequest::Relative(entry));
     }
 
+    for entry in &args.server_entries {
+        builder = builder.server_entry_request(EntryRequest::Relative(entry.clone()));
+    }
+
     builder.build().await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use turbo_tasks::{TurboTasks, Vc};
+    use turbo_tasks_env::EnvMap;
+    use turbo_tasks_memory::MemoryBackend;
+
+    use super::build_process_env;
+    use crate::util::project_fs;
+
+    #[tokio::test]
+    async fn env_overrides_dotenv() {
+        crate::register();
+
+        let tmp = tempfile::tempdir().unwrap();
+        let project_dir = dunce::canonicalize(tmp.path()).unwrap();
+        fs::write(
+            project_dir.join(".env"),
+            "TURBOPACK_CLI_TEST_DOTENV=dotenv\nTURBOPACK_CLI_TEST_OVERRIDE=dotenv\n",
+        )
+        .unwrap();
+        let project_dir = project_dir.to_str().unwrap().to_string();
+
+        let tt = TurboTasks::new(MemoryBackend::default());
+        let (from_dotenv, overridden) = tt
+            .run_once(async move {
+                let env: Vc<EnvMap> = Vc::cell(
+                    [(
+                        "TURBOPACK_CLI_TEST_OVERRIDE".to_string(),
+                        "builder".to_string(),
+                    )]
+                    .into_iter()
+                    .collect(),
+                );
+                let process_env = build_process_env(project_fs(project_dir).root(), env);
+                let from_dotenv = process_env
+                    .read("TURBOPACK_CLI_TEST_DOTENV".to_string())
+                    .await?
+                    .clone_value();
+                let overridden = process_env
+                    .read("TURBOPACK_CLI_TEST_OVERRIDE".to_string())
+                    .await?
+                    .clone_value();
+                Ok((from_dotenv, overridden))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(from_dotenv.as_deref(), Some("dotenv"));
+        assert_eq!(overridden.as_deref(), Some("builder"));
+    }
+}
@@ -17,7 +17,7 @@ use turbopack_core::{
     compile_time_info::{CompileTimeDefines, CompileTimeInfo},
     condition::ContextCondition,
     context::AssetContext,
-    environment::{BrowserEnvironment, Environment, ExecutionEnvironment},
+    environment::{BrowserEnvironment, Environment, ExecutionEnvironment, NodeJsEnvironment},
     resolve::options::{ImportMap, ImportMapping},
 };
 use turbopack_ecmascript_plugins::transform::{
@@ -95,6 +95,32 @@ pub async fn get_client_resolve_options_context(
     .cell())
 }
 
+/// Resolve options for modules that run in Node.js as part of a build's server
+/// target.
+#[turbo_tasks::function]
+pub async fn get_server_resolve_options_context(
+    project_path: Vc<FileSystemPath>,
+) -> Result<Vc<ResolveOptionsContext>> {
+    let module_options_context = ResolveOptionsContext {
+        enable_node_modules: Some(project_path.root().resolve().await?),
+        enable_node_native_modules: true,
+        custom_conditions: vec!["development".to_string()],
+        import_map: Some(get_client_import_map(project_path)),
+        module: true,
+        ..Default::default()
+    };
+    Ok(ResolveOptionsContext {
+        enable_typescript: true,
+        enable_react: true,
+        rules: vec![(
+            foreign_code_context_condition().await?,
+            module_options_context.clone().cell(),
+        )],
+        ..module_options_context
+    }
+    .cell())
+}
+
 #[turbo_tasks::function]
 async fn get_client_module_options_context(
     project_path: Vc<FileSystemPath>,
@@ -195,6 +221,30 @@ pub fn get_client_asset_context(
     asset_context
 }
 
+#[turbo_tasks::function]
+pub fn get_server_asset_context(
+    project_path: Vc<FileSystemPath>,
+    execution_context: Vc<ExecutionContext>,
+    compile_time_info: Vc<CompileTimeInfo>,
+    node_env: Vc<NodeEnv>,
+) -> Vc<Box<dyn AssetContext>> {
+    let resolve_options_context = get_server_resolve_options_context(project_path);
+    let module_options_context = get_client_module_options_context(
+        project_path,
+        execution_context,
+        compile_time_info.environment(),
+        node_env,
+    );
+
+    Vc::upcast(ModuleAssetContext::new(
+        Vc::cell(HashMap::new()),
+        compile_time_info,
+        module_options_context,
+        resolve_options_context,
+        Vc::cell("server".to_string()),
+    ))
+}
+
 fn client_defines(node_env: &NodeEnv) -> Vc<CompileTimeDefines> {
     compile_time_defines!(
         process.turbopack = true,
@@ -223,3 +273,12 @@ pub async fn get_client_compile_time_info(
         .cell(),
     )
 }
+
+#[turbo_tasks::function]
+pub async fn get_server_compile_time_info(node_env: Vc<NodeEnv>) -> Result<Vc<CompileTimeInfo>> {
+    Ok(CompileTimeInfo::builder(Environment::new(Value::new(
+        ExecutionEnvironment::NodeJsLambda(NodeJsEnvironment::default().into()),
+    )))
+    .defines(client_defines(&*node_env.await?))
+    .cell())
+}
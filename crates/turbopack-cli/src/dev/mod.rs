@@ -27,11 +27,12 @@ use turbopack_core::{
     server_fs::ServerFileSystem,
 };
 use turbopack_dev_server::{
-    introspect::IntrospectionSource,
+    introspect::{graph::GraphIntrospectionSource, IntrospectionSource},
     source::{
         combined::CombinedContentSource, router::PrefixedRouterContentSource,
         static_assets::StaticAssetsContentSource, ContentSource,
     },
+    tls::{LocalCertificateAuthority, TlsConfig},
     DevServer, DevServerBuilder,
 };
 use turbopack_ecmascript_runtime::RuntimeType;
@@ -57,6 +58,7 @@ pub struct TurbopackDevServerBuilder {
     entry_requests: Vec<EntryRequest>,
     eager_compile: bool,
     hostname: Option<IpAddr>,
+    https: bool,
     issue_reporter: Option<Box<dyn IssueReporterProvider>>,
     port: Option<u16>,
     browserslist_query: String,
@@ -64,6 +66,7 @@ pub struct TurbopackDevServerBuilder {
     show_all: bool,
     log_detail: bool,
     allow_retry: bool,
+    introspect_graph: bool,
 }
 
 impl TurbopackDevServerBuilder {
@@ -79,6 +82,7 @@ impl TurbopackDevServerBuilder {
             entry_requests: vec![],
             eager_compile: false,
             hostname: None,
+            https: false,
             issue_reporter: None,
             port: None,
             browserslist_query: "last 1 Chrome versions, last 1 Firefox versions, last 1 Safari \
@@ -88,6 +92,7 @@ impl TurbopackDevServerBuilder {
             show_all: false,
             log_detail: false,
             allow_retry: false,
+            introspect_graph: false,
         }
     }
 
@@ -106,6 +111,11 @@ impl TurbopackDevServerBuilder {
         self
     }
 
+    pub fn https(mut self, https: bool) -> TurbopackDevServerBuilder {
+        self.https = https;
+        self
+    }
+
     pub fn port(mut self, port: u16) -> TurbopackDevServerBuilder {
         self.port = Some(port);
         self
@@ -131,6 +141,11 @@ impl TurbopackDevServerBuilder {
         self
     }
 
+    pub fn introspect_graph(mut self, introspect_graph: bool) -> TurbopackDevServerBuilder {
+        self.introspect_graph = introspect_graph;
+        self
+    }
+
     pub fn log_detail(mut self, log_detail: bool) -> TurbopackDevServerBuilder {
         self.log_detail = log_detail;
         self
@@ -145,14 +160,23 @@ impl TurbopackDevServerBuilder {
     }
 
     /// Attempts to find an open port to bind.
-    fn find_port(&self, host: IpAddr, port: u16, max_attempts: u16) -> Result<DevServerBuilder> {
+    fn find_port(
+        &self,
+        host: IpAddr,
+        port: u16,
+        max_attempts: u16,
+        tls: Option<&TlsConfig>,
+    ) -> Result<DevServerBuilder> {
         // max_attempts of 1 means we loop 0 times.
         let max_attempts = max_attempts - 1;
         let mut attempts = 0;
         loop {
             let current_port = port + attempts;
             let addr = SocketAddr::new(host, current_port);
-            let listen_result = DevServer::listen(addr);
+            let listen_result = match tls {
+                Some(tls) => DevServer::listen_https(addr, tls.clone()),
+                None => DevServer::listen(addr),
+            };
 
             if let Err(e) = &listen_result {
                 if self.allow_retry && attempts < max_attempts {
@@ -188,12 +212,27 @@ impl TurbopackDevServerBuilder {
         let port = self.port.context("port must be set")?;
         let host = self.hostname.context("hostname must be set")?;
 
-        let server = self.find_port(host, port, 10)?;
+        let tls = if self.https {
+            let ca = LocalCertificateAuthority::load_or_create()?;
+            if ca.created() {
+                println!(
+                    "{} - created a local certificate authority in {}, run `mkcert -install` to \
+                     trust it",
+                    "info ".cyan(),
+                    ca.root().display()
+                );
+            }
+            Some(ca.tls_config(host)?)
+        } else {
+            None
+        };
+        let server = self.find_port(host, port, 10, tls.as_ref())?;
 
         let turbo_tasks = self.turbo_tasks;
         let project_dir = self.project_dir;
         let root_dir = self.root_dir;
         let eager_compile = self.eager_compile;
+        let introspect_graph = self.introspect_graph;
         let show_all = self.show_all;
         let log_detail = self.log_detail;
         let browserslist_query = self.browserslist_query;
@@ -217,6 +256,7 @@ impl TurbopackDevServerBuilder {
                 project_dir.clone(),
                 entry_requests.clone().into(),
                 eager_compile,
+                introspect_graph,
                 turbo_tasks.clone().into(),
                 browserslist_query.clone(),
             )
@@ -233,6 +273,7 @@ async fn source(
     project_dir: String,
     entry_requests: TransientInstance<Vec<EntryRequest>>,
     eager_compile: bool,
+    introspect_graph: bool,
     turbo_tasks: TransientInstance<TurboTasks<MemoryBackend>>,
     browserslist_query: String,
 ) -> Result<Vc<Box<dyn ContentSource>>> {
@@ -299,13 +340,28 @@ async fn source(
         }
         .cell(),
     );
+    let mut routes = vec![
+        ("__turbopack__".to_string(), introspect),
+        ("__turbo_tasks__".to_string(), viz),
+    ];
+    if introspect_graph {
+        routes.insert(
+            0,
+            (
+                "__turbopack__/graph.json".to_string(),
+                Vc::upcast(
+                    GraphIntrospectionSource {
+                        roots: HashSet::from([Vc::upcast(main_source)]),
+                    }
+                    .cell(),
+                ),
+            ),
+        );
+    }
     let main_source = Vc::upcast(main_source);
     let source = Vc::upcast(PrefixedRouterContentSource::new(
         Default::default(),
-        vec![
-            ("__turbopack__".to_string(), introspect),
-            ("__turbo_tasks__".to_string(), viz),
-        ],
+        routes,
         main_source,
     ));
 
@@ -346,7 +402,9 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
 
     let mut server = TurbopackDevServerBuilder::new(tt, project_dir, root_dir)
         .eager_compile(args.eager_compile)
+        .introspect_graph(args.introspect_graph)
         .hostname(args.hostname)
+        .https(args.https)
         .port(args.port)
         .log_detail(args.common.log_detail)
         .show_all(args.common.show_all)
@@ -378,10 +436,11 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
         } else {
             addr.ip().to_string()
         };
-        let index_uri = match addr.port() {
-            443 => format!("https://{hostname}"),
-            80 => format!("http://{hostname}"),
-            port => format!("http://{hostname}:{port}"),
+        let index_uri = match (addr.port(), args.https) {
+            (443, true) => format!("https://{hostname}"),
+            (80, false) => format!("http://{hostname}"),
+            (port, true) => format!("https://{hostname}:{port}"),
+            (port, false) => format!("http://{hostname}:{port}"),
         };
         println!(
             "{} - started server on {}, url: {}",
@@ -0,0 +1,110 @@
+use std::fs;
+
+use turbo_tasks::TurboTasks;
+use turbo_tasks_memory::MemoryBackend;
+use turbopack_cli::build::{EntryRequest, TurbopackBuildBuilder};
+use turbopack_core::{chunk::MinifyType, issue::IssueSeverity};
+
+#[tokio::test]
+async fn build_returns_output_assets_and_issues() {
+    turbopack_cli::register();
+
+    let tmp = tempfile::tempdir().unwrap();
+    let project_dir = dunce::canonicalize(tmp.path()).unwrap();
+    fs::create_dir_all(project_dir.join("src")).unwrap();
+    // A missing module inside of a try block is only a warning, so the build
+    // still succeeds
+    fs::write(
+        project_dir.join("src/entry.js"),
+        "try { require(\"does-not-exist\") } catch {}\nconsole.log(\"hello\");\n",
+    )
+    .unwrap();
+
+    let project_dir = project_dir.to_str().unwrap().to_string();
+    let result = TurbopackBuildBuilder::new(
+        TurboTasks::new(MemoryBackend::default()),
+        project_dir.clone(),
+        project_dir.clone(),
+    )
+    .output_dir("out".to_string())
+    .entry_request(EntryRequest::Relative("src/entry".to_string()))
+    .minify_type(MinifyType::NoMinify)
+    .log_level(IssueSeverity::Error)
+    .build()
+    .await
+    .unwrap();
+
+    assert!(!result.output_assets.is_empty());
+    let mut sorted = result.output_assets.clone();
+    sorted.sort();
+    assert_eq!(result.output_assets, sorted);
+    for output_asset in &result.output_assets {
+        assert!(
+            !output_asset.starts_with('/') && !output_asset.starts_with(".."),
+            "{output_asset} must be relative to the output directory"
+        );
+        let path = std::path::Path::new(&project_dir)
+            .join("out")
+            .join(output_asset);
+        assert!(path.exists(), "{} must be written", path.display());
+    }
+    assert!(!std::path::Path::new(&project_dir).join("dist").exists());
+
+    assert!(
+        result.issues.iter().any(|issue| {
+            issue.severity == IssueSeverity::Warning && issue.file_path.ends_with("src/entry.js")
+        }),
+        "the missing module must be reported: {:#?}",
+        result.issues
+    );
+}
+
+#[tokio::test]
+async fn server_only_assets_are_emitted_into_the_server_output() {
+    turbopack_cli::register();
+
+    let tmp = tempfile::tempdir().unwrap();
+    let project_dir = dunce::canonicalize(tmp.path()).unwrap();
+    fs::create_dir_all(project_dir.join("src")).unwrap();
+    fs::write(
+        project_dir.join("src/entry.js"),
+        "console.log(\"hello\");\n",
+    )
+    .unwrap();
+    fs::write(
+        project_dir.join("src/server.js"),
+        "import font from \"./font.woff2\";\nconsole.log(font);\n",
+    )
+    .unwrap();
+    fs::write(project_dir.join("src/font.woff2"), "font").unwrap();
+
+    let project_dir = project_dir.to_str().unwrap().to_string();
+    let result = TurbopackBuildBuilder::new(
+        TurboTasks::new(MemoryBackend::default()),
+        project_dir.clone(),
+        project_dir.clone(),
+    )
+    .entry_request(EntryRequest::Relative("src/entry".to_string()))
+    .server_entry_request(EntryRequest::Relative("src/server".to_string()))
+    .minify_type(MinifyType::NoMinify)
+    .log_level(IssueSeverity::Error)
+    .build()
+    .await
+    .unwrap();
+
+    let fonts = result
+        .output_assets
+        .iter()
+        .filter(|output_asset| output_asset.ends_with(".woff2"))
+        .collect::<Vec<_>>();
+    assert_eq!(fonts.len(), 1, "{:#?}", result.output_assets);
+    assert!(
+        fonts[0].starts_with("server/"),
+        "{} must not be emitted into the browser output",
+        fonts[0]
+    );
+    assert!(std::path::Path::new(&project_dir)
+        .join("dist")
+        .join(fonts[0])
+        .exists());
+}
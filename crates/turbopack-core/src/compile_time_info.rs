@@ -175,12 +175,51 @@ impl FreeVarReferences {
     }
 }
 
+/// Decides which [FreeVarReference::EcmaScriptModule] references are applied.
+/// These are used to inject runtime shims, e.g. for node globals like `Buffer`
+/// or `process` in browser builds. Names are matched either by their first
+/// segment (`process`) or by the full dotted name (`process.env`).
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeShimPolicy {
+    /// When set, only these free variables are shimmed.
+    pub allow: Option<Vec<String>>,
+    /// Free variables that are never shimmed, even if they are allowed.
+    pub deny: Vec<String>,
+    /// Reports an issue for every module that triggered a shim.
+    pub report: bool,
+}
+
+impl RuntimeShimPolicy {
+    pub fn is_allowed(&self, name: &[String]) -> bool {
+        let dotted = name.join(".");
+        let matches =
+            |entry: &String| *entry == dotted || name.first().is_some_and(|first| entry == first);
+        if self.deny.iter().any(matches) {
+            return false;
+        }
+        self.allow
+            .as_ref()
+            .map_or(true, |allow| allow.iter().any(matches))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl RuntimeShimPolicy {
+    /// Applies all shims without reporting them.
+    #[turbo_tasks::function]
+    pub fn allow_all() -> Vc<Self> {
+        Self::default().cell()
+    }
+}
+
 #[turbo_tasks::value(shared)]
 #[derive(Debug, Clone)]
 pub struct CompileTimeInfo {
     pub environment: Vc<Environment>,
     pub defines: Vc<CompileTimeDefines>,
     pub free_var_references: Vc<FreeVarReferences>,
+    pub runtime_shim_policy: Vc<RuntimeShimPolicy>,
 }
 
 impl CompileTimeInfo {
@@ -189,6 +228,7 @@ impl CompileTimeInfo {
             environment,
             defines: None,
             free_var_references: None,
+            runtime_shim_policy: None,
         }
     }
 }
@@ -201,6 +241,7 @@ impl CompileTimeInfo {
             environment,
             defines: CompileTimeDefines::empty(),
             free_var_references: FreeVarReferences::empty(),
+            runtime_shim_policy: RuntimeShimPolicy::allow_all(),
         }
         .cell()
     }
@@ -215,6 +256,7 @@ pub struct CompileTimeInfoBuilder {
     environment: Vc<Environment>,
     defines: Option<Vc<CompileTimeDefines>>,
     free_var_references: Option<Vc<FreeVarReferences>>,
+    runtime_shim_policy: Option<Vc<RuntimeShimPolicy>>,
 }
 
 impl CompileTimeInfoBuilder {
@@ -228,6 +270,11 @@ impl CompileTimeInfoBuilder {
         self
     }
 
+    pub fn runtime_shim_policy(mut self, runtime_shim_policy: Vc<RuntimeShimPolicy>) -> Self {
+        self.runtime_shim_policy = Some(runtime_shim_policy);
+        self
+    }
+
     pub fn build(self) -> CompileTimeInfo {
         CompileTimeInfo {
             environment: self.environment,
@@ -235,6 +282,9 @@ impl CompileTimeInfoBuilder {
             free_var_references: self
                 .free_var_references
                 .unwrap_or_else(FreeVarReferences::empty),
+            runtime_shim_policy: self
+                .runtime_shim_policy
+                .unwrap_or_else(RuntimeShimPolicy::allow_all),
         }
     }
 
@@ -242,3 +292,41 @@ impl CompileTimeInfoBuilder {
         self.build().cell()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+
+    use super::RuntimeShimPolicy;
+
+    fn policy(allow: Option<&[&str]>, deny: &[&str]) -> RuntimeShimPolicy {
+        let to_strings = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        RuntimeShimPolicy {
+            allow: allow.map(to_strings),
+            deny: to_strings(deny),
+            report: false,
+        }
+    }
+
+    #[rstest]
+    #[case::default(None, &[], "Buffer", true)]
+    #[case::allowed(Some(&["Buffer"][..]), &[], "Buffer", true)]
+    #[case::not_allowed(Some(&["Buffer"][..]), &[], "process", false)]
+    #[case::allowed_by_first_segment(Some(&["process"][..]), &[], "process.env", true)]
+    #[case::allowed_by_dotted_name(Some(&["process.env"][..]), &[], "process.env", true)]
+    #[case::dotted_name_doesnt_allow_parent(Some(&["process.env"][..]), &[], "process", false)]
+    #[case::denied(None, &["Buffer"], "Buffer", false)]
+    #[case::denied_by_first_segment(None, &["process"], "process.env", false)]
+    #[case::denied_by_dotted_name(None, &["process.env"], "process.env", false)]
+    #[case::deny_wins_over_allow(Some(&["process"][..]), &["process.env"], "process.env", false)]
+    #[case::sibling_not_denied(Some(&["process"][..]), &["process.env"], "process.argv", true)]
+    fn runtime_shim_policy(
+        #[case] allow: Option<&[&str]>,
+        #[case] deny: &[&str],
+        #[case] name: &str,
+        #[case] expected: bool,
+    ) {
+        let name = name.split('.').map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(policy(allow, deny).is_allowed(&name), expected);
+    }
+}
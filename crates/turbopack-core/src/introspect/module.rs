@@ -18,6 +18,12 @@ impl IntrospectableModule {
             .await?
             .unwrap_or_else(|| Vc::upcast(IntrospectableModule(asset).cell())))
     }
+
+    /// The module that this introspects.
+    #[turbo_tasks::function]
+    pub fn module(&self) -> Vc<Box<dyn Module>> {
+        self.0
+    }
 }
 
 #[turbo_tasks::function]
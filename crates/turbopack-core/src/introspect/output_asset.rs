@@ -18,6 +18,12 @@ impl IntrospectableOutputAsset {
             .await?
             .unwrap_or_else(|| Vc::upcast(IntrospectableOutputAsset(asset).cell())))
     }
+
+    /// The output asset that this introspects.
+    #[turbo_tasks::function]
+    pub fn output_asset(&self) -> Vc<Box<dyn OutputAsset>> {
+        self.0
+    }
 }
 
 #[turbo_tasks::function]
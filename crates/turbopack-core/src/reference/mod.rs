@@ -1,16 +1,18 @@
 use std::collections::{HashSet, VecDeque};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use indexmap::IndexSet;
 use turbo_tasks::{
     graph::{AdjacencyMap, GraphTraversal},
     TryJoinIterExt, ValueToString, Vc,
 };
+use turbo_tasks_fs::FileSystemPath;
 
 use crate::{
     issue::IssueDescriptionExt,
     module::{Module, Modules},
     output::{OutputAsset, OutputAssets},
+    proxied_asset::ProxiedAsset,
     raw_module::RawModule,
     resolve::{ModuleResolveResult, RequestKey},
 };
@@ -236,6 +238,76 @@ pub async fn all_assets_from_entries(entries: Vc<OutputAssets>) -> Result<Vc<Out
     ))
 }
 
+/// Walks the asset graphs of a server and a browser target and collects the
+/// assets that should be emitted.
+///
+/// Both graphs can share modules and therefore output assets, but an asset
+/// that is only referenced from one target doesn't belong in the output root
+/// of the other one. Emitting the whole combined graph would e.g. place files
+/// that only server code references into the publicly served browser output.
+/// Assets located in one target's output root that are only reachable from the
+/// other target are moved to the same relative path in the output root of the
+/// target that references them. When the output roots are nested, the
+/// innermost root decides which target an asset belongs to.
+#[turbo_tasks::function]
+pub async fn all_assets_from_target_entries(
+    server_entries: Vc<OutputAssets>,
+    server_output_root: Vc<FileSystemPath>,
+    browser_entries: Vc<OutputAssets>,
+    browser_output_root: Vc<FileSystemPath>,
+) -> Result<Vc<OutputAssets>> {
+    let server_assets = all_assets_from_entries(server_entries).await?;
+    let browser_assets = all_assets_from_entries(browser_entries).await?;
+    let server_root = &*server_output_root.await?;
+    let browser_root = &*browser_output_root.await?;
+
+    let server_set: HashSet<_> = server_assets.iter().copied().collect();
+    let browser_set: HashSet<_> = browser_assets.iter().copied().collect();
+
+    let mut assets = IndexSet::new();
+    for (asset, referenced_by_server) in server_assets
+        .iter()
+        .map(|asset| (*asset, true))
+        .chain(browser_assets.iter().map(|asset| (*asset, false)))
+    {
+        if server_set.contains(&asset) && browser_set.contains(&asset) {
+            assets.insert(asset);
+            continue;
+        }
+        let path = asset.ident().path().await?;
+        let belongs_to_server = match (
+            path.is_inside_ref(server_root),
+            path.is_inside_ref(browser_root),
+        ) {
+            (false, false) => {
+                // Outside of both output roots, so there is nothing to leak
+                assets.insert(asset);
+                continue;
+            }
+            (true, true) => server_root.is_inside_ref(browser_root),
+            (in_server_root, _) => in_server_root,
+        };
+        if belongs_to_server == referenced_by_server {
+            assets.insert(asset);
+            continue;
+        }
+        let (from_root, to_root) = if referenced_by_server {
+            (browser_root, server_output_root)
+        } else {
+            (server_root, browser_output_root)
+        };
+        let relative_path = from_root
+            .get_path_to(&path)
+            .context("asset must be inside the output root it belongs to")?;
+        assets.insert(Vc::upcast(ProxiedAsset::new(
+            asset,
+            to_root.join(relative_path.to_string()),
+        )));
+    }
+
+    Ok(Vc::cell(assets.into_iter().collect()))
+}
+
 /// Computes the list of all chunk children of a given chunk.
 pub async fn get_referenced_assets(
     asset: Vc<Box<dyn OutputAsset>>,
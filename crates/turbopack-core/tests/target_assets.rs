@@ -0,0 +1,100 @@
+#![feature(arbitrary_self_types)]
+
+use turbo_tasks::Vc;
+use turbo_tasks_fs::{File, FileSystem, FileSystemPath, VirtualFileSystem};
+use turbo_tasks_testing::{register, run};
+use turbopack_core::{
+    asset::{Asset, AssetContent},
+    ident::AssetIdent,
+    output::{OutputAsset, OutputAssets},
+    reference::all_assets_from_target_entries,
+};
+
+register!();
+
+#[turbo_tasks::value]
+struct TestAsset {
+    path: Vc<FileSystemPath>,
+    references: Vc<OutputAssets>,
+}
+
+#[turbo_tasks::value_impl]
+impl OutputAsset for TestAsset {
+    #[turbo_tasks::function]
+    fn ident(&self) -> Vc<AssetIdent> {
+        AssetIdent::from_path(self.path)
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> Vc<OutputAssets> {
+        self.references
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for TestAsset {
+    #[turbo_tasks::function]
+    fn content(&self) -> Vc<AssetContent> {
+        AssetContent::file(File::from("content").into())
+    }
+}
+
+#[tokio::test]
+async fn assets_are_moved_to_the_root_of_the_target_referencing_them() {
+    run! {
+        turbopack_core::register();
+
+        let fs = VirtualFileSystem::new();
+        let server_root = fs.root().join("server".to_string());
+        let browser_root = fs.root().join("static".to_string());
+        let asset = |path: &str, root: Vc<FileSystemPath>, references| {
+            Vc::upcast::<Box<dyn OutputAsset>>(
+                TestAsset {
+                    path: root.join(path.to_string()),
+                    references: Vc::cell(references),
+                }
+                .cell(),
+            )
+        };
+
+        // The server references a file that is placed in the browser output
+        // root, e.g. a font that is only inlined during server rendering
+        let server_only = asset("server-only.woff", browser_root, vec![]);
+        // ...and the other way around
+        let browser_only = asset("browser-only.css", server_root, vec![]);
+        let shared = asset("shared.js", browser_root, vec![]);
+        let server_entry = asset("page.js", server_root, vec![server_only, shared]);
+        let browser_entry = asset("page.js", browser_root, vec![browser_only, shared]);
+        let server_entries: Vc<OutputAssets> = Vc::cell(vec![server_entry]);
+        let browser_entries: Vc<OutputAssets> = Vc::cell(vec![browser_entry]);
+
+        let assets = all_assets_from_target_entries(
+            server_entries,
+            server_root,
+            browser_entries,
+            browser_root,
+        )
+        .await?;
+        assert!(!assets.contains(&server_only));
+        assert!(!assets.contains(&browser_only));
+        assert!(assets.contains(&shared));
+        assert!(assets.contains(&server_entry));
+        assert!(assets.contains(&browser_entry));
+
+        let mut paths = Vec::new();
+        for asset in assets.iter() {
+            paths.push(asset.ident().path().await?.path.clone());
+        }
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "server/page.js",
+                "server/server-only.woff",
+                "static/browser-only.css",
+                "static/page.js",
+                "static/shared.js",
+            ]
+        );
+    }
+}
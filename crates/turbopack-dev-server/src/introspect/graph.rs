@@ -0,0 +1,345 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    future::Future,
+    hash::Hash,
+};
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::Serialize;
+use turbo_tasks::Vc;
+use turbo_tasks_fs::{File, FileContent};
+use turbopack_core::{
+    asset::{Asset, AssetContent},
+    introspect::{
+        module::IntrospectableModule, output_asset::IntrospectableOutputAsset, Introspectable,
+    },
+    version::VersionedContentExt,
+};
+
+use crate::source::{
+    route_tree::{RouteTree, RouteType},
+    ContentSource, ContentSourceContent, ContentSourceData, GetContentSourceContent,
+};
+
+/// Serves the whole introspection graph below `roots` as a single JSON
+/// document, so that tools can visualize why a module was included without
+/// crawling the HTML introspection pages.
+///
+/// Every [Introspectable] becomes a node. Modules, chunks, chunk items and
+/// output assets are told apart by their `ty`, and the edges are labeled with
+/// the name the parent gave to the child, e.g. `reference`, `async reference`
+/// or `chunk item`. Each node also lists the chunks it's a part of and the
+/// size of its content.
+#[turbo_tasks::value(shared)]
+pub struct GraphIntrospectionSource {
+    pub roots: HashSet<Vc<Box<dyn Introspectable>>>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct GraphNode {
+    ty: String,
+    title: String,
+    /// The size of the asset's content in bytes, if the node has content.
+    size: Option<usize>,
+    /// The ids of the chunks that this node is a part of.
+    chunks: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct GraphEdge {
+    from: usize,
+    to: usize,
+    kind: String,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct Graph {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+/// What [traverse] needs to know about each node it visits.
+struct Visited<K> {
+    ty: String,
+    title: String,
+    size: Option<usize>,
+    children: Vec<(String, K)>,
+}
+
+/// The edges from a chunk to the modules that it contains.
+const CHUNK_MEMBERSHIP_KINDS: [&str; 2] = ["module", "entry module"];
+
+fn is_chunk(ty: &str) -> bool {
+    ty == "chunk" || ty.ends_with(" chunk")
+}
+
+/// Visits every node reachable from `roots` breadth first, numbering them in
+/// the order they're discovered. Each node is only visited once, even if it's
+/// reachable along several paths or through a cycle.
+async fn traverse<K, F, Fut>(roots: impl IntoIterator<Item = K>, mut visit: F) -> Result<Graph>
+where
+    K: Copy + Eq + Hash,
+    F: FnMut(K) -> Fut,
+    Fut: Future<Output = Result<Visited<K>>>,
+{
+    let mut ids = IndexMap::new();
+    for root in roots {
+        let id = ids.len();
+        ids.entry(root).or_insert(id);
+    }
+    let mut queue = VecDeque::with_capacity(ids.len());
+    queue.extend(ids.keys().copied());
+
+    let mut graph = Graph::default();
+    while let Some(key) = queue.pop_front() {
+        let from = ids[&key];
+        let visited = visit(key).await?;
+        graph.nodes.push(GraphNode {
+            ty: visited.ty,
+            title: visited.title,
+            size: visited.size,
+            chunks: Vec::new(),
+        });
+        for (kind, child) in visited.children {
+            let to = match ids.get(&child) {
+                Some(id) => *id,
+                None => {
+                    let id = ids.len();
+                    ids.insert(child, id);
+                    queue.push_back(child);
+                    id
+                }
+            };
+            graph.edges.push(GraphEdge { from, to, kind });
+        }
+    }
+
+    // Nodes are numbered as they're discovered, so every edge points at a node
+    // that has been visited by now.
+    for edge in &graph.edges {
+        if is_chunk(&graph.nodes[edge.from].ty)
+            && CHUNK_MEMBERSHIP_KINDS.contains(&edge.kind.as_str())
+        {
+            let chunks = &mut graph.nodes[edge.to].chunks;
+            if !chunks.contains(&edge.from) {
+                chunks.push(edge.from);
+            }
+        }
+    }
+    Ok(graph)
+}
+
+/// The size of the content of the asset behind `introspectable`, if there is
+/// one.
+async fn content_size(introspectable: Vc<Box<dyn Introspectable>>) -> Result<Option<usize>> {
+    let content =
+        if let Some(asset) = Vc::try_resolve_sidecast::<Box<dyn Asset>>(introspectable).await? {
+            asset.content()
+        } else if let Some(module) =
+            Vc::try_resolve_downcast_type::<IntrospectableModule>(introspectable).await?
+        {
+            module.module().content()
+        } else if let Some(output_asset) =
+            Vc::try_resolve_downcast_type::<IntrospectableOutputAsset>(introspectable).await?
+        {
+            output_asset.output_asset().content()
+        } else {
+            return Ok(None);
+        };
+    Ok(match &*content.await? {
+        AssetContent::File(file) => match &*file.await? {
+            FileContent::Content(file) => Some(file.content().len()),
+            FileContent::NotFound => None,
+        },
+        AssetContent::Redirect { .. } => None,
+    })
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for GraphIntrospectionSource {
+    #[turbo_tasks::function]
+    fn get_routes(self: Vc<Self>) -> Vc<RouteTree> {
+        RouteTree::new_route(Vec::new(), RouteType::Exact, Vc::upcast(self))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl GetContentSourceContent for GraphIntrospectionSource {
+    #[turbo_tasks::function]
+    async fn get(
+        &self,
+        _path: String,
+        _data: turbo_tasks::Value<ContentSourceData>,
+    ) -> Result<Vc<ContentSourceContent>> {
+        let graph = traverse(self.roots.iter().copied(), |introspectable| async move {
+            let mut children = Vec::new();
+            for &(kind, child) in introspectable.children().await?.iter() {
+                children.push((kind.await?.clone_value(), child));
+            }
+            Ok(Visited {
+                ty: introspectable.ty().await?.clone_value(),
+                title: introspectable.title().await?.clone_value(),
+                size: content_size(introspectable).await?,
+                children,
+            })
+        })
+        .await?;
+
+        let json = serde_json::to_string(&graph)?;
+        Ok(ContentSourceContent::static_content(
+            AssetContent::file(
+                File::from(json)
+                    .with_content_type(mime::APPLICATION_JSON)
+                    .into(),
+            )
+            .versioned(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use anyhow::{anyhow, Result};
+
+    use super::{traverse, Graph, GraphEdge, GraphNode, Visited};
+
+    /// A graph of `(ty, size, children)` keyed by title.
+    type Fixture = HashMap<
+        &'static str,
+        (
+            &'static str,
+            Option<usize>,
+            Vec<(&'static str, &'static str)>,
+        ),
+    >;
+
+    async fn traverse_fixture(roots: &[&'static str], fixture: &Fixture) -> Result<Graph> {
+        traverse(roots.iter().copied(), |title| async move {
+            let (ty, size, children) = fixture
+                .get(title)
+                .ok_or_else(|| anyhow!("{title} is not in the fixture"))?;
+            Ok(Visited {
+                ty: ty.to_string(),
+                title: title.to_string(),
+                size: *size,
+                children: children
+                    .iter()
+                    .map(|(kind, child)| (kind.to_string(), *child))
+                    .collect(),
+            })
+        })
+        .await
+    }
+
+    fn node(ty: &str, title: &str, size: Option<usize>, chunks: &[usize]) -> GraphNode {
+        GraphNode {
+            ty: ty.to_string(),
+            title: title.to_string(),
+            size,
+            chunks: chunks.to_vec(),
+        }
+    }
+
+    fn edge(from: usize, to: usize, kind: &str) -> GraphEdge {
+        GraphEdge {
+            from,
+            to,
+            kind: kind.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_visits_each_node_once() {
+        let fixture = Fixture::from([
+            (
+                "a",
+                (
+                    "asset",
+                    Some(1),
+                    vec![("reference", "b"), ("reference", "c")],
+                ),
+            ),
+            ("b", ("asset", Some(2), vec![("reference", "c")])),
+            ("c", ("asset", Some(3), vec![("async reference", "a")])),
+        ]);
+
+        let graph = traverse_fixture(&["a", "a"], &fixture).await.unwrap();
+
+        assert_eq!(
+            graph,
+            Graph {
+                nodes: vec![
+                    node("asset", "a", Some(1), &[]),
+                    node("asset", "b", Some(2), &[]),
+                    node("asset", "c", Some(3), &[]),
+                ],
+                edges: vec![
+                    edge(0, 1, "reference"),
+                    edge(0, 2, "reference"),
+                    edge(1, 2, "reference"),
+                    edge(2, 0, "async reference"),
+                ],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chunk_membership() {
+        let fixture = Fixture::from([
+            (
+                "main.js",
+                (
+                    "ecmascript chunk",
+                    Some(100),
+                    vec![("module", "index.js"), ("module", "shared.js")],
+                ),
+            ),
+            (
+                "main.css",
+                ("css chunk", Some(10), vec![("entry module", "index.css")]),
+            ),
+            (
+                "index.js",
+                ("ecmascript", Some(40), vec![("reference", "shared.js")]),
+            ),
+            ("index.css", ("css", Some(10), vec![])),
+            ("shared.js", ("ecmascript", Some(60), vec![])),
+            (
+                "page.js",
+                (
+                    "ecmascript chunk",
+                    Some(60),
+                    vec![("module", "shared.js"), ("chunk", "main.js")],
+                ),
+            ),
+        ]);
+
+        let graph = traverse_fixture(&["page.js", "main.css"], &fixture)
+            .await
+            .unwrap();
+
+        let chunks_of = |title: &str| {
+            let node = graph.nodes.iter().find(|node| node.title == title).unwrap();
+            node.chunks
+                .iter()
+                .map(|&id| graph.nodes[id].title.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(chunks_of("shared.js"), ["page.js", "main.js"]);
+        assert_eq!(chunks_of("index.js"), ["main.js"]);
+        assert_eq!(chunks_of("index.css"), ["main.css"]);
+        // chunks referencing each other doesn't make them members
+        assert!(chunks_of("main.js").is_empty());
+        assert_eq!(graph.nodes[0].size, Some(60));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_node_fails() {
+        let fixture = Fixture::from([("a", ("asset", None, vec![("reference", "missing")]))]);
+
+        assert!(traverse_fixture(&["a"], &fixture).await.is_err());
+    }
+}
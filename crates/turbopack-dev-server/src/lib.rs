@@ -10,6 +10,7 @@ mod http;
 pub mod introspect;
 mod invalidation;
 pub mod source;
+pub mod tls;
 pub mod update;
 
 use std::{
@@ -39,7 +40,11 @@ use turbopack_core::{
     issue::{handle_issues, IssueReporter, IssueSeverity},
 };
 
-use self::{source::ContentSource, update::UpdateServer};
+use self::{
+    source::ContentSource,
+    tls::{TlsConfig, TlsIncoming},
+    update::UpdateServer,
+};
 use crate::{
     invalidation::{ServerRequest, ServerRequestSideEffects},
     source::ContentSourceSideEffect,
@@ -64,7 +69,21 @@ pub struct DevServerBuilder {
     #[turbo_tasks(trace_ignore)]
     pub addr: SocketAddr,
     #[turbo_tasks(trace_ignore)]
-    server: Builder<AddrIncoming>,
+    server: Listener,
+}
+
+enum Listener {
+    Http(Builder<AddrIncoming>),
+    Https(Builder<TlsIncoming>),
+}
+
+impl std::fmt::Debug for Listener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Listener::Http(_) => f.write_str("Http"),
+            Listener::Https(_) => f.write_str("Https"),
+        }
+    }
 }
 
 #[derive(TraceRawVcs)]
@@ -77,6 +96,33 @@ pub struct DevServer {
 
 impl DevServer {
     pub fn listen(addr: SocketAddr) -> Result<DevServerBuilder, anyhow::Error> {
+        let (addr, listener) = Self::bind(addr)?;
+        let server = Server::from_tcp(listener).context("Not able to start server")?;
+        Ok(DevServerBuilder {
+            addr,
+            server: Listener::Http(server),
+        })
+    }
+
+    /// Like [DevServer::listen], but serves HTTPS (and HTTP/2) using `tls`.
+    pub fn listen_https(
+        addr: SocketAddr,
+        tls: TlsConfig,
+    ) -> Result<DevServerBuilder, anyhow::Error> {
+        let (addr, listener) = Self::bind(addr)?;
+        listener
+            .set_nonblocking(true)
+            .context("Not able to start server")?;
+        let listener =
+            tokio::net::TcpListener::from_std(listener).context("Not able to start server")?;
+        let incoming = AddrIncoming::from_listener(listener).context("Not able to start server")?;
+        Ok(DevServerBuilder {
+            addr,
+            server: Listener::Https(Server::builder(TlsIncoming::new(incoming, tls))),
+        })
+    }
+
+    fn bind(addr: SocketAddr) -> Result<(SocketAddr, TcpListener), anyhow::Error> {
         // This is annoying. The hyper::Server doesn't allow us to know which port was
         // bound (until we build it with a request handler) when using the standard
         // `server::try_bind` approach. This is important when binding the `0` port,
@@ -105,8 +151,7 @@ impl DevServer {
         let addr = listener
             .local_addr()
             .context("not able to get bound address")?;
-        let server = Server::from_tcp(listener).context("Not able to start server")?;
-        Ok(DevServerBuilder { addr, server })
+        Ok((addr, listener))
     }
 }
 
@@ -120,171 +165,186 @@ impl DevServerBuilder {
         let ongoing_side_effects = Arc::new(Mutex::new(VecDeque::<
             Arc<tokio::sync::Mutex<Option<JoinHandle<Result<()>>>>>,
         >::with_capacity(16)));
-        let make_svc = make_service_fn(move |_| {
+        let new_service = move || {
             let tt = turbo_tasks.clone();
             let source_provider = source_provider.clone();
             let get_issue_reporter = get_issue_reporter.clone();
             let ongoing_side_effects = ongoing_side_effects.clone();
-            async move {
-                let handler = move |request: Request<hyper::Body>| {
-                    let request_span = info_span!(parent: None, "request", name = ?request.uri());
-                    let start = Instant::now();
-                    let tt = tt.clone();
-                    let get_issue_reporter = get_issue_reporter.clone();
-                    let ongoing_side_effects = ongoing_side_effects.clone();
-                    let source_provider = source_provider.clone();
-                    let future = async move {
-                        event!(parent: Span::current(), Level::DEBUG, "request start");
-                        // Wait until all ongoing side effects are completed
-                        // We only need to wait for the ongoing side effects that were started
-                        // before this request. Later added side effects are not relevant for this.
-                        let current_ongoing_side_effects = {
-                            // Cleanup the ongoing_side_effects list
-                            let mut guard = ongoing_side_effects.lock();
-                            while let Some(front) = guard.front() {
-                                let Ok(front_guard) = front.try_lock() else {
-                                    break;
-                                };
-                                if front_guard.is_some() {
-                                    break;
-                                }
-                                drop(front_guard);
-                                guard.pop_front();
-                            }
-                            // Get a clone of the remaining list
-                            (*guard).clone()
-                        };
-                        // Wait for the side effects to complete
-                        for side_effect_mutex in current_ongoing_side_effects {
-                            let mut guard = side_effect_mutex.lock().await;
-                            if let Some(join_handle) = guard.take() {
-                                join_handle.await??;
+            let handler = move |request: Request<hyper::Body>| {
+                let request_span = info_span!(parent: None, "request", name = ?request.uri());
+                let start = Instant::now();
+                let tt = tt.clone();
+                let get_issue_reporter = get_issue_reporter.clone();
+                let ongoing_side_effects = ongoing_side_effects.clone();
+                let source_provider = source_provider.clone();
+                let future = async move {
+                    event!(parent: Span::current(), Level::DEBUG, "request start");
+                    // Wait until all ongoing side effects are completed
+                    // We only need to wait for the ongoing side effects that were started
+                    // before this request. Later added side effects are not relevant for this.
+                    let current_ongoing_side_effects = {
+                        // Cleanup the ongoing_side_effects list
+                        let mut guard = ongoing_side_effects.lock();
+                        while let Some(front) = guard.front() {
+                            let Ok(front_guard) = front.try_lock() else {
+                                break;
+                            };
+                            if front_guard.is_some() {
+                                break;
                             }
-                            drop(guard);
+                            drop(front_guard);
+                            guard.pop_front();
                         }
-                        let reason = ServerRequest {
-                            method: request.method().clone(),
-                            uri: request.uri().clone(),
-                        };
-                        let side_effects_reason = ServerRequestSideEffects {
-                            method: request.method().clone(),
-                            uri: request.uri().clone(),
-                        };
-                        run_once_with_reason(tt.clone(), reason, async move {
-                            let issue_reporter = get_issue_reporter();
-
-                            if hyper_tungstenite::is_upgrade_request(&request) {
-                                let uri = request.uri();
-                                let path = uri.path();
-
-                                if path == "/turbopack-hmr" {
-                                    let (response, websocket) =
-                                        hyper_tungstenite::upgrade(request, None)?;
-                                    let update_server =
-                                        UpdateServer::new(source_provider, issue_reporter);
-                                    update_server.run(&*tt, websocket);
-                                    return Ok(response);
-                                }
+                        // Get a clone of the remaining list
+                        (*guard).clone()
+                    };
+                    // Wait for the side effects to complete
+                    for side_effect_mutex in current_ongoing_side_effects {
+                        let mut guard = side_effect_mutex.lock().await;
+                        if let Some(join_handle) = guard.take() {
+                            join_handle.await??;
+                        }
+                        drop(guard);
+                    }
+                    let reason = ServerRequest {
+                        method: request.method().clone(),
+                        uri: request.uri().clone(),
+                    };
+                    let side_effects_reason = ServerRequestSideEffects {
+                        method: request.method().clone(),
+                        uri: request.uri().clone(),
+                    };
+                    run_once_with_reason(tt.clone(), reason, async move {
+                        let issue_reporter = get_issue_reporter();
 
-                                println!("[404] {} (WebSocket)", path);
-                                if path == "/_next/webpack-hmr" {
-                                    // Special-case requests to webpack-hmr as these are made by
-                                    // Next.js clients built
-                                    // without turbopack, which may be making requests in
-                                    // development.
-                                    println!(
-                                        "A non-turbopack next.js client is trying to connect."
-                                    );
-                                    println!(
-                                        "Make sure to reload/close any browser window which has \
-                                         been opened without --turbo."
-                                    );
-                                }
+                        if hyper_tungstenite::is_upgrade_request(&request) {
+                            let uri = request.uri();
+                            let path = uri.path();
 
-                                return Ok(Response::builder()
-                                    .status(404)
-                                    .body(hyper::Body::empty())?);
+                            if path == "/turbopack-hmr" {
+                                let (response, websocket) =
+                                    hyper_tungstenite::upgrade(request, None)?;
+                                let update_server =
+                                    UpdateServer::new(source_provider, issue_reporter);
+                                update_server.run(&*tt, websocket);
+                                return Ok(response);
                             }
 
-                            let uri = request.uri();
-                            let path = uri.path().to_string();
-                            let source = source_provider.get_source();
-                            let resolved_source = source.resolve_strongly_consistent().await?;
-                            handle_issues(
-                                source,
-                                issue_reporter,
-                                IssueSeverity::Fatal.cell(),
-                                Some(&path),
-                                Some("get source"),
-                            )
-                            .await?;
-                            let (response, side_effects) =
-                                http::process_request_with_content_source(
-                                    resolved_source,
-                                    request,
-                                    issue_reporter,
-                                )
-                                .await?;
-                            let status = response.status().as_u16();
-                            let is_error = response.status().is_client_error()
-                                || response.status().is_server_error();
-                            let elapsed = start.elapsed();
-                            if is_error
-                                || (cfg!(feature = "log_request_stats")
-                                    && elapsed > Duration::from_secs(1))
-                            {
-                                println!(
-                                    "[{status}] {path} ({duration})",
-                                    duration = FormatDuration(elapsed)
-                                );
-                            }
-                            if !side_effects.is_empty() {
-                                let join_handle = tokio::spawn(run_once_with_reason(
-                                    tt.clone(),
-                                    side_effects_reason,
-                                    async move {
-                                        for side_effect in side_effects {
-                                            side_effect.apply().await?;
-                                        }
-                                        Ok(())
-                                    },
-                                ));
-                                ongoing_side_effects.lock().push_back(Arc::new(
-                                    tokio::sync::Mutex::new(Some(join_handle)),
-                                ));
-                            }
-                            Ok(response)
-                        })
-                        .await
-                    };
-                    async move {
-                        match future.await {
-                            Ok(r) => Ok::<_, hyper::http::Error>(r),
-                            Err(e) => {
+                            println!("[404] {} (WebSocket)", path);
+                            if path == "/_next/webpack-hmr" {
+                                // Special-case requests to webpack-hmr as these are made by
+                                // Next.js clients built
+                                // without turbopack, which may be making requests in
+                                // development.
+                                println!("A non-turbopack next.js client is trying to connect.");
                                 println!(
-                                    "[500] error ({}): {}",
-                                    FormatDuration(start.elapsed()),
-                                    PrettyPrintError(&e),
+                                    "Make sure to reload/close any browser window which has been \
+                                     opened without --turbo."
                                 );
-                                Ok(Response::builder()
-                                    .status(500)
-                                    .body(hyper::Body::from(format!("{}", PrettyPrintError(&e))))?)
                             }
+
+                            return Ok(Response::builder()
+                                .status(404)
+                                .body(hyper::Body::empty())?);
                         }
-                    }
-                    .instrument(request_span)
+
+                        let uri = request.uri();
+                        let path = uri.path().to_string();
+                        let source = source_provider.get_source();
+                        let resolved_source = source.resolve_strongly_consistent().await?;
+                        handle_issues(
+                            source,
+                            issue_reporter,
+                            IssueSeverity::Fatal.cell(),
+                            Some(&path),
+                            Some("get source"),
+                        )
+                        .await?;
+                        let (response, side_effects) = http::process_request_with_content_source(
+                            resolved_source,
+                            request,
+                            issue_reporter,
+                        )
+                        .await?;
+                        let status = response.status().as_u16();
+                        let is_error = response.status().is_client_error()
+                            || response.status().is_server_error();
+                        let elapsed = start.elapsed();
+                        if is_error
+                            || (cfg!(feature = "log_request_stats")
+                                && elapsed > Duration::from_secs(1))
+                        {
+                            println!(
+                                "[{status}] {path} ({duration})",
+                                duration = FormatDuration(elapsed)
+                            );
+                        }
+                        if !side_effects.is_empty() {
+                            let join_handle = tokio::spawn(run_once_with_reason(
+                                tt.clone(),
+                                side_effects_reason,
+                                async move {
+                                    for side_effect in side_effects {
+                                        side_effect.apply().await?;
+                                    }
+                                    Ok(())
+                                },
+                            ));
+                            ongoing_side_effects
+                                .lock()
+                                .push_back(Arc::new(tokio::sync::Mutex::new(Some(join_handle))));
+                        }
+                        Ok(response)
+                    })
+                    .await
                 };
-                anyhow::Ok(service_fn(handler))
+                async move {
+                    match future.await {
+                        Ok(r) => Ok::<_, hyper::http::Error>(r),
+                        Err(e) => {
+                            println!(
+                                "[500] error ({}): {}",
+                                FormatDuration(start.elapsed()),
+                                PrettyPrintError(&e),
+                            );
+                            Ok(Response::builder()
+                                .status(500)
+                                .body(hyper::Body::from(format!("{}", PrettyPrintError(&e))))?)
+                        }
+                    }
+                }
+                .instrument(request_span)
+            };
+            service_fn(handler)
+        };
+        // The connection type differs between HTTP and HTTPS, so each needs its
+        // own `make_service_fn`.
+        let future: Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>> = match self.server {
+            Listener::Http(server) => {
+                let server = server.serve(make_service_fn(move |_| {
+                    let service = new_service();
+                    async move { anyhow::Ok(service) }
+                }));
+                Box::pin(async move {
+                    server.await?;
+                    Ok(())
+                })
+            }
+            Listener::Https(server) => {
+                let server = server.serve(make_service_fn(move |_| {
+                    let service = new_service();
+                    async move { anyhow::Ok(service) }
+                }));
+                Box::pin(async move {
+                    server.await?;
+                    Ok(())
+                })
             }
-        });
-        let server = self.server.serve(make_svc);
+        };
 
         DevServer {
             addr: self.addr,
-            future: Box::pin(async move {
-                server.await?;
-                Ok(())
-            }),
+            future,
         }
     }
 }
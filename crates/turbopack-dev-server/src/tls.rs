@@ -0,0 +1,417 @@
+//! HTTPS support for the dev server.
+//!
+//! Certificates are issued by a local certificate authority which is stored in
+//! the same place and format as [mkcert](https://github.com/FiloSottile/mkcert)'s,
+//! so a CA that was already installed with `mkcert -install` is picked up and
+//! the dev server is trusted without any further setup. When there is no CA
+//! yet, one is created there, but it's never added to the system trust store
+//! implicitly: that's left to `mkcert -install`.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{Context as TaskContext, Poll},
+    thread,
+    time::{Duration as StdDuration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use futures::{stream::FuturesUnordered, StreamExt};
+use hyper::server::{
+    accept::Accept,
+    conn::{AddrIncoming, AddrStream},
+};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyPair, KeyUsagePurpose, SanType,
+};
+use time::{Duration, OffsetDateTime};
+use tokio_rustls::{
+    rustls::{self, ServerConfig},
+    server::TlsStream,
+    TlsAcceptor,
+};
+use tracing::debug;
+
+const ROOT_CERT_FILE: &str = "rootCA.pem";
+const ROOT_KEY_FILE: &str = "rootCA-key.pem";
+
+/// How long the certificates issued for the dev server are valid for. They
+/// are issued again every time the server starts, so this only needs to cover
+/// a single (long) session.
+const CERTIFICATE_VALIDITY: Duration = Duration::days(30);
+
+/// How long to wait for another process that is creating the CA at the same
+/// time to finish.
+const CREATE_TIMEOUT: StdDuration = StdDuration::from_secs(5);
+
+/// A certificate authority for issuing certificates for the dev server.
+pub struct LocalCertificateAuthority {
+    root: PathBuf,
+    certificate: Certificate,
+    created: bool,
+}
+
+impl LocalCertificateAuthority {
+    /// Loads the CA from mkcert's CAROOT, creating it if it doesn't exist yet.
+    pub fn load_or_create() -> Result<Self> {
+        let root = ca_root().context("unable to find a directory for the local CA")?;
+        Self::load_or_create_at(root)
+    }
+
+    fn load_or_create_at(root: PathBuf) -> Result<Self> {
+        let cert_path = root.join(ROOT_CERT_FILE);
+        if !cert_path.exists() {
+            if let Some(ca) = Self::create_at(&root)? {
+                return Ok(ca);
+            }
+            // another process is creating the CA, so wait for it to finish
+            let start = Instant::now();
+            while !cert_path.exists() {
+                if start.elapsed() > CREATE_TIMEOUT {
+                    bail!(
+                        "found {} without {}, remove it to create a new CA",
+                        root.join(ROOT_KEY_FILE).display(),
+                        cert_path.display()
+                    );
+                }
+                thread::sleep(StdDuration::from_millis(50));
+            }
+        }
+        Self::load_at(root)
+    }
+
+    fn load_at(root: PathBuf) -> Result<Self> {
+        let cert_path = root.join(ROOT_CERT_FILE);
+        let key_path = root.join(ROOT_KEY_FILE);
+
+        let cert = fs::read_to_string(&cert_path)
+            .with_context(|| format!("unable to read {}", cert_path.display()))?;
+        let key = fs::read_to_string(&key_path)
+            .with_context(|| format!("unable to read {}", key_path.display()))?;
+        let key_pair = KeyPair::from_pem(&key)
+            .with_context(|| format!("no supported CA key in {}", key_path.display()))?;
+        let params = CertificateParams::from_ca_cert_pem(&cert, key_pair)
+            .with_context(|| format!("invalid CA certificate in {}", cert_path.display()))?;
+
+        Ok(Self {
+            root,
+            certificate: Certificate::from_params(params)?,
+            created: false,
+        })
+    }
+
+    /// Creates a new CA in `root`. Returns `None` if another process beat us
+    /// to it.
+    fn create_at(root: &Path) -> Result<Option<Self>> {
+        let mut params = CertificateParams::default();
+        params
+            .distinguished_name
+            .push(DnType::OrganizationName, "turbopack development CA");
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "turbopack development CA");
+        params.is_ca = IsCa::Ca(BasicConstraints::Constrained(0));
+        params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+        let now = OffsetDateTime::now_utc();
+        params.not_before = now - Duration::days(1);
+        params.not_after = now + Duration::days(10 * 365);
+        let certificate = Certificate::from_params(params)?;
+
+        fs::create_dir_all(root).with_context(|| format!("unable to create {}", root.display()))?;
+        let cert_path = root.join(ROOT_CERT_FILE);
+        let key_path = root.join(ROOT_KEY_FILE);
+
+        // The key is linked into place first, which fails rather than replacing
+        // a key written by another process. The certificate is moved into place
+        // last, so finding it means the CA is complete.
+        let key_temp = write_temporary(&key_path, &certificate.serialize_private_key_pem())
+            .with_context(|| format!("unable to write {}", key_path.display()))?;
+        let linked = fs::hard_link(&key_temp, &key_path);
+        let _ = fs::remove_file(&key_temp);
+        match linked {
+            Ok(()) => (),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Ok(None),
+            Err(e) => {
+                return Err(e).with_context(|| format!("unable to write {}", key_path.display()))
+            }
+        }
+        write_temporary(&cert_path, &certificate.serialize_pem()?)
+            .and_then(|cert_temp| fs::rename(cert_temp, &cert_path))
+            .with_context(|| format!("unable to write {}", cert_path.display()))?;
+
+        Ok(Some(Self {
+            root: root.to_path_buf(),
+            certificate,
+            created: true,
+        }))
+    }
+
+    /// The directory the CA is stored in.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether the CA was created by [Self::load_or_create], in which case it
+    /// isn't trusted by anything yet.
+    pub fn created(&self) -> bool {
+        self.created
+    }
+
+    /// Issues a certificate for `localhost` and `host`, and returns the TLS
+    /// configuration to serve it with.
+    pub fn tls_config(&self, host: IpAddr) -> Result<TlsConfig> {
+        let mut subject_alt_names = vec![
+            SanType::DnsName("localhost".to_string()),
+            SanType::IpAddress(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            SanType::IpAddress(IpAddr::V6(Ipv6Addr::LOCALHOST)),
+        ];
+        if !host.is_unspecified() && !host.is_loopback() {
+            subject_alt_names.push(SanType::IpAddress(host));
+        }
+
+        let mut params = CertificateParams::default();
+        params.distinguished_name.push(
+            DnType::OrganizationName,
+            "turbopack development certificate",
+        );
+        params.subject_alt_names = subject_alt_names;
+        params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        let now = OffsetDateTime::now_utc();
+        params.not_before = now - Duration::days(1);
+        params.not_after = now + CERTIFICATE_VALIDITY;
+        let certificate = Certificate::from_params(params)?;
+
+        let mut config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![rustls::Certificate(
+                    certificate.serialize_der_with_signer(&self.certificate)?,
+                )],
+                rustls::PrivateKey(certificate.serialize_private_key_der()),
+            )
+            .context("invalid dev server certificate")?;
+        // hyper detects which protocol a connection uses on its own, so both
+        // can be offered.
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        Ok(TlsConfig(Arc::new(config)))
+    }
+}
+
+/// The TLS configuration of a dev server.
+#[derive(Clone)]
+pub struct TlsConfig(Arc<ServerConfig>);
+
+/// Accepts TLS connections. Handshakes are driven concurrently, so a client
+/// that stalls during its handshake doesn't hold up any other connection.
+pub(crate) struct TlsIncoming {
+    incoming: AddrIncoming,
+    acceptor: TlsAcceptor,
+    handshakes: FuturesUnordered<tokio_rustls::Accept<AddrStream>>,
+}
+
+impl TlsIncoming {
+    pub(crate) fn new(incoming: AddrIncoming, config: TlsConfig) -> Self {
+        Self {
+            incoming,
+            acceptor: TlsAcceptor::from(config.0),
+            handshakes: FuturesUnordered::new(),
+        }
+    }
+}
+
+impl Accept for TlsIncoming {
+    type Conn = TlsStream<AddrStream>;
+    type Error = io::Error;
+
+    fn poll_accept(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        let this = &mut *self;
+        while let Poll::Ready(stream) = Pin::new(&mut this.incoming).poll_accept(cx) {
+            match stream {
+                Some(Ok(stream)) => this.handshakes.push(this.acceptor.accept(stream)),
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => return Poll::Ready(None),
+            }
+        }
+        while let Poll::Ready(Some(stream)) = this.handshakes.poll_next_unpin(cx) {
+            match stream {
+                Ok(stream) => return Poll::Ready(Some(Ok(stream))),
+                // e.g. a browser that doesn't trust the certificate (yet)
+                Err(e) => debug!("TLS handshake failed: {}", e),
+            }
+        }
+        Poll::Pending
+    }
+}
+
+/// mkcert's CAROOT: `$CAROOT` if it's set, otherwise `mkcert` in the user's
+/// local data directory.
+fn ca_root() -> Option<PathBuf> {
+    if let Some(root) = std::env::var_os("CAROOT") {
+        return Some(PathBuf::from(root));
+    }
+    dirs_next::data_local_dir().map(|dir| dir.join("mkcert"))
+}
+
+/// Writes `contents` to a file next to `path` that only the current user can
+/// read, and returns its path.
+fn write_temporary(path: &Path, contents: &str) -> io::Result<PathBuf> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.{}", file_name, std::process::id()));
+    let mut opts = OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(0o600);
+    }
+    opts.open(&temp)?.write_all(contents.as_bytes())?;
+    Ok(temp)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs::File,
+        future::poll_fn,
+        io::{self, BufReader},
+        net::{IpAddr, Ipv4Addr, SocketAddr},
+        path::Path,
+        pin::Pin,
+        sync::Arc,
+    };
+
+    use hyper::server::{accept::Accept, conn::AddrIncoming};
+    use tempfile::TempDir;
+    use tokio::net::TcpStream;
+    use tokio_rustls::{
+        rustls::{self, ClientConfig, RootCertStore, ServerName},
+        TlsConnector,
+    };
+
+    use super::{LocalCertificateAuthority, TlsIncoming, ROOT_CERT_FILE, ROOT_KEY_FILE};
+
+    fn listen(ca: &LocalCertificateAuthority) -> (SocketAddr, TlsIncoming) {
+        let tls = ca.tls_config(IpAddr::V4(Ipv4Addr::UNSPECIFIED)).unwrap();
+        let incoming = AddrIncoming::bind(&SocketAddr::from((Ipv4Addr::LOCALHOST, 0))).unwrap();
+        (incoming.local_addr(), TlsIncoming::new(incoming, tls))
+    }
+
+    /// The DER encoded certificate of `ca`, as stored in its CAROOT.
+    fn root_certificate(ca: &LocalCertificateAuthority) -> Vec<u8> {
+        let file = File::open(ca.root().join(ROOT_CERT_FILE)).unwrap();
+        rustls_pemfile::certs(&mut BufReader::new(file))
+            .unwrap()
+            .remove(0)
+    }
+
+    /// Connects to `addr` as `server_name`, trusting only `ca`, and returns
+    /// the negotiated ALPN protocol.
+    async fn connect(
+        addr: SocketAddr,
+        ca: Vec<u8>,
+        server_name: &'static str,
+    ) -> io::Result<Option<Vec<u8>>> {
+        let mut roots = RootCertStore::empty();
+        roots.add(&rustls::Certificate(ca)).unwrap();
+        let mut config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        let stream = TcpStream::connect(addr).await?;
+        let stream = TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from(server_name).unwrap(), stream)
+            .await?;
+        Ok(stream.get_ref().1.alpn_protocol().map(|p| p.to_vec()))
+    }
+
+    async fn assert_handshake(ca: &LocalCertificateAuthority) {
+        let (addr, mut incoming) = listen(ca);
+        let client = tokio::spawn(connect(addr, root_certificate(ca), "localhost"));
+
+        let stream = poll_fn(|cx| Pin::new(&mut incoming).poll_accept(cx))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"h2"[..]));
+        assert_eq!(client.await.unwrap().unwrap(), Some(b"h2".to_vec()));
+    }
+
+    #[test]
+    fn test_reload_created_ca() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("mkcert");
+
+        let ca = LocalCertificateAuthority::load_or_create_at(root.clone()).unwrap();
+        assert!(ca.created());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = root
+                .join(ROOT_KEY_FILE)
+                .metadata()
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let reloaded = LocalCertificateAuthority::load_or_create_at(root).unwrap();
+        assert!(!reloaded.created());
+        assert_eq!(
+            reloaded.certificate.get_params().distinguished_name,
+            ca.certificate.get_params().distinguished_name
+        );
+        assert_eq!(
+            reloaded.certificate.serialize_private_key_der(),
+            ca.certificate.serialize_private_key_der()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handshake() {
+        let tmp = TempDir::new().unwrap();
+        let ca = LocalCertificateAuthority::load_or_create_at(tmp.path().to_path_buf()).unwrap();
+        assert_handshake(&ca).await;
+    }
+
+    #[tokio::test]
+    async fn test_handshake_with_mkcert_ca() {
+        // a CA in the format that mkcert creates, with an RSA key
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/mkcert");
+        let tmp = TempDir::new().unwrap();
+        for file in [ROOT_CERT_FILE, ROOT_KEY_FILE] {
+            std::fs::copy(fixture.join(file), tmp.path().join(file)).unwrap();
+        }
+
+        let ca = LocalCertificateAuthority::load_or_create_at(tmp.path().to_path_buf()).unwrap();
+        assert!(!ca.created());
+        assert_handshake(&ca).await;
+    }
+
+    #[tokio::test]
+    async fn test_only_issued_for_localhost() {
+        let tmp = TempDir::new().unwrap();
+        let ca = LocalCertificateAuthority::load_or_create_at(tmp.path().to_path_buf()).unwrap();
+        let (addr, mut incoming) = listen(&ca);
+        // the failed handshake is skipped, so this never yields a connection
+        tokio::spawn(async move { poll_fn(|cx| Pin::new(&mut incoming).poll_accept(cx)).await });
+
+        let err = connect(addr, root_certificate(&ca), "example.com")
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -137,6 +137,9 @@ pub struct EcmascriptOptions {
     /// If false, they will reference the whole directory. If true, they won't
     /// reference anything and lead to an runtime error instead.
     pub ignore_dynamic_requests: bool,
+    /// Emit a warning for every implicit ESM/CommonJS interop decision, e.g. a
+    /// default import of a CommonJS module.
+    pub strict_interop_diagnostics: bool,
 }
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
@@ -0,0 +1,215 @@
+use anyhow::Result;
+use turbo_tasks::{Completion, Value, Vc};
+use turbopack_core::{
+    ident::AssetIdent,
+    issue::{analyze::AnalyzeIssue, IssueExt, IssueSeverity, IssueSource, StyledString},
+    reference::ModuleReference,
+    resolve::ExternalType,
+};
+
+use super::esm::base::ReferencedAsset;
+use crate::chunk::EcmascriptExports;
+
+/// The kind of import that may need implicit ESM/CommonJS interop.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Debug, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum InteropKind {
+    /// `import x from "..."` or `export { default } from "..."`
+    DefaultImport,
+    /// `import * as x from "..."` or `export * as x from "..."`
+    NamespaceImport,
+    /// `require("...")`
+    Require,
+}
+
+/// Reports the interop semantics chosen for a reference when strict interop
+/// diagnostics are enabled. It doesn't change the generated code.
+#[turbo_tasks::value]
+pub struct InteropDiagnostic {
+    reference: Vc<Box<dyn ModuleReference>>,
+    kind: InteropKind,
+    source_ident: Vc<AssetIdent>,
+    issue_source: Option<Vc<IssueSource>>,
+}
+
+#[turbo_tasks::value_impl]
+impl InteropDiagnostic {
+    #[turbo_tasks::function]
+    pub fn new(
+        reference: Vc<Box<dyn ModuleReference>>,
+        kind: Value<InteropKind>,
+        source_ident: Vc<AssetIdent>,
+        issue_source: Option<Vc<IssueSource>>,
+    ) -> Vc<Self> {
+        Self::cell(InteropDiagnostic {
+            reference,
+            kind: kind.into_value(),
+            source_ident,
+            issue_source,
+        })
+    }
+
+    /// Emits a warning when the reference relies on implicit interop.
+    ///
+    /// This reads the exports of the referenced module, which depend on its
+    /// analysis, so the analysis calls this without awaiting it. Otherwise
+    /// modules that import each other would wait on each other's analysis.
+    #[turbo_tasks::function]
+    pub async fn emit(self: Vc<Self>) -> Result<Vc<Completion>> {
+        let this = self.await?;
+        let referenced_asset =
+            ReferencedAsset::from_resolve_result(this.reference.resolve_reference()).await?;
+
+        let target = match &*referenced_asset {
+            ReferencedAsset::Some(module) => match &*module.get_exports().await? {
+                EcmascriptExports::CommonJs => InteropTarget::CommonJs,
+                EcmascriptExports::Value => InteropTarget::Value,
+                _ => InteropTarget::Other,
+            },
+            ReferencedAsset::External(request, ty) => InteropTarget::External(request, *ty),
+            ReferencedAsset::None => InteropTarget::Other,
+        };
+
+        if let Some(message) = interop_message(target, this.kind) {
+            AnalyzeIssue {
+                code: None,
+                message: StyledString::Text(message).cell(),
+                source_ident: this.source_ident,
+                severity: IssueSeverity::Warning.into(),
+                source: this.issue_source,
+                title: Vc::cell("implicit ESM/CommonJS interop".to_string()),
+            }
+            .cell()
+            .emit();
+        }
+
+        Ok(Completion::new())
+    }
+}
+
+/// What a reference resolved to, as far as interop is concerned.
+#[derive(Debug, Clone, Copy)]
+enum InteropTarget<'a> {
+    /// A module with CommonJS exports
+    CommonJs,
+    /// A module that exports a single value, e.g. JSON
+    Value,
+    /// An external with the given request
+    External(&'a str, ExternalType),
+    /// Anything that doesn't need implicit interop, e.g. an ES module
+    Other,
+}
+
+/// Describes the semantics chosen when importing `target` with an import of
+/// the given kind, or `None` if no implicit interop is involved.
+fn interop_message(target: InteropTarget<'_>, kind: InteropKind) -> Option<String> {
+    match (target, kind) {
+        (InteropTarget::CommonJs, InteropKind::DefaultImport) => Some(
+            "default import of a CommonJS module. The default binding is `module.exports`, unless \
+             the module marks itself with `__esModule` and provides an `exports.default`."
+                .to_string(),
+        ),
+        (InteropTarget::Value, InteropKind::DefaultImport) => Some(
+            "default import of a non-module value. The default binding is the value itself."
+                .to_string(),
+        ),
+        (InteropTarget::CommonJs, InteropKind::NamespaceImport) => Some(
+            "namespace import of a CommonJS module. The namespace contains every property of \
+             `module.exports` at the time of import, and `default` is `module.exports` itself."
+                .to_string(),
+        ),
+        (InteropTarget::Value, InteropKind::NamespaceImport) => Some(
+            "namespace import of a non-module value. The namespace only contains `default`, which \
+             is the value itself."
+                .to_string(),
+        ),
+        (InteropTarget::External(request, ExternalType::CommonJs), InteropKind::DefaultImport) => {
+            Some(format!(
+                "default import of the CommonJS external \"{request}\". The default binding is \
+                 `module.exports`, unless the module marks itself with `__esModule` and provides \
+                 an `exports.default`."
+            ))
+        }
+        (
+            InteropTarget::External(request, ExternalType::CommonJs),
+            InteropKind::NamespaceImport,
+        ) => Some(format!(
+            "namespace import of the CommonJS external \"{request}\". The namespace contains \
+             every property of `module.exports`, and `default` is `module.exports` itself."
+        )),
+        (
+            InteropTarget::External(request, ExternalType::EcmaScriptModule),
+            InteropKind::Require,
+        ) => Some(format!(
+            "require() of the ESM external \"{request}\". The result is the module namespace \
+             object, so the default export is only available as `.default`."
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use turbopack_core::resolve::ExternalType;
+
+    use super::{interop_message, InteropKind, InteropTarget};
+
+    #[rstest]
+    #[case::cjs_default(InteropTarget::CommonJs, InteropKind::DefaultImport, true)]
+    #[case::cjs_namespace(InteropTarget::CommonJs, InteropKind::NamespaceImport, true)]
+    #[case::cjs_require(InteropTarget::CommonJs, InteropKind::Require, false)]
+    #[case::value_default(InteropTarget::Value, InteropKind::DefaultImport, true)]
+    #[case::value_namespace(InteropTarget::Value, InteropKind::NamespaceImport, true)]
+    #[case::value_require(InteropTarget::Value, InteropKind::Require, false)]
+    #[case::esm_default(InteropTarget::Other, InteropKind::DefaultImport, false)]
+    #[case::esm_namespace(InteropTarget::Other, InteropKind::NamespaceImport, false)]
+    #[case::esm_require(InteropTarget::Other, InteropKind::Require, false)]
+    #[case::cjs_external_default(
+        InteropTarget::External("pkg", ExternalType::CommonJs),
+        InteropKind::DefaultImport,
+        true
+    )]
+    #[case::cjs_external_namespace(
+        InteropTarget::External("pkg", ExternalType::CommonJs),
+        InteropKind::NamespaceImport,
+        true
+    )]
+    #[case::cjs_external_require(
+        InteropTarget::External("pkg", ExternalType::CommonJs),
+        InteropKind::Require,
+        false
+    )]
+    #[case::esm_external_default(
+        InteropTarget::External("pkg", ExternalType::EcmaScriptModule),
+        InteropKind::DefaultImport,
+        false
+    )]
+    #[case::esm_external_require(
+        InteropTarget::External("pkg", ExternalType::EcmaScriptModule),
+        InteropKind::Require,
+        true
+    )]
+    #[case::url_external_default(
+        InteropTarget::External("pkg", ExternalType::Url),
+        InteropKind::DefaultImport,
+        false
+    )]
+    fn reports_implicit_interop(
+        #[case] target: InteropTarget<'static>,
+        #[case] kind: InteropKind,
+        #[case] reported: bool,
+    ) {
+        assert_eq!(interop_message(target, kind).is_some(), reported);
+    }
+
+    #[test]
+    fn names_the_external_request() {
+        let message = interop_message(
+            InteropTarget::External("some-pkg", ExternalType::EcmaScriptModule),
+            InteropKind::Require,
+        )
+        .unwrap();
+        assert!(message.contains("\"some-pkg\""), "{message}");
+    }
+}
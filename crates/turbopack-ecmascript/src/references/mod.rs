@@ -6,10 +6,12 @@ pub mod constant_value;
 pub mod dynamic_expression;
 pub mod esm;
 pub mod external_module;
+pub mod interop;
 pub mod node;
 pub mod pattern_mapping;
 pub mod raw;
 pub mod require_context;
+pub mod runtime_shim;
 pub mod type_issue;
 pub mod typescript;
 pub mod unreachable;
@@ -28,6 +30,7 @@ use anyhow::Result;
 use constant_condition::{ConstantCondition, ConstantConditionValue};
 use constant_value::ConstantValue;
 use indexmap::IndexSet;
+use interop::{InteropDiagnostic, InteropKind};
 use lazy_static::lazy_static;
 use num_traits::Zero;
 use parking_lot::Mutex;
@@ -129,6 +132,7 @@ use crate::{
         esm::{module_id::EsmModuleIdAssetReference, EsmBinding, UrlRewriteBehavior},
         node::PackageJsonReference,
         require_context::{RequireContextAssetReference, RequireContextMap},
+        runtime_shim::RuntimeShimIssue,
         type_issue::SpecifiedModuleTypeIssue,
     },
     tree_shake::{part_of_module, split},
@@ -341,6 +345,7 @@ struct AnalysisState<'a> {
     tree_shaking_mode: Option<TreeShakingMode>,
     import_externals: bool,
     ignore_dynamic_requests: bool,
+    strict_interop_diagnostics: bool,
 }
 
 impl<'a> AnalysisState<'a> {
@@ -582,6 +587,23 @@ pub(crate) async fn analyse_ecmascript_module_internal(
     for i in evaluation_references {
         analysis.add_evaluation_reference(import_references[i]);
     }
+    if options.strict_interop_diagnostics {
+        for (i, r) in eval_context.imports.references().enumerate() {
+            let kind = match &r.imported_symbol {
+                ImportedSymbol::Symbol(name) if &**name == "default" => InteropKind::DefaultImport,
+                ImportedSymbol::Namespace => InteropKind::NamespaceImport,
+                _ => continue,
+            };
+            // Not awaited, see `InteropDiagnostic::emit`
+            let _ = InteropDiagnostic::new(
+                Vc::upcast(import_references[i]),
+                Value::new(kind),
+                source.ident(),
+                r.issue_source,
+            )
+            .emit();
+        }
+    }
 
     let (webpack_runtime, webpack_entry, webpack_chunks, esm_exports, esm_star_exports) =
         set_handler_and_globals(&handler, globals, || {
@@ -786,6 +808,7 @@ pub(crate) async fn analyse_ecmascript_module_internal(
         tree_shaking_mode: options.tree_shaking_mode,
         import_externals: options.import_externals,
         ignore_dynamic_requests: options.ignore_dynamic_requests,
+        strict_interop_diagnostics: options.strict_interop_diagnostics,
     };
 
     enum Action {
@@ -1247,13 +1270,24 @@ async fn handle_call<G: Fn(Vec<Effect>) + Send + Sync>(
                         return Ok(());
                     }
                 }
-                analysis.add_reference(CjsRequireAssetReference::new(
+                let reference = CjsRequireAssetReference::new(
                     origin,
                     Request::parse(Value::new(pat)),
                     Vc::cell(ast_path.to_vec()),
                     issue_source(source, span),
                     in_try,
-                ));
+                );
+                analysis.add_reference(reference);
+                if state.strict_interop_diagnostics {
+                    // Not awaited, see `InteropDiagnostic::emit`
+                    let _ = InteropDiagnostic::new(
+                        Vc::upcast(reference),
+                        Value::new(InteropKind::Require),
+                        source.ident(),
+                        Some(issue_source(source, span)),
+                    )
+                    .emit();
+                }
                 return Ok(());
             }
             let (args, hints) = explain_args(&args);
@@ -1832,7 +1866,8 @@ async fn handle_member(
                     continue;
                 }
                 if obj.iter_defineable_name_rev().eq(it)
-                    && handle_free_var_reference(ast_path, value, span, state, analysis).await?
+                    && handle_free_var_reference(ast_path, name, value, span, state, analysis)
+                        .await?
                 {
                     return Ok(());
                 }
@@ -1874,7 +1909,7 @@ async fn handle_free_var(
             if var
                 .iter_defineable_name_rev()
                 .eq(name.iter().map(Cow::Borrowed).rev())
-                && handle_free_var_reference(ast_path, value, span, state, analysis).await?
+                && handle_free_var_reference(ast_path, name, value, span, state, analysis).await?
             {
                 return Ok(());
             }
@@ -1886,6 +1921,7 @@ async fn handle_free_var(
 
 async fn handle_free_var_reference(
     ast_path: &[AstParentKind],
+    name: &[String],
     value: &FreeVarReference,
     span: Span,
     state: &AnalysisState<'_>,
@@ -1924,6 +1960,20 @@ async fn handle_free_var_reference(
             lookup_path,
             export,
         } => {
+            let runtime_shim_policy = state.compile_time_info.await?.runtime_shim_policy.await?;
+            if !runtime_shim_policy.is_allowed(name) {
+                return Ok(false);
+            }
+            if runtime_shim_policy.report {
+                RuntimeShimIssue {
+                    path: state.source.ident().path(),
+                    name: name.join("."),
+                    request: request.clone(),
+                }
+                .cell()
+                .emit();
+            }
+
             let esm_reference = EsmAssetReference::new(
                 lookup_path.map_or(state.origin, |lookup_path| {
                     Vc::upcast(PlainResolveOrigin::new(
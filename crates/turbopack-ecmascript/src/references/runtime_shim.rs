@@ -0,0 +1,60 @@
+use turbo_tasks::Vc;
+use turbo_tasks_fs::FileSystemPath;
+use turbopack_core::issue::{Issue, IssueSeverity, IssueStage, OptionStyledString, StyledString};
+
+/// Reported for every module that triggered a runtime shim when
+/// [RuntimeShimPolicy::report] is enabled.
+///
+/// [RuntimeShimPolicy::report]: turbopack_core::compile_time_info::RuntimeShimPolicy::report
+#[turbo_tasks::value(shared)]
+pub struct RuntimeShimIssue {
+    pub path: Vc<FileSystemPath>,
+    /// The dotted name of the free variable, e.g. `Buffer` or `process.env`
+    pub name: String,
+    /// The request of the module that is injected instead
+    pub request: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for RuntimeShimIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> Vc<IssueSeverity> {
+        IssueSeverity::Info.into()
+    }
+
+    #[turbo_tasks::function]
+    fn stage(&self) -> Vc<IssueStage> {
+        IssueStage::Analysis.into()
+    }
+
+    #[turbo_tasks::function]
+    fn file_path(&self) -> Vc<FileSystemPath> {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> Vc<StyledString> {
+        StyledString::Line(vec![
+            StyledString::Text("Runtime shim injected for ".to_string()),
+            StyledString::Code(self.name.clone()),
+        ])
+        .cell()
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> Vc<OptionStyledString> {
+        Vc::cell(Some(
+            StyledString::Line(vec![
+                StyledString::Text("References to ".to_string()),
+                StyledString::Code(self.name.clone()),
+                StyledString::Text(" were replaced with an import of ".to_string()),
+                StyledString::Code(self.request.clone()),
+                StyledString::Text(
+                    ". Add it to the deny list of the runtime shim policy to disable this."
+                        .to_string(),
+                ),
+            ])
+            .cell(),
+        ))
+    }
+}
@@ -81,6 +81,7 @@ impl ModuleOptions {
             esm_url_rewrite_behavior,
             import_externals,
             ignore_dynamic_requests,
+            strict_interop_diagnostics,
             use_swc_css,
             ..
         } = *module_options_context.await?;
@@ -120,6 +121,7 @@ impl ModuleOptions {
             url_rewrite_behavior: esm_url_rewrite_behavior,
             import_externals,
             ignore_dynamic_requests,
+            strict_interop_diagnostics,
             ..Default::default()
         };
 
@@ -157,6 +157,9 @@ pub struct ModuleOptionsContext {
     /// If false, they will reference the whole directory. If true, they won't
     /// reference anything and lead to an runtime error instead.
     pub ignore_dynamic_requests: bool,
+    /// Emit a warning for every implicit ESM/CommonJS interop decision, to help
+    /// find ambiguous imports when migrating to ESM.
+    pub strict_interop_diagnostics: bool,
 
     pub use_swc_css: bool,
 
@@ -66,6 +66,7 @@ pub trait CacheClient {
         hash: &str,
         artifact_body: &[u8],
         duration: u64,
+        ttl: Option<u64>,
         tag: Option<&str>,
         token: &str,
         team_id: Option<&str>,
@@ -339,6 +340,7 @@ impl CacheClient for APIClient {
         hash: &str,
         artifact_body: &[u8],
         duration: u64,
+        ttl: Option<u64>,
         tag: Option<&str>,
         token: &str,
         team_id: Option<&str>,
@@ -353,7 +355,8 @@ impl CacheClient for APIClient {
                     token,
                     request_url.clone(),
                     "PUT",
-                    "Authorization, Content-Type, User-Agent, x-artifact-duration, x-artifact-tag",
+                    "Authorization, Content-Type, User-Agent, x-artifact-duration, \
+                     x-artifact-tag, x-artifact-ttl",
                 )
                 .await?;
 
@@ -381,6 +384,10 @@ impl CacheClient for APIClient {
             request_builder = request_builder.header("x-artifact-tag", tag);
         }
 
+        if let Some(ttl) = ttl {
+            request_builder = request_builder.header("x-artifact-ttl", ttl.to_string());
+        }
+
         let response = retry::make_retryable_request(request_builder).await?;
 
         if response.status() == StatusCode::FORBIDDEN {
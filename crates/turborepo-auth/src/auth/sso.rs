@@ -315,6 +315,7 @@ mod tests {
             _hash: &str,
             _artifact_body: &[u8],
             _duration: u64,
+            _ttl: Option<u64>,
             _tag: Option<&str>,
             _token: &str,
             _team_id: Option<&str>,
@@ -1,4 +1,7 @@
-use std::sync::{atomic::AtomicU8, Arc};
+use std::{
+    sync::{atomic::AtomicU8, Arc},
+    time::Duration,
+};
 
 use futures::{stream::FuturesUnordered, StreamExt};
 use tokio::sync::{mpsc, Semaphore};
@@ -22,6 +25,7 @@ enum WorkerRequest {
         anchor: AbsoluteSystemPathBuf,
         key: String,
         duration: u64,
+        ttl: Option<Duration>,
         files: Vec<AnchoredSystemPathBuf>,
     },
     Flush(tokio::sync::oneshot::Sender<()>),
@@ -61,6 +65,7 @@ impl AsyncCache {
                         anchor,
                         key,
                         duration,
+                        ttl,
                         files,
                     } => {
                         let permit = semaphore.clone().acquire_owned().await.unwrap();
@@ -70,7 +75,7 @@ impl AsyncCache {
                         workers.push(tokio::spawn(
                             async move {
                                 if let Err(err) =
-                                    real_cache.put(&anchor, &key, &files, duration).await
+                                    real_cache.put(&anchor, &key, &files, duration, ttl).await
                                 {
                                     let num_warnings =
                                         warnings.load(std::sync::atomic::Ordering::Acquire);
@@ -126,6 +131,7 @@ impl AsyncCache {
         key: String,
         files: Vec<AnchoredSystemPathBuf>,
         duration: u64,
+        ttl: Option<Duration>,
     ) -> Result<(), CacheError> {
         if self
             .writer_sender
@@ -133,6 +139,7 @@ impl AsyncCache {
                 anchor,
                 key,
                 duration,
+                ttl,
                 files,
             })
             .await
@@ -149,13 +156,20 @@ impl AsyncCache {
         self.real_cache.exists(key).await
     }
 
+    /// The total size of the files in the artifact for `key` once restored.
+    /// Only known for artifacts in the local cache.
+    pub fn uncompressed_size(&self, key: &str) -> Option<u64> {
+        self.real_cache.uncompressed_size(key)
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn fetch(
         &self,
         anchor: &AbsoluteSystemPath,
         key: &str,
+        ttl: Option<Duration>,
     ) -> Result<Option<(CacheHitMetadata, Vec<AnchoredSystemPathBuf>)>, CacheError> {
-        self.real_cache.fetch(anchor, key).await
+        self.real_cache.fetch(anchor, key, ttl).await
     }
 
     // Used for testing to ensure that the workers resolve
@@ -180,6 +194,7 @@ impl AsyncCache {
             .await
             .map_err(|_| CacheError::CacheShuttingDown)?;
         rx.await.ok();
+        self.real_cache.persist_remote_misses();
         Ok(())
     }
 }
@@ -233,6 +248,7 @@ mod tests {
                 unused_team_id: Some("my-team".to_string()),
                 signature: false,
             }),
+            remote_miss_ttl: None,
         };
 
         let api_client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?;
@@ -259,6 +275,7 @@ mod tests {
                     .map(|f| f.path().to_owned())
                     .collect(),
                 test_case.duration,
+                None,
             )
             .await
             .unwrap();
@@ -313,6 +330,7 @@ mod tests {
                 unused_team_id: Some("my-team".to_string()),
                 signature: false,
             }),
+            remote_miss_ttl: None,
         };
 
         // Initialize client with invalid API url to ensure that we don't hit the
@@ -341,6 +359,7 @@ mod tests {
                     .map(|f| f.path().to_owned())
                     .collect(),
                 test_case.duration,
+                None,
             )
             .await
             .unwrap();
@@ -403,6 +422,7 @@ mod tests {
                 unused_team_id: Some("my-team".to_string()),
                 signature: false,
             }),
+            remote_miss_ttl: None,
         };
 
         let api_client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?;
@@ -429,6 +449,7 @@ mod tests {
                     .map(|f| f.path().to_owned())
                     .collect(),
                 test_case.duration,
+                None,
             )
             .await
             .unwrap();
@@ -1,18 +1,25 @@
-use std::{backtrace::Backtrace, fs::OpenOptions};
+use std::{
+    backtrace::Backtrace,
+    fs::OpenOptions,
+    time::{Duration, SystemTime},
+};
 
 use camino::Utf8Path;
 use serde::{Deserialize, Serialize};
+use tracing::debug;
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
 use turborepo_analytics::AnalyticsSender;
 use turborepo_api_client::{analytics, analytics::AnalyticsEvent};
 
 use crate::{
     cache_archive::{CacheReader, CacheWriter},
+    layout::{self, CacheLayout, FlatLayout},
     CacheError, CacheHitMetadata, CacheSource,
 };
 
 pub struct FSCache {
     cache_directory: AbsoluteSystemPathBuf,
+    layout: Box<dyn CacheLayout>,
     analytics_recorder: Option<AnalyticsSender>,
 }
 
@@ -20,6 +27,12 @@ pub struct FSCache {
 struct CacheMetadata {
     hash: String,
     duration: u64,
+    /// How long, in seconds, the artifact should be kept around for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ttl: Option<u64>,
+    /// The total size, in bytes, of the files in the artifact once restored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
 }
 
 impl CacheMetadata {
@@ -30,7 +43,7 @@ impl CacheMetadata {
 }
 
 impl FSCache {
-    fn resolve_cache_dir(
+    pub(crate) fn resolve_cache_dir(
         repo_root: &AbsoluteSystemPath,
         override_dir: Option<&Utf8Path>,
     ) -> AbsoluteSystemPathBuf {
@@ -50,8 +63,12 @@ impl FSCache {
         let cache_directory = Self::resolve_cache_dir(repo_root, override_dir);
         cache_directory.create_dir_all()?;
 
+        let layout: Box<dyn CacheLayout> = Box::new(FlatLayout);
+        layout::open(&cache_directory, layout.as_ref())?;
+
         Ok(FSCache {
             cache_directory,
+            layout,
             analytics_recorder,
         })
     }
@@ -78,11 +95,14 @@ impl FSCache {
         hash: &str,
     ) -> Result<Option<(CacheHitMetadata, Vec<AnchoredSystemPathBuf>)>, CacheError> {
         let uncompressed_cache_path = self
-            .cache_directory
-            .join_component(&format!("{}.tar", hash));
-        let compressed_cache_path = self
-            .cache_directory
-            .join_component(&format!("{}.tar.zst", hash));
+            .layout
+            .artifact_path(&self.cache_directory, hash, false);
+        let compressed_cache_path = self.layout.artifact_path(&self.cache_directory, hash, true);
+
+        if self.evict_if_expired(hash)? {
+            self.log_fetch(analytics::CacheEvent::Miss, hash, 0);
+            return Ok(None);
+        }
 
         let cache_path = if uncompressed_cache_path.exists() {
             uncompressed_cache_path
@@ -97,11 +117,7 @@ impl FSCache {
 
         let restored_files = cache_reader.restore(anchor)?;
 
-        let meta = CacheMetadata::read(
-            &self
-                .cache_directory
-                .join_component(&format!("{}-meta.json", hash)),
-        )?;
+        let meta = CacheMetadata::read(&self.layout.metadata_path(&self.cache_directory, hash))?;
 
         self.log_fetch(analytics::CacheEvent::Hit, hash, meta.duration);
 
@@ -117,23 +133,21 @@ impl FSCache {
     #[tracing::instrument(skip_all)]
     pub(crate) fn exists(&self, hash: &str) -> Result<Option<CacheHitMetadata>, CacheError> {
         let uncompressed_cache_path = self
-            .cache_directory
-            .join_component(&format!("{}.tar", hash));
-        let compressed_cache_path = self
-            .cache_directory
-            .join_component(&format!("{}.tar.zst", hash));
+            .layout
+            .artifact_path(&self.cache_directory, hash, false);
+        let compressed_cache_path = self.layout.artifact_path(&self.cache_directory, hash, true);
 
         if !uncompressed_cache_path.exists() && !compressed_cache_path.exists() {
             return Ok(None);
         }
 
-        let duration = CacheMetadata::read(
-            &self
-                .cache_directory
-                .join_component(&format!("{}-meta.json", hash)),
-        )
-        .map(|meta| meta.duration)
-        .unwrap_or(0);
+        if self.evict_if_expired(hash)? {
+            return Ok(None);
+        }
+
+        let duration = CacheMetadata::read(&self.layout.metadata_path(&self.cache_directory, hash))
+            .map(|meta| meta.duration)
+            .unwrap_or(0);
 
         Ok(Some(CacheHitMetadata {
             time_saved: duration,
@@ -141,6 +155,44 @@ impl FSCache {
         }))
     }
 
+    /// The total size of the files in the artifact for `hash` once restored,
+    /// if it's stored locally and was stored with its size.
+    pub(crate) fn uncompressed_size(&self, hash: &str) -> Option<u64> {
+        CacheMetadata::read(&self.layout.metadata_path(&self.cache_directory, hash))
+            .ok()?
+            .size
+    }
+
+    /// Removes the artifact for `hash` if it was stored with a ttl that has
+    /// since passed. Returns whether the artifact was evicted.
+    fn evict_if_expired(&self, hash: &str) -> Result<bool, CacheError> {
+        let metadata_path = self.layout.metadata_path(&self.cache_directory, hash);
+        let Ok(CacheMetadata { ttl: Some(ttl), .. }) = CacheMetadata::read(&metadata_path) else {
+            return Ok(false);
+        };
+        let stored_at = metadata_path.symlink_metadata()?.modified()?;
+        let expired = stored_at
+            .checked_add(Duration::from_secs(ttl))
+            .map_or(false, |expires_at| expires_at <= SystemTime::now());
+        if !expired {
+            return Ok(false);
+        }
+
+        debug!("evicting expired cache artifact {}", hash);
+        for path in [
+            self.layout
+                .artifact_path(&self.cache_directory, hash, false),
+            self.layout.artifact_path(&self.cache_directory, hash, true),
+            metadata_path,
+        ] {
+            if path.exists() {
+                path.remove_file()?;
+            }
+        }
+
+        Ok(true)
+    }
+
     #[tracing::instrument(skip_all)]
     pub fn put(
         &self,
@@ -148,24 +200,29 @@ impl FSCache {
         hash: &str,
         files: &[AnchoredSystemPathBuf],
         duration: u64,
+        ttl: Option<Duration>,
     ) -> Result<(), CacheError> {
-        let cache_path = self
-            .cache_directory
-            .join_component(&format!("{}.tar.zst", hash));
+        let cache_path = self.layout.artifact_path(&self.cache_directory, hash, true);
+        cache_path.ensure_dir()?;
 
         let mut cache_item = CacheWriter::create(&cache_path)?;
 
+        let mut size = 0;
         for file in files {
             cache_item.add_file(anchor, file)?;
+            let metadata = anchor.resolve(file).symlink_metadata()?;
+            if metadata.is_file() {
+                size += metadata.len();
+            }
         }
 
-        let metadata_path = self
-            .cache_directory
-            .join_component(&format!("{}-meta.json", hash));
+        let metadata_path = self.layout.metadata_path(&self.cache_directory, hash);
 
         let meta = CacheMetadata {
             hash: hash.to_string(),
             duration,
+            ttl: ttl.map(|ttl| ttl.as_secs()),
+            size: Some(size),
         };
 
         let mut metadata_options = OpenOptions::new();
@@ -235,7 +292,13 @@ mod test {
             .iter()
             .map(|f| f.path().to_owned())
             .collect();
-        cache.put(repo_root_path, test_case.hash, &files, test_case.duration)?;
+        cache.put(
+            repo_root_path,
+            test_case.hash,
+            &files,
+            test_case.duration,
+            None,
+        )?;
 
         let (status, files) = cache.fetch(repo_root_path, test_case.hash)?.unwrap();
 
@@ -262,4 +325,69 @@ mod test {
         analytics_handle.close_with_timeout().await;
         Ok(())
     }
+
+    #[test]
+    fn test_expired_artifact_is_evicted() -> Result<()> {
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPath::from_std_path(repo_root.path())?;
+        let test_case = &get_test_cases()[0];
+        test_case.initialize(repo_root_path)?;
+        let files: Vec<_> = test_case
+            .files
+            .iter()
+            .map(|f| f.path().to_owned())
+            .collect();
+
+        let cache = FSCache::new(None, repo_root_path, None)?;
+        cache.put(
+            repo_root_path,
+            "kept",
+            &files,
+            test_case.duration,
+            Some(Duration::from_secs(60 * 60)),
+        )?;
+        cache.put(
+            repo_root_path,
+            "expired",
+            &files,
+            test_case.duration,
+            Some(Duration::ZERO),
+        )?;
+
+        assert!(cache.exists("kept")?.is_some());
+        assert!(cache.exists("expired")?.is_none());
+        assert!(!cache
+            .layout
+            .artifact_path(&cache.cache_directory, "expired", true)
+            .exists());
+        assert!(cache.fetch(repo_root_path, "expired")?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_uncompressed_size() -> Result<()> {
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPath::from_std_path(repo_root.path())?;
+        let test_case = &get_test_cases()[0];
+        test_case.initialize(repo_root_path)?;
+        let files: Vec<_> = test_case
+            .files
+            .iter()
+            .map(|f| f.path().to_owned())
+            .collect();
+        let expected = files
+            .iter()
+            .map(|file| repo_root_path.resolve(file).symlink_metadata())
+            .filter(|metadata| metadata.as_ref().map_or(true, |m| m.is_file()))
+            .map(|metadata| metadata.map(|m| m.len()))
+            .sum::<Result<u64, _>>()?;
+
+        let cache = FSCache::new(None, repo_root_path, None)?;
+        assert_eq!(cache.uncompressed_size("hash"), None);
+        cache.put(repo_root_path, "hash", &files, test_case.duration, None)?;
+        assert_eq!(cache.uncompressed_size("hash"), Some(expected));
+
+        Ok(())
+    }
 }
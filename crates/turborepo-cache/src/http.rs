@@ -1,4 +1,4 @@
-use std::{backtrace::Backtrace, io::Write};
+use std::{backtrace::Backtrace, io::Write, time::Duration};
 
 use tracing::debug;
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
@@ -65,6 +65,7 @@ impl HTTPCache {
         hash: &str,
         files: &[AnchoredSystemPathBuf],
         duration: u64,
+        ttl: Option<Duration>,
     ) -> Result<(), CacheError> {
         let mut artifact_body = Vec::new();
         self.write(&mut artifact_body, anchor, files).await?;
@@ -80,6 +81,7 @@ impl HTTPCache {
                 hash,
                 &artifact_body,
                 duration,
+                ttl.map(|ttl| ttl.as_secs()),
                 tag.as_deref(),
                 &self.api_auth.token,
                 self.api_auth.team_id.as_deref(),
@@ -300,7 +302,7 @@ mod test {
 
         let anchored_files: Vec<_> = files.iter().map(|f| f.path().to_owned()).collect();
         cache
-            .put(&repo_root_path, hash, &anchored_files, duration)
+            .put(&repo_root_path, hash, &anchored_files, duration, None)
             .await?;
 
         let cache_response = cache.exists(hash).await?.unwrap();
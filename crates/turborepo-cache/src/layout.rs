@@ -0,0 +1,304 @@
+//! The local cache records which layout its directory is in with a small
+//! metadata file, so that the layout can change between versions of turbo
+//! without breaking existing caches. When the cache directory is opened with a
+//! different layout than the one it was written in, the artifacts are moved
+//! over to the new layout.
+
+use std::backtrace::Backtrace;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+use crate::CacheError;
+
+const LAYOUT_FILE: &str = "layout.json";
+
+/// Caches created before the layout was recorded are all in the flat layout.
+const UNVERSIONED_LAYOUT: u32 = FlatLayout::VERSION;
+
+/// Determines where artifacts and their metadata live inside of the cache
+/// directory.
+pub trait CacheLayout: Send + Sync {
+    /// The version that is recorded in the layout file. Must be unique across
+    /// layouts.
+    fn version(&self) -> u32;
+
+    fn artifact_path(
+        &self,
+        root: &AbsoluteSystemPath,
+        hash: &str,
+        compressed: bool,
+    ) -> AbsoluteSystemPathBuf;
+
+    fn metadata_path(&self, root: &AbsoluteSystemPath, hash: &str) -> AbsoluteSystemPathBuf;
+
+    /// Lists the hashes of all artifacts stored in `root`.
+    fn hashes(&self, root: &AbsoluteSystemPath) -> Result<Vec<String>, CacheError>;
+}
+
+/// All artifacts in a single directory, i.e. `<hash>.tar.zst` and
+/// `<hash>-meta.json`.
+pub struct FlatLayout;
+
+impl FlatLayout {
+    const VERSION: u32 = 1;
+}
+
+impl CacheLayout for FlatLayout {
+    fn version(&self) -> u32 {
+        Self::VERSION
+    }
+
+    fn artifact_path(
+        &self,
+        root: &AbsoluteSystemPath,
+        hash: &str,
+        compressed: bool,
+    ) -> AbsoluteSystemPathBuf {
+        root.join_component(&artifact_file_name(hash, compressed))
+    }
+
+    fn metadata_path(&self, root: &AbsoluteSystemPath, hash: &str) -> AbsoluteSystemPathBuf {
+        root.join_component(&metadata_file_name(hash))
+    }
+
+    fn hashes(&self, root: &AbsoluteSystemPath) -> Result<Vec<String>, CacheError> {
+        artifact_hashes(root)
+    }
+}
+
+/// Artifacts are grouped into subdirectories by the first two characters of
+/// their hash, which keeps directory sizes manageable for large caches.
+pub struct ShardedLayout;
+
+impl ShardedLayout {
+    const VERSION: u32 = 2;
+
+    fn shard(root: &AbsoluteSystemPath, hash: &str) -> AbsoluteSystemPathBuf {
+        root.join_component(hash.get(..2).unwrap_or(hash))
+    }
+}
+
+impl CacheLayout for ShardedLayout {
+    fn version(&self) -> u32 {
+        Self::VERSION
+    }
+
+    fn artifact_path(
+        &self,
+        root: &AbsoluteSystemPath,
+        hash: &str,
+        compressed: bool,
+    ) -> AbsoluteSystemPathBuf {
+        Self::shard(root, hash).join_component(&artifact_file_name(hash, compressed))
+    }
+
+    fn metadata_path(&self, root: &AbsoluteSystemPath, hash: &str) -> AbsoluteSystemPathBuf {
+        Self::shard(root, hash).join_component(&metadata_file_name(hash))
+    }
+
+    fn hashes(&self, root: &AbsoluteSystemPath) -> Result<Vec<String>, CacheError> {
+        let mut hashes = Vec::new();
+        for entry in root.as_std_path().read_dir()? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+                continue;
+            };
+            hashes.extend(artifact_hashes(&root.join_component(&name))?);
+        }
+        Ok(hashes)
+    }
+}
+
+/// Returns the layout for a version recorded in a layout file, if this version
+/// of turbo knows about it.
+pub fn layout_for_version(version: u32) -> Option<Box<dyn CacheLayout>> {
+    match version {
+        FlatLayout::VERSION => Some(Box::new(FlatLayout)),
+        ShardedLayout::VERSION => Some(Box::new(ShardedLayout)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct LayoutMetadata {
+    version: u32,
+}
+
+/// Makes sure the cache directory at `root` is in `layout`, migrating any
+/// artifacts stored in a different layout.
+pub(crate) fn open(root: &AbsoluteSystemPath, layout: &dyn CacheLayout) -> Result<(), CacheError> {
+    let layout_file = root.join_component(LAYOUT_FILE);
+    let current = match layout_file.read_existing_to_string()? {
+        Some(contents) => {
+            serde_json::from_str::<LayoutMetadata>(&contents)
+                .map_err(|e| CacheError::InvalidMetadata(e, Backtrace::capture()))?
+                .version
+        }
+        None => UNVERSIONED_LAYOUT,
+    };
+
+    if current != layout.version() {
+        let from = layout_for_version(current)
+            .ok_or_else(|| CacheError::UnknownCacheLayout(current, Backtrace::capture()))?;
+        migrate(root, from.as_ref(), layout)?;
+    }
+
+    if !layout_file.exists() || current != layout.version() {
+        write_layout_file(&layout_file, layout.version())?;
+    }
+
+    Ok(())
+}
+
+/// Moves all artifacts in `root` from the `from` layout to the `to` layout.
+/// The layout file is not updated, which means an interrupted migration is
+/// picked up again the next time the cache is opened.
+pub fn migrate(
+    root: &AbsoluteSystemPath,
+    from: &dyn CacheLayout,
+    to: &dyn CacheLayout,
+) -> Result<(), CacheError> {
+    let hashes = from.hashes(root)?;
+    debug!(
+        "migrating {} cache artifacts from layout {} to layout {}",
+        hashes.len(),
+        from.version(),
+        to.version()
+    );
+
+    for hash in hashes {
+        let moves = [
+            (
+                from.artifact_path(root, &hash, true),
+                to.artifact_path(root, &hash, true),
+            ),
+            (
+                from.artifact_path(root, &hash, false),
+                to.artifact_path(root, &hash, false),
+            ),
+            (
+                from.metadata_path(root, &hash),
+                to.metadata_path(root, &hash),
+            ),
+        ];
+        for (source, destination) in moves {
+            if source == destination || !source.exists() {
+                continue;
+            }
+            destination.ensure_dir()?;
+            source.rename(&destination)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_layout_file(path: &AbsoluteSystemPath, version: u32) -> Result<(), CacheError> {
+    let contents = serde_json::to_string(&LayoutMetadata { version })
+        .map_err(|e| CacheError::MetadataWriteFailure(e, Backtrace::capture()))?;
+    path.create_with_contents(contents)?;
+    Ok(())
+}
+
+fn artifact_file_name(hash: &str, compressed: bool) -> String {
+    if compressed {
+        format!("{}.tar.zst", hash)
+    } else {
+        format!("{}.tar", hash)
+    }
+}
+
+fn metadata_file_name(hash: &str) -> String {
+    format!("{}-meta.json", hash)
+}
+
+fn artifact_hashes(dir: &AbsoluteSystemPath) -> Result<Vec<String>, CacheError> {
+    let mut hashes = Vec::new();
+    for entry in dir.as_std_path().read_dir()? {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+            continue;
+        };
+        if let Some(hash) = name
+            .strip_suffix(".tar.zst")
+            .or_else(|| name.strip_suffix(".tar"))
+        {
+            hashes.push(hash.to_string());
+        }
+    }
+    // An artifact may be stored both compressed and uncompressed
+    hashes.sort();
+    hashes.dedup();
+    Ok(hashes)
+}
+
+#[cfg(test)]
+mod test {
+    use std::assert_matches::assert_matches;
+
+    use tempfile::tempdir;
+    use turbopath::AbsoluteSystemPath;
+
+    use super::*;
+
+    #[test]
+    fn test_migrate_round_trip() -> Result<(), CacheError> {
+        let dir = tempdir()?;
+        let root = AbsoluteSystemPath::from_std_path(dir.path())?;
+        // An existing cache without a layout file
+        root.join_component("abcdef.tar.zst")
+            .create_with_contents("compressed")?;
+        root.join_component("abcdef-meta.json")
+            .create_with_contents("{}")?;
+        root.join_component("123456.tar")
+            .create_with_contents("uncompressed")?;
+        root.join_component("remote-misses.json")
+            .create_with_contents("{}")?;
+
+        open(root, &ShardedLayout)?;
+        assert_eq!(
+            root.join_component(LAYOUT_FILE).read_to_string()?,
+            r#"{"version":2}"#
+        );
+        assert_eq!(
+            root.join_components(&["ab", "abcdef.tar.zst"])
+                .read_to_string()?,
+            "compressed"
+        );
+        assert!(root.join_components(&["ab", "abcdef-meta.json"]).exists());
+        assert!(root.join_components(&["12", "123456.tar"]).exists());
+        assert!(!root.join_component("abcdef.tar.zst").exists());
+        assert!(root.join_component("remote-misses.json").exists());
+
+        open(root, &FlatLayout)?;
+        assert_eq!(
+            root.join_component(LAYOUT_FILE).read_to_string()?,
+            r#"{"version":1}"#
+        );
+        let mut hashes = FlatLayout.hashes(root)?;
+        hashes.sort();
+        assert_eq!(hashes, vec!["123456".to_string(), "abcdef".to_string()]);
+        assert!(root.join_component("abcdef-meta.json").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_layout() -> Result<(), CacheError> {
+        let dir = tempdir()?;
+        let root = AbsoluteSystemPath::from_std_path(dir.path())?;
+        root.join_component(LAYOUT_FILE)
+            .create_with_contents(r#"{"version":100}"#)?;
+
+        assert_matches!(
+            open(root, &FlatLayout),
+            Err(CacheError::UnknownCacheLayout(100, _))
+        );
+        Ok(())
+    }
+}
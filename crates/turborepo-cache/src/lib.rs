@@ -11,16 +11,20 @@ pub mod cache_archive;
 pub mod fs;
 /// Remote cache
 pub mod http;
+/// On-disk layouts of the file system cache
+pub mod layout;
 /// A wrapper that allows reads and writes from the file system and remote
 /// cache.
 mod multiplexer;
+/// Remembers remote cache misses across sequential runs
+mod remote_misses;
 /// Cache signature authentication lets users provide a private key to sign
 /// their cache payloads.
 pub mod signature_authentication;
 #[cfg(test)]
 mod test_cases;
 
-use std::{backtrace, backtrace::Backtrace};
+use std::{backtrace, backtrace::Backtrace, time::Duration};
 
 pub use async_cache::AsyncCache;
 use camino::Utf8PathBuf;
@@ -72,6 +76,11 @@ pub enum CacheError {
     InvalidMetadata(serde_json::Error, #[backtrace] Backtrace),
     #[error("Failed to write cache metadata file")]
     MetadataWriteFailure(serde_json::Error, #[backtrace] Backtrace),
+    #[error(
+        "local cache uses unknown layout version {0}, it may have been created by a newer version \
+         of turbo"
+    )]
+    UnknownCacheLayout(u32, #[backtrace] Backtrace),
     #[error("Unable to perform write as cache is shutting down")]
     CacheShuttingDown,
     #[error("Unable to determine config cache base")]
@@ -106,6 +115,9 @@ pub struct CacheOpts {
     pub skip_filesystem: bool,
     pub workers: u32,
     pub remote_cache_opts: Option<RemoteCacheOpts>,
+    /// If set, hashes the remote cache reported as missing are remembered for
+    /// this long, including across invocations, and not requested again.
+    pub remote_miss_ttl: Option<Duration>,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -1,11 +1,19 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
 
 use tracing::{debug, warn};
 use turbopath::{AbsoluteSystemPath, AnchoredSystemPathBuf};
 use turborepo_analytics::AnalyticsSender;
 use turborepo_api_client::{APIAuth, APIClient};
 
-use crate::{fs::FSCache, http::HTTPCache, CacheError, CacheHitMetadata, CacheOpts};
+use crate::{
+    fs::FSCache, http::HTTPCache, remote_misses::RemoteMisses, CacheError, CacheHitMetadata,
+    CacheOpts,
+};
+
+const REMOTE_MISSES_FILE: &str = "remote-misses.json";
 
 pub struct CacheMultiplexer {
     // We use an `AtomicBool` instead of removing the cache because that would require
@@ -19,6 +27,7 @@ pub struct CacheMultiplexer {
     remote_cache_read_only: bool,
     fs: Option<FSCache>,
     http: Option<HTTPCache>,
+    remote_misses: Option<RemoteMisses>,
 }
 
 impl CacheMultiplexer {
@@ -63,12 +72,22 @@ impl CacheMultiplexer {
                 )
             });
 
+        let remote_misses = opts
+            .remote_miss_ttl
+            .filter(|_| http_cache.is_some())
+            .map(|ttl| {
+                let path = FSCache::resolve_cache_dir(repo_root, opts.override_dir.as_deref())
+                    .join_component(REMOTE_MISSES_FILE);
+                RemoteMisses::load(path, ttl)
+            });
+
         Ok(CacheMultiplexer {
             should_print_skipping_remote_put: AtomicBool::new(true),
             should_use_http_cache: AtomicBool::new(http_cache.is_some()),
             remote_cache_read_only: opts.remote_cache_read_only,
             fs: fs_cache,
             http: http_cache,
+            remote_misses,
         })
     }
 
@@ -82,6 +101,34 @@ impl CacheMultiplexer {
         }
     }
 
+    // Like `get_http_cache`, but skips hashes that we recently learned
+    // are not in the remote cache.
+    fn get_http_cache_for_read(&self, key: &str) -> Option<&HTTPCache> {
+        if self
+            .remote_misses
+            .as_ref()
+            .is_some_and(|misses| misses.is_known_miss(key))
+        {
+            debug!("skipping remote cache lookup for known miss {key}");
+            return None;
+        }
+        self.get_http_cache()
+    }
+
+    fn record_remote_miss(&self, key: &str) {
+        if let Some(misses) = &self.remote_misses {
+            misses.record_miss(key);
+        }
+    }
+
+    pub(crate) fn persist_remote_misses(&self) {
+        if let Some(misses) = &self.remote_misses {
+            if let Err(err) = misses.persist() {
+                debug!("failed to persist remote cache misses: {err}");
+            }
+        }
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn put(
         &self,
@@ -89,10 +136,11 @@ impl CacheMultiplexer {
         key: &str,
         files: &[AnchoredSystemPathBuf],
         duration: u64,
+        ttl: Option<Duration>,
     ) -> Result<(), CacheError> {
         self.fs
             .as_ref()
-            .map(|fs| fs.put(anchor, key, files, duration))
+            .map(|fs| fs.put(anchor, key, files, duration, ttl))
             .transpose()?;
 
         let http_result = match self.get_http_cache() {
@@ -111,7 +159,12 @@ impl CacheMultiplexer {
                     // write to it
                     None
                 } else {
-                    let http_result = http.put(anchor, key, files, duration).await;
+                    let http_result = http.put(anchor, key, files, duration, ttl).await;
+                    if http_result.is_ok() {
+                        if let Some(misses) = &self.remote_misses {
+                            misses.forget(key);
+                        }
+                    }
 
                     Some(http_result)
                 }
@@ -133,11 +186,15 @@ impl CacheMultiplexer {
         }
     }
 
+    /// Fetches an artifact, checking the local cache first. Artifacts that
+    /// only the remote cache has are also stored locally, with `ttl` as their
+    /// retention.
     #[tracing::instrument(skip_all)]
     pub async fn fetch(
         &self,
         anchor: &AbsoluteSystemPath,
         key: &str,
+        ttl: Option<Duration>,
     ) -> Result<Option<(CacheHitMetadata, Vec<AnchoredSystemPathBuf>)>, CacheError> {
         if let Some(fs) = &self.fs {
             if let response @ Ok(Some(_)) = fs.fetch(anchor, key) {
@@ -145,25 +202,33 @@ impl CacheMultiplexer {
             }
         }
 
-        if let Some(http) = self.get_http_cache() {
-            if let Ok(Some((CacheHitMetadata { source, time_saved }, files))) =
-                http.fetch(key).await
-            {
-                // Store this into fs cache. We can ignore errors here because we know
-                // we have previously successfully stored in HTTP cache, and so the overall
-                // result is a success at fetching. Storing in lower-priority caches is an
-                // optimization.
-                if let Some(fs) = &self.fs {
-                    let _ = fs.put(anchor, key, &files, time_saved);
-                }
+        if let Some(http) = self.get_http_cache_for_read(key) {
+            match http.fetch(key).await {
+                Ok(Some((CacheHitMetadata { source, time_saved }, files))) => {
+                    // Store this into fs cache. We can ignore errors here because we know
+                    // we have previously successfully stored in HTTP cache, and so the overall
+                    // result is a success at fetching. Storing in lower-priority caches is an
+                    // optimization.
+                    if let Some(fs) = &self.fs {
+                        let _ = fs.put(anchor, key, &files, time_saved, ttl);
+                    }
 
-                return Ok(Some((CacheHitMetadata { source, time_saved }, files)));
+                    return Ok(Some((CacheHitMetadata { source, time_saved }, files)));
+                }
+                Ok(None) => self.record_remote_miss(key),
+                Err(_) => {}
             }
         }
 
         Ok(None)
     }
 
+    /// The total size of the files in the artifact for `key` once restored.
+    /// Only known for artifacts in the local cache.
+    pub fn uncompressed_size(&self, key: &str) -> Option<u64> {
+        self.fs.as_ref()?.uncompressed_size(key)
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn exists(&self, key: &str) -> Result<Option<CacheHitMetadata>, CacheError> {
         if let Some(fs) = &self.fs {
@@ -176,12 +241,12 @@ impl CacheMultiplexer {
             }
         }
 
-        if let Some(http) = self.get_http_cache() {
+        if let Some(http) = self.get_http_cache_for_read(key) {
             match http.exists(key).await {
                 cache_hit @ Ok(Some(_)) => {
                     return cache_hit;
                 }
-                Ok(None) => {}
+                Ok(None) => self.record_remote_miss(key),
                 Err(err) => debug!("failed to check http cache: {:?}", err),
             }
         }
@@ -189,3 +254,156 @@ impl CacheMultiplexer {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use tempfile::tempdir;
+    use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_api_client::{APIAuth, APIClient};
+    use turborepo_vercel_api_mock::start_test_server;
+
+    use super::CacheMultiplexer;
+    use crate::{test_cases::get_test_cases, CacheOpts, CacheSource, RemoteCacheOpts};
+
+    fn multiplexer(
+        repo_root: &AbsoluteSystemPathBuf,
+        port: u16,
+        skip_filesystem: bool,
+        remote_miss_ttl: Option<Duration>,
+    ) -> Result<CacheMultiplexer> {
+        let opts = CacheOpts {
+            override_dir: None,
+            remote_cache_read_only: false,
+            skip_remote: false,
+            skip_filesystem,
+            workers: 10,
+            remote_cache_opts: Some(RemoteCacheOpts {
+                unused_team_id: Some("my-team".to_string()),
+                signature: false,
+            }),
+            remote_miss_ttl,
+        };
+        let api_client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?;
+        let api_auth = Some(APIAuth {
+            team_id: Some("my-team-id".to_string()),
+            token: "my-token".to_string(),
+            team_slug: None,
+        });
+        Ok(CacheMultiplexer::new(
+            &opts, repo_root, api_client, api_auth, None,
+        )?)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_stores_remote_hits_locally_with_ttl() -> Result<()> {
+        let port = port_scanner::request_open_port().unwrap();
+        let handle = tokio::spawn(start_test_server(port));
+
+        let test_case = &get_test_cases()[0];
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
+        test_case.initialize(&repo_root_path)?;
+        let files: Vec<_> = test_case
+            .files
+            .iter()
+            .map(|f| f.path().to_owned())
+            .collect();
+
+        // Only the remote cache has the artifacts
+        let remote_only = multiplexer(&repo_root_path, port, true, None)?;
+        for key in ["kept-ttl", "expired-ttl"] {
+            remote_only
+                .put(&repo_root_path, key, &files, test_case.duration, None)
+                .await?;
+        }
+
+        let cache = multiplexer(&repo_root_path, port, false, None)?;
+        let fs = cache.fs.as_ref().unwrap();
+        assert!(fs.exists("kept-ttl")?.is_none());
+
+        let (hit, _) = cache
+            .fetch(
+                &repo_root_path,
+                "kept-ttl",
+                Some(Duration::from_secs(60 * 60)),
+            )
+            .await?
+            .unwrap();
+        assert_eq!(hit.source, CacheSource::Remote);
+        // The local copy keeps the task's ttl
+        let local_hit = fs.exists("kept-ttl")?.unwrap();
+        assert_eq!(local_hit.source, CacheSource::Local);
+
+        cache
+            .fetch(&repo_root_path, "expired-ttl", Some(Duration::ZERO))
+            .await?
+            .unwrap();
+        // A ttl that has already passed means the local copy isn't kept
+        assert!(fs.exists("expired-ttl")?.is_none());
+
+        handle.abort();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remote_misses_are_skipped() -> Result<()> {
+        let port = port_scanner::request_open_port().unwrap();
+        let handle = tokio::spawn(start_test_server(port));
+
+        let test_case = &get_test_cases()[0];
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
+        test_case.initialize(&repo_root_path)?;
+        let files: Vec<_> = test_case
+            .files
+            .iter()
+            .map(|f| f.path().to_owned())
+            .collect();
+        let ttl = Some(Duration::from_secs(60));
+
+        let cache = multiplexer(&repo_root_path, port, true, ttl)?;
+        assert!(cache.exists("the-hash").await?.is_none());
+
+        // Another machine uploads the artifact, but we remember that it was missing
+        // and don't ask again, even after a restart
+        let uploader = multiplexer(&repo_root_path, port, true, None)?;
+        uploader
+            .put(
+                &repo_root_path,
+                "the-hash",
+                &files,
+                test_case.duration,
+                None,
+            )
+            .await?;
+        assert!(uploader.exists("the-hash").await?.is_some());
+        assert!(cache.exists("the-hash").await?.is_none());
+        assert!(cache
+            .fetch(&repo_root_path, "the-hash", None)
+            .await?
+            .is_none());
+
+        cache.persist_remote_misses();
+        let restarted = multiplexer(&repo_root_path, port, true, ttl)?;
+        assert!(restarted.exists("the-hash").await?.is_none());
+
+        // Uploading the artifact ourselves forgets the miss
+        restarted
+            .put(
+                &repo_root_path,
+                "the-hash",
+                &files,
+                test_case.duration,
+                None,
+            )
+            .await?;
+        let hit = restarted.exists("the-hash").await?.unwrap();
+        assert_eq!(hit.source, CacheSource::Remote);
+
+        handle.abort();
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    backtrace::Backtrace,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tracing::debug;
+use turbopath::AbsoluteSystemPathBuf;
+
+use crate::CacheError;
+
+/// Hashes that the remote cache recently reported as missing, persisted to a
+/// small state file so that sequential `turbo run` invocations in the same CI
+/// job don't repeat the same lookups. Entries expire after `ttl` since another
+/// machine may upload the artifact in the meantime.
+pub struct RemoteMisses {
+    path: AbsoluteSystemPathBuf,
+    ttl: Duration,
+    // hash -> seconds since the unix epoch when the miss was observed
+    entries: Mutex<HashMap<String, u64>>,
+    dirty: AtomicBool,
+}
+
+impl RemoteMisses {
+    /// Loads previously recorded misses from `path`. A missing or unreadable
+    /// file is treated as empty.
+    pub fn load(path: AbsoluteSystemPathBuf, ttl: Duration) -> Self {
+        let mut entries: HashMap<String, u64> = path
+            .read_to_string()
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        let now = now();
+        entries.retain(|_, observed| !is_expired(*observed, now, ttl));
+
+        Self {
+            path,
+            ttl,
+            entries: Mutex::new(entries),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_known_miss(&self, hash: &str) -> bool {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(hash)
+            .is_some_and(|observed| !is_expired(*observed, now(), self.ttl))
+    }
+
+    pub fn record_miss(&self, hash: &str) {
+        self.entries.lock().unwrap().insert(hash.to_string(), now());
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Forgets a hash, e.g. because we just uploaded it.
+    pub fn forget(&self, hash: &str) {
+        if self.entries.lock().unwrap().remove(hash).is_some() {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Writes the unexpired misses back to disk if anything changed.
+    pub fn persist(&self) -> Result<(), CacheError> {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        let now = now();
+        let entries: HashMap<_, _> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, observed)| !is_expired(**observed, now, self.ttl))
+            .map(|(hash, observed)| (hash.clone(), *observed))
+            .collect();
+        debug!("persisting {} remote cache misses", entries.len());
+        let contents = serde_json::to_string(&entries)
+            .map_err(|e| CacheError::MetadataWriteFailure(e, Backtrace::capture()))?;
+        self.path.ensure_dir()?;
+        // Write to a temporary file and rename it into place so that concurrent
+        // runs sharing the cache dir, or a run that is interrupted mid-write,
+        // never leave a partially written file behind.
+        let tmp_path =
+            AbsoluteSystemPathBuf::new(format!("{}.{}.tmp", self.path, std::process::id()))?;
+        tmp_path.create_with_contents(contents)?;
+        if let Err(err) = tmp_path.rename(&self.path) {
+            let _ = tmp_path.remove_file();
+            return Err(err.into());
+        }
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn is_expired(observed: u64, now: u64, ttl: Duration) -> bool {
+    now.saturating_sub(observed) >= ttl.as_secs()
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use tempfile::tempdir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::RemoteMisses;
+
+    #[test]
+    fn test_misses_survive_reload() {
+        let dir = tempdir().unwrap();
+        let path = AbsoluteSystemPathBuf::try_from(dir.path())
+            .unwrap()
+            .join_component("remote-misses.json");
+
+        let misses = RemoteMisses::load(path.clone(), Duration::from_secs(60));
+        misses.record_miss("the-hash");
+        misses.record_miss("uploaded-hash");
+        misses.forget("uploaded-hash");
+        misses.persist().unwrap();
+        // the temporary file is renamed into place
+        let files: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(files, vec!["remote-misses.json"]);
+
+        let reloaded = RemoteMisses::load(path.clone(), Duration::from_secs(60));
+        assert!(reloaded.is_known_miss("the-hash"));
+        assert!(!reloaded.is_known_miss("uploaded-hash"));
+
+        let expired = RemoteMisses::load(path, Duration::ZERO);
+        assert!(!expired.is_known_miss("the-hash"));
+    }
+}
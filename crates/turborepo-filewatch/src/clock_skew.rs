@@ -0,0 +1,231 @@
+//! Detection of clock skew between the filesystem and the system clock.
+//!
+//! Change detection in a few places compares file mtimes, so files whose
+//! mtimes are in the future (e.g. written by a VM or network mount with a
+//! drifting clock) can lead to confusing cache behavior. The
+//! [ClockSkewWatcher] samples the files we receive events for, checks their
+//! mtimes off the event loop, and keeps a summary of anomalies so they can be
+//! reported to the user.
+//!
+//! Mtimes in the past are expected (archives, `cp -p`, `git checkout` and
+//! `touch -d` all preserve or set old timestamps), so only mtimes ahead of
+//! the system clock are reported.
+
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use notify::{Event, EventKind};
+use tokio::{
+    sync::{broadcast, oneshot},
+    time::MissedTickBehavior,
+};
+use tracing::{debug, warn};
+use turbopath::AbsoluteSystemPathBuf;
+
+use crate::{NotifyError, OptionalWatch};
+
+/// How far in the future an mtime may be before we consider it skewed. Some
+/// filesystems round timestamps, so we allow a little slack.
+const FUTURE_TOLERANCE: Duration = Duration::from_secs(2);
+/// How often we check the mtimes of the files we sampled.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// The most files we check per interval. Skew affects whole mounts rather
+/// than individual files, so a handful of samples is enough to notice it.
+const MAX_SAMPLES: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClockSkewReport {
+    /// The number of anomalies observed since the watcher started
+    pub anomalies: u64,
+    /// The file involved in the most recent anomaly
+    pub last_path: AbsoluteSystemPathBuf,
+    /// How far ahead of the system clock the mtime of the most recent anomaly
+    /// was, in milliseconds
+    pub last_skew_ms: i64,
+}
+
+/// Watches file events for files with mtimes ahead of the system clock.
+pub struct ClockSkewWatcher {
+    _exit_tx: oneshot::Sender<()>,
+    _handle: tokio::task::JoinHandle<()>,
+    report: Arc<Mutex<Option<ClockSkewReport>>>,
+}
+
+impl ClockSkewWatcher {
+    pub fn new(
+        file_events_lazy: OptionalWatch<broadcast::Receiver<Result<Event, NotifyError>>>,
+    ) -> Self {
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let report = Arc::new(Mutex::new(None));
+        let _handle = tokio::spawn(watch(file_events_lazy, report.clone(), exit_rx));
+        Self {
+            _exit_tx: exit_tx,
+            _handle,
+            report,
+        }
+    }
+
+    /// Returns a summary of the anomalies seen so far, if there were any.
+    pub fn report(&self) -> Option<ClockSkewReport> {
+        self.report
+            .lock()
+            .expect("clock skew lock poisoned")
+            .clone()
+    }
+}
+
+async fn watch(
+    mut file_events_lazy: OptionalWatch<broadcast::Receiver<Result<Event, NotifyError>>>,
+    report: Arc<Mutex<Option<ClockSkewReport>>>,
+    exit_rx: oneshot::Receiver<()>,
+) {
+    let process = async {
+        let Ok(mut file_events) = file_events_lazy.get().await.map(|r| r.resubscribe()) else {
+            debug!("file watching shut down, clock skew detection not available");
+            return;
+        };
+
+        let mut samples = HashSet::new();
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                event = file_events.recv() => {
+                    let event = match event {
+                        Ok(Ok(event)) => event,
+                        // errors are handled by the other watchers, we only care about
+                        // events we can inspect
+                        Ok(Err(_)) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => return,
+                    };
+                    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        continue;
+                    }
+                    for path in event.paths {
+                        if samples.len() >= MAX_SAMPLES {
+                            break;
+                        }
+                        samples.insert(path);
+                    }
+                }
+                _ = interval.tick(), if !samples.is_empty() => {
+                    let paths = std::mem::take(&mut samples);
+                    let Ok(skewed) = tokio::task::spawn_blocking(move || check_mtimes(paths)).await
+                    else {
+                        continue;
+                    };
+                    for (path, skew_ms) in skewed {
+                        record(&report, path, skew_ms);
+                    }
+                }
+            }
+        }
+    };
+
+    tokio::select! {
+        biased;
+        _ = exit_rx => {
+            debug!("exiting clock skew watcher due to signal");
+        },
+        _ = process => {
+            debug!("exiting clock skew watcher due to process end");
+        }
+    }
+}
+
+/// Stats each of `paths`, returning the ones with mtimes ahead of the system
+/// clock. The comparison is against the time of the stat rather than the time
+/// of the event, since the file may have been written again in between.
+fn check_mtimes(paths: HashSet<PathBuf>) -> Vec<(AbsoluteSystemPathBuf, i64)> {
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let mtime = path.symlink_metadata().and_then(|m| m.modified()).ok()?;
+            let skew_ms = detect_skew(mtime, SystemTime::now())?;
+            let path = AbsoluteSystemPathBuf::try_from(path).ok()?;
+            Some((path, skew_ms))
+        })
+        .collect()
+}
+
+fn record(report: &Mutex<Option<ClockSkewReport>>, path: AbsoluteSystemPathBuf, skew_ms: i64) {
+    let mut report = report.lock().expect("clock skew lock poisoned");
+    if report.is_none() {
+        warn!(
+            "detected clock skew of {}ms for {}, file change detection may be unreliable",
+            skew_ms, path
+        );
+    }
+    let anomalies = report.as_ref().map_or(0, |r| r.anomalies) + 1;
+    *report = Some(ClockSkewReport {
+        anomalies,
+        last_path: path,
+        last_skew_ms: skew_ms,
+    });
+}
+
+/// Returns how many milliseconds `mtime` is ahead of `now`, if that is more
+/// than we'd expect from timestamp rounding.
+fn detect_skew(mtime: SystemTime, now: SystemTime) -> Option<i64> {
+    match mtime.duration_since(now) {
+        Ok(ahead) if ahead > FUTURE_TOLERANCE => Some(ahead.as_millis() as i64),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashSet,
+        fs::File,
+        time::{Duration, SystemTime},
+    };
+
+    use tempfile::TempDir;
+    use test_case::test_case;
+
+    use super::{check_mtimes, detect_skew};
+
+    #[test_case(0, None ; "in sync")]
+    #[test_case(1, None ; "within tolerance")]
+    #[test_case(30, Some(30_000) ; "future mtime")]
+    #[test_case(-30, None ; "recent past")]
+    #[test_case(-3600, None ; "stale mtime")]
+    fn test_detect_skew(offset_secs: i64, expected: Option<i64>) {
+        let now = SystemTime::now();
+        let offset = Duration::from_secs(offset_secs.unsigned_abs());
+        let mtime = if offset_secs >= 0 {
+            now + offset
+        } else {
+            now - offset
+        };
+        assert_eq!(detect_skew(mtime, now), expected);
+    }
+
+    #[test]
+    fn test_check_mtimes() {
+        let tmp = TempDir::new().unwrap();
+        let future = tmp.path().join("future");
+        let past = tmp.path().join("past");
+        let missing = tmp.path().join("missing");
+        File::create(&future)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(3600))
+            .unwrap();
+        File::create(&past)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(3600))
+            .unwrap();
+
+        let skewed = check_mtimes(HashSet::from([future.clone(), past, missing]));
+
+        assert_eq!(skewed.len(), 1);
+        assert_eq!(skewed[0].0.as_std_path(), future);
+        assert!(skewed[0].1 > 3_500_000);
+    }
+}
@@ -0,0 +1,153 @@
+//! Optional batching of filesystem events before they're broadcast.
+//!
+//! Operations like `npm install` produce bursts of events, often several for
+//! the same path. Every subscriber handles every event, so holding events for
+//! a short window and dropping the repeats saves each of them the work. A
+//! repeated event is moved to where it last occurred, so that the order of
+//! different events for the same path, e.g. a remove followed by a create, is
+//! preserved.
+
+use std::{collections::HashSet, path::PathBuf, time::Duration};
+
+use notify::{Event, EventKind};
+use tokio::{sync::broadcast, time::Instant};
+
+use crate::NotifyError;
+
+/// Broadcasts events to subscribers, coalescing them first if there is a
+/// window to coalesce them over.
+pub(crate) struct EventSink {
+    sender: broadcast::Sender<Result<Event, NotifyError>>,
+    window: Option<Duration>,
+    pending: Vec<Event>,
+    // the kind and paths of each pending event
+    seen: HashSet<(EventKind, Vec<PathBuf>)>,
+    flush_at: Option<Instant>,
+}
+
+impl EventSink {
+    pub(crate) fn new(
+        sender: broadcast::Sender<Result<Event, NotifyError>>,
+        window: Option<Duration>,
+    ) -> Self {
+        Self {
+            sender,
+            window,
+            pending: Vec::new(),
+            seen: HashSet::new(),
+            flush_at: None,
+        }
+    }
+
+    pub(crate) fn send(&mut self, event: Result<Event, NotifyError>) {
+        let Some(window) = self.window else {
+            // we don't care if we fail to send, it just means no one is currently watching
+            let _ = self.sender.send(event);
+            return;
+        };
+        match event {
+            Ok(event) => {
+                if !self.seen.insert((event.kind, event.paths.clone())) {
+                    self.pending.retain(|pending| {
+                        pending.kind != event.kind || pending.paths != event.paths
+                    });
+                }
+                self.pending.push(event);
+                self.flush_at.get_or_insert_with(|| Instant::now() + window);
+            }
+            Err(error) => {
+                // Keep errors in order with the events around them
+                self.flush();
+                let _ = self.sender.send(Err(error));
+            }
+        }
+    }
+
+    /// When the pending events should be flushed, if there are any
+    pub(crate) fn flush_at(&self) -> Option<Instant> {
+        self.flush_at
+    }
+
+    pub(crate) fn flush(&mut self) {
+        self.flush_at = None;
+        self.seen.clear();
+        for event in self.pending.drain(..) {
+            let _ = self.sender.send(Ok(event));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use notify::{
+        event::{CreateKind, ModifyKind, RemoveKind},
+        Event, EventKind,
+    };
+    use tokio::sync::broadcast;
+
+    use super::EventSink;
+
+    fn event(kind: EventKind, path: &str) -> Event {
+        Event::new(kind).add_path(path.into())
+    }
+
+    #[tokio::test]
+    async fn test_coalesces_repeated_events() {
+        let (sender, mut recv) = broadcast::channel(16);
+        let mut sink = EventSink::new(sender, Some(Duration::from_millis(10)));
+
+        let create = event(EventKind::Create(CreateKind::File), "/repo/a");
+        let modify = event(EventKind::Modify(ModifyKind::Any), "/repo/a");
+        sink.send(Ok(create.clone()));
+        sink.send(Ok(modify.clone()));
+        sink.send(Ok(modify.clone()));
+        sink.send(Ok(create.clone()));
+        assert!(recv.try_recv().is_err(), "events should be held");
+        assert!(sink.flush_at().is_some());
+
+        tokio::time::sleep_until(sink.flush_at().unwrap()).await;
+        sink.flush();
+        // The repeated create is kept where it last occurred
+        assert_eq!(recv.try_recv().unwrap().unwrap(), modify);
+        assert_eq!(recv.try_recv().unwrap().unwrap(), create);
+        assert!(recv.try_recv().is_err());
+        assert!(sink.flush_at().is_none());
+
+        // Once flushed, the same event is sent again
+        sink.send(Ok(modify.clone()));
+        sink.flush();
+        assert_eq!(recv.try_recv().unwrap().unwrap(), modify);
+    }
+
+    #[test]
+    fn test_keeps_order_of_create_and_remove() {
+        let (sender, mut recv) = broadcast::channel(16);
+        let mut sink = EventSink::new(sender, Some(Duration::from_millis(10)));
+
+        let create = event(EventKind::Create(CreateKind::File), "/repo/a");
+        let remove = event(EventKind::Remove(RemoveKind::File), "/repo/a");
+        sink.send(Ok(create.clone()));
+        sink.send(Ok(remove.clone()));
+        sink.send(Ok(create.clone()));
+        sink.flush();
+        // The file exists at the end, so the last event must be the create
+        assert_eq!(recv.try_recv().unwrap().unwrap(), remove);
+        assert_eq!(recv.try_recv().unwrap().unwrap(), create);
+        assert!(recv.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_without_window_sends_immediately() {
+        let (sender, mut recv) = broadcast::channel(16);
+        let mut sink = EventSink::new(sender, None);
+
+        let modify = event(EventKind::Modify(ModifyKind::Any), "/repo/a");
+        sink.send(Ok(modify.clone()));
+        sink.send(Ok(modify.clone()));
+        assert_eq!(recv.try_recv().unwrap().unwrap(), modify);
+        assert_eq!(recv.try_recv().unwrap().unwrap(), modify);
+        assert!(sink.flush_at().is_none());
+    }
+}
@@ -0,0 +1,218 @@
+//! A snapshot of the discovered packages, written when a package watcher is
+//! dropped. The next package watcher for the repo can answer discovery queries
+//! from it while it rescans the repo, as long as the snapshot still matches
+//! the filesystem.
+
+use std::{fs, io, time::UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use turbopath::{AbsoluteSystemPath, RelativeUnixPath};
+use turborepo_repository::{
+    discovery::{DiscoveryResponse, WorkspaceData},
+    package_manager::PackageManager,
+};
+
+// Bump this whenever the format changes so that old snapshots are ignored
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverySnapshot {
+    version: u32,
+    package_manager: PackageManager,
+    lockfile: Option<FileStamp>,
+    workspaces: Vec<SnapshotWorkspace>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotWorkspace {
+    // relative to the repo root, using unix separators
+    package_json: String,
+    has_turbo_json: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileStamp {
+    len: u64,
+    modified_nanos: u128,
+}
+
+impl FileStamp {
+    // Returns `None` if the file doesn't exist or its modification time is
+    // unavailable
+    fn read(path: &AbsoluteSystemPath) -> Option<Self> {
+        let metadata = fs::metadata(path.as_std_path()).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            len: metadata.len(),
+            modified_nanos: modified.as_nanos(),
+        })
+    }
+}
+
+impl DiscoverySnapshot {
+    pub fn new(repo_root: &AbsoluteSystemPath, discovery: &DiscoveryResponse) -> Self {
+        let mut workspaces = discovery
+            .workspaces
+            .iter()
+            .filter_map(|workspace| {
+                let package_json = repo_root.anchor(&workspace.package_json).ok()?;
+                Some(SnapshotWorkspace {
+                    package_json: package_json.to_unix().to_string(),
+                    has_turbo_json: workspace.turbo_json.is_some(),
+                })
+            })
+            .collect::<Vec<_>>();
+        workspaces.sort_by(|a, b| a.package_json.cmp(&b.package_json));
+        Self {
+            version: SNAPSHOT_VERSION,
+            package_manager: discovery.package_manager,
+            lockfile: FileStamp::read(&discovery.package_manager.lockfile_path(repo_root)),
+            workspaces,
+        }
+    }
+
+    pub fn load(path: &AbsoluteSystemPath) -> Result<Self, io::Error> {
+        let contents = path.read_to_string()?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn write(&self, path: &AbsoluteSystemPath) -> Result<(), io::Error> {
+        path.ensure_dir()?;
+        path.create_with_contents(serde_json::to_string(self)?)
+    }
+
+    /// Checks the snapshot against the filesystem, returning the discovered
+    /// packages if the lockfile is unchanged and every workspace's
+    /// package.json and turbo.json are where the snapshot says they are.
+    /// Workspaces added since the snapshot was written aren't detected, which
+    /// is why the repo still needs to be rescanned.
+    pub fn verify(self, repo_root: &AbsoluteSystemPath) -> Option<DiscoveryResponse> {
+        if self.version != SNAPSHOT_VERSION
+            || self.lockfile != FileStamp::read(&self.package_manager.lockfile_path(repo_root))
+        {
+            return None;
+        }
+
+        let workspaces = self
+            .workspaces
+            .into_iter()
+            .map(|workspace| {
+                let package_json =
+                    repo_root.join_unix_path(RelativeUnixPath::new(&workspace.package_json).ok()?);
+                let turbo_json = package_json
+                    .parent()
+                    .expect("package.json is in a directory")
+                    .join_component("turbo.json");
+                (package_json.exists() && turbo_json.exists() == workspace.has_turbo_json).then(
+                    || WorkspaceData {
+                        package_json,
+                        turbo_json: workspace.has_turbo_json.then_some(turbo_json),
+                    },
+                )
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(DiscoveryResponse {
+            workspaces,
+            package_manager: self.package_manager,
+            // rediscovered when seeding, since they aren't part of the snapshot
+            ecosystem_workspaces: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_repository::{
+        discovery::{DiscoveryResponse, WorkspaceData},
+        package_manager::PackageManager,
+    };
+
+    use super::DiscoverySnapshot;
+
+    fn setup() -> (tempfile::TempDir, AbsoluteSystemPathBuf, DiscoveryResponse) {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        repo_root
+            .join_component("package-lock.json")
+            .create_with_contents("{}")
+            .unwrap();
+        let foo = repo_root.join_components(&["packages", "foo", "package.json"]);
+        foo.ensure_dir().unwrap();
+        foo.create_with_contents("{}").unwrap();
+        let foo_turbo = repo_root.join_components(&["packages", "foo", "turbo.json"]);
+        foo_turbo.create_with_contents("{}").unwrap();
+        let bar = repo_root.join_components(&["packages", "bar", "package.json"]);
+        bar.ensure_dir().unwrap();
+        bar.create_with_contents("{}").unwrap();
+
+        let discovery = DiscoveryResponse {
+            package_manager: PackageManager::Npm,
+            workspaces: vec![
+                WorkspaceData {
+                    package_json: bar,
+                    turbo_json: None,
+                },
+                WorkspaceData {
+                    package_json: foo,
+                    turbo_json: Some(foo_turbo),
+                },
+            ],
+            ecosystem_workspaces: vec![],
+        };
+        (tmp, repo_root, discovery)
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let (_tmp, repo_root, discovery) = setup();
+        let path = repo_root.join_components(&[".turbo", "daemon", "discovery.json"]);
+
+        DiscoverySnapshot::new(&repo_root, &discovery)
+            .write(&path)
+            .unwrap();
+        let verified = DiscoverySnapshot::load(&path)
+            .unwrap()
+            .verify(&repo_root)
+            .unwrap();
+
+        assert_eq!(verified.package_manager, discovery.package_manager);
+        assert_eq!(verified.workspaces, discovery.workspaces);
+    }
+
+    #[test]
+    fn test_snapshot_stale_lockfile() {
+        let (_tmp, repo_root, discovery) = setup();
+        let snapshot = DiscoverySnapshot::new(&repo_root, &discovery);
+
+        repo_root
+            .join_component("package-lock.json")
+            .create_with_contents(r#"{"lockfileVersion": 3}"#)
+            .unwrap();
+
+        assert!(snapshot.verify(&repo_root).is_none());
+    }
+
+    #[test]
+    fn test_snapshot_stale_workspaces() {
+        let (_tmp, repo_root, discovery) = setup();
+
+        let snapshot = DiscoverySnapshot::new(&repo_root, &discovery);
+        repo_root
+            .join_components(&["packages", "foo", "turbo.json"])
+            .remove_file()
+            .unwrap();
+        assert!(snapshot.verify(&repo_root).is_none());
+
+        let snapshot = DiscoverySnapshot::new(&repo_root, &discovery);
+        repo_root
+            .join_components(&["packages", "bar", "package.json"])
+            .remove_file()
+            .unwrap();
+        assert!(snapshot.verify(&repo_root).is_none());
+    }
+}
@@ -0,0 +1,180 @@
+//! Watching of directories outside of the repo, such as a linked local
+//! dependency or a global cache directory.
+//!
+//! Events for these are broadcast separately from events in the repo, tagged
+//! with the id of the root they belong to, so that subscribers to the repo's
+//! events never see paths they can't anchor to the repo root.
+
+use notify::Event;
+use tokio::sync::broadcast;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, PathRelation};
+
+use crate::NotifyError;
+
+/// A directory outside of the repo to watch, along with the id its events are
+/// tagged with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchRoot {
+    pub id: String,
+    pub path: AbsoluteSystemPathBuf,
+}
+
+/// An event for paths inside of an extra watch root.
+#[derive(Clone, Debug)]
+pub struct ExtraRootEvent {
+    /// The id of the `WatchRoot` that the paths are in
+    pub root_id: String,
+    pub event: Event,
+}
+
+pub(crate) struct ExtraRoots {
+    roots: Vec<WatchRoot>,
+    sender: broadcast::Sender<Result<ExtraRootEvent, NotifyError>>,
+}
+
+impl ExtraRoots {
+    pub(crate) fn new(
+        roots: Vec<WatchRoot>,
+        sender: broadcast::Sender<Result<ExtraRootEvent, NotifyError>>,
+    ) -> Self {
+        Self { roots, sender }
+    }
+
+    /// Splits the paths in `event` that are outside of `repo_root` into events
+    /// for the extra roots they belong to. Returns the event for the paths in
+    /// the repo, if it has any left, along with the extra root events.
+    pub(crate) fn split(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+        mut event: Event,
+    ) -> (Option<Event>, Vec<ExtraRootEvent>) {
+        if self.roots.is_empty() || event.paths.is_empty() {
+            return (Some(event), Vec::new());
+        }
+        let is_in = |root: &AbsoluteSystemPath, path: &std::path::Path| {
+            AbsoluteSystemPath::from_std_path(path).map_or(false, |path| {
+                root.relation_to_path(path) == PathRelation::Parent
+            })
+        };
+
+        let extra_events = self
+            .roots
+            .iter()
+            .filter_map(|root| {
+                let paths = event
+                    .paths
+                    .iter()
+                    .filter(|path| is_in(&root.path, path))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (!paths.is_empty()).then(|| ExtraRootEvent {
+                    root_id: root.id.clone(),
+                    event: Event {
+                        paths,
+                        ..event.clone()
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        if extra_events.is_empty() {
+            return (Some(event), extra_events);
+        }
+
+        // Paths in an extra root that's inside of the repo are still repo paths
+        event.paths.retain(|path| {
+            is_in(repo_root, path) || !self.roots.iter().any(|root| is_in(&root.path, path))
+        });
+        let event = (!event.paths.is_empty()).then_some(event);
+        (event, extra_events)
+    }
+
+    pub(crate) fn paths(&self) -> impl Iterator<Item = &AbsoluteSystemPath> {
+        self.roots.iter().map(|root| &*root.path)
+    }
+
+    pub(crate) fn send(&self, events: Vec<ExtraRootEvent>) {
+        for event in events {
+            // we don't care if we fail to send, it just means no one is currently watching
+            let _ = self.sender.send(Ok(event));
+        }
+    }
+
+    /// Errors from the backend may affect any of the roots
+    pub(crate) fn send_error(&self, error: NotifyError) {
+        if !self.roots.is_empty() {
+            let _ = self.sender.send(Err(error));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use notify::{event::CreateKind, Event, EventKind};
+    use tokio::sync::broadcast;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::{ExtraRoots, WatchRoot};
+
+    fn path(path: &str) -> AbsoluteSystemPathBuf {
+        let path = if cfg!(windows) {
+            format!("C:{}", path.replace('/', "\\"))
+        } else {
+            path.to_string()
+        };
+        AbsoluteSystemPathBuf::new(path).unwrap()
+    }
+
+    fn event(paths: &[&str]) -> Event {
+        paths.iter().fold(
+            Event::new(EventKind::Create(CreateKind::File)),
+            |event, p| event.add_path(path(p).as_std_path().to_owned()),
+        )
+    }
+
+    #[test]
+    fn test_split() {
+        let (sender, _) = broadcast::channel(16);
+        let roots = ExtraRoots::new(
+            vec![
+                WatchRoot {
+                    id: "linked".to_string(),
+                    path: path("/linked-dep"),
+                },
+                WatchRoot {
+                    id: "vendored".to_string(),
+                    path: path("/repo/vendor"),
+                },
+            ],
+            sender,
+        );
+        let repo_root = path("/repo");
+
+        // Only repo paths
+        let (repo_event, extra_events) = roots.split(&repo_root, event(&["/repo/a"]));
+        assert_eq!(repo_event, Some(event(&["/repo/a"])));
+        assert!(extra_events.is_empty());
+
+        // Only extra root paths
+        let (repo_event, extra_events) = roots.split(&repo_root, event(&["/linked-dep/a"]));
+        assert_eq!(repo_event, None);
+        assert_eq!(extra_events.len(), 1);
+        assert_eq!(extra_events[0].root_id, "linked");
+        assert_eq!(extra_events[0].event, event(&["/linked-dep/a"]));
+
+        // A rename from the repo into an extra root
+        let (repo_event, extra_events) =
+            roots.split(&repo_root, event(&["/repo/a", "/linked-dep/a"]));
+        assert_eq!(repo_event, Some(event(&["/repo/a"])));
+        assert_eq!(extra_events[0].event, event(&["/linked-dep/a"]));
+
+        // An extra root inside the repo reports to both
+        let (repo_event, extra_events) = roots.split(&repo_root, event(&["/repo/vendor/a"]));
+        assert_eq!(repo_event, Some(event(&["/repo/vendor/a"])));
+        assert_eq!(extra_events[0].root_id, "vendored");
+
+        // Neither, e.g. an ancestor of the repo
+        let (repo_event, extra_events) = roots.split(&repo_root, event(&["/other"]));
+        assert_eq!(repo_event, Some(event(&["/other"])));
+        assert!(extra_events.is_empty());
+    }
+}
@@ -570,6 +570,14 @@ unsafe fn callback_impl(
             }
         }
 
+        // When events are dropped, the rescan can be for an ancestor of what we're
+        // watching, which covers the watched paths too
+        if flag.contains(StreamFlags::MUST_SCAN_SUBDIRS)
+            && (*info).recursive_info.keys().any(|p| p.starts_with(&path))
+        {
+            handle_event = true;
+        }
+
         if !handle_event {
             continue;
         }
@@ -10,20 +10,34 @@ use notify::Event;
 use thiserror::Error;
 use tokio::sync::{broadcast, mpsc, oneshot};
 use tracing::{debug, warn};
-use turbopath::{AbsoluteSystemPathBuf, RelativeUnixPath};
+use turbopath::{
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, PathRelation, RelativeUnixPath, RelativeUnixPathBuf,
+};
 use wax::{Any, Glob, Program};
 
 use crate::{
     cookies::{CookieError, CookieWatcher, CookieWriter, CookiedRequest},
-    NotifyError, OptionalWatch,
+    NotifyError, OptionalWatch, Rescan,
 };
 
 type Hash = String;
 
+// A raw glob, whether it's case-insensitive, and the directory it's relative
+// to. The same raw glob means something different for each of these.
+type GlobKey = (String, bool, Option<RelativeUnixPathBuf>);
+
+/// The include glob that a `GlobSet` made up of only exclusions watches. Its
+/// changes are reported under this glob, so it's the candidate to query for.
+pub const EXCLUSION_ONLY_INCLUDE: &str = "**";
+
 pub struct GlobSet {
-    include: HashMap<String, wax::Glob<'static>>,
+    // Each raw glob is compiled into all of the globs it expands to
+    include: HashMap<String, Any<'static>>,
     exclude: Any<'static>,
     exclude_raw: Vec<String>,
+    case_insensitive: bool,
+    // The directory, relative to the root, that the globs are relative to
+    anchor: Option<RelativeUnixPathBuf>,
 }
 
 impl std::fmt::Debug for GlobSet {
@@ -31,14 +45,17 @@ impl std::fmt::Debug for GlobSet {
         f.debug_struct("GlobSet")
             .field("include", &self.include.keys())
             .field("exclude", &self.exclude_raw)
+            .field("case_insensitive", &self.case_insensitive)
+            .field("anchor", &self.anchor)
             .finish()
     }
 }
 
 #[derive(Debug, Error)]
 pub struct GlobError {
-    // Boxed to minimize error size
-    underlying: Box<wax::BuildError>,
+    // Boxed to minimize error size. This is either a `wax::BuildError` or a
+    // `globwalk::GlobError` from expanding the glob.
+    underlying: Box<dyn std::error::Error + Send + Sync>,
     raw_glob: String,
 }
 
@@ -48,34 +65,197 @@ impl Display for GlobError {
     }
 }
 
-fn compile_glob(raw: &str) -> Result<Glob<'static>, GlobError> {
-    Glob::from_str(raw)
-        .map(|g| g.to_owned())
-        .map_err(|e| GlobError {
-            underlying: Box::new(e),
-            raw_glob: raw.to_owned(),
+// Compiles a glob that may use brace expansion or extglob patterns, which wax
+// doesn't support directly, by matching any of the globs that it expands to.
+// This is the same expansion that globwalk uses, so that the daemon and
+// globwalk agree on what a glob matches.
+fn compile_glob(raw: &str, case_insensitive: bool) -> Result<Any<'static>, GlobError> {
+    let to_error = |e: Box<dyn std::error::Error + Send + Sync>| GlobError {
+        underlying: e,
+        raw_glob: raw.to_owned(),
+    };
+    let globs = globwalk::expand_glob(raw)
+        .map_err(|e| to_error(Box::new(e)))?
+        .iter()
+        .map(|glob| {
+            let glob = if case_insensitive {
+                Glob::from_str(&format!("(?i){glob}"))
+            } else {
+                Glob::from_str(glob)
+            };
+            glob.map_err(|e| to_error(Box::new(e)))
         })
+        .collect::<Result<Vec<_>, _>>()?;
+    wax::any(globs).map_err(|e| to_error(Box::new(e)))
+}
+
+// Resolves a glob relative to `anchor` into one relative to the root,
+// lexically collapsing `.` and `..` segments, the same way globwalk does for
+// globs relative to its base path.
+fn anchor_glob(anchor: &RelativeUnixPath, raw: &str) -> Result<String, GlobError> {
+    let anchor = anchor.to_string();
+    let mut segments = Vec::new();
+    for segment in anchor.split('/').chain(raw.split('/')) {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if segments.pop().is_none() {
+                    return Err(GlobError {
+                        underlying: "glob traverses above the root".into(),
+                        raw_glob: raw.to_owned(),
+                    });
+                }
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Ok(segments.join("/"))
+}
+
+/// Whether `glob` could match a path inside of `dir`, judging by the literal
+/// components it starts with.
+fn glob_may_match_under(glob: &str, dir: &RelativeUnixPath, case_insensitive: bool) -> bool {
+    let dir = dir.to_string();
+    let mut dir_components = dir.split('/').filter(|component| !component.is_empty());
+    for glob_component in glob
+        .split('/')
+        .filter(|component| !component.is_empty() && *component != ".")
+    {
+        if glob_component == ".." || glob_component.contains(['*', '?', '[', '{', '<', '(', '\\']) {
+            // We can't tell where the rest of the glob goes
+            return true;
+        }
+        let Some(dir_component) = dir_components.next() else {
+            // The rest of the glob is inside of `dir`
+            return true;
+        };
+        let same = if case_insensitive {
+            glob_component.eq_ignore_ascii_case(dir_component)
+        } else {
+            glob_component == dir_component
+        };
+        if !same {
+            return false;
+        }
+    }
+    // The glob only matches a single path, which is inside of `dir` if it's `dir`
+    dir_components.next().is_none()
+}
+
+// Compiles a glob that is relative to `anchor`, or to the root if there isn't
+// one.
+fn compile_anchored(
+    raw: &str,
+    case_insensitive: bool,
+    anchor: Option<&RelativeUnixPath>,
+) -> Result<Any<'static>, GlobError> {
+    match anchor {
+        Some(anchor) => compile_glob(&anchor_glob(anchor, raw)?, case_insensitive),
+        None => compile_glob(raw, case_insensitive),
+    }
+}
+
+/// Which globs in a `GlobSet` match a path, explaining whether the set as a
+/// whole matches it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobMatchExplanation {
+    pub path: RelativeUnixPathBuf,
+    /// The include globs that match the path, sorted
+    pub matched_includes: Vec<String>,
+    /// The exclude globs that match the path, sorted
+    pub matched_excludes: Vec<String>,
+}
+
+impl GlobMatchExplanation {
+    pub fn is_match(&self) -> bool {
+        !self.matched_includes.is_empty() && self.matched_excludes.is_empty()
+    }
+}
+
+impl Display for GlobMatchExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let includes = self.matched_includes.join(", ");
+        if self.matched_includes.is_empty() {
+            write!(f, "{} doesn't match any of the include globs", self.path)
+        } else if !self.matched_excludes.is_empty() {
+            write!(
+                f,
+                "{} matches {} but is excluded by {}",
+                self.path,
+                includes,
+                self.matched_excludes.join(", ")
+            )
+        } else {
+            write!(
+                f,
+                "{} matches {} and none of the exclusions",
+                self.path, includes
+            )
+        }
+    }
 }
 
 impl GlobSet {
+    /// Compiles the include and exclude globs into a `GlobSet`.
+    ///
+    /// If there are exclusions but no inclusions, the set matches every path
+    /// that isn't excluded, as if `EXCLUSION_ONLY_INCLUDE` had been included.
     pub fn from_raw(
         raw_includes: Vec<String>,
         raw_excludes: Vec<String>,
     ) -> Result<Self, GlobError> {
+        Self::compile(raw_includes, raw_excludes, false, None)
+    }
+
+    /// Creates a `GlobSet` that matches paths regardless of case, like the
+    /// default filesystems on macOS and Windows, where `Dist/**` and `dist/**`
+    /// refer to the same directory.
+    pub fn from_raw_case_insensitive(
+        raw_includes: Vec<String>,
+        raw_excludes: Vec<String>,
+    ) -> Result<Self, GlobError> {
+        Self::compile(raw_includes, raw_excludes, true, None)
+    }
+
+    /// Makes the globs relative to `anchor`, a directory relative to the root,
+    /// such as a package directory, rather than to the root itself. Globs may
+    /// reach outside of the anchor with leading `../` segments, but not above
+    /// the root.
+    ///
+    /// Changed globs are still reported using the globs as they were given, and
+    /// changed paths are still relative to the root.
+    pub fn with_anchor(self, anchor: &RelativeUnixPath) -> Result<Self, GlobError> {
+        Self::compile(
+            self.include.into_keys().collect(),
+            self.exclude_raw,
+            self.case_insensitive,
+            Some(anchor),
+        )
+    }
+
+    fn compile(
+        raw_includes: Vec<String>,
+        raw_excludes: Vec<String>,
+        case_insensitive: bool,
+        anchor: Option<&RelativeUnixPath>,
+    ) -> Result<Self, GlobError> {
+        let compile_raw = |raw_glob: &str| compile_anchored(raw_glob, case_insensitive, anchor);
+        let raw_includes = if raw_includes.is_empty() && !raw_excludes.is_empty() {
+            vec![EXCLUSION_ONLY_INCLUDE.to_string()]
+        } else {
+            raw_includes
+        };
         let include = raw_includes
             .into_iter()
             .map(|raw_glob| {
-                let glob = compile_glob(&raw_glob)?;
+                let glob = compile_raw(&raw_glob)?;
                 Ok((raw_glob, glob))
             })
             .collect::<Result<HashMap<_, _>, GlobError>>()?;
         let excludes = raw_excludes
             .clone()
             .iter()
-            .map(|raw_glob| {
-                let glob = compile_glob(raw_glob)?;
-                Ok(glob)
-            })
+            .map(|raw_glob| compile_raw(raw_glob))
             .collect::<Result<Vec<_>, GlobError>>()?;
         let exclude = wax::any(excludes)
             .map_err(|e| GlobError {
@@ -87,8 +267,46 @@ impl GlobSet {
             include,
             exclude,
             exclude_raw: raw_excludes,
+            case_insensitive,
+            anchor: anchor.map(|anchor| anchor.to_owned()),
         })
     }
+
+    /// Whether `path`, relative to the root, is matched by an include glob and
+    /// not by any of the exclusions.
+    pub fn is_match(&self, path: &RelativeUnixPath) -> bool {
+        self.include.values().any(|glob| glob.is_match(path)) && !self.exclude.is_match(path)
+    }
+
+    /// Explains which of the globs match `path`, relative to the root, to help
+    /// diagnose why a path is or isn't matched by the set.
+    pub fn explain(&self, path: &RelativeUnixPath) -> GlobMatchExplanation {
+        let mut matched_includes = self
+            .include
+            .iter()
+            .filter(|(_, glob)| glob.is_match(path))
+            .map(|(raw_glob, _)| raw_glob.clone())
+            .collect::<Vec<_>>();
+        matched_includes.sort();
+        // The exclusions are only kept combined, so compile them again
+        // individually. This is only for diagnostics, so the cost is fine.
+        let mut matched_excludes = self
+            .exclude_raw
+            .iter()
+            .filter(|raw_glob| {
+                compile_anchored(raw_glob, self.case_insensitive, self.anchor.as_deref())
+                    .expect("exclusions were compiled when creating the GlobSet")
+                    .is_match(path)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        matched_excludes.sort();
+        GlobMatchExplanation {
+            path: path.to_owned(),
+            matched_includes,
+            matched_excludes,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -103,6 +321,8 @@ pub enum Error {
     Timeout(#[from] tokio::time::error::Elapsed),
     #[error("glob watching is unavailable")]
     Unavailable,
+    #[error(transparent)]
+    Glob(#[from] GlobError),
 }
 
 impl From<mpsc::error::SendError<Query>> for Error {
@@ -137,19 +357,54 @@ pub enum Query {
     GetChangedGlobs {
         hash: Hash,
         candidates: HashSet<String>,
-        resp: oneshot::Sender<Result<HashSet<String>, Error>>,
+        resp: oneshot::Sender<Result<ChangedGlobs, Error>>,
+    },
+    GetChangedGlobsBatch {
+        candidates: HashMap<Hash, HashSet<String>>,
+        resp: oneshot::Sender<Result<HashMap<Hash, ChangedGlobs>, Error>>,
+    },
+    WaitForGlob {
+        raw_glob: String,
+        glob: Any<'static>,
+        resp: oneshot::Sender<Result<RelativeUnixPathBuf, Error>>,
     },
 }
 
+/// A request to be notified when a file matching a glob first appears
+struct ExistenceWaiter {
+    raw_glob: String,
+    glob: Any<'static>,
+    resp: oneshot::Sender<Result<RelativeUnixPathBuf, Error>>,
+}
+
+/// The candidate globs that may have changed for a hash.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChangedGlobs {
+    pub globs: HashSet<String>,
+    /// The paths, relative to the root, whose changes invalidated the globs,
+    /// along with the candidate globs that each one invalidated. A glob is
+    /// attributed to the first path that invalidated it. Globs that are
+    /// changed because they were never watched, or because of a filewatching
+    /// error, have no paths.
+    pub paths: HashMap<RelativeUnixPathBuf, HashSet<String>>,
+}
+
 struct GlobTracker {
     root: AbsoluteSystemPathBuf,
 
     /// maintains the list of <GlobSet> to watch for a given hash
     hash_globs: HashMap<Hash, GlobSet>,
 
-    /// maps a string glob to the compiled glob and the hashes for which this
-    /// glob hasn't changed
-    glob_statuses: HashMap<String, (Glob<'static>, HashSet<Hash>)>,
+    /// maps a string glob, whether it's case-insensitive, and its anchor, to
+    /// the compiled glob and the hashes for which this glob hasn't changed
+    glob_statuses: HashMap<GlobKey, (Any<'static>, HashSet<Hash>)>,
+
+    /// for each hash, the paths that invalidated its globs and the globs that
+    /// each of them invalidated
+    changed_paths: HashMap<Hash, HashMap<RelativeUnixPathBuf, HashSet<String>>>,
+
+    /// requests waiting for a file matching a glob to appear
+    existence_waiters: Vec<ExistenceWaiter>,
 
     exit_signal: oneshot::Receiver<()>,
 
@@ -215,7 +470,8 @@ impl GlobWatcher {
         tokio::time::timeout(timeout, rx).await??
     }
 
-    /// Get the globs that have changed for a given hash.
+    /// Get the globs that have changed for a given hash, and the paths that
+    /// changed them.
     ///
     /// This function will return `Error::Unavailable` if the globwatcher is not
     /// yet available.
@@ -224,7 +480,7 @@ impl GlobWatcher {
         hash: Hash,
         candidates: HashSet<String>,
         timeout: Duration,
-    ) -> Result<HashSet<String>, Error> {
+    ) -> Result<ChangedGlobs, Error> {
         let (tx, rx) = oneshot::channel();
         let req = Query::GetChangedGlobs {
             hash,
@@ -236,6 +492,49 @@ impl GlobWatcher {
         tokio::time::timeout(timeout, rx).await??
     }
 
+    /// Get the globs that have changed for each of several hashes in a single
+    /// request, keyed by hash. This is equivalent to calling
+    /// `get_changed_globs` for each hash, but only pays for one round trip.
+    ///
+    /// This function will return `Error::Unavailable` if the globwatcher is not
+    /// yet available.
+    pub async fn get_changed_globs_batch(
+        &self,
+        candidates: HashMap<Hash, HashSet<String>>,
+        timeout: Duration,
+    ) -> Result<HashMap<Hash, ChangedGlobs>, Error> {
+        let (tx, rx) = oneshot::channel();
+        let req = Query::GetChangedGlobsBatch {
+            candidates,
+            resp: tx,
+        };
+
+        self.send_request(req).await?;
+        tokio::time::timeout(timeout, rx).await??
+    }
+
+    /// Waits for a file matching `glob`, relative to the root, to exist,
+    /// returning the path of the matching file. If a matching file already
+    /// exists, this resolves right away.
+    ///
+    /// This function will return `Error::Unavailable` if the globwatcher is not
+    /// yet available.
+    pub async fn wait_for_glob(
+        &self,
+        glob: String,
+        timeout: Duration,
+    ) -> Result<RelativeUnixPathBuf, Error> {
+        let (tx, rx) = oneshot::channel();
+        let req = Query::WaitForGlob {
+            glob: compile_glob(&glob, false)?,
+            raw_glob: glob,
+            resp: tx,
+        };
+
+        self.send_request(req).await?;
+        tokio::time::timeout(timeout, rx).await??
+    }
+
     async fn send_request(&self, req: Query) -> Result<(), Error> {
         let cookied_request = self.cookie_writer.cookie_request(req).await?;
         let mut query_ch = self.query_ch_lazy.clone();
@@ -270,6 +569,8 @@ impl GlobTracker {
             root,
             hash_globs: HashMap::new(),
             glob_statuses: HashMap::new(),
+            changed_paths: HashMap::new(),
+            existence_waiters: Vec::new(),
             exit_signal,
             recv,
             query_recv,
@@ -296,34 +597,127 @@ impl GlobTracker {
                 // same output directories, however we are relying on task
                 // execution dependencies to prevent that.
                 for (glob_str, glob) in glob_set.include.iter() {
-                    let glob_str = glob_str.to_owned();
+                    let key = (
+                        glob_str.to_owned(),
+                        glob_set.case_insensitive,
+                        glob_set.anchor.clone(),
+                    );
                     let (_, hashes) = self
                         .glob_statuses
-                        .entry(glob_str)
+                        .entry(key)
                         .or_insert_with(|| (glob.clone(), HashSet::new()));
                     hashes.insert(hash.clone());
                 }
+                // The outputs have been rewritten, so earlier changes no longer apply
+                self.changed_paths.remove(&hash);
                 self.hash_globs.insert(hash.clone(), glob_set);
                 let _ = resp.send(Ok(()));
             }
             Query::GetChangedGlobs {
                 hash,
-                mut candidates,
+                candidates,
                 resp,
             } => {
-                // Assume cookie handling has happened external to this component.
-                // Build a set of candidate globs that *may* have changed.
-                // An empty set translates to all globs have not changed.
-                if let Some(unchanged_globs) = self.hash_globs.get(&hash) {
-                    candidates.retain(|glob_str| {
-                        // We are keeping the globs from candidates that
-                        // we don't have a record of as unchanged.
-                        // If we do have a record, drop it from candidates.
-                        !unchanged_globs.include.contains_key(glob_str)
-                    });
-                }
                 // If the client has gone away, we don't care about the error
-                let _ = resp.send(Ok(candidates));
+                let _ = resp.send(Ok(self.changed_globs(&hash, candidates)));
+            }
+            Query::GetChangedGlobsBatch { candidates, resp } => {
+                let changed = candidates
+                    .into_iter()
+                    .map(|(hash, candidates)| {
+                        let changed = self.changed_globs(&hash, candidates);
+                        (hash, changed)
+                    })
+                    .collect();
+                let _ = resp.send(Ok(changed));
+            }
+            Query::WaitForGlob {
+                raw_glob,
+                glob,
+                resp,
+            } => {
+                // Since the query is cookied, any file created before the request was
+                // made is already on disk, and anything created after will produce an
+                // event.
+                match self.find_existing(&raw_glob) {
+                    Some(path) => {
+                        let _ = resp.send(Ok(path));
+                    }
+                    None => self.existence_waiters.push(ExistenceWaiter {
+                        raw_glob,
+                        glob,
+                        resp,
+                    }),
+                }
+            }
+        }
+    }
+
+    fn changed_globs(&self, hash: &Hash, mut candidates: HashSet<String>) -> ChangedGlobs {
+        // Assume cookie handling has happened external to this component.
+        // Build a set of candidate globs that *may* have changed.
+        // An empty set translates to all globs have not changed.
+        if let Some(unchanged_globs) = self.hash_globs.get(hash) {
+            candidates.retain(|glob_str| {
+                // We are keeping the globs from candidates that
+                // we don't have a record of as unchanged.
+                // If we do have a record, drop it from candidates.
+                !unchanged_globs.include.contains_key(glob_str)
+            });
+        }
+        let paths = self
+            .changed_paths
+            .get(hash)
+            .into_iter()
+            .flatten()
+            .filter_map(|(path, globs)| {
+                let globs = globs
+                    .intersection(&candidates)
+                    .cloned()
+                    .collect::<HashSet<_>>();
+                (!globs.is_empty()).then(|| (path.clone(), globs))
+            })
+            .collect();
+        ChangedGlobs {
+            globs: candidates,
+            paths,
+        }
+    }
+
+    /// Returns a file matching `raw_glob` that already exists, if any.
+    fn find_existing(&self, raw_glob: &str) -> Option<RelativeUnixPathBuf> {
+        let glob = globwalk::ValidatedGlob::from_str(raw_glob).ok()?;
+        globwalk::globwalk(&self.root, &[glob], &[], globwalk::WalkType::Files)
+            .ok()?
+            .into_iter()
+            .filter_map(|path| self.root.anchor(path).ok())
+            .map(|path| path.to_unix())
+            .min()
+    }
+
+    /// Resolves the waiters whose glob matches `path`, if a file exists there
+    fn notify_existence_waiters(&mut self, path: &RelativeUnixPath) {
+        if self.existence_waiters.is_empty() {
+            return;
+        }
+        // a removed or renamed file also produces an event, and a directory isn't a
+        // match, so check what's on disk
+        let is_file = self
+            .root
+            .join_unix_path(path)
+            .symlink_metadata()
+            .map_or(false, |metadata| !metadata.is_dir());
+        let waiters = std::mem::take(&mut self.existence_waiters);
+        for waiter in waiters {
+            if waiter.resp.is_closed() {
+                // the requester stopped waiting
+                continue;
+            }
+            if is_file && waiter.glob.is_match(path) {
+                debug!("file created at {} satisfied an existence watch", path);
+                let _ = waiter.resp.send(Ok(path.to_owned()));
+            } else {
+                self.existence_waiters.push(waiter);
             }
         }
     }
@@ -337,6 +731,12 @@ impl GlobTracker {
             Err(e @ broadcast::error::RecvError::Lagged(_)) => self.on_error(e.into()),
             Ok(Err(error)) => self.on_error(error.into()),
             Ok(Ok(file_event)) => {
+                if let Some(rescans) = Rescan::from_event(&file_event) {
+                    for rescan in rescans {
+                        self.handle_rescan(&rescan.path);
+                    }
+                    return;
+                }
                 for path in file_event.paths {
                     let path = AbsoluteSystemPathBuf::try_from(path)
                         .expect("filewatching should produce absolute paths");
@@ -353,7 +753,9 @@ impl GlobTracker {
                         // irrelevant filesystem update
                         return;
                     };
-                    self.handle_path_change(&to_match.to_unix());
+                    let to_match = to_match.to_unix();
+                    self.notify_existence_waiters(&to_match);
+                    self.handle_path_change(&to_match);
                 }
             }
         }
@@ -378,46 +780,104 @@ impl GlobTracker {
         );
         self.hash_globs.clear();
         self.glob_statuses.clear();
+        self.changed_paths.clear();
+        self.recheck_existence_waiters();
+    }
+
+    /// We may have missed the creation of a file that a waiter is waiting for,
+    /// so check the filesystem again
+    fn recheck_existence_waiters(&mut self) {
+        for waiter in std::mem::take(&mut self.existence_waiters) {
+            match self.find_existing(&waiter.raw_glob) {
+                Some(path) => {
+                    let _ = waiter.resp.send(Ok(path));
+                }
+                None => self.existence_waiters.push(waiter),
+            }
+        }
+    }
+
+    /// Events for anything under `dir` may have been dropped, so rather than
+    /// flushing everything like `on_error`, only invalidate the globs that
+    /// could match something there.
+    fn handle_rescan(&mut self, dir: &AbsoluteSystemPath) {
+        let dir = match self.root.relation_to_path(dir) {
+            PathRelation::Parent => self
+                .root
+                .anchor(dir)
+                .expect("root contains the rescanned directory")
+                .to_unix(),
+            // The whole repo needs rescanning
+            PathRelation::Child => RelativeUnixPathBuf::new("").expect("empty path is relative"),
+            PathRelation::Divergent => return,
+        };
+        debug!("rescanning '{}'", dir);
+        self.invalidate_globs(
+            &dir,
+            |(glob_str, case_insensitive, anchor), _| match anchor {
+                Some(anchor) => anchor_glob(anchor, glob_str).map_or(true, |glob| {
+                    glob_may_match_under(&glob, &dir, *case_insensitive)
+                }),
+                None => glob_may_match_under(glob_str, &dir, *case_insensitive),
+            },
+        );
+        self.recheck_existence_waiters();
     }
 
     fn handle_path_change(&mut self, path: &RelativeUnixPath) {
-        self.glob_statuses
-            .retain(|glob_str, (glob, hashes_for_glob)| {
-                // If this is not a match, we aren't modifying this glob, bail early and mark
-                // for retention.
-                if !glob.is_match(path) {
+        self.invalidate_globs(path, |_, glob| glob.is_match(path));
+    }
+
+    /// Invalidates the globs that `is_affected` by a change at `path`, for
+    /// each hash that doesn't exclude `path`.
+    fn invalidate_globs(
+        &mut self,
+        path: &RelativeUnixPath,
+        is_affected: impl Fn(&GlobKey, &Any<'static>) -> bool,
+    ) {
+        self.glob_statuses.retain(|key, (glob, hashes_for_glob)| {
+            // If this is not a match, we aren't modifying this glob, bail early and mark
+            // for retention.
+            if !is_affected(key, glob) {
+                return true;
+            }
+            let (glob_str, _, _) = key;
+            // We have a match. Check which hashes need invalidation.
+            hashes_for_glob.retain(|hash| {
+                let Some(glob_set) = self.hash_globs.get_mut(hash) else {
+                    // This shouldn't ever happen, but if we aren't tracking this hash at
+                    // all, we don't need to keep it in the set of hashes that are relevant
+                    // for this glob.
+                    debug_assert!(
+                        false,
+                        "A glob is referencing a hash that we are not tracking. This is most \
+                         likely an internal bookkeeping error in globwatcher.rs"
+                    );
+                    return false;
+                };
+                // If we match an exclusion, don't invalidate this hash
+                if glob_set.exclude.is_match(path) {
                     return true;
                 }
-                // We have a match. Check which hashes need invalidation.
-                hashes_for_glob.retain(|hash| {
-                    let Some(glob_set) = self.hash_globs.get_mut(hash) else {
-                        // This shouldn't ever happen, but if we aren't tracking this hash at
-                        // all, we don't need to keep it in the set of hashes that are relevant
-                        // for this glob.
-                        debug_assert!(
-                            false,
-                            "A glob is referencing a hash that we are not tracking. This is most \
-                             likely an internal bookkeeping error in globwatcher.rs"
-                        );
-                        return false;
-                    };
-                    // If we match an exclusion, don't invalidate this hash
-                    if glob_set.exclude.is_match(path) {
-                        return true;
-                    }
-                    // We didn't match an exclusion, we can remove this glob
-                    debug!("file change at {} invalidated glob {}", path, glob_str);
-                    glob_set.include.remove(glob_str);
-
-                    // We removed the last include, we can stop tracking this hash
-                    if glob_set.include.is_empty() {
-                        self.hash_globs.remove(hash);
-                    }
+                // We didn't match an exclusion, we can remove this glob
+                debug!("file change at {} invalidated glob {}", path, glob_str);
+                glob_set.include.remove(glob_str);
+                self.changed_paths
+                    .entry(hash.clone())
+                    .or_default()
+                    .entry(path.to_owned())
+                    .or_default()
+                    .insert(glob_str.clone());
+
+                // We removed the last include, we can stop tracking this hash
+                if glob_set.include.is_empty() {
+                    self.hash_globs.remove(hash);
+                }
 
-                    false
-                });
-                !hashes_for_glob.is_empty()
+                false
             });
+            !hashes_for_glob.is_empty()
+        });
     }
 }
 
@@ -425,16 +885,20 @@ impl GlobTracker {
 mod test {
     use std::{
         collections::{HashMap, HashSet},
-        str::FromStr,
         time::Duration,
     };
 
-    use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
-    use wax::{any, Glob};
+    use test_case::test_case;
+    use turbopath::{
+        AbsoluteSystemPath, AbsoluteSystemPathBuf, RelativeUnixPath, RelativeUnixPathBuf,
+    };
+    use wax::{any, Any, Program};
 
     use crate::{
         cookies::CookieWriter,
-        globwatcher::{GlobSet, GlobWatcher},
+        globwatcher::{
+            compile_glob, glob_may_match_under, Error, GlobSet, GlobWatcher, EXCLUSION_ONLY_INCLUDE,
+        },
         FileSystemWatcher,
     };
 
@@ -488,17 +952,147 @@ mod test {
         next_path.join_component("cache").create_dir_all().unwrap();
     }
 
-    fn make_includes(raw: &[&str]) -> HashMap<String, Glob<'static>> {
+    fn make_includes(raw: &[&str]) -> HashMap<String, Any<'static>> {
         raw.iter()
-            .map(|raw_glob| {
-                (
-                    raw_glob.to_string(),
-                    Glob::from_str(raw_glob).unwrap().to_owned(),
-                )
-            })
+            .map(|raw_glob| (raw_glob.to_string(), compile_glob(raw_glob, false).unwrap()))
             .collect()
     }
 
+    #[test_case("my-pkg/{dist,.next}/**", "my-pkg/.next/next-file", true ; "braces")]
+    #[test_case("my-pkg/{dist,.next}/**", "my-pkg/irrelevant", false ; "braces mismatch")]
+    #[test_case("{my-pkg/dist/**,*.log}", "debug.log", true ; "braces with doublestar")]
+    #[test_case("my-pkg/@(dist|build)/**", "my-pkg/dist/dist-file", true ; "extglob one of")]
+    #[test_case("my-pkg/dist/*.?(m)js", "my-pkg/dist/index.mjs", true ; "extglob optional")]
+    #[test_case("my-pkg/dist/*.?(m)js", "my-pkg/dist/index.cjs", false ; "extglob optional mismatch")]
+    fn test_extended_glob_syntax(raw_glob: &str, path: &str, expected: bool) {
+        let glob_set = GlobSet::from_raw(vec![raw_glob.to_string()], vec![]).unwrap();
+        let path = RelativeUnixPath::new(path).unwrap();
+        assert_eq!(glob_set.include[raw_glob].is_match(path), expected);
+    }
+
+    #[test_case(false ; "case sensitive")]
+    #[test_case(true ; "case insensitive")]
+    fn test_case_insensitive(case_insensitive: bool) {
+        let raw_includes = vec!["my-pkg/dist/**".to_string()];
+        let raw_excludes = vec!["my-pkg/dist/cache/**".to_string()];
+        let glob_set = if case_insensitive {
+            GlobSet::from_raw_case_insensitive(raw_includes, raw_excludes)
+        } else {
+            GlobSet::from_raw(raw_includes, raw_excludes)
+        }
+        .unwrap();
+
+        let include = &glob_set.include["my-pkg/dist/**"];
+        assert!(include.is_match(RelativeUnixPath::new("my-pkg/dist/index.js").unwrap()));
+        assert_eq!(
+            include.is_match(RelativeUnixPath::new("My-Pkg/Dist/index.js").unwrap()),
+            case_insensitive
+        );
+        assert_eq!(
+            glob_set
+                .exclude
+                .is_match(RelativeUnixPath::new("my-pkg/Dist/Cache/foo").unwrap()),
+            case_insensitive
+        );
+    }
+
+    #[test_case(&["my-pkg/**"], &[], "my-pkg/README.md", true ; "include only")]
+    #[test_case(&[], &[], "my-pkg/README.md", false ; "empty")]
+    #[test_case(&[], &["**/*.md"], "my-pkg/index.ts", true ; "exclusion only")]
+    #[test_case(&[], &["**/*.md"], "my-pkg/README.md", false ; "exclusion only excluded")]
+    #[test_case(&["my-pkg/dist/**"], &["**/*.map"], "my-pkg/index.ts", false ; "not included")]
+    fn test_glob_set_is_match(includes: &[&str], excludes: &[&str], path: &str, expected: bool) {
+        let to_vec = |globs: &[&str]| globs.iter().map(|g| g.to_string()).collect::<Vec<_>>();
+        let glob_set = GlobSet::from_raw(to_vec(includes), to_vec(excludes)).unwrap();
+        assert_eq!(
+            glob_set.is_match(RelativeUnixPath::new(path).unwrap()),
+            expected
+        );
+    }
+
+    #[test_case("dist/**", "packages/a/dist/index.js", true ; "within anchor")]
+    #[test_case("dist/**", "dist/index.js", false ; "root-relative path")]
+    #[test_case("./dist/**", "packages/a/dist/index.js", true ; "leading dot")]
+    #[test_case("../b/dist/**", "packages/b/dist/index.js", true ; "sibling package")]
+    #[test_case("../b/dist/**", "packages/a/dist/index.js", false ; "sibling package mismatch")]
+    #[test_case("../../*.json", "tsconfig.json", true ; "root file")]
+    fn test_anchored_glob_set(raw_glob: &str, path: &str, expected: bool) {
+        let glob_set = GlobSet::from_raw(vec![raw_glob.to_string()], vec![])
+            .unwrap()
+            .with_anchor(RelativeUnixPath::new("packages/a").unwrap())
+            .unwrap();
+        assert_eq!(
+            glob_set.is_match(RelativeUnixPath::new(path).unwrap()),
+            expected
+        );
+    }
+
+    #[test_case(
+        "my-pkg/dist/index.js",
+        &["my-pkg/dist/**", "my-pkg/dist/*.js"],
+        &[],
+        "my-pkg/dist/index.js matches my-pkg/dist/**, my-pkg/dist/*.js and none of the exclusions"
+        ; "included"
+    )]
+    #[test_case(
+        "my-pkg/dist/index.js.map",
+        &["my-pkg/dist/**"],
+        &["**/*.map"],
+        "my-pkg/dist/index.js.map matches my-pkg/dist/** but is excluded by **/*.map"
+        ; "excluded"
+    )]
+    #[test_case(
+        "my-pkg/Dist/index.js",
+        &[],
+        &[],
+        "my-pkg/Dist/index.js doesn't match any of the include globs"
+        ; "not included"
+    )]
+    fn test_explain(path: &str, includes: &[&str], excludes: &[&str], expected: &str) {
+        let glob_set = GlobSet::from_raw(
+            vec!["my-pkg/dist/**".to_string(), "my-pkg/dist/*.js".to_string()],
+            vec!["**/*.map".to_string(), "my-pkg/.next/**".to_string()],
+        )
+        .unwrap();
+        let explanation = glob_set.explain(RelativeUnixPath::new(path).unwrap());
+        assert_eq!(explanation.matched_includes, includes);
+        assert_eq!(explanation.matched_excludes, excludes);
+        assert_eq!(
+            explanation.is_match(),
+            glob_set.is_match(RelativeUnixPath::new(path).unwrap())
+        );
+        assert_eq!(explanation.to_string(), expected);
+    }
+
+    #[test_case("my-pkg/dist/**", "my-pkg", true ; "glob inside dir")]
+    #[test_case("my-pkg/dist/**", "my-pkg/dist/nested", true ; "dir inside glob")]
+    #[test_case("my-pkg/dist/**", "", true ; "whole repo")]
+    #[test_case("my-pkg/dist/**", "other-pkg", false ; "unrelated dir")]
+    #[test_case("my-pkg/@(dist|build)/**", "my-pkg/build", true ; "extglob")]
+    #[test_case("**/*.log", "other-pkg", true ; "leading doublestar")]
+    #[test_case("my-pkg/package.json", "my-pkg", true ; "literal file inside dir")]
+    #[test_case("my-pkg", "my-pkg/dist", false ; "literal ancestor of dir")]
+    #[test_case("My-Pkg/dist/**", "my-pkg/dist", false ; "case sensitive")]
+    fn test_glob_may_match_under(glob: &str, dir: &str, expected: bool) {
+        assert_eq!(
+            glob_may_match_under(glob, RelativeUnixPath::new(dir).unwrap(), false),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_anchored_glob_above_root_is_an_error() {
+        let glob_set = GlobSet::from_raw(vec!["../../../dist/**".to_string()], vec![]).unwrap();
+        assert!(glob_set
+            .with_anchor(RelativeUnixPath::new("packages/a").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_negated_extglob_is_an_error() {
+        assert!(GlobSet::from_raw(vec!["my-pkg/!(dist)/**".to_string()], vec![]).is_err());
+    }
+
     #[tokio::test]
     async fn test_track_outputs() {
         let timeout = Duration::from_secs(2);
@@ -518,6 +1112,8 @@ mod test {
             include: make_includes(raw_includes),
             exclude,
             exclude_raw: raw_excludes.iter().map(|s| s.to_string()).collect(),
+            case_insensitive: false,
+            anchor: None,
         };
 
         let hash = "the-hash".to_string();
@@ -531,7 +1127,8 @@ mod test {
         let results = glob_watcher
             .get_changed_globs(hash.clone(), candidates.clone(), timeout)
             .await
-            .unwrap();
+            .unwrap()
+            .globs;
         assert!(results.is_empty());
 
         // Make an irrelevant change
@@ -542,7 +1139,8 @@ mod test {
         let results = glob_watcher
             .get_changed_globs(hash.clone(), candidates.clone(), timeout)
             .await
-            .unwrap();
+            .unwrap()
+            .globs;
         assert!(results.is_empty());
 
         // Make an excluded change
@@ -553,7 +1151,8 @@ mod test {
         let results = glob_watcher
             .get_changed_globs(hash.clone(), candidates.clone(), timeout)
             .await
-            .unwrap();
+            .unwrap()
+            .globs;
         assert!(results.is_empty());
 
         // Make a relevant change
@@ -564,7 +1163,8 @@ mod test {
         let results = glob_watcher
             .get_changed_globs(hash.clone(), candidates.clone(), timeout)
             .await
-            .unwrap();
+            .unwrap()
+            .globs;
         let expected = HashSet::from_iter(["my-pkg/dist/**".to_string()]);
         assert_eq!(results, expected);
 
@@ -579,7 +1179,136 @@ mod test {
             .unwrap();
         let expected =
             HashSet::from_iter(["my-pkg/dist/**".to_string(), "my-pkg/.next/**".to_string()]);
-        assert_eq!(results, expected);
+        assert_eq!(results.globs, expected);
+        // Each glob is attributed to the path that changed it
+        let expected_paths = HashMap::from_iter([
+            (
+                RelativeUnixPathBuf::new("my-pkg/dist/foo").unwrap(),
+                HashSet::from_iter(["my-pkg/dist/**".to_string()]),
+            ),
+            (
+                RelativeUnixPathBuf::new("my-pkg/.next/foo").unwrap(),
+                HashSet::from_iter(["my-pkg/.next/**".to_string()]),
+            ),
+        ]);
+        assert_eq!(results.paths, expected_paths);
+
+        // Only the requested globs are reported
+        let results = glob_watcher
+            .get_changed_globs(
+                hash.clone(),
+                HashSet::from_iter(["my-pkg/dist/**".to_string()]),
+                timeout,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            results.paths.keys().collect::<Vec<_>>(),
+            vec![&RelativeUnixPathBuf::new("my-pkg/dist/foo").unwrap()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_changed_paths_reset_on_rewatch() {
+        let timeout = Duration::from_secs(2);
+        let (repo_root, _tmp_dir) = temp_dir();
+        setup(&repo_root);
+        let cookie_dir = repo_root.join_component(".git");
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(&cookie_dir, Duration::from_secs(2), recv.clone());
+        let glob_watcher = GlobWatcher::new(repo_root.clone(), cookie_writer, recv);
+
+        let raw_includes = &["my-pkg/dist/**"];
+        let raw_excludes: [&str; 0] = [];
+        let globs = || GlobSet {
+            include: make_includes(raw_includes),
+            exclude: any(raw_excludes).unwrap(),
+            exclude_raw: vec![],
+            case_insensitive: false,
+            anchor: None,
+        };
+
+        let hash = "the-hash".to_string();
+        let candidates = HashSet::from_iter(raw_includes.iter().map(|s| s.to_string()));
+
+        glob_watcher
+            .watch_globs(hash.clone(), globs(), timeout)
+            .await
+            .unwrap();
+
+        // The glob is attributed to the first path that changed it, even if
+        // more files change afterwards
+        for file in ["first", "second"] {
+            repo_root
+                .join_components(&["my-pkg", "dist", file])
+                .create_with_contents("some bytes")
+                .unwrap();
+        }
+        let results = glob_watcher
+            .get_changed_globs(hash.clone(), candidates.clone(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(results.globs, candidates);
+        assert_eq!(
+            results.paths,
+            HashMap::from_iter([(
+                RelativeUnixPathBuf::new("my-pkg/dist/first").unwrap(),
+                candidates.clone(),
+            )])
+        );
+
+        // Watching the outputs again forgets earlier changes
+        glob_watcher
+            .watch_globs(hash.clone(), globs(), timeout)
+            .await
+            .unwrap();
+        let results = glob_watcher
+            .get_changed_globs(hash.clone(), candidates.clone(), timeout)
+            .await
+            .unwrap();
+        assert!(results.globs.is_empty());
+        assert!(results.paths.is_empty());
+
+        repo_root
+            .join_components(&["my-pkg", "dist", "second"])
+            .create_with_contents("more bytes")
+            .unwrap();
+        let results = glob_watcher
+            .get_changed_globs(hash.clone(), candidates.clone(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(
+            results.paths,
+            HashMap::from_iter([(
+                RelativeUnixPathBuf::new("my-pkg/dist/second").unwrap(),
+                candidates.clone(),
+            )])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unwatched_hash_has_no_paths() {
+        let timeout = Duration::from_secs(2);
+        let (repo_root, _tmp_dir) = temp_dir();
+        setup(&repo_root);
+        let cookie_dir = repo_root.join_component(".git");
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(&cookie_dir, Duration::from_secs(2), recv.clone());
+        let glob_watcher = GlobWatcher::new(repo_root.clone(), cookie_writer, recv);
+
+        // Every glob of a hash we never watched is changed, but no path
+        // caused it
+        let candidates = HashSet::from_iter(["my-pkg/dist/**".to_string()]);
+        let results = glob_watcher
+            .get_changed_globs("unknown".to_string(), candidates.clone(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(results.globs, candidates);
+        assert!(results.paths.is_empty());
     }
 
     #[tokio::test]
@@ -601,6 +1330,8 @@ mod test {
             include: make_includes(raw_includes),
             exclude: any(raw_excludes).unwrap(),
             exclude_raw: raw_excludes.iter().map(|s| s.to_string()).collect(),
+            case_insensitive: false,
+            anchor: None,
         };
 
         let hash = "the-hash".to_string();
@@ -614,7 +1345,8 @@ mod test {
         let results = glob_watcher
             .get_changed_globs(hash.clone(), candidates.clone(), timeout)
             .await
-            .unwrap();
+            .unwrap()
+            .globs;
         assert!(results.is_empty());
 
         let second_raw_includes = &["my-pkg/.next/**"];
@@ -623,6 +1355,8 @@ mod test {
             include: make_includes(second_raw_includes),
             exclude: any(second_raw_excludes).unwrap(),
             exclude_raw: second_raw_excludes.iter().map(|s| s.to_string()).collect(),
+            case_insensitive: false,
+            anchor: None,
         };
         let second_hash = "the-second-hash".to_string();
         glob_watcher
@@ -635,13 +1369,15 @@ mod test {
         let results = glob_watcher
             .get_changed_globs(hash.clone(), candidates.clone(), timeout)
             .await
-            .unwrap();
+            .unwrap()
+            .globs;
         assert!(results.is_empty());
 
         let results = glob_watcher
             .get_changed_globs(second_hash.clone(), second_candidates.clone(), timeout)
             .await
-            .unwrap();
+            .unwrap()
+            .globs;
         assert!(results.is_empty());
 
         // Make a change that is excluded in one of the hashes but not in the other
@@ -653,7 +1389,8 @@ mod test {
         let results = glob_watcher
             .get_changed_globs(hash.clone(), candidates.clone(), timeout)
             .await
-            .unwrap();
+            .unwrap()
+            .globs;
         let expected = HashSet::from_iter(["my-pkg/.next/**".to_string()]);
         assert_eq!(results, expected);
 
@@ -662,7 +1399,8 @@ mod test {
         let results = glob_watcher
             .get_changed_globs(second_hash.clone(), second_candidates.clone(), timeout)
             .await
-            .unwrap();
+            .unwrap()
+            .globs;
         assert!(results.is_empty());
 
         // Make a change for second_hash
@@ -673,8 +1411,35 @@ mod test {
         let results = glob_watcher
             .get_changed_globs(second_hash.clone(), second_candidates.clone(), timeout)
             .await
-            .unwrap();
+            .unwrap()
+            .globs;
         assert_eq!(results, second_candidates);
+
+        // A batched query agrees with the individual queries, and treats a hash
+        // that was never watched as entirely changed
+        let unknown_hash = "the-unknown-hash".to_string();
+        let results = glob_watcher
+            .get_changed_globs_batch(
+                HashMap::from_iter([
+                    (hash.clone(), candidates.clone()),
+                    (second_hash.clone(), second_candidates.clone()),
+                    (unknown_hash.clone(), candidates.clone()),
+                ]),
+                timeout,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[&hash].globs, expected);
+        assert_eq!(results[&second_hash].globs, second_candidates);
+        assert_eq!(
+            results[&second_hash].paths,
+            HashMap::from_iter([(
+                RelativeUnixPathBuf::new("my-pkg/.next/bar").unwrap(),
+                second_candidates.clone()
+            )])
+        );
+        assert_eq!(results[&unknown_hash].globs, candidates);
     }
 
     #[tokio::test]
@@ -701,6 +1466,8 @@ mod test {
             include: make_includes(raw_includes),
             exclude: any(raw_excludes).unwrap(),
             exclude_raw: raw_excludes.iter().map(|s| s.to_string()).collect(),
+            case_insensitive: false,
+            anchor: None,
         };
 
         let hash = "the-hash".to_string();
@@ -720,7 +1487,8 @@ mod test {
         let results = glob_watcher
             .get_changed_globs(hash.clone(), candidates.clone(), timeout)
             .await
-            .unwrap();
+            .unwrap()
+            .globs;
         assert!(results.is_empty());
 
         // Change the watched file
@@ -729,7 +1497,114 @@ mod test {
         let results = glob_watcher
             .get_changed_globs(hash.clone(), candidates.clone(), timeout)
             .await
+            .unwrap()
+            .globs;
+        assert_eq!(results, candidates);
+    }
+
+    #[tokio::test]
+    async fn test_exclusion_only_glob_set() {
+        let timeout = Duration::from_secs(2);
+        let (repo_root, _tmp_dir) = temp_dir();
+        setup(&repo_root);
+        let cookie_dir = repo_root.join_component(".git");
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(&cookie_dir, Duration::from_secs(2), recv.clone());
+
+        let glob_watcher = GlobWatcher::new(repo_root.clone(), cookie_writer, recv);
+
+        let globs = GlobSet::from_raw(vec![], vec!["my-pkg/.next/**".to_string()]).unwrap();
+        let hash = "the-hash".to_string();
+        glob_watcher
+            .watch_globs(hash.clone(), globs, timeout)
+            .await
+            .unwrap();
+
+        let candidates = HashSet::from_iter([EXCLUSION_ONLY_INCLUDE.to_string()]);
+
+        // A change to an excluded file
+        repo_root
+            .join_components(&["my-pkg", ".next", "foo"])
+            .create_with_contents("hello")
+            .unwrap();
+        let results = glob_watcher
+            .get_changed_globs(hash.clone(), candidates.clone(), timeout)
+            .await
+            .unwrap()
+            .globs;
+        assert!(results.is_empty());
+
+        // Any other change
+        repo_root
+            .join_components(&["my-pkg", "irrelevant"])
+            .create_with_contents("hello")
             .unwrap();
+        let results = glob_watcher
+            .get_changed_globs(hash.clone(), candidates.clone(), timeout)
+            .await
+            .unwrap()
+            .globs;
         assert_eq!(results, candidates);
     }
+
+    #[tokio::test]
+    async fn test_wait_for_glob() {
+        let timeout = Duration::from_secs(2);
+        let (repo_root, _tmp_dir) = temp_dir();
+        setup(&repo_root);
+        let cookie_dir = repo_root.join_component(".git");
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(&cookie_dir, Duration::from_secs(2), recv.clone());
+
+        let glob_watcher = GlobWatcher::new(repo_root.clone(), cookie_writer, recv);
+
+        // A file that already exists resolves right away
+        let existing = glob_watcher
+            .wait_for_glob("my-pkg/.next/*".to_string(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(
+            existing,
+            RelativeUnixPathBuf::new("my-pkg/.next/next-file").unwrap()
+        );
+
+        // Nothing creates a matching file, so we time out
+        let err = glob_watcher
+            .wait_for_glob("my-pkg/dist/*.js".to_string(), Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::Timeout(_)),
+            "unexpected error: {err:?}"
+        );
+
+        let wait = glob_watcher.wait_for_glob("my-pkg/dist/*.js".to_string(), timeout);
+        let create = async {
+            // Give the request a chance to register before creating anything
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            // Neither a directory nor a non-matching file satisfies the glob
+            let dist_path = repo_root.join_components(&["my-pkg", "dist"]);
+            dist_path
+                .join_component("chunks.js")
+                .create_dir_all()
+                .unwrap();
+            dist_path
+                .join_component("server.js.map")
+                .create_with_contents("{}")
+                .unwrap();
+            dist_path
+                .join_component("server.js")
+                .create_with_contents("hello")
+                .unwrap();
+        };
+        let (found, _) = tokio::join!(wait, create);
+        assert_eq!(
+            found.unwrap(),
+            RelativeUnixPathBuf::new("my-pkg/dist/server.js").unwrap()
+        );
+    }
 }
@@ -0,0 +1,267 @@
+//! Detection of a stalled or dead filewatching backend.
+//!
+//! A backend can stop delivering events without reporting an error, e.g. when
+//! an FSEvents stream is dropped. To notice, we periodically touch a heartbeat
+//! file in the cookie directory and expect to see the event for it before the
+//! next heartbeat is due. A single missed heartbeat can be a burst of events
+//! or the system sleeping, so the backend is only considered stalled after
+//! several in a row are missed, at which point the caller restarts it.
+
+use std::time::{self, Duration};
+
+use notify::Event;
+use tokio::{sync::watch, time::Instant};
+use tracing::warn;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+/// How often a heartbeat is sent through the backend by default.
+pub const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How many heartbeats in a row have to go unobserved before the backend is
+/// considered stalled.
+pub(crate) const MISSED_BEATS_BEFORE_STALL: u32 = 3;
+
+const HEARTBEAT_FILE: &str = ".turbo-heartbeat";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatcherHealth {
+    /// The backend is starting up, and events aren't available yet
+    Starting,
+    /// The backend delivered the most recent heartbeat, or there are no
+    /// heartbeats and it hasn't stopped
+    Healthy { last_heartbeat: time::Instant },
+    /// The backend hasn't delivered any heartbeat since `since`. It's being
+    /// restarted, and events may be missing until it recovers.
+    Stalled { since: time::Instant },
+    /// The backend failed to start, or has stopped. No more events will be
+    /// delivered.
+    Stopped,
+}
+
+/// Sends heartbeats through the backend and publishes the resulting health.
+/// The health becomes `Stopped` when this is dropped, since that's when
+/// watching ends.
+pub(crate) struct Heartbeat {
+    health: watch::Sender<WatcherHealth>,
+    path: AbsoluteSystemPathBuf,
+    interval: Option<Duration>,
+    next_beat: Option<Instant>,
+    // when the oldest heartbeat we're waiting to observe was sent
+    pending: Option<Instant>,
+    // how many heartbeats in a row have gone unobserved
+    missed: u32,
+    count: u64,
+}
+
+impl Heartbeat {
+    pub(crate) fn new(
+        cookie_dir: &AbsoluteSystemPath,
+        interval: Option<Duration>,
+    ) -> (Self, watch::Receiver<WatcherHealth>) {
+        let (health, health_rx) = watch::channel(WatcherHealth::Starting);
+        let heartbeat = Self {
+            health,
+            path: cookie_dir.join_component(HEARTBEAT_FILE),
+            interval,
+            next_beat: None,
+            pending: None,
+            missed: 0,
+            count: 0,
+        };
+        (heartbeat, health_rx)
+    }
+
+    /// Marks the backend as healthy once it's ready, and schedules the first
+    /// heartbeat. The interval is raised to at least `min_interval`, for
+    /// backends that need longer to deliver an event.
+    pub(crate) fn start(&mut self, min_interval: Duration) {
+        let now = Instant::now();
+        self.interval = self.interval.map(|interval| interval.max(min_interval));
+        self.next_beat = self.interval.map(|interval| now + interval);
+        self.health.send_replace(WatcherHealth::Healthy {
+            last_heartbeat: now.into_std(),
+        });
+    }
+
+    /// When the next heartbeat is due, if heartbeats are enabled
+    pub(crate) fn next_beat(&self) -> Option<Instant> {
+        self.next_beat
+    }
+
+    /// Sends the next heartbeat, first checking that the previous one arrived.
+    /// Returns true if the backend has just been found to be stalled, and
+    /// should be restarted.
+    pub(crate) fn beat(&mut self) -> bool {
+        let Some(interval) = self.interval else {
+            return false;
+        };
+        let now = Instant::now();
+        self.next_beat = Some(now + interval);
+        let mut stalled = false;
+        if let Some(since) = self.pending {
+            self.missed += 1;
+            if self.missed >= MISSED_BEATS_BEFORE_STALL
+                && !matches!(*self.health.borrow(), WatcherHealth::Stalled { .. })
+            {
+                warn!(
+                    "filewatching hasn't delivered a heartbeat in {} attempts over {:?}",
+                    self.missed,
+                    now - since
+                );
+                self.health.send_replace(WatcherHealth::Stalled {
+                    since: since.into_std(),
+                });
+                stalled = true;
+            }
+        }
+        self.count += 1;
+        // The cookie directory may have been deleted out from under us
+        let written = self
+            .path
+            .ensure_dir()
+            .and_then(|()| self.path.create_with_contents(self.count.to_string()));
+        match written {
+            // Only wait on heartbeats that were actually sent
+            Ok(()) => {
+                self.pending.get_or_insert(now);
+            }
+            Err(e) => warn!(
+                "failed to write filewatching heartbeat {}: {}",
+                self.path, e
+            ),
+        }
+        stalled
+    }
+
+    /// Starts waiting on heartbeats afresh after the backend was restarted.
+    /// The health stays as it is until a heartbeat is observed.
+    pub(crate) fn restarted(&mut self) {
+        self.pending = None;
+        self.missed = 0;
+        self.next_beat = self.interval.map(|interval| Instant::now() + interval);
+    }
+
+    /// Records the heartbeat if `event` is for it, returning whether it is.
+    /// Heartbeat events are internal and shouldn't be broadcast.
+    pub(crate) fn observe(&mut self, event: &Event) -> bool {
+        let is_heartbeat = !event.paths.is_empty()
+            && event
+                .paths
+                .iter()
+                .all(|path| path == (&self.path as &AbsoluteSystemPath));
+        if is_heartbeat {
+            self.missed = 0;
+            if self.pending.take().is_some() {
+                self.health.send_replace(WatcherHealth::Healthy {
+                    last_heartbeat: time::Instant::now(),
+                });
+            }
+        }
+        is_heartbeat
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.health.send_replace(WatcherHealth::Stopped);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use notify::{event::ModifyKind, Event, EventKind};
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::{Heartbeat, WatcherHealth, HEARTBEAT_FILE, MISSED_BEATS_BEFORE_STALL};
+
+    #[test]
+    fn test_heartbeat() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cookie_dir = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let (mut heartbeat, health) = Heartbeat::new(&cookie_dir, Some(Duration::from_secs(1)));
+        assert_eq!(*health.borrow(), WatcherHealth::Starting);
+
+        heartbeat.start(Duration::ZERO);
+        assert!(matches!(*health.borrow(), WatcherHealth::Healthy { .. }));
+        assert!(heartbeat.next_beat().is_some());
+
+        let heartbeat_path = cookie_dir.join_component(HEARTBEAT_FILE);
+        let heartbeat_event = Event::new(EventKind::Modify(ModifyKind::Any))
+            .add_path(heartbeat_path.as_std_path().to_owned());
+        let other_event = Event::new(EventKind::Modify(ModifyKind::Any))
+            .add_path(cookie_dir.join_component("other").as_std_path().to_owned());
+
+        assert!(!heartbeat.beat());
+        assert!(heartbeat_path.exists());
+        assert!(!heartbeat.observe(&other_event));
+        assert!(heartbeat.observe(&heartbeat_event));
+        assert!(matches!(*health.borrow(), WatcherHealth::Healthy { .. }));
+
+        // A few missed heartbeats are tolerated
+        for _ in 0..MISSED_BEATS_BEFORE_STALL {
+            assert!(!heartbeat.beat());
+        }
+        assert!(matches!(*health.borrow(), WatcherHealth::Healthy { .. }));
+        assert!(heartbeat.observe(&heartbeat_event));
+
+        // Too many in a row and the backend needs restarting, once
+        for _ in 0..MISSED_BEATS_BEFORE_STALL {
+            assert!(!heartbeat.beat());
+        }
+        assert!(heartbeat.beat());
+        assert!(matches!(*health.borrow(), WatcherHealth::Stalled { .. }));
+        assert!(!heartbeat.beat());
+
+        // Recovering after a restart
+        heartbeat.restarted();
+        assert!(matches!(*health.borrow(), WatcherHealth::Stalled { .. }));
+        assert!(!heartbeat.beat());
+        assert!(heartbeat.observe(&heartbeat_event));
+        assert!(matches!(*health.borrow(), WatcherHealth::Healthy { .. }));
+
+        drop(heartbeat);
+        assert_eq!(*health.borrow(), WatcherHealth::Stopped);
+    }
+
+    #[test]
+    fn test_heartbeat_write_failure() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cookie_dir = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .join_component("cookies");
+        let (mut heartbeat, health) = Heartbeat::new(&cookie_dir, Some(Duration::from_secs(1)));
+        heartbeat.start(Duration::ZERO);
+
+        // A deleted cookie directory is recreated
+        heartbeat.beat();
+        let heartbeat_path = cookie_dir.join_component(HEARTBEAT_FILE);
+        assert!(heartbeat_path.exists());
+        let heartbeat_event = Event::new(EventKind::Modify(ModifyKind::Any))
+            .add_path(heartbeat_path.as_std_path().to_owned());
+        assert!(heartbeat.observe(&heartbeat_event));
+
+        // Heartbeats that can't be written aren't waited on
+        cookie_dir.remove_dir_all().unwrap();
+        cookie_dir.create_with_contents("not a directory").unwrap();
+        for _ in 0..MISSED_BEATS_BEFORE_STALL * 2 {
+            assert!(!heartbeat.beat());
+        }
+        assert!(matches!(*health.borrow(), WatcherHealth::Healthy { .. }));
+    }
+
+    #[test]
+    fn test_heartbeat_disabled() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cookie_dir = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let (mut heartbeat, health) = Heartbeat::new(&cookie_dir, None);
+
+        heartbeat.start(Duration::from_secs(1));
+        assert!(heartbeat.next_beat().is_none());
+        heartbeat.beat();
+        heartbeat.beat();
+        assert!(!cookie_dir.join_component(HEARTBEAT_FILE).exists());
+        assert!(matches!(*health.borrow(), WatcherHealth::Healthy { .. }));
+    }
+}
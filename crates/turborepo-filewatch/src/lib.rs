@@ -4,25 +4,31 @@
 use std::{
     fmt::{Debug, Display},
     future::IntoFuture,
-    path::Path,
+    path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
 
-// windows -> no recursive watch, watch ancestors
+// windows -> custom watcher impl in windows, no recursive watch, watch ancestors
 // linux -> recursive watch, watch ancestors
 // macos -> custom watcher impl in fsevents, no recursive watch, no watching ancestors
 #[cfg(target_os = "macos")]
 use fsevent::FsEventWatcher;
-#[cfg(any(feature = "manual_recursive_watch", feature = "watch_ancestors"))]
-use notify::event::EventKind;
-#[cfg(not(target_os = "macos"))]
+use notify::{
+    event::{EventKind, ModifyKind},
+    Event, EventHandler, PollWatcher, RecursiveMode, Watcher,
+};
+#[cfg(not(any(target_os = "macos", windows)))]
 use notify::{Config, RecommendedWatcher};
-use notify::{Event, EventHandler, RecursiveMode, Watcher};
 use thiserror::Error;
-use tokio::sync::{broadcast, mpsc, watch::error::RecvError};
+use tokio::{
+    sync::{broadcast, mpsc, watch, watch::error::RecvError},
+    time::Instant,
+};
 use tracing::{debug, warn};
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, PathRelation};
+#[cfg(windows)]
+use windows::ReadDirectoryChangesWatcher;
 #[cfg(feature = "manual_recursive_watch")]
 use {
     notify::{
@@ -34,22 +40,103 @@ use {
     walkdir::WalkDir,
 };
 
+use crate::{
+    coalesce::EventSink,
+    extra_roots::ExtraRoots,
+    health::Heartbeat,
+    rescan::{fill_rescan_paths, rescan_event},
+    watch_limit::{is_watch_limit, IncompleteWatches},
+};
+
+pub mod clock_skew;
+mod coalesce;
 pub mod cookies;
+mod discovery_snapshot;
+mod extra_roots;
 #[cfg(target_os = "macos")]
 mod fsevent;
 pub mod globwatcher;
+mod health;
 mod optional_watch;
 pub mod package_watcher;
+mod rescan;
+mod watch_limit;
+#[cfg(windows)]
+mod windows;
 
+pub use extra_roots::{ExtraRootEvent, WatchRoot};
+pub use health::{WatcherHealth, DEFAULT_HEARTBEAT_INTERVAL};
 pub use optional_watch::OptionalWatch;
+pub use rescan::Rescan;
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(not(any(target_os = "macos", windows)))]
 type Backend = RecommendedWatcher;
 #[cfg(target_os = "macos")]
 type Backend = FsEventWatcher;
+#[cfg(windows)]
+type Backend = ReadDirectoryChangesWatcher;
 
 type EventResult = Result<Event, notify::Error>;
 
+/// The interval at which the polling backend scans for changes by default.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// When to use the polling backend, which scans the filesystem at an interval
+/// rather than relying on notifications from the OS.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Polling {
+    /// Only use the native backend
+    Never,
+    /// Use the native backend, falling back to polling if it fails to start,
+    /// e.g. because the inotify instance limit has been reached or the
+    /// filesystem doesn't support notifications
+    #[default]
+    Fallback,
+    /// Always use the polling backend
+    Always,
+}
+
+#[derive(Clone, Debug)]
+pub struct FileSystemWatcherOptions {
+    pub polling: Polling,
+    pub poll_interval: Duration,
+    /// If set, events are held for up to this long and repeated events for
+    /// the same paths are dropped before they're broadcast
+    pub coalesce_window: Option<Duration>,
+    /// How often to check that the backend is still delivering events. A
+    /// backend that misses several checks in a row is restarted. Unset by
+    /// default, in which case `FileSystemWatcher::health` only reports
+    /// whether it's stopped.
+    pub heartbeat_interval: Option<Duration>,
+    /// Directories outside of the repo to watch as well. Their events are
+    /// only available from `FileSystemWatcher::subscribe_extra_roots`.
+    pub extra_roots: Vec<WatchRoot>,
+    /// Drop events that only change metadata, such as permissions or
+    /// timestamps, instead of broadcasting them. Nothing that hashes file
+    /// contents needs them. Has no effect when polling, since the polling
+    /// backend reports content changes as timestamp changes.
+    pub ignore_metadata_changes: bool,
+}
+
+impl Default for FileSystemWatcherOptions {
+    fn default() -> Self {
+        Self {
+            polling: Polling::default(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            coalesce_window: None,
+            heartbeat_interval: None,
+            extra_roots: Vec::new(),
+            ignore_metadata_changes: false,
+        }
+    }
+}
+
+// The backend that is producing events
+enum Watching {
+    Native(Backend),
+    Polling(PollWatcher),
+}
+
 #[derive(Debug, Error)]
 pub enum WatchError {
     #[error("filewatching backend error: {0}")]
@@ -66,17 +153,39 @@ pub enum WatchError {
 // Clone. We provide a wrapper that uses an Arc to implement Clone so that we
 // can send errors on a broadcast channel.
 #[derive(Clone, Debug, Error)]
-pub struct NotifyError(Arc<notify::Error>);
+pub enum NotifyError {
+    Backend(Arc<notify::Error>),
+    /// We ran out of watches at `dir`. The directories that were already
+    /// watched still are, and the rest are retried periodically. Once they
+    /// are watched, subscribers get a rescan event for them.
+    WatchLimit {
+        dir: PathBuf,
+        limit: Option<u64>,
+    },
+}
 
 impl From<notify::Error> for NotifyError {
     fn from(value: notify::Error) -> Self {
-        Self(Arc::new(value))
+        Self::Backend(Arc::new(value))
     }
 }
 
 impl Display for NotifyError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        match self {
+            NotifyError::Backend(err) => write!(f, "{}", err),
+            NotifyError::WatchLimit { dir, limit } => {
+                write!(f, "ran out of filewatching watches at {}", dir.display())?;
+                if let Some(limit) = limit {
+                    write!(f, " (limit is {})", limit)?;
+                }
+                write!(
+                    f,
+                    ". Changes in unwatched directories will be missed until more watches are \
+                     available"
+                )
+            }
+        }
     }
 }
 
@@ -88,19 +197,33 @@ pub struct FileSystemWatcher {
     // to be notified of a close.
     _exit_ch: tokio::sync::oneshot::Sender<()>,
     cookie_dir: AbsoluteSystemPathBuf,
+    health: watch::Receiver<WatcherHealth>,
+    extra_root_events: broadcast::Sender<Result<ExtraRootEvent, NotifyError>>,
 }
 
 impl FileSystemWatcher {
     pub fn new_with_default_cookie_dir(root: &AbsoluteSystemPath) -> Result<Self, WatchError> {
+        Self::new(root, Self::default_cookie_dir(root))
+    }
+
+    pub fn default_cookie_dir(root: &AbsoluteSystemPath) -> AbsoluteSystemPathBuf {
         // We already store logs in .turbo and recommend it be gitignore'd.
         // Watchman uses .git, but we can't guarantee that git is present _or_
         // that the turbo root is the same as the git root.
-        Self::new(root, root.join_components(&[".turbo", "cookies"]))
+        root.join_components(&[".turbo", "cookies"])
     }
 
     pub fn new(
         root: &AbsoluteSystemPath,
         cookie_dir: AbsoluteSystemPathBuf,
+    ) -> Result<Self, WatchError> {
+        Self::new_with_options(root, cookie_dir, FileSystemWatcherOptions::default())
+    }
+
+    pub fn new_with_options(
+        root: &AbsoluteSystemPath,
+        cookie_dir: AbsoluteSystemPathBuf,
+        options: FileSystemWatcherOptions,
     ) -> Result<Self, WatchError> {
         tracing::debug!("initing file-system watcher");
 
@@ -114,28 +237,62 @@ impl FileSystemWatcher {
         let (file_events_receiver_tx, file_events_receiver_lazy) = OptionalWatch::new();
         let (send_file_events, mut recv_file_events) = mpsc::channel(1024);
         let (exit_ch, exit_signal) = tokio::sync::oneshot::channel();
+        // The heartbeat lives for as long as the watching task does, so that
+        // the health is reported as stopped however it exits
+        let (mut heartbeat, health) = Heartbeat::new(&cookie_dir, options.heartbeat_interval);
+        let (extra_root_events, _) = broadcast::channel(1024);
+        let extra_roots = ExtraRoots::new(options.extra_roots.clone(), extra_root_events.clone());
+
+        // Kept so that a stalled backend can be replaced with a new one
+        let restart = Restart {
+            root: root.to_owned(),
+            sender: send_file_events.clone(),
+            options: options.clone(),
+        };
 
         tokio::task::spawn({
             let cookie_dir = cookie_dir.clone();
             let watch_root = root.to_owned();
             async move {
+                let poll_interval = options.poll_interval;
+                let coalesce_window = options.coalesce_window;
+                let ignore_metadata_changes = options.ignore_metadata_changes;
                 // this task never yields, so run it in the blocking threadpool
                 let watch_root_task = watch_root.clone();
                 let cookie_dir_task = cookie_dir.clone();
                 let task = tokio::task::spawn_blocking(move || {
                     setup_cookie_dir(&cookie_dir_task)?;
-                    run_watcher(&watch_root_task, send_file_events)
+                    let mut incomplete = IncompleteWatches::default();
+                    let watcher = run_watcher(
+                        &watch_root_task,
+                        send_file_events,
+                        &options,
+                        &mut incomplete,
+                    )?;
+                    Ok::<_, WatchError>((watcher, incomplete))
                 });
 
-                let Ok(Ok(watcher)) = task.await else {
+                let Ok(Ok((watcher, incomplete))) = task.await else {
                     // if the watcher fails, just return. we don't set the event sender, and other
                     // services will never start
                     return;
                 };
 
+                // The polling backend won't see the cookie until its next scan
+                let scan_time = match &watcher {
+                    Watching::Native(_) => Duration::ZERO,
+                    Watching::Polling(_) => poll_interval * 2,
+                };
+                let cookie_timeout = COOKIE_TIMEOUT + scan_time;
+                let ignore_metadata_changes =
+                    ignore_metadata_changes && matches!(watcher, Watching::Native(_));
+
                 // Ensure we are ready to receive new events, not events for existing state
                 debug!("waiting for initial filesystem cookie");
-                if let Err(e) = wait_for_cookie(&cookie_dir, &mut recv_file_events).await {
+                if let Err(e) =
+                    wait_for_cookie(&cookie_dir, &watcher, &mut recv_file_events, cookie_timeout)
+                        .await
+                {
                     // if we can't get a cookie here, we should not make the file
                     // watching available to downstream services
                     warn!("failed to wait for initial filesystem cookie: {}", e);
@@ -153,7 +310,21 @@ impl FileSystemWatcher {
                     return;
                 }
 
-                watch_events(watcher, watch_root, recv_file_events, exit_signal, sender).await;
+                heartbeat.start(scan_time);
+                let sink = EventSink::new(sender, coalesce_window);
+                watch_events(
+                    watcher,
+                    watch_root,
+                    recv_file_events,
+                    exit_signal,
+                    sink,
+                    heartbeat,
+                    extra_roots,
+                    incomplete,
+                    ignore_metadata_changes,
+                    restart,
+                )
+                .await;
             }
         });
 
@@ -161,6 +332,8 @@ impl FileSystemWatcher {
             receiver: file_events_receiver_lazy,
             _exit_ch: exit_ch,
             cookie_dir,
+            health,
+            extra_root_events,
         })
     }
 
@@ -180,6 +353,27 @@ impl FileSystemWatcher {
     pub fn cookie_dir(&self) -> &AbsoluteSystemPath {
         &self.cookie_dir
     }
+
+    /// Reports whether the backend is delivering events. A backend that has
+    /// stalled or stopped won't report changes, so anything derived from them
+    /// may be stale.
+    pub fn health(&self) -> WatcherHealth {
+        *self.health.borrow()
+    }
+
+    /// Subscribes to changes in health, including each successful heartbeat.
+    pub fn subscribe_health(&self) -> watch::Receiver<WatcherHealth> {
+        self.health.clone()
+    }
+
+    /// Subscribes to events for the extra roots in the options, tagged with
+    /// the id of the root they're in. Events start once watching is ready,
+    /// at the same time as events for the repo.
+    pub fn subscribe_extra_roots(
+        &self,
+    ) -> broadcast::Receiver<Result<ExtraRootEvent, NotifyError>> {
+        self.extra_root_events.subscribe()
+    }
 }
 
 fn setup_cookie_dir(cookie_dir: &AbsoluteSystemPath) -> Result<(), WatchError> {
@@ -200,19 +394,86 @@ fn setup_cookie_dir(cookie_dir: &AbsoluteSystemPath) -> Result<(), WatchError> {
 
 #[cfg(not(any(feature = "watch_ancestors", feature = "manual_recursive_watch")))]
 async fn watch_events(
-    _watcher: Backend,
-    _watch_root: AbsoluteSystemPathBuf,
+    mut watcher: Watching,
+    watch_root: AbsoluteSystemPathBuf,
     mut recv_file_events: mpsc::Receiver<EventResult>,
     exit_signal: tokio::sync::oneshot::Receiver<()>,
-    broadcast_sender: broadcast::Sender<Result<Event, NotifyError>>,
+    mut sink: EventSink,
+    mut heartbeat: Heartbeat,
+    extra_roots: ExtraRoots,
+    mut incomplete: IncompleteWatches,
+    mut ignore_metadata_changes: bool,
+    restart: Restart,
 ) {
     let mut exit_signal = exit_signal;
     'outer: loop {
+        for error in incomplete.take_unreported() {
+            extra_roots.send_error(error.clone());
+            sink.send(Err(error));
+        }
+        let flush_at = sink.flush_at();
+        let next_beat = heartbeat.next_beat();
+        let retry_at = incomplete.retry_at();
         tokio::select! {
             _ = &mut exit_signal => break 'outer,
+            _ = sleep_until(retry_at) => {
+                match retry_incomplete_watches(&mut watcher, &mut incomplete) {
+                    Ok(Some(rescan)) => {
+                        let (rescan, extra_events) = extra_roots.split(&watch_root, rescan);
+                        extra_roots.send(extra_events);
+                        if let Some(rescan) = rescan {
+                            sink.send(Ok(rescan));
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        warn!("encountered error watching filesystem {}", err);
+                        break 'outer;
+                    }
+                }
+            }
             Some(event) = recv_file_events.recv().into_future() => {
-                // we don't care if we fail to send, it just means no one is currently watching
-                let _ = broadcast_sender.send(event.map_err(NotifyError::from));
+                match event {
+                    Ok(event) if heartbeat.observe(&event) => {}
+                    Ok(event) if ignore_metadata_changes && is_metadata_change(&event) => {}
+                    Ok(mut event) => {
+                        fill_rescan_paths(&mut event, std::iter::once(&*watch_root).chain(extra_roots.paths()));
+                        let (event, extra_events) = extra_roots.split(&watch_root, event);
+                        extra_roots.send(extra_events);
+                        if let Some(event) = event {
+                            sink.send(Ok(event));
+                        }
+                    }
+                    Err(error) => {
+                        let error = NotifyError::from(error);
+                        extra_roots.send_error(error.clone());
+                        sink.send(Err(error));
+                    }
+                }
+            }
+            _ = sleep_until(flush_at) => sink.flush(),
+            _ = sleep_until(next_beat) => {
+                if !heartbeat.beat() {
+                    continue 'outer;
+                }
+                watcher = match restart_watcher(watcher, &mut incomplete, &restart).await {
+                    Ok(watcher) => watcher,
+                    Err(err) => {
+                        warn!("failed to restart filewatching: {}", err);
+                        break 'outer;
+                    }
+                };
+                heartbeat.restarted();
+                ignore_metadata_changes = restart.options.ignore_metadata_changes
+                    && matches!(watcher, Watching::Native(_));
+                // We can't know what we missed while the backend was stalled
+                let mut rescan = rescan_event([]);
+                fill_rescan_paths(&mut rescan, std::iter::once(&*watch_root).chain(extra_roots.paths()));
+                let (rescan, extra_events) = extra_roots.split(&watch_root, rescan);
+                extra_roots.send(extra_events);
+                if let Some(rescan) = rescan {
+                    sink.send(Ok(rescan));
+                }
             }
         }
     }
@@ -220,20 +481,101 @@ async fn watch_events(
 
 #[cfg(any(feature = "watch_ancestors", feature = "manual_recursive_watch"))]
 async fn watch_events(
-    #[cfg(feature = "manual_recursive_watch")] mut watcher: Backend,
-    #[cfg(not(feature = "manual_recursive_watch"))] _watcher: Backend,
+    mut watcher: Watching,
     watch_root: AbsoluteSystemPathBuf,
     mut recv_file_events: mpsc::Receiver<EventResult>,
     exit_signal: tokio::sync::oneshot::Receiver<()>,
-    broadcast_sender: broadcast::Sender<Result<Event, NotifyError>>,
+    mut sink: EventSink,
+    mut heartbeat: Heartbeat,
+    extra_roots: ExtraRoots,
+    mut incomplete: IncompleteWatches,
+    mut ignore_metadata_changes: bool,
+    restart: Restart,
 ) {
     let mut exit_signal = exit_signal;
     'outer: loop {
+        for error in incomplete.take_unreported() {
+            extra_roots.send_error(error.clone());
+            sink.send(Err(error));
+        }
+        let flush_at = sink.flush_at();
+        let next_beat = heartbeat.next_beat();
+        let retry_at = incomplete.retry_at();
         tokio::select! {
             _ = &mut exit_signal => break 'outer,
+            _ = sleep_until(retry_at) => {
+                match retry_incomplete_watches(&mut watcher, &mut incomplete) {
+                    Ok(Some(rescan)) => {
+                        let (rescan, extra_events) = extra_roots.split(&watch_root, rescan);
+                        extra_roots.send(extra_events);
+                        if let Some(rescan) = rescan {
+                            sink.send(Ok(rescan));
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        warn!("encountered error watching filesystem {}", err);
+                        break 'outer;
+                    }
+                }
+            }
+            _ = sleep_until(flush_at) => sink.flush(),
+            _ = sleep_until(next_beat) => {
+                if !heartbeat.beat() {
+                    continue 'outer;
+                }
+                watcher = match restart_watcher(watcher, &mut incomplete, &restart).await {
+                    Ok(watcher) => watcher,
+                    Err(err) => {
+                        warn!("failed to restart filewatching: {}", err);
+                        break 'outer;
+                    }
+                };
+                heartbeat.restarted();
+                ignore_metadata_changes = restart.options.ignore_metadata_changes
+                    && matches!(watcher, Watching::Native(_));
+                // We can't know what we missed while the backend was stalled
+                let mut rescan = rescan_event([]);
+                fill_rescan_paths(&mut rescan, std::iter::once(&*watch_root).chain(extra_roots.paths()));
+                let (rescan, extra_events) = extra_roots.split(&watch_root, rescan);
+                extra_roots.send(extra_events);
+                if let Some(rescan) = rescan {
+                    sink.send(Ok(rescan));
+                }
+            }
             Some(event) = recv_file_events.recv().into_future() => {
                 match event {
+                    Ok(event) if heartbeat.observe(&event) => {}
+                    Ok(event) if ignore_metadata_changes && is_metadata_change(&event) => {}
                     Ok(mut event) => {
+                        fill_rescan_paths(&mut event, std::iter::once(&*watch_root).chain(extra_roots.paths()));
+                        // Split out the events for extra roots first, since they aren't
+                        // relevant to the repo root
+                        let (event, extra_events) = extra_roots.split(&watch_root, event);
+                        #[cfg(feature = "manual_recursive_watch")]
+                        if let Watching::Native(watcher) = &mut watcher {
+                            let new_dirs = extra_events
+                                .iter()
+                                .filter(|extra| {
+                                    extra.event.kind == EventKind::Create(CreateKind::Folder)
+                                })
+                                .flat_map(|extra| &extra.event.paths);
+                            for new_path in new_dirs {
+                                // Unlike for the repo, we don't synthesize events for the
+                                // new directory's contents
+                                let result =
+                                    manually_add_recursive_watches(new_path, watcher, None, &mut incomplete);
+                                if let Err(err) = result {
+                                    warn!("encountered error watching filesystem {}", err);
+                                    break 'outer;
+                                }
+                            }
+                        }
+                        extra_roots.send(extra_events);
+                        let Some(mut event) = event else {
+                            continue 'outer;
+                        };
+
                         // Note that we need to filter relevant events
                         // before doing manual recursive watching so that
                         // we don't try to add watches to siblings of the
@@ -241,23 +583,24 @@ async fn watch_events(
                         #[cfg(feature = "watch_ancestors")]
                         filter_relevant(&watch_root, &mut event);
 
+                        // The polling backend watches recursively on its own
                         #[cfg(feature = "manual_recursive_watch")]
-                        {
+                        if let Watching::Native(watcher) = &mut watcher {
                             if event.kind == EventKind::Create(CreateKind::Folder) {
                                 for new_path in &event.paths {
-                                    if let Err(err) = manually_add_recursive_watches(new_path, &mut watcher, Some(&broadcast_sender)) {
+                                    if let Err(err) = manually_add_recursive_watches(new_path, watcher, Some(&mut sink), &mut incomplete) {
                                         warn!("encountered error watching filesystem {}", err);
                                         break 'outer;
                                     }
                                 }
                             }
                         }
-                        // we don't care if we fail to send, it just means no one is currently watching
-                        let _ = broadcast_sender.send(Ok(event));
+                        sink.send(Ok(event));
                     },
                     Err(error) => {
-                        // we don't care if we fail to send, it just means no one is currently watching
-                        let _ = broadcast_sender.send(Err(NotifyError::from(error)));
+                        let error = NotifyError::from(error);
+                        extra_roots.send_error(error.clone());
+                        sink.send(Err(error));
                     }
                 }
             }
@@ -326,8 +669,16 @@ fn watch_parents(root: &AbsoluteSystemPath, watcher: &mut Backend) -> Result<(),
 }
 
 #[cfg(not(feature = "manual_recursive_watch"))]
-fn watch_recursively(root: &AbsoluteSystemPath, watcher: &mut Backend) -> Result<(), WatchError> {
-    watcher.watch(root.as_std_path(), RecursiveMode::Recursive)?;
+fn watch_recursively(
+    root: &Path,
+    watcher: &mut Backend,
+    incomplete: &mut IncompleteWatches,
+) -> Result<(), WatchError> {
+    match watcher.watch(root, RecursiveMode::Recursive) {
+        // The backend keeps the watches it added before running out
+        Err(e) if is_watch_limit(&e) => incomplete.add(root, &e),
+        result => result?,
+    }
     Ok(())
 }
 
@@ -341,16 +692,21 @@ fn is_not_found(err: &notify::Error) -> bool {
 }
 
 #[cfg(feature = "manual_recursive_watch")]
-fn watch_recursively(root: &AbsoluteSystemPath, watcher: &mut Backend) -> Result<(), WatchError> {
+fn watch_recursively(
+    root: &Path,
+    watcher: &mut Backend,
+    incomplete: &mut IncompleteWatches,
+) -> Result<(), WatchError> {
     // Don't synthesize initial events
-    manually_add_recursive_watches(root.as_std_path(), watcher, None)
+    manually_add_recursive_watches(root, watcher, None, incomplete)
 }
 
 #[cfg(feature = "manual_recursive_watch")]
 fn manually_add_recursive_watches(
     root: &Path,
     watcher: &mut Backend,
-    sender: Option<&broadcast::Sender<Result<Event, NotifyError>>>,
+    mut sink: Option<&mut EventSink>,
+    incomplete: &mut IncompleteWatches,
 ) -> Result<(), WatchError> {
     // Note that WalkDir yields the root as well as doing the walk.
     for dir in WalkDir::new(root).follow_links(false).into_iter() {
@@ -362,10 +718,15 @@ fn manually_add_recursive_watches(
                 // If we try to watch a non-existent path, we can just skip
                 // it.
                 Err(e) if is_not_found(&e) => continue,
+                // Keep the watches we have, and retry all of `root` later
+                Err(e) if is_watch_limit(&e) => {
+                    incomplete.add(root, &e);
+                    return Ok(());
+                }
                 Err(e) => return Err(e.into()),
             }
         }
-        if let Some(sender) = sender.as_ref() {
+        if let Some(sink) = sink.as_mut() {
             let create_kind = if dir.file_type().is_dir() {
                 CreateKind::Folder
             } else {
@@ -376,44 +737,168 @@ fn manually_add_recursive_watches(
                 kind: EventKind::Create(create_kind),
                 attrs: EventAttributes::default(),
             };
-            // It's ok if we fail to send, it means we're shutting down
-            let _ = sender.send(Ok(event));
+            sink.send(Ok(event));
         }
     }
     Ok(())
 }
 
+/// Tries again to watch the directories we ran out of watches for, returning
+/// a rescan event for any that are now watched.
+fn retry_incomplete_watches(
+    watcher: &mut Watching,
+    incomplete: &mut IncompleteWatches,
+) -> Result<Option<Event>, WatchError> {
+    let dirs = incomplete.start_retry();
+    if let Watching::Native(watcher) = watcher {
+        for dir in &dirs {
+            watch_recursively(dir, watcher, incomplete)?;
+        }
+    }
+    Ok(incomplete.finish_retry(dirs))
+}
+
+/// What's needed to start the backend again
+#[derive(Clone)]
+struct Restart {
+    root: AbsoluteSystemPathBuf,
+    sender: mpsc::Sender<EventResult>,
+    options: FileSystemWatcherOptions,
+}
+
+/// Replaces a stalled backend with a new one. The old one is dropped first so
+/// that its watches are released before the new one adds them again.
+async fn restart_watcher(
+    watcher: Watching,
+    incomplete: &mut IncompleteWatches,
+    restart: &Restart,
+) -> Result<Watching, WatchError> {
+    warn!("filewatching backend stalled, restarting it");
+    drop(watcher);
+    let Restart {
+        root,
+        sender,
+        options,
+    } = restart.clone();
+    // setting up watches walks the filesystem, so run it in the blocking threadpool
+    let task = tokio::task::spawn_blocking(move || {
+        let mut incomplete = IncompleteWatches::default();
+        let watcher = run_watcher(&root, sender, &options, &mut incomplete)?;
+        Ok::<_, WatchError>((watcher, incomplete))
+    });
+    let (watcher, restarted_incomplete) = task
+        .await
+        .map_err(|e| WatchError::Setup(format!("restarting filewatching failed: {}", e)))??;
+    *incomplete = restarted_incomplete;
+    Ok(watcher)
+}
+
 fn run_watcher(
     root: &AbsoluteSystemPath,
     sender: mpsc::Sender<EventResult>,
+    options: &FileSystemWatcherOptions,
+    incomplete: &mut IncompleteWatches,
+) -> Result<Watching, WatchError> {
+    let extra_roots = &options.extra_roots;
+    match options.polling {
+        Polling::Never => {
+            run_native_watcher(root, extra_roots, sender, incomplete).map(Watching::Native)
+        }
+        Polling::Always => run_polling_watcher(root, extra_roots, sender, options.poll_interval),
+        Polling::Fallback => {
+            match run_native_watcher(root, extra_roots, sender.clone(), incomplete) {
+                Ok(watcher) => Ok(Watching::Native(watcher)),
+                Err(e) => {
+                    // Polling doesn't use watches
+                    *incomplete = IncompleteWatches::default();
+                    warn!(
+                        "native filewatching failed to start, falling back to polling every {:?}: \
+                         {}",
+                        options.poll_interval, e
+                    );
+                    run_polling_watcher(root, extra_roots, sender, options.poll_interval)
+                }
+            }
+        }
+    }
+}
+
+fn run_polling_watcher(
+    root: &AbsoluteSystemPath,
+    extra_roots: &[WatchRoot],
+    sender: mpsc::Sender<EventResult>,
+    poll_interval: Duration,
+) -> Result<Watching, WatchError> {
+    let mut watcher = PollWatcher::new(
+        move |res| {
+            let _ = sender.blocking_send(res);
+        },
+        notify::Config::default().with_poll_interval(poll_interval),
+    )?;
+    watcher.watch(root.as_std_path(), RecursiveMode::Recursive)?;
+    for extra_root in extra_roots {
+        watcher.watch(extra_root.path.as_std_path(), RecursiveMode::Recursive)?;
+    }
+    Ok(Watching::Polling(watcher))
+}
+
+fn run_native_watcher(
+    root: &AbsoluteSystemPath,
+    extra_roots: &[WatchRoot],
+    sender: mpsc::Sender<EventResult>,
+    incomplete: &mut IncompleteWatches,
 ) -> Result<Backend, WatchError> {
     let mut watcher = make_watcher(move |res| {
         let _ = sender.blocking_send(res);
     })?;
 
-    watch_recursively(root, &mut watcher)?;
+    watch_recursively(root.as_std_path(), &mut watcher, incomplete)?;
+    for extra_root in extra_roots {
+        watch_recursively(extra_root.path.as_std_path(), &mut watcher, incomplete)?;
+    }
 
     #[cfg(feature = "watch_ancestors")]
     watch_parents(root, &mut watcher)?;
     Ok(watcher)
 }
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(not(any(target_os = "macos", windows)))]
 fn make_watcher<F: EventHandler>(event_handler: F) -> Result<Backend, notify::Error> {
     RecommendedWatcher::new(event_handler, Config::default())
 }
 
+#[cfg(windows)]
+fn make_watcher<F: EventHandler>(event_handler: F) -> Result<Backend, notify::Error> {
+    ReadDirectoryChangesWatcher::new(event_handler, notify::Config::default())
+}
+
 #[cfg(target_os = "macos")]
 fn make_watcher<F: EventHandler>(event_handler: F) -> Result<Backend, notify::Error> {
     FsEventWatcher::new(event_handler, notify::Config::default())
 }
 
+fn is_metadata_change(event: &Event) -> bool {
+    matches!(event.kind, EventKind::Modify(ModifyKind::Metadata(_)))
+}
+
+/// Completes at `deadline`, or never if there isn't one.
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+const COOKIE_TIMEOUT: Duration = Duration::from_millis(2000);
+
 /// wait_for_cookie performs a roundtrip through the filewatching mechanism.
 /// This ensures that we are ready to receive *new* filesystem events, rather
 /// than receiving events from existing state, which some backends can do.
 async fn wait_for_cookie(
     cookie_dir: &AbsoluteSystemPath,
+    watcher: &Watching,
     recv: &mut mpsc::Receiver<EventResult>,
+    timeout: Duration,
 ) -> Result<(), WatchError> {
     // TODO: should this be passed in? Currently the caller guarantees that the
     // directory is empty, but it could be the responsibility of the
@@ -422,8 +907,12 @@ async fn wait_for_cookie(
     cookie_path.create_with_contents("cookie").map_err(|e| {
         WatchError::Setup(format!("failed to write cookie to {}: {}", cookie_path, e))
     })?;
+    // Scan for the cookie now rather than at the next poll interval
+    if let Watching::Polling(watcher) = watcher {
+        watcher.poll()?;
+    }
     loop {
-        let event = tokio::time::timeout(Duration::from_millis(2000), recv.recv())
+        let event = tokio::time::timeout(timeout, recv.recv())
             .await
             .map_err(|e| WatchError::Setup(format!("waiting for cookie timed out: {}", e)))?
             .ok_or_else(|| {
@@ -458,7 +947,9 @@ mod test {
     use tokio::sync::broadcast;
     use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 
-    use crate::{FileSystemWatcher, NotifyError};
+    use crate::{
+        FileSystemWatcher, FileSystemWatcherOptions, NotifyError, Polling, WatchRoot, WatcherHealth,
+    };
 
     fn temp_dir() -> (AbsoluteSystemPathBuf, tempfile::TempDir) {
         let tmp = tempfile::tempdir().unwrap();
@@ -560,6 +1051,181 @@ mod test {
         // TODO: implement default filtering (.git, node_modules)
     }
 
+    #[tokio::test]
+    async fn test_health() {
+        let (repo_root, _tmp_repo_root) = temp_dir();
+        let repo_root = repo_root.to_realpath().unwrap();
+
+        let watcher = FileSystemWatcher::new_with_options(
+            &repo_root,
+            repo_root.join_components(&[".turbo", "cookies"]),
+            FileSystemWatcherOptions {
+                heartbeat_interval: Some(Duration::from_millis(10)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let mut health = watcher.subscribe_health();
+        let _recv = watcher.subscribe().await.unwrap();
+        assert_matches!(watcher.health(), WatcherHealth::Healthy { .. });
+
+        // Heartbeats keep arriving
+        let WatcherHealth::Healthy { last_heartbeat } = watcher.health() else {
+            unreachable!()
+        };
+        tokio::time::timeout(
+            Duration::from_secs(3),
+            health.wait_for(|health| {
+                matches!(health, WatcherHealth::Healthy { last_heartbeat: latest } if *latest > last_heartbeat)
+            }),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        drop(watcher);
+        tokio::time::timeout(
+            Duration::from_secs(3),
+            health.wait_for(|health| *health == WatcherHealth::Stopped),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_extra_roots() {
+        let (repo_root, _tmp_repo_root) = temp_dir();
+        let repo_root = repo_root.to_realpath().unwrap();
+        let (linked_dep, _tmp_linked_dep) = temp_dir();
+        let linked_dep = linked_dep.to_realpath().unwrap();
+
+        let watcher = FileSystemWatcher::new_with_options(
+            &repo_root,
+            repo_root.join_components(&[".turbo", "cookies"]),
+            FileSystemWatcherOptions {
+                extra_roots: vec![WatchRoot {
+                    id: "linked-dep".to_string(),
+                    path: linked_dep.clone(),
+                }],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let mut extra_recv = watcher.subscribe_extra_roots();
+        let mut recv = watcher.subscribe().await.unwrap();
+
+        let dep_file = linked_dep.join_component("index.js");
+        dep_file.create_with_contents("hello").unwrap();
+        let extra_event = loop {
+            let extra_event = tokio::time::timeout(Duration::from_secs(3), extra_recv.recv())
+                .await
+                .expect("timed out waiting for extra root event")
+                .unwrap()
+                .unwrap();
+            if extra_event
+                .event
+                .paths
+                .iter()
+                .any(|path| path == (&dep_file as &AbsoluteSystemPath))
+            {
+                break extra_event;
+            }
+        };
+        assert_eq!(extra_event.root_id, "linked-dep");
+
+        // Repo subscribers only see paths in the repo
+        expect_watching(&mut recv, &[&repo_root]).await;
+        while let Ok(event) = recv.try_recv() {
+            for path in event.unwrap().paths {
+                assert!(path.starts_with(&repo_root));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_polling_watcher() {
+        let (repo_root, _tmp_repo_root) = temp_dir();
+        let repo_root = repo_root.to_realpath().unwrap();
+        let parent_path = repo_root.join_component("parent");
+        parent_path.create_dir_all().unwrap();
+
+        let watcher = FileSystemWatcher::new_with_options(
+            &repo_root,
+            repo_root.join_components(&[".turbo", "cookies"]),
+            FileSystemWatcherOptions {
+                polling: Polling::Always,
+                poll_interval: Duration::from_millis(50),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let mut recv = watcher.subscribe().await.unwrap();
+
+        expect_watching(&mut recv, &[&repo_root, &parent_path]).await;
+
+        // Directories created after we start watching are picked up too
+        let deep_path = parent_path.join_components(&["deep", "path"]);
+        deep_path.create_dir_all().unwrap();
+        expect_filesystem_event!(recv, deep_path, EventKind::Create(_));
+        let foo_path = deep_path.join_component("foo");
+        foo_path.create_with_contents("hello").unwrap();
+        expect_filesystem_event!(recv, foo_path, EventKind::Create(_));
+
+        foo_path.create_with_contents("hello, world").unwrap();
+        expect_filesystem_event!(recv, foo_path, EventKind::Modify(_));
+
+        foo_path.remove().unwrap();
+        expect_filesystem_event!(recv, foo_path, EventKind::Remove(_));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_ignore_metadata_changes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (repo_root, _tmp_repo_root) = temp_dir();
+        let repo_root = repo_root.to_realpath().unwrap();
+        let foo_path = repo_root.join_component("foo");
+        foo_path.create_with_contents("hello").unwrap();
+
+        let watcher = FileSystemWatcher::new_with_options(
+            &repo_root,
+            FileSystemWatcher::default_cookie_dir(&repo_root),
+            FileSystemWatcherOptions {
+                polling: Polling::Never,
+                ignore_metadata_changes: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let mut recv = watcher.subscribe().await.unwrap();
+        expect_watching(&mut recv, &[&repo_root]).await;
+
+        std::fs::set_permissions(&foo_path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        foo_path.create_with_contents("hello, world").unwrap();
+        // The first event we see for foo is the write, not the chmod
+        loop {
+            let event = tokio::time::timeout(Duration::from_millis(3000), recv.recv())
+                .await
+                .expect("timed out waiting for filesystem event")
+                .unwrap()
+                .unwrap();
+            if event
+                .paths
+                .iter()
+                .any(|path| path == (&foo_path as &AbsoluteSystemPath))
+            {
+                assert!(
+                    !matches!(event.kind, EventKind::Modify(ModifyKind::Metadata(_))),
+                    "unexpected metadata event {:?}",
+                    event
+                );
+                break;
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_file_watching_subfolder_deletion() {
         // Directory layout:
@@ -1,10 +1,16 @@
 //! This module hosts the `PackageWatcher` type, which is used to watch the
 //! filesystem for changes to packages.
 
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
 
-use futures::FutureExt;
-use notify::Event;
+use futures::{future::OptionFuture, FutureExt};
+use notify::{event::Flag, Event};
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 use tokio::{
     join,
@@ -12,18 +18,25 @@ use tokio::{
         broadcast::{self, error::RecvError},
         oneshot, watch,
     },
+    time::Instant,
 };
-use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath};
+use turborepo_lockfiles::Lockfile;
 use turborepo_repository::{
     discovery::{
-        DiscoveryResponse, LocalPackageDiscoveryBuilder, PackageDiscovery, PackageDiscoveryBuilder,
-        WorkspaceData,
+        apply_nested_workspace_policy, discover_ecosystem_workspaces, owning_workspace,
+        validate_workspaces, DiscoveryBackend, DiscoveryDiagnostic, DiscoveryResponse,
+        EcosystemWorkspace, LocalPackageDiscoveryBuilder, NestedWorkspacePolicy, PackageDiscovery,
+        PackageDiscoveryBuilder, WorkspaceData,
     },
-    package_manager::{self, PackageManager, WorkspaceGlobs},
+    package_graph::PackageGraphBuilder,
+    package_json::PackageJson,
+    package_manager::{self, PackageManager, WorkspaceGlobs, YarnRc},
 };
 
 use crate::{
     cookies::{CookieRegister, CookieWriter, CookiedOptionalWatch},
+    discovery_snapshot::DiscoverySnapshot,
     optional_watch::OptionalWatch,
     NotifyError,
 };
@@ -49,6 +62,82 @@ pub enum PackageWatchError {
 // making a change.
 type DiscoveryData = Result<DiscoveryResponse, String>;
 
+/// The transitive closure of external dependencies resolved from the lockfile,
+/// keyed by the path to each package's `package.json`. These are the sets that
+/// a package's external dependencies hash is computed from; the hash itself
+/// is left to the consumer, since its format belongs to task hashing.
+pub type ExternalDependencies =
+    HashMap<AbsoluteSystemPathBuf, HashSet<turborepo_lockfiles::Package>>;
+
+// Consumers that fall further behind than this will get a `Lagged` error and
+// should fall back to the full snapshot.
+const PACKAGE_CHANGE_CAPACITY: usize = 128;
+
+/// The longest the package watcher waits for the filesystem to settle by
+/// default.
+pub const DEFAULT_MAX_WAIT: Duration = Duration::from_secs(2);
+
+/// Controls how long the package watcher waits for the filesystem to settle
+/// before updating discovery. Events received while waiting are handled
+/// together, so that operations touching many files, like a `git checkout`,
+/// only cause discovery to be recomputed once.
+#[derive(Clone, Debug)]
+pub struct PackageWatcherOptions {
+    /// How long to wait after the first event before handling it
+    pub debounce: Duration,
+    /// How long there must be no events affecting workspaces, such as changes
+    /// to package.json files or workspace directories, before handling them
+    pub quiescence: Duration,
+    /// The longest to wait for the filesystem to settle. Events are handled
+    /// once this has passed since the first one, even if more keep arriving,
+    /// so that continuous churn can't hold up discovery indefinitely. This
+    /// never cuts the debounce short.
+    pub max_wait: Duration,
+    /// Where to write the discovered packages when the package watcher is
+    /// dropped. If a snapshot from a previous package watcher is here, it's
+    /// used to answer discovery queries while the repo is rescanned.
+    pub snapshot_path: Option<AbsoluteSystemPathBuf>,
+    /// Discovers workspaces from other ecosystems, such as Cargo, alongside
+    /// the package manager's workspaces. They're rediscovered whenever one of
+    /// the backend's manifests changes. There are none by default.
+    pub backends: Vec<Arc<dyn DiscoveryBackend>>,
+}
+
+impl Default for PackageWatcherOptions {
+    fn default() -> Self {
+        Self {
+            debounce: Duration::ZERO,
+            quiescence: Duration::ZERO,
+            max_wait: DEFAULT_MAX_WAIT,
+            snapshot_path: None,
+            backends: Vec::new(),
+        }
+    }
+}
+
+/// A change to the set of discovered packages. Changes are computed between
+/// consecutive valid discovery states, so consumers can update their own
+/// state without diffing full snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PackageChangeEvent {
+    PackagesAdded(Vec<WorkspaceData>),
+    PackagesRemoved(Vec<WorkspaceData>),
+    /// The package's `package.json` was modified, or its `turbo.json` was
+    /// added or removed.
+    PackageChanged(WorkspaceData),
+    /// The `name` in the package's `package.json` changed. This is sent in
+    /// addition to `PackageChanged`, and anything that refers to packages by
+    /// name, such as the package graph, needs to be rebuilt.
+    PackageRenamed {
+        workspace: WorkspaceData,
+        previous_name: Option<String>,
+        name: Option<String>,
+    },
+    /// The package's `turbo.json` was added, modified, or removed, so its task
+    /// definitions need to be reloaded.
+    PackageConfigChanged(WorkspaceData),
+}
+
 /// Watches the filesystem for changes to packages and package managers.
 pub struct PackageWatcher {
     // _exit_ch exists to trigger a close on the receiver when an instance
@@ -58,6 +147,11 @@ pub struct PackageWatcher {
     _exit_tx: oneshot::Sender<()>,
     _handle: tokio::task::JoinHandle<()>,
     package_discovery_lazy: CookiedOptionalWatch<DiscoveryData, ()>,
+    package_change_tx: broadcast::Sender<PackageChangeEvent>,
+    external_dependencies_rx: watch::Receiver<Option<ExternalDependencies>>,
+    diagnostics_rx: watch::Receiver<Vec<DiscoveryDiagnostic>>,
+    repo_root: AbsoluteSystemPathBuf,
+    snapshot_path: Option<AbsoluteSystemPathBuf>,
 }
 
 impl PackageWatcher {
@@ -68,18 +162,65 @@ impl PackageWatcher {
         root: AbsoluteSystemPathBuf,
         recv: OptionalWatch<broadcast::Receiver<Result<Event, NotifyError>>>,
         cookie_writer: CookieWriter,
+    ) -> Result<Self, package_manager::Error> {
+        Self::new_with_options(root, recv, cookie_writer, PackageWatcherOptions::default())
+    }
+
+    /// Creates a new package watcher that waits for the filesystem to settle
+    /// according to `options` before updating discovery.
+    pub fn new_with_options(
+        root: AbsoluteSystemPathBuf,
+        recv: OptionalWatch<broadcast::Receiver<Result<Event, NotifyError>>>,
+        cookie_writer: CookieWriter,
+        options: PackageWatcherOptions,
     ) -> Result<Self, package_manager::Error> {
         let (exit_tx, exit_rx) = oneshot::channel();
-        let subscriber = Subscriber::new(root, cookie_writer)?;
+        let snapshot_path = options.snapshot_path.clone();
+        let subscriber = Subscriber::new(root.clone(), cookie_writer, options)?;
         let package_discovery_lazy = subscriber.package_discovery();
+        let package_change_tx = subscriber.package_change_tx.clone();
+        let external_dependencies_rx = subscriber.external_dependencies_tx.subscribe();
+        let diagnostics_rx = subscriber.diagnostics_tx.subscribe();
         let handle = tokio::spawn(subscriber.watch(exit_rx, recv));
         Ok(Self {
             _exit_tx: exit_tx,
             _handle: handle,
             package_discovery_lazy,
+            package_change_tx,
+            external_dependencies_rx,
+            diagnostics_rx,
+            repo_root: root,
+            snapshot_path,
         })
     }
 
+    /// The external dependencies of each package, resolved from the lockfile
+    /// and kept up to date as the lockfile and package.json files change. The
+    /// value is `None` until they have been resolved, or if the lockfile is
+    /// missing or can't be parsed.
+    ///
+    /// Runs don't read this yet: they still parse the lockfile themselves,
+    /// since the global hash, `--filter=[ref]` change detection and prune all
+    /// need the parsed lockfile rather than the resolved closures.
+    pub fn external_dependencies(&self) -> watch::Receiver<Option<ExternalDependencies>> {
+        self.external_dependencies_rx.clone()
+    }
+
+    /// Problems found with the current set of workspaces, such as workspaces
+    /// nested inside of other workspaces, duplicate package names, or
+    /// package.json files that can't be parsed. Re-checked whenever a
+    /// workspace is added, removed, or its package.json changes.
+    pub fn diagnostics(&self) -> watch::Receiver<Vec<DiscoveryDiagnostic>> {
+        self.diagnostics_rx.clone()
+    }
+
+    /// Subscribes to changes to the set of discovered packages. Only changes
+    /// made after subscribing are received, so the initial state should be
+    /// read with `discover_packages` or `discover_packages_blocking`.
+    pub fn subscribe_package_changes(&self) -> broadcast::Receiver<PackageChangeEvent> {
+        self.package_change_tx.subscribe()
+    }
+
     pub async fn discover_packages(&self) -> Option<Result<DiscoveryResponse, PackageWatchError>> {
         tracing::debug!("discovering packages using watcher implementation");
 
@@ -95,6 +236,20 @@ impl PackageWatcher {
         })
     }
 
+    /// Finds the package that `path`, relative to the repo root, belongs to.
+    /// A path inside of a nested workspace belongs to the innermost one that
+    /// is discovered. Returns `None` if the path isn't inside of any package.
+    pub async fn owning_package(
+        &self,
+        path: &AnchoredSystemPath,
+    ) -> Result<Option<WorkspaceData>, PackageWatchError> {
+        let discovery = self.discover_packages_blocking().await?;
+        Ok(owning_workspace(
+            discovery.workspaces,
+            &self.repo_root.resolve(path),
+        ))
+    }
+
     // if the event that either of the dependencies will never resolve,
     // this will still return unavailable
     pub async fn discover_packages_blocking(&self) -> Result<DiscoveryResponse, PackageWatchError> {
@@ -109,16 +264,65 @@ impl PackageWatcher {
     }
 }
 
+impl Drop for PackageWatcher {
+    // Writes the current discovery state to the snapshot, or removes the
+    // snapshot if there isn't a valid state to write
+    fn drop(&mut self) {
+        let Some(snapshot_path) = &self.snapshot_path else {
+            return;
+        };
+        let mut discovery = self.package_discovery_lazy.clone();
+        let result = match discovery
+            .get_immediate_raw("writing discovery snapshot")
+            .now_or_never()
+            .flatten()
+        {
+            Some(Ok(data)) if data.is_ok() => {
+                let response = data.as_ref().expect("checked");
+                DiscoverySnapshot::new(&self.repo_root, response).write(snapshot_path)
+            }
+            _ => match snapshot_path.remove_file() {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                result => result,
+            },
+        };
+        if let Err(e) = result {
+            tracing::debug!("failed to write discovery snapshot: {}", e);
+        }
+    }
+}
+
 /// The underlying task that listens to file system events and updates the
 /// internal package state.
 struct Subscriber {
     repo_root: AbsoluteSystemPathBuf,
+    options: PackageWatcherOptions,
+    // The full scan that runs after seeding from a snapshot
+    rescan: Option<tokio::task::JoinHandle<State>>,
     // This is the list of paths that will trigger rediscovering everything.
     invalidation_paths: Vec<AbsoluteSystemPathBuf>,
+    // Paths written by installing dependencies that aren't covered by the
+    // lockfile, such as Yarn's Plug'n'Play files and package cache. Changes to
+    // these only affect external dependencies.
+    install_state_paths: Vec<AbsoluteSystemPathBuf>,
+    // The workspaces found by `options.backends`, published alongside the
+    // package manager's workspaces
+    ecosystem_workspaces: Vec<EcosystemWorkspace>,
 
     package_discovery_tx: watch::Sender<Option<DiscoveryData>>,
     package_discovery_lazy: CookiedOptionalWatch<DiscoveryData, ()>,
     cookie_tx: CookieRegister,
+    package_change_tx: broadcast::Sender<PackageChangeEvent>,
+    // The workspaces of the last valid state, used to compute package changes
+    last_workspaces: HashMap<AbsoluteSystemPathBuf, WorkspaceData>,
+    // The last readable fields of each workspace's package.json, keyed by its
+    // path, used to detect renames and dependency changes
+    manifests: HashMap<AbsoluteSystemPathBuf, ManifestFields>,
+    // The discovery to resolve external dependencies for, which is done in a
+    // separate task since it can mean parsing a large lockfile
+    external_dependencies_requests: watch::Sender<Option<DiscoveryResponse>>,
+    external_dependencies_tx: watch::Sender<Option<ExternalDependencies>>,
+    diagnostics_tx: watch::Sender<Vec<DiscoveryDiagnostic>>,
 }
 
 /// PackageWatcher state. We either don't have a valid package manager,
@@ -146,6 +350,8 @@ const INVALIDATION_PATHS: &[&str] = &[
     "package-lock.json",
     "yarn.lock",
     "bun.lockb",
+    "bun.lock",
+    ".yarnrc.yml",
 ];
 
 impl Subscriber {
@@ -156,6 +362,7 @@ impl Subscriber {
     fn new(
         repo_root: AbsoluteSystemPathBuf,
         writer: CookieWriter,
+        options: PackageWatcherOptions,
     ) -> Result<Self, package_manager::Error> {
         let (package_discovery_tx, cookie_tx, package_discovery_lazy) =
             CookiedOptionalWatch::new(writer);
@@ -163,12 +370,26 @@ impl Subscriber {
             .iter()
             .map(|p| repo_root.join_component(p))
             .collect();
+        let (package_change_tx, _) = broadcast::channel(PACKAGE_CHANGE_CAPACITY);
+        let (external_dependencies_requests, _) = watch::channel(None);
+        let (external_dependencies_tx, _) = watch::channel(None);
+        let (diagnostics_tx, _) = watch::channel(Vec::new());
         Ok(Self {
             repo_root,
+            options,
+            rescan: None,
             invalidation_paths,
+            install_state_paths: Vec::new(),
+            ecosystem_workspaces: Vec::new(),
             package_discovery_tx,
             package_discovery_lazy,
             cookie_tx,
+            package_change_tx,
+            last_workspaces: HashMap::new(),
+            manifests: HashMap::new(),
+            external_dependencies_requests,
+            external_dependencies_tx,
+            diagnostics_tx,
         })
     }
 
@@ -186,13 +407,48 @@ impl Subscriber {
         // is expected to be mutated in place by handle_file_event. Both
         // rediscover_everything and handle_file_event are responsible for
         // broadcasting updates to state.
-        let mut state = self.rediscover_and_write_state().await;
+        //
+        // If there's a valid snapshot, it's published right away, and the repo is
+        // rescanned in the background while file events are handled as usual.
+        let mut state = match self.seed_from_snapshot() {
+            Some(state) => {
+                self.update_install_state_paths(&state);
+                self.update_ecosystem_workspaces().await;
+                self.write_state(&state);
+                self.rescan = Some(tokio::spawn(Self::rediscover(self.repo_root.clone())));
+                state
+            }
+            None => self.rediscover_and_write_state().await,
+        };
 
         tracing::debug!("package watcher ready {:?}", state);
+        // a message that interrupted waiting for the filesystem to settle
+        let mut pending = None;
         loop {
-            let file_event = recv.recv().await;
+            let file_event = match pending.take() {
+                Some(file_event) => file_event,
+                None => tokio::select! {
+                    Some(rescanned) = OptionFuture::from(self.rescan.as_mut()) => {
+                        self.rescan = None;
+                        state = match rescanned {
+                            Ok(state) => state,
+                            Err(e) => {
+                                tracing::debug!("background rescan failed: {}", e);
+                                Self::rediscover(self.repo_root.clone()).await
+                            }
+                        };
+                        self.write_rediscovered_state(&state).await;
+                        continue;
+                    }
+                    file_event = recv.recv() => file_event,
+                },
+            };
             match file_event {
-                Ok(Ok(event)) => self.handle_file_event(&mut state, &event).await,
+                Ok(Ok(event)) => {
+                    let (event, interrupted) = self.settle(event, &mut recv, &state).await;
+                    pending = interrupted;
+                    self.handle_file_event(&mut state, &event).await
+                }
                 // if we get an error, we need to re-discover the packages
                 Ok(Err(_)) => state = self.rediscover_and_write_state().await,
                 Err(e @ RecvError::Closed) => {
@@ -213,6 +469,11 @@ impl Subscriber {
         exit_rx: oneshot::Receiver<()>,
         recv: OptionalWatch<broadcast::Receiver<Result<Event, NotifyError>>>,
     ) {
+        let resolver = tokio::spawn(resolve_external_dependencies(
+            self.repo_root.clone(),
+            self.external_dependencies_requests.subscribe(),
+            self.external_dependencies_tx.clone(),
+        ));
         let process = tokio::spawn(self.watch_process(recv));
         tokio::select! {
             biased;
@@ -227,6 +488,100 @@ impl Subscriber {
                 }
             }
         }
+        resolver.abort();
+    }
+
+    /// Waits for the filesystem to settle, merging the events received in the
+    /// meantime into `event`. If anything other than an event is received,
+    /// this stops waiting and returns it so that it can be handled next.
+    async fn settle(
+        &self,
+        mut event: Event,
+        recv: &mut broadcast::Receiver<Result<Event, NotifyError>>,
+        state: &State,
+    ) -> (Event, Option<Result<Result<Event, NotifyError>, RecvError>>) {
+        let PackageWatcherOptions {
+            debounce,
+            quiescence,
+            max_wait,
+            ..
+        } = self.options;
+        if debounce.is_zero() && quiescence.is_zero() {
+            return (event, None);
+        }
+        let start = Instant::now();
+        let latest_deadline = start + max_wait.max(debounce);
+        let mut last_relevant = self.event_is_relevant(state, &event).then_some(start);
+        let mut interrupted = None;
+        loop {
+            let deadline = last_relevant
+                .map_or(start + debounce, |last| {
+                    (start + debounce).max(last + quiescence)
+                })
+                .min(latest_deadline);
+            match tokio::time::timeout_at(deadline, recv.recv()).await {
+                Err(_) => break,
+                Ok(Ok(Ok(next))) => {
+                    if self.event_is_relevant(state, &next) {
+                        last_relevant = Some(Instant::now());
+                    }
+                    if next.need_rescan() {
+                        event = event.set_flag(Flag::Rescan);
+                    }
+                    event.paths.extend(next.paths);
+                }
+                Ok(other) => {
+                    interrupted = Some(other);
+                    break;
+                }
+            }
+        }
+        event.paths.sort();
+        event.paths.dedup();
+        (event, interrupted)
+    }
+
+    // Whether an event could change the discovered workspaces
+    fn event_is_relevant(&self, state: &State, event: &Event) -> bool {
+        if event.need_rescan() {
+            return true;
+        }
+        event.paths.iter().any(|path| {
+            if self.path_invalidates_everything(path)
+                || self.path_is_install_state(path)
+                || self.path_is_ecosystem_manifest(path)
+            {
+                return true;
+            }
+            let State::ValidWorkspaces { filter, .. } = state else {
+                return false;
+            };
+            path.to_str()
+                .and_then(|path| AbsoluteSystemPathBuf::new(path).ok())
+                .map_or(false, |path| {
+                    self.workspace_for_path(filter, &path).is_some()
+                })
+        })
+    }
+
+    // Returns the workspace directory that a change to `path` could affect:
+    // the parent of a package.json or turbo.json, or a workspace directory
+    // itself.
+    fn workspace_for_path<'a>(
+        &self,
+        filter: &WorkspaceGlobs,
+        path: &'a AbsoluteSystemPath,
+    ) -> Option<&'a AbsoluteSystemPath> {
+        let workspace = if matches!(path.file_name(), Some("package.json" | "turbo.json")) {
+            path.parent()
+                .expect("watched paths will not be at the root")
+        } else {
+            path
+        };
+        filter
+            .target_is_workspace(&self.repo_root, workspace)
+            .unwrap_or(false)
+            .then_some(workspace)
     }
 
     fn package_discovery(&self) -> CookiedOptionalWatch<DiscoveryData, ()> {
@@ -239,10 +594,29 @@ impl Subscriber {
             .any(|invalidation_path| path.eq(invalidation_path as &AbsoluteSystemPath))
     }
 
+    fn path_is_install_state(&self, path: &Path) -> bool {
+        self.install_state_paths
+            .iter()
+            .any(|install_state_path| path.starts_with(install_state_path))
+    }
+
+    fn path_is_ecosystem_manifest(&self, path: &Path) -> bool {
+        AbsoluteSystemPath::from_std_path(path).map_or(false, |path| {
+            self.options
+                .backends
+                .iter()
+                .any(|backend| backend.is_manifest(path))
+        })
+    }
+
     async fn handle_file_event(&mut self, state: &mut State, file_event: &Event) {
         tracing::trace!("file event: {:?} {:?}", file_event.kind, file_event.paths);
 
-        if file_event
+        if file_event.need_rescan() {
+            // we may have missed changes to any of the workspaces under the rescanned
+            // directories, so rediscover everything
+            *state = self.rediscover_and_write_state().await;
+        } else if file_event
             .paths
             .iter()
             .any(|path| self.path_invalidates_everything(path))
@@ -252,6 +626,22 @@ impl Subscriber {
         } else {
             tracing::trace!("handling non-root package.json change");
             self.handle_workspace_changes(state, file_event).await;
+            if file_event
+                .paths
+                .iter()
+                .any(|path| self.path_is_install_state(path))
+            {
+                // treated like a lockfile change, but the workspaces are unaffected
+                self.update_external_dependencies(state);
+            }
+            if file_event
+                .paths
+                .iter()
+                .any(|path| self.path_is_ecosystem_manifest(path))
+                && self.update_ecosystem_workspaces().await
+            {
+                self.write_state(state);
+            }
         }
 
         tracing::trace!("updating the cookies");
@@ -267,8 +657,8 @@ impl Subscriber {
         );
     }
 
-    // checks if the file event contains any changes to package.json files, or
-    // directories that would map to a workspace.
+    // checks if the file event contains any changes to package.json or turbo.json
+    // files, or directories that would map to a workspace.
     async fn handle_workspace_changes(&mut self, state: &mut State, file_event: &Event) {
         // If we don't have a valid package manager and workspace globs, nothing to be
         // done here
@@ -281,39 +671,23 @@ impl Subscriber {
 
         // here, we can only update if we have a valid package state
         let mut changed = false;
+        // existing workspaces whose package.json was modified
+        let mut modified = Vec::new();
+        // workspaces whose turbo.json was added, modified, or removed
+        let mut config_changed = Vec::new();
         // if a path is not a valid utf8 string, it is not a valid path, so ignore
         for path in file_event
             .paths
             .iter()
+            .filter(|p| !self.path_is_install_state(p))
             .filter_map(|p| p.as_os_str().to_str())
         {
             let path_file = AbsoluteSystemPathBuf::new(path).expect("watched paths are absolute");
-            let path_workspace: &AbsoluteSystemPath =
-                if path_file.file_name() == Some("package.json") {
-                    // The file event is for a package.json file. Check if the parent is a workspace
-                    let path_parent = path_file
-                        .parent()
-                        .expect("watched paths will not be at the root");
-                    if filter
-                        .target_is_workspace(&self.repo_root, path_parent)
-                        .unwrap_or(false)
-                    {
-                        path_parent
-                    } else {
-                        // irrelevant package.json file update, it's not in a directory
-                        // matching workspace globs
-                        continue;
-                    }
-                } else if filter
-                    .target_is_workspace(&self.repo_root, &path_file)
-                    .unwrap_or(false)
-                {
-                    // The file event is for a workspace directory itself
-                    &path_file
-                } else {
-                    // irrelevant file update, it's not a package.json file or a workspace directory
-                    continue;
-                };
+            let Some(path_workspace) = self.workspace_for_path(filter, &path_file) else {
+                // irrelevant file update, it's not a package.json or turbo.json file in a
+                // workspace, or a workspace directory
+                continue;
+            };
 
             tracing::debug!("handling change to workspace {path_workspace}");
             let package_json = path_workspace.join_component("package.json");
@@ -328,23 +702,83 @@ impl Subscriber {
             );
 
             changed |= if package_exists {
-                workspaces
-                    .insert(
-                        path_workspace.to_owned(),
-                        WorkspaceData {
-                            package_json,
-                            turbo_json: turbo_exists.unwrap_or_default().then_some(turbo_json),
-                        },
-                    )
-                    .is_none()
+                let data = WorkspaceData {
+                    package_json,
+                    turbo_json: turbo_exists.unwrap_or_default().then_some(turbo_json),
+                };
+                if path_file.file_name() == Some("turbo.json") && !config_changed.contains(&data) {
+                    config_changed.push(data.clone());
+                }
+                match workspaces.insert(path_workspace.to_owned(), data.clone()) {
+                    Some(previous) if previous == data => {
+                        if path_file.file_name() == Some("package.json") {
+                            modified.push(data);
+                        }
+                        false
+                    }
+                    _ => true,
+                }
             } else {
                 workspaces.remove(path_workspace).is_some()
             }
         }
 
-        if changed {
+        let any_modified = !modified.is_empty();
+        let mut renamed = Vec::new();
+        let mut dependencies_changed = false;
+        for data in &modified {
+            let (rename, changed_dependencies) = self.update_manifest(data);
+            renamed.extend(rename);
+            dependencies_changed |= changed_dependencies;
+        }
+
+        // A rename doesn't change the discovered workspaces, but we still publish
+        // the state again so that anyone waiting on discovery picks up the new name.
+        if changed || !renamed.is_empty() {
             self.write_state(state);
+        } else if any_modified {
+            self.send_diagnostics(visible_workspaces(workspaces).1);
+        }
+        for data in modified {
+            let _ = self
+                .package_change_tx
+                .send(PackageChangeEvent::PackageChanged(data));
+        }
+        for event in renamed {
+            let _ = self.package_change_tx.send(event);
         }
+        for data in config_changed {
+            let _ = self
+                .package_change_tx
+                .send(PackageChangeEvent::PackageConfigChanged(data));
+        }
+        // Edits that don't touch dependencies can't change external dependencies
+        if changed || dependencies_changed {
+            self.update_external_dependencies(state);
+        }
+    }
+
+    /// Re-reads the package.json of a workspace, returning a `PackageRenamed`
+    /// event if its name differs from the last name we read, and whether its
+    /// dependencies changed. If we haven't read it before, we can't tell, so
+    /// its dependencies are assumed to have changed.
+    fn update_manifest(&mut self, data: &WorkspaceData) -> (Option<PackageChangeEvent>, bool) {
+        let Some(fields) = read_manifest_fields(&data.package_json) else {
+            return (None, false);
+        };
+        let Some(previous) = self
+            .manifests
+            .insert(data.package_json.clone(), fields.clone())
+        else {
+            return (None, true);
+        };
+        let dependencies_changed = previous.dependencies != fields.dependencies;
+        let renamed = (previous.name != fields.name).then(|| PackageChangeEvent::PackageRenamed {
+            workspace: data.clone(),
+            previous_name: previous.name,
+            name: fields.name,
+        });
+        (renamed, dependencies_changed)
     }
 
     fn reset_discovery_data(&self) {
@@ -359,21 +793,137 @@ impl Subscriber {
     }
 
     async fn rediscover_and_write_state(&mut self) -> State {
+        // A full rediscovery supersedes any rescan that's still running
+        if let Some(rescan) = self.rescan.take() {
+            rescan.abort();
+        }
         // If we're rediscovering the package manager, clear all data
         self.reset_discovery_data();
-        let state = self.rediscover().await;
-        self.write_state(&state);
+        let state = Self::rediscover(self.repo_root.clone()).await;
+        self.write_rediscovered_state(&state).await;
         state
     }
 
-    async fn rediscover(&self) -> State {
+    async fn write_rediscovered_state(&mut self, state: &State) {
+        self.update_install_state_paths(state);
+        self.update_ecosystem_workspaces().await;
+        self.write_state(state);
+        self.update_external_dependencies(state);
+    }
+
+    fn update_install_state_paths(&mut self, state: &State) {
+        self.install_state_paths = match state {
+            State::ValidWorkspaces {
+                package_manager: PackageManager::Berry,
+                ..
+            } => match YarnRc::load(&self.repo_root) {
+                Ok(yarnrc) => yarnrc.install_state_paths(&self.repo_root),
+                Err(e) => {
+                    tracing::debug!("failed to read .yarnrc.yml: {}", e);
+                    Vec::new()
+                }
+            },
+            _ => Vec::new(),
+        };
+    }
+
+    /// Reruns the discovery backends, returning whether the workspaces they
+    /// found changed.
+    async fn update_ecosystem_workspaces(&mut self) -> bool {
+        if self.options.backends.is_empty() {
+            return false;
+        }
+        // backends glob the filesystem, so keep them off the event loop
+        let backends = self.options.backends.clone();
+        let repo_root = self.repo_root.clone();
+        let ecosystem_workspaces = match tokio::task::spawn_blocking(move || {
+            discover_ecosystem_workspaces(&backends, &repo_root)
+        })
+        .await
+        {
+            Ok(ecosystem_workspaces) => ecosystem_workspaces,
+            Err(e) => {
+                tracing::warn!("failed to discover ecosystem workspaces: {}", e);
+                return false;
+            }
+        };
+        if ecosystem_workspaces == self.ecosystem_workspaces {
+            return false;
+        }
+        tracing::debug!(
+            "rediscovered ecosystem workspaces: {:?}",
+            ecosystem_workspaces
+        );
+        self.ecosystem_workspaces = ecosystem_workspaces;
+        true
+    }
+
+    /// Builds the initial state from the snapshot left by a previous package
+    /// watcher, if there is one and it still matches the filesystem.
+    fn seed_from_snapshot(&self) -> Option<State> {
+        let snapshot_path = self.options.snapshot_path.as_ref()?;
+        let snapshot = match DiscoverySnapshot::load(snapshot_path) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                tracing::debug!("failed to load discovery snapshot: {}", e);
+                return None;
+            }
+        };
+        let Some(discovery) = snapshot.verify(&self.repo_root) else {
+            tracing::debug!("discovery snapshot is stale");
+            return None;
+        };
+        let filter = discovery
+            .package_manager
+            .get_workspace_globs(&self.repo_root)
+            .ok()?;
+        tracing::debug!("seeding package discovery from snapshot");
+        Some(State::ValidWorkspaces {
+            package_manager: discovery.package_manager,
+            filter,
+            workspaces: discovery
+                .workspaces
+                .into_iter()
+                .map(|p| (p.package_json.parent().expect("non-root").to_owned(), p))
+                .collect(),
+        })
+    }
+
+    /// Requests that the external dependencies of every workspace are
+    /// resolved again. Requests made while resolving are coalesced, so only
+    /// the latest state is resolved.
+    fn update_external_dependencies(&self, state: &State) {
+        let State::ValidWorkspaces {
+            package_manager,
+            workspaces,
+            ..
+        } = state
+        else {
+            self.external_dependencies_requests.send_replace(None);
+            return;
+        };
+        let discovery = DiscoveryResponse {
+            package_manager: *package_manager,
+            workspaces: visible_workspaces(workspaces).0,
+            // the package graph only contains the package manager's workspaces
+            ecosystem_workspaces: Vec::new(),
+        };
+        self.external_dependencies_requests
+            .send_replace(Some(discovery));
+    }
+
+    async fn rediscover(repo_root: AbsoluteSystemPathBuf) -> State {
         // If we're rediscovering everything, we need to rediscover the package manager.
         // It may have changed if a lockfile changed or package.json changed.
-        let discovery =
-            match LocalPackageDiscoveryBuilder::new(self.repo_root.clone(), None, None).build() {
-                Ok(discovery) => discovery,
-                Err(e) => return State::NoPackageManager(e.to_string()),
-            };
+        // Nested workspaces are kept in the state, since they can become visible
+        // when their parent is removed. The policy is applied when writing the state.
+        let discovery = match LocalPackageDiscoveryBuilder::new(repo_root.clone(), None, None)
+            .with_nested_workspace_policy(NestedWorkspacePolicy::Include)
+            .build()
+        {
+            Ok(discovery) => discovery,
+            Err(e) => return State::NoPackageManager(e.to_string()),
+        };
         let initial_discovery = match discovery.discover_packages().await {
             Ok(discovery) => discovery,
             // If we failed the discovery, that's fine, we've reset the values, leave them as None
@@ -386,7 +936,7 @@ impl Subscriber {
         tracing::debug!("rediscovered packages: {:?}", initial_discovery);
         let filter = match initial_discovery
             .package_manager
-            .get_workspace_globs(&self.repo_root)
+            .get_workspace_globs(&repo_root)
         {
             Ok(filter) => filter,
             Err(e) => {
@@ -408,7 +958,7 @@ impl Subscriber {
         }
     }
 
-    fn write_state(&self, state: &State) {
+    fn write_state(&mut self, state: &State) {
         match state {
             State::NoPackageManager(e) | State::InvalidGlobs(e) => {
                 self.package_discovery_tx.send_if_modified(|existing| {
@@ -421,32 +971,278 @@ impl Subscriber {
                         }
                     }
                 });
+                self.send_diagnostics(Vec::new());
             }
             State::ValidWorkspaces {
                 package_manager,
                 workspaces,
                 ..
             } => {
+                let (workspaces, diagnostics) = visible_workspaces(workspaces);
                 let resp = DiscoveryResponse {
                     package_manager: *package_manager,
-                    workspaces: workspaces.values().cloned().collect(),
+                    workspaces: workspaces.clone(),
+                    ecosystem_workspaces: self.ecosystem_workspaces.clone(),
                 };
                 // Note that we could implement PartialEq for DiscoveryResponse, but we
                 // would need to sort the workspace data.
                 let _ = self.package_discovery_tx.send(Some(Ok(resp)));
+                self.send_diagnostics(diagnostics);
+                self.send_package_changes(
+                    &workspaces
+                        .into_iter()
+                        .map(|data| {
+                            (
+                                data.package_json.parent().expect("non-root").to_owned(),
+                                data,
+                            )
+                        })
+                        .collect(),
+                );
             }
         }
     }
+
+    fn send_diagnostics(&self, diagnostics: Vec<DiscoveryDiagnostic>) {
+        self.diagnostics_tx.send_if_modified(|existing| {
+            if *existing == diagnostics {
+                false
+            } else {
+                *existing = diagnostics;
+                true
+            }
+        });
+    }
+
+    fn send_package_changes(&mut self, workspaces: &HashMap<AbsoluteSystemPathBuf, WorkspaceData>) {
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        for (path, data) in workspaces {
+            match self.last_workspaces.remove(path) {
+                None => added.push(data.clone()),
+                Some(previous) if previous != *data => changed.push(data.clone()),
+                Some(_) => {}
+            }
+        }
+        // anything left over from the last state is no longer a workspace
+        let removed = std::mem::replace(&mut self.last_workspaces, workspaces.clone())
+            .into_values()
+            .collect::<Vec<_>>();
+
+        for data in &removed {
+            self.manifests.remove(&data.package_json);
+        }
+        for data in &added {
+            if let Some(fields) = read_manifest_fields(&data.package_json) {
+                self.manifests.insert(data.package_json.clone(), fields);
+            }
+        }
+
+        // send errors just mean there are no subscribers
+        if !removed.is_empty() {
+            let _ = self
+                .package_change_tx
+                .send(PackageChangeEvent::PackagesRemoved(removed));
+        }
+        if !added.is_empty() {
+            let _ = self
+                .package_change_tx
+                .send(PackageChangeEvent::PackagesAdded(added));
+        }
+        for data in changed {
+            let _ = self
+                .package_change_tx
+                .send(PackageChangeEvent::PackageChanged(data));
+        }
+    }
 }
 
-#[cfg(test)]
-mod test {
-    use std::time::Duration;
+// Applies the nested workspace policy to the watched workspaces and validates
+// the remaining ones. They're sorted first so that the diagnostics are stable.
+fn visible_workspaces(
+    workspaces: &HashMap<AbsoluteSystemPathBuf, WorkspaceData>,
+) -> (Vec<WorkspaceData>, Vec<DiscoveryDiagnostic>) {
+    let mut workspaces = workspaces.values().cloned().collect::<Vec<_>>();
+    workspaces.sort_by(|a, b| a.package_json.cmp(&b.package_json));
+    let (workspaces, mut diagnostics) =
+        apply_nested_workspace_policy(workspaces, NestedWorkspacePolicy::default());
+    diagnostics.extend(validate_workspaces(&workspaces));
+    (workspaces, diagnostics)
+}
+
+// The fields of a package.json that we track changes to
+#[derive(Clone, Debug, PartialEq)]
+struct ManifestFields {
+    name: Option<String>,
+    // the dependencies that external dependencies are resolved from
+    dependencies: BTreeMap<String, String>,
+}
+
+// Returns `None` if the package.json can't be read, which is usually because
+// it's in the middle of being edited.
+fn read_manifest_fields(package_json: &AbsoluteSystemPath) -> Option<ManifestFields> {
+    let package_json = PackageJson::load(package_json).ok()?;
+    let dependencies = package_json
+        .all_dependencies()
+        .map(|(name, version)| (name.clone(), version.clone()))
+        .collect();
+    Some(ManifestFields {
+        name: package_json.name,
+        dependencies,
+    })
+}
+
+/// A parsed lockfile, along with what it was parsed from so that we can tell
+/// whether it's still current.
+struct CachedLockfile {
+    key: LockfileKey,
+    lockfile: Box<dyn Lockfile>,
+}
+
+#[derive(Debug, PartialEq)]
+struct LockfileKey {
+    package_manager: PackageManager,
+    // a digest of the lockfile's contents, which is much cheaper than parsing it
+    contents: Option<Vec<u8>>,
+    // Berry's lockfile is parsed with the resolutions from the root package.json
+    resolutions: Option<BTreeMap<String, String>>,
+}
+
+impl LockfileKey {
+    fn new(
+        repo_root: &AbsoluteSystemPath,
+        package_manager: PackageManager,
+        root_package_json: &PackageJson,
+    ) -> Self {
+        let contents = package_manager
+            .lockfile_path(repo_root)
+            .read()
+            .ok()
+            .map(|contents| Sha256::digest(contents).to_vec());
+        Self {
+            package_manager,
+            contents,
+            resolutions: (package_manager == PackageManager::Berry)
+                .then(|| root_package_json.resolutions.clone())
+                .flatten(),
+        }
+    }
+}
+
+/// Resolves the external dependencies of each discovery sent on `requests`,
+/// until the sender is dropped. The lockfile is only parsed again when it
+/// changes, and the work happens on the blocking threadpool so that it
+/// doesn't hold up anything else.
+async fn resolve_external_dependencies(
+    repo_root: AbsoluteSystemPathBuf,
+    mut requests: watch::Receiver<Option<DiscoveryResponse>>,
+    external_dependencies_tx: watch::Sender<Option<ExternalDependencies>>,
+) {
+    let mut cached = None;
+    while requests.changed().await.is_ok() {
+        let Some(discovery) = requests.borrow_and_update().clone() else {
+            external_dependencies_tx.send_replace(None);
+            continue;
+        };
+        let repo_root = repo_root.clone();
+        let previous = cached.take();
+        let task = tokio::task::spawn_blocking(move || {
+            resolve_with_lockfile(&repo_root, discovery, previous)
+        });
+        let external_dependencies = match task.await {
+            Ok((result, lockfile)) => {
+                cached = lockfile;
+                result
+            }
+            Err(e) => Err(e.to_string()),
+        };
+        let external_dependencies = match external_dependencies {
+            Ok(external_dependencies) => Some(external_dependencies),
+            Err(e) => {
+                tracing::debug!("failed to resolve external dependencies: {}", e);
+                None
+            }
+        };
+        external_dependencies_tx.send_replace(external_dependencies);
+    }
+}
+
+/// Builds a package graph so that external dependencies are resolved the same
+/// way as during a run, reusing `cached` if the lockfile hasn't changed since
+/// it was parsed. Returns the lockfile so that it can be reused next time.
+fn resolve_with_lockfile(
+    repo_root: &AbsoluteSystemPath,
+    discovery: DiscoveryResponse,
+    cached: Option<CachedLockfile>,
+) -> (Result<ExternalDependencies, String>, Option<CachedLockfile>) {
+    let root_package_json = match PackageJson::load(&repo_root.join_component("package.json")) {
+        Ok(root_package_json) => root_package_json,
+        Err(e) => return (Err(e.to_string()), cached),
+    };
+    let key = LockfileKey::new(repo_root, discovery.package_manager, &root_package_json);
+    let lockfile = match cached {
+        Some(cached) if cached.key == key => cached.lockfile,
+        _ => {
+            tracing::debug!("parsing lockfile to resolve external dependencies");
+            match discovery
+                .package_manager
+                .read_lockfile(repo_root, &root_package_json)
+            {
+                Ok(lockfile) => lockfile,
+                Err(e) => return (Err(e.to_string()), None),
+            }
+        }
+    };
+
+    let build = PackageGraphBuilder::new(repo_root, root_package_json)
+        .with_package_discovery(discovery)
+        .with_lockfile(Some(lockfile))
+        .build();
+    // we're on the blocking threadpool, so we can wait on the build here
+    let package_graph = match tokio::runtime::Handle::current().block_on(build) {
+        Ok(package_graph) => package_graph,
+        Err(e) => return (Err(e.to_string()), None),
+    };
+    if package_graph.lockfile().is_none() {
+        return (Err("lockfile is not available".to_string()), None);
+    }
 
-    use turbopath::AbsoluteSystemPathBuf;
-    use turborepo_repository::{discovery::WorkspaceData, package_manager::PackageManager};
+    let external_dependencies = package_graph
+        .packages()
+        .filter_map(|(_, info)| {
+            let dependencies = info.transitive_dependencies.clone()?;
+            Some((repo_root.resolve(info.package_json_path()), dependencies))
+        })
+        .collect();
+    let cached = package_graph
+        .into_lockfile()
+        .map(|lockfile| CachedLockfile { key, lockfile });
+    (Ok(external_dependencies), cached)
+}
 
-    use crate::{cookies::CookieWriter, package_watcher::PackageWatcher, FileSystemWatcher};
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::sync::{broadcast, watch};
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+    use turborepo_repository::{
+        cargo::CargoDiscovery,
+        discovery::{
+            DiscoveryDiagnostic, DiscoveryResponse, Ecosystem, EcosystemWorkspace, WorkspaceData,
+        },
+        package_json::PackageJson,
+        package_manager::PackageManager,
+    };
+
+    use crate::{
+        cookies::CookieWriter,
+        package_watcher::{
+            resolve_with_lockfile, CachedLockfile, ExternalDependencies, LockfileKey,
+            PackageChangeEvent, PackageWatcher, PackageWatcherOptions,
+        },
+        FileSystemWatcher,
+    };
 
     #[tokio::test]
     #[tracing_test::traced_test]
@@ -674,6 +1470,1013 @@ mod test {
         );
     }
 
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn subscriber_package_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        let foo = repo_root.join_components(&["packages", "foo", "package.json"]);
+        foo.ensure_dir().unwrap();
+        foo.create_with_contents(r#"{"name": "foo"}"#).unwrap();
+        repo_root
+            .join_component("package-lock.json")
+            .create_with_contents("")
+            .unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces":["packages/*"]}"#)
+            .unwrap();
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(
+            watcher.cookie_dir(),
+            Duration::from_millis(100),
+            recv.clone(),
+        );
+
+        let package_watcher = PackageWatcher::new(repo_root.clone(), recv, cookie_writer).unwrap();
+        package_watcher.discover_packages_blocking().await.unwrap();
+        let mut changes = package_watcher.subscribe_package_changes();
+
+        // waits for the next add or remove, skipping modifications
+        async fn next_change(
+            changes: &mut broadcast::Receiver<PackageChangeEvent>,
+        ) -> PackageChangeEvent {
+            loop {
+                let event = tokio::time::timeout(Duration::from_secs(5), changes.recv())
+                    .await
+                    .expect("timed out waiting for package change")
+                    .unwrap();
+                if !matches!(event, PackageChangeEvent::PackageChanged(_)) {
+                    return event;
+                }
+            }
+        }
+
+        let bar = repo_root.join_components(&["packages", "bar", "package.json"]);
+        bar.ensure_dir().unwrap();
+        bar.create_with_contents(r#"{"name": "bar"}"#).unwrap();
+        assert_eq!(
+            next_change(&mut changes).await,
+            PackageChangeEvent::PackagesAdded(vec![WorkspaceData {
+                package_json: bar.clone(),
+                turbo_json: None,
+            }])
+        );
+
+        foo.remove_file().unwrap();
+        assert_eq!(
+            next_change(&mut changes).await,
+            PackageChangeEvent::PackagesRemoved(vec![WorkspaceData {
+                package_json: foo,
+                turbo_json: None,
+            }])
+        );
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn subscriber_settles_before_discovery() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        repo_root
+            .join_component("package-lock.json")
+            .create_with_contents("")
+            .unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces":["packages/*"]}"#)
+            .unwrap();
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(
+            watcher.cookie_dir(),
+            Duration::from_millis(100),
+            recv.clone(),
+        );
+
+        let package_watcher = PackageWatcher::new_with_options(
+            repo_root.clone(),
+            recv,
+            cookie_writer,
+            PackageWatcherOptions {
+                debounce: Duration::from_millis(50),
+                quiescence: Duration::from_millis(500),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        package_watcher.discover_packages_blocking().await.unwrap();
+        let mut changes = package_watcher.subscribe_package_changes();
+
+        // each package is created well within the quiescence window of the last
+        let mut expected = Vec::new();
+        for name in ["a", "b", "c"] {
+            let package_json = repo_root.join_components(&["packages", name, "package.json"]);
+            package_json.ensure_dir().unwrap();
+            package_json
+                .create_with_contents(format!(r#"{{"name": "{name}"}}"#))
+                .unwrap();
+            expected.push(WorkspaceData {
+                package_json,
+                turbo_json: None,
+            });
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let event = tokio::time::timeout(Duration::from_secs(5), changes.recv())
+            .await
+            .expect("timed out waiting for package change")
+            .unwrap();
+        let PackageChangeEvent::PackagesAdded(mut added) = event else {
+            panic!("expected packages to be added, got {event:?}");
+        };
+        added.sort_by_key(|workspace| workspace.package_json.clone());
+        assert_eq!(added, expected);
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn subscriber_settles_within_max_wait() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        repo_root
+            .join_component("package-lock.json")
+            .create_with_contents("")
+            .unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces":["packages/*"]}"#)
+            .unwrap();
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(
+            watcher.cookie_dir(),
+            Duration::from_millis(100),
+            recv.clone(),
+        );
+
+        let package_watcher = PackageWatcher::new_with_options(
+            repo_root.clone(),
+            recv,
+            cookie_writer,
+            PackageWatcherOptions {
+                debounce: Duration::from_millis(50),
+                quiescence: Duration::from_millis(500),
+                max_wait: Duration::from_secs(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        package_watcher.discover_packages_blocking().await.unwrap();
+        let mut changes = package_watcher.subscribe_package_changes();
+
+        // keep creating packages well within the quiescence window of the last,
+        // for much longer than the max wait
+        let churn = tokio::spawn({
+            let repo_root = repo_root.clone();
+            async move {
+                for i in 0..50 {
+                    let package_json = repo_root.join_components(&[
+                        "packages",
+                        &format!("pkg-{i}"),
+                        "package.json",
+                    ]);
+                    package_json.ensure_dir().unwrap();
+                    package_json
+                        .create_with_contents(format!(r#"{{"name": "pkg-{i}"}}"#))
+                        .unwrap();
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+        });
+
+        let event = tokio::time::timeout(Duration::from_secs(3), changes.recv())
+            .await
+            .expect("discovery was held up by continuous events")
+            .unwrap();
+        assert!(
+            matches!(event, PackageChangeEvent::PackagesAdded(_)),
+            "expected packages to be added, got {event:?}"
+        );
+        assert!(!churn.is_finished(), "events should still be arriving");
+        churn.abort();
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn subscriber_discovery_snapshot() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        let foo = repo_root.join_components(&["packages", "foo", "package.json"]);
+        foo.ensure_dir().unwrap();
+        foo.create_with_contents(r#"{"name": "foo"}"#).unwrap();
+        repo_root
+            .join_component("package-lock.json")
+            .create_with_contents("")
+            .unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces":["packages/*"]}"#)
+            .unwrap();
+        let snapshot_path = repo_root.join_components(&[".turbo", "daemon", "discovery.json"]);
+        let options = PackageWatcherOptions {
+            snapshot_path: Some(snapshot_path.clone()),
+            ..Default::default()
+        };
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(
+            watcher.cookie_dir(),
+            Duration::from_millis(100),
+            recv.clone(),
+        );
+
+        let package_watcher = PackageWatcher::new_with_options(
+            repo_root.clone(),
+            recv.clone(),
+            cookie_writer.clone(),
+            options.clone(),
+        )
+        .unwrap();
+        let discovered = package_watcher.discover_packages_blocking().await.unwrap();
+        drop(package_watcher);
+        assert!(snapshot_path.exists());
+
+        // the next package watcher starts from the snapshot, and still picks up
+        // workspaces added since it was written
+        let bar = repo_root.join_components(&["packages", "bar", "package.json"]);
+        bar.ensure_dir().unwrap();
+        bar.create_with_contents(r#"{"name": "bar"}"#).unwrap();
+
+        let package_watcher =
+            PackageWatcher::new_with_options(repo_root.clone(), recv, cookie_writer, options)
+                .unwrap();
+        let mut changes = package_watcher.subscribe_package_changes();
+        let seeded = package_watcher.discover_packages_blocking().await.unwrap();
+        assert_eq!(seeded.workspaces, discovered.workspaces);
+
+        let event = tokio::time::timeout(Duration::from_secs(5), changes.recv())
+            .await
+            .expect("timed out waiting for rescan")
+            .unwrap();
+        assert_eq!(
+            event,
+            PackageChangeEvent::PackagesAdded(vec![WorkspaceData {
+                package_json: bar,
+                turbo_json: None,
+            }])
+        );
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn subscriber_owning_package() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        let foo = repo_root.join_components(&["packages", "foo", "package.json"]);
+        foo.ensure_dir().unwrap();
+        foo.create_with_contents(r#"{"name": "foo"}"#).unwrap();
+        repo_root
+            .join_component("package-lock.json")
+            .create_with_contents("")
+            .unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces":["packages/*"]}"#)
+            .unwrap();
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(
+            watcher.cookie_dir(),
+            Duration::from_millis(100),
+            recv.clone(),
+        );
+
+        let package_watcher = PackageWatcher::new(repo_root.clone(), recv, cookie_writer).unwrap();
+
+        let path = AnchoredSystemPathBuf::from_raw(
+            ["packages", "foo", "src", "index.ts"].join(std::path::MAIN_SEPARATOR_STR),
+        )
+        .unwrap();
+        assert_eq!(
+            package_watcher.owning_package(&path).await.unwrap(),
+            Some(WorkspaceData {
+                package_json: foo,
+                turbo_json: None,
+            })
+        );
+
+        let path = AnchoredSystemPathBuf::from_raw("README.md").unwrap();
+        assert_eq!(package_watcher.owning_package(&path).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn subscriber_cargo_workspaces() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        let foo = repo_root.join_components(&["packages", "foo", "package.json"]);
+        foo.ensure_dir().unwrap();
+        foo.create_with_contents(r#"{"name": "foo"}"#).unwrap();
+        let cli = repo_root.join_components(&["crates", "cli", "Cargo.toml"]);
+        cli.ensure_dir().unwrap();
+        cli.create_with_contents("[package]\nname = \"cli\"\n")
+            .unwrap();
+        repo_root
+            .join_component("Cargo.toml")
+            .create_with_contents("[workspace]\nmembers = [\"crates/*\"]\n")
+            .unwrap();
+        repo_root
+            .join_component("package-lock.json")
+            .create_with_contents("")
+            .unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces":["packages/*"]}"#)
+            .unwrap();
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(
+            watcher.cookie_dir(),
+            Duration::from_millis(100),
+            recv.clone(),
+        );
+
+        let package_watcher = PackageWatcher::new_with_options(
+            repo_root.clone(),
+            recv,
+            cookie_writer,
+            crate::package_watcher::PackageWatcherOptions {
+                backends: vec![Arc::new(CargoDiscovery)],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let discovery = package_watcher.discover_packages_blocking().await.unwrap();
+        assert_eq!(
+            discovery.workspaces,
+            vec![WorkspaceData {
+                package_json: foo,
+                turbo_json: None,
+            }]
+        );
+        assert_eq!(
+            discovery.ecosystem_workspaces,
+            vec![EcosystemWorkspace {
+                ecosystem: Ecosystem::Cargo,
+                manifest: cli.clone(),
+                name: Some("cli".to_string()),
+            }]
+        );
+
+        // adding a member is picked up without a package.json changing
+        let core = repo_root.join_components(&["crates", "core", "Cargo.toml"]);
+        core.ensure_dir().unwrap();
+        core.create_with_contents("[package]\nname = \"core\"\n")
+            .unwrap();
+
+        let discovery = package_watcher.discover_packages_blocking().await.unwrap();
+        assert_eq!(
+            discovery.ecosystem_workspaces,
+            vec![
+                EcosystemWorkspace {
+                    ecosystem: Ecosystem::Cargo,
+                    manifest: cli,
+                    name: Some("cli".to_string()),
+                },
+                EcosystemWorkspace {
+                    ecosystem: Ecosystem::Cargo,
+                    manifest: core,
+                    name: Some("core".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn subscriber_package_renamed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        let foo = repo_root.join_components(&["packages", "foo", "package.json"]);
+        foo.ensure_dir().unwrap();
+        foo.create_with_contents(r#"{"name": "foo"}"#).unwrap();
+        repo_root
+            .join_component("package-lock.json")
+            .create_with_contents("")
+            .unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces":["packages/*"]}"#)
+            .unwrap();
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(
+            watcher.cookie_dir(),
+            Duration::from_millis(100),
+            recv.clone(),
+        );
+
+        let package_watcher = PackageWatcher::new(repo_root.clone(), recv, cookie_writer).unwrap();
+        package_watcher.discover_packages_blocking().await.unwrap();
+        let mut changes = package_watcher.subscribe_package_changes();
+
+        foo.create_with_contents(r#"{"name": "renamed-foo"}"#)
+            .unwrap();
+
+        let event = loop {
+            let event = tokio::time::timeout(Duration::from_secs(5), changes.recv())
+                .await
+                .expect("timed out waiting for package rename")
+                .unwrap();
+            if matches!(event, PackageChangeEvent::PackageRenamed { .. }) {
+                break event;
+            }
+        };
+        assert_eq!(
+            event,
+            PackageChangeEvent::PackageRenamed {
+                workspace: WorkspaceData {
+                    package_json: foo.clone(),
+                    turbo_json: None,
+                },
+                previous_name: Some("foo".to_string()),
+                name: Some("renamed-foo".to_string()),
+            }
+        );
+
+        let data = package_watcher.discover_packages_blocking().await.unwrap();
+        assert_eq!(
+            data.workspaces,
+            vec![WorkspaceData {
+                package_json: foo,
+                turbo_json: None,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn subscriber_package_config_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        let foo = repo_root.join_components(&["packages", "foo", "package.json"]);
+        foo.ensure_dir().unwrap();
+        foo.create_with_contents(r#"{"name": "foo"}"#).unwrap();
+        repo_root
+            .join_component("package-lock.json")
+            .create_with_contents("")
+            .unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces":["packages/*"]}"#)
+            .unwrap();
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(
+            watcher.cookie_dir(),
+            Duration::from_millis(100),
+            recv.clone(),
+        );
+
+        let package_watcher = PackageWatcher::new(repo_root.clone(), recv, cookie_writer).unwrap();
+        package_watcher.discover_packages_blocking().await.unwrap();
+        let mut changes = package_watcher.subscribe_package_changes();
+
+        // waits for a config change where the turbo.json does or doesn't exist
+        async fn next_config_change(
+            changes: &mut broadcast::Receiver<PackageChangeEvent>,
+            turbo_json_exists: bool,
+        ) -> WorkspaceData {
+            loop {
+                let event = tokio::time::timeout(Duration::from_secs(5), changes.recv())
+                    .await
+                    .expect("timed out waiting for package config change")
+                    .unwrap();
+                if let PackageChangeEvent::PackageConfigChanged(data) = event {
+                    if data.turbo_json.is_some() == turbo_json_exists {
+                        return data;
+                    }
+                }
+            }
+        }
+
+        let turbo_json = repo_root.join_components(&["packages", "foo", "turbo.json"]);
+        turbo_json
+            .create_with_contents(r#"{"extends": ["//"]}"#)
+            .unwrap();
+        assert_eq!(
+            next_config_change(&mut changes, true).await,
+            WorkspaceData {
+                package_json: foo.clone(),
+                turbo_json: Some(turbo_json.clone()),
+            }
+        );
+
+        turbo_json.remove_file().unwrap();
+        assert_eq!(
+            next_config_change(&mut changes, false).await,
+            WorkspaceData {
+                package_json: foo,
+                turbo_json: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn subscriber_nested_workspaces() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        let foo = repo_root.join_components(&["packages", "foo", "package.json"]);
+        foo.ensure_dir().unwrap();
+        foo.create_with_contents(r#"{"name": "foo"}"#).unwrap();
+        repo_root
+            .join_component("package-lock.json")
+            .create_with_contents("")
+            .unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces":["packages/*", "packages/*/examples/*"]}"#)
+            .unwrap();
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(
+            watcher.cookie_dir(),
+            Duration::from_millis(100),
+            recv.clone(),
+        );
+
+        let package_watcher = PackageWatcher::new(repo_root.clone(), recv, cookie_writer).unwrap();
+        package_watcher.discover_packages_blocking().await.unwrap();
+        let mut diagnostics = package_watcher.diagnostics();
+        assert!(diagnostics.borrow().is_empty());
+
+        let example =
+            repo_root.join_components(&["packages", "foo", "examples", "app", "package.json"]);
+        example.ensure_dir().unwrap();
+        example
+            .create_with_contents(r#"{"name": "example"}"#)
+            .unwrap();
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            diagnostics.wait_for(|diagnostics| !diagnostics.is_empty()),
+        )
+        .await
+        .expect("timed out waiting for nested workspace diagnostic")
+        .unwrap();
+        assert_eq!(
+            *diagnostics.borrow(),
+            vec![DiscoveryDiagnostic::NestedWorkspace {
+                package_json: example.clone(),
+                parent: foo.clone(),
+                excluded: true,
+            }]
+        );
+        // the nested workspace isn't a package of its own
+        assert_eq!(
+            package_watcher
+                .discover_packages_blocking()
+                .await
+                .unwrap()
+                .workspaces,
+            vec![WorkspaceData {
+                package_json: foo.clone(),
+                turbo_json: None,
+            }]
+        );
+
+        // once the parent is gone, the nested workspace is no longer nested
+        foo.remove_file().unwrap();
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            diagnostics.wait_for(|diagnostics| diagnostics.is_empty()),
+        )
+        .await
+        .expect("timed out waiting for nested workspace diagnostic to clear")
+        .unwrap();
+        assert_eq!(
+            package_watcher
+                .discover_packages_blocking()
+                .await
+                .unwrap()
+                .workspaces,
+            vec![WorkspaceData {
+                package_json: example,
+                turbo_json: None,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn subscriber_validation_diagnostics() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        let foo = repo_root.join_components(&["packages", "foo", "package.json"]);
+        foo.ensure_dir().unwrap();
+        foo.create_with_contents(r#"{"name": "foo"}"#).unwrap();
+        let bar = repo_root.join_components(&["packages", "bar", "package.json"]);
+        bar.ensure_dir().unwrap();
+        bar.create_with_contents(r#"{"name": "bar"}"#).unwrap();
+        repo_root
+            .join_component("package-lock.json")
+            .create_with_contents("")
+            .unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces":["packages/*"]}"#)
+            .unwrap();
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(
+            watcher.cookie_dir(),
+            Duration::from_millis(100),
+            recv.clone(),
+        );
+
+        let package_watcher = PackageWatcher::new(repo_root.clone(), recv, cookie_writer).unwrap();
+        package_watcher.discover_packages_blocking().await.unwrap();
+        let mut diagnostics = package_watcher.diagnostics();
+        assert!(diagnostics.borrow().is_empty());
+
+        async fn wait_for(
+            diagnostics: &mut watch::Receiver<Vec<DiscoveryDiagnostic>>,
+            f: impl FnMut(&Vec<DiscoveryDiagnostic>) -> bool,
+        ) -> Vec<DiscoveryDiagnostic> {
+            tokio::time::timeout(Duration::from_secs(5), diagnostics.wait_for(f))
+                .await
+                .expect("timed out waiting for diagnostics")
+                .unwrap()
+                .clone()
+        }
+
+        bar.create_with_contents(r#"{"name": "foo"}"#).unwrap();
+        assert_eq!(
+            wait_for(&mut diagnostics, |d| !d.is_empty()).await,
+            vec![DiscoveryDiagnostic::DuplicatePackageName {
+                name: "foo".to_string(),
+                package_jsons: vec![bar.clone(), foo.clone()],
+            }]
+        );
+
+        bar.create_with_contents(r#"{"version": "1.0.0"}"#).unwrap();
+        assert_eq!(
+            wait_for(&mut diagnostics, |d| matches!(
+                d.as_slice(),
+                [DiscoveryDiagnostic::MissingPackageName { .. }]
+            ))
+            .await,
+            vec![DiscoveryDiagnostic::MissingPackageName {
+                package_json: bar.clone(),
+            }]
+        );
+
+        bar.create_with_contents(r#"{"name": "#).unwrap();
+        wait_for(&mut diagnostics, |d| {
+            matches!(
+                d.as_slice(),
+                [DiscoveryDiagnostic::InvalidPackageJson { .. }]
+            )
+        })
+        .await;
+
+        bar.create_with_contents(r#"{"name": "bar"}"#).unwrap();
+        wait_for(&mut diagnostics, |d| d.is_empty()).await;
+        // the broken package was never dropped from discovery
+        assert_eq!(
+            package_watcher
+                .discover_packages_blocking()
+                .await
+                .unwrap()
+                .workspaces
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn subscriber_external_dependencies() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        let foo = repo_root.join_components(&["packages", "foo", "package.json"]);
+        foo.ensure_dir().unwrap();
+        foo.create_with_contents(r#"{"name": "foo", "dependencies": {"left-pad": "^1.0.0"}}"#)
+            .unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"name": "root", "workspaces":["packages/*"]}"#)
+            .unwrap();
+        let lockfile = |left_pad_version: &str| {
+            format!(
+                r#"{{
+                    "name": "root",
+                    "lockfileVersion": 3,
+                    "requires": true,
+                    "packages": {{
+                        "": {{ "name": "root", "workspaces": ["packages/*"] }},
+                        "node_modules/foo": {{ "resolved": "packages/foo", "link": true }},
+                        "node_modules/left-pad": {{ "version": "{left_pad_version}" }},
+                        "packages/foo": {{
+                            "name": "foo",
+                            "dependencies": {{ "left-pad": "^1.0.0" }}
+                        }}
+                    }}
+                }}"#
+            )
+        };
+        let package_lock = repo_root.join_component("package-lock.json");
+        package_lock
+            .create_with_contents(lockfile("1.1.0"))
+            .unwrap();
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(
+            watcher.cookie_dir(),
+            Duration::from_millis(100),
+            recv.clone(),
+        );
+
+        let package_watcher = PackageWatcher::new(repo_root.clone(), recv, cookie_writer).unwrap();
+        let mut external_dependencies = package_watcher.external_dependencies();
+
+        let left_pad_version = |deps: &Option<ExternalDependencies>| {
+            deps.as_ref()?
+                .get(&foo)?
+                .iter()
+                .find(|package| package.key.ends_with("left-pad"))
+                .map(|package| package.version.clone())
+        };
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            external_dependencies
+                .wait_for(|deps| left_pad_version(deps).as_deref() == Some("1.1.0")),
+        )
+        .await
+        .expect("timed out waiting for external dependencies")
+        .unwrap();
+
+        package_lock
+            .create_with_contents(lockfile("1.3.0"))
+            .unwrap();
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            external_dependencies
+                .wait_for(|deps| left_pad_version(deps).as_deref() == Some("1.3.0")),
+        )
+        .await
+        .expect("timed out waiting for updated external dependencies")
+        .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn resolve_reuses_cached_lockfile() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        let foo = repo_root.join_components(&["packages", "foo", "package.json"]);
+        foo.ensure_dir().unwrap();
+        foo.create_with_contents(r#"{"name": "foo", "dependencies": {"left-pad": "^1.0.0"}}"#)
+            .unwrap();
+        let root_package_json = repo_root.join_component("package.json");
+        root_package_json
+            .create_with_contents(r#"{"name": "root", "workspaces":["packages/*"]}"#)
+            .unwrap();
+        let lockfile = |left_pad_version: &str| {
+            format!(
+                r#"{{
+                    "name": "root",
+                    "lockfileVersion": 3,
+                    "requires": true,
+                    "packages": {{
+                        "": {{ "name": "root", "workspaces": ["packages/*"] }},
+                        "node_modules/foo": {{ "resolved": "packages/foo", "link": true }},
+                        "node_modules/left-pad": {{ "version": "{left_pad_version}" }},
+                        "packages/foo": {{
+                            "name": "foo",
+                            "dependencies": {{ "left-pad": "^1.0.0" }}
+                        }}
+                    }}
+                }}"#
+            )
+        };
+        let package_lock = repo_root.join_component("package-lock.json");
+        package_lock
+            .create_with_contents(lockfile("1.1.0"))
+            .unwrap();
+
+        let discovery = DiscoveryResponse {
+            package_manager: PackageManager::Npm,
+            workspaces: vec![WorkspaceData {
+                package_json: foo.clone(),
+                turbo_json: None,
+            }],
+            ecosystem_workspaces: Vec::new(),
+        };
+        let resolve = |cached: Option<CachedLockfile>| {
+            let repo_root = repo_root.clone();
+            let discovery = discovery.clone();
+            tokio::task::spawn_blocking(move || {
+                resolve_with_lockfile(&repo_root, discovery, cached)
+            })
+        };
+        let left_pad_version = |deps: &ExternalDependencies| {
+            deps.get(&foo)?
+                .iter()
+                .find(|package| package.key.ends_with("left-pad"))
+                .map(|package| package.version.clone())
+        };
+
+        let (result, cached) = resolve(None).await.unwrap();
+        assert_eq!(left_pad_version(&result.unwrap()).as_deref(), Some("1.1.0"));
+        let cached = cached.expect("lockfile should be cached");
+
+        package_lock
+            .create_with_contents(lockfile("1.3.0"))
+            .unwrap();
+
+        // A cache that matches the lockfile on disk is used instead of parsing it
+        let key = LockfileKey::new(
+            &repo_root,
+            PackageManager::Npm,
+            &PackageJson::load(&root_package_json).unwrap(),
+        );
+        let (result, cached) = resolve(Some(CachedLockfile {
+            key,
+            lockfile: cached.lockfile,
+        }))
+        .await
+        .unwrap();
+        assert_eq!(left_pad_version(&result.unwrap()).as_deref(), Some("1.1.0"));
+
+        // Otherwise the lockfile is parsed again
+        let stale = CachedLockfile {
+            key: LockfileKey {
+                contents: None,
+                ..LockfileKey::new(
+                    &repo_root,
+                    PackageManager::Npm,
+                    &PackageJson::load(&root_package_json).unwrap(),
+                )
+            },
+            lockfile: cached.unwrap().lockfile,
+        };
+        let (result, _) = resolve(Some(stale)).await.unwrap();
+        assert_eq!(left_pad_version(&result.unwrap()).as_deref(), Some("1.3.0"));
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn pnpm_update_workspaces() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        let foo = repo_root.join_components(&["packages", "foo", "package.json"]);
+        let bar = repo_root.join_components(&["packages2", "bar", "package.json"]);
+        for package_json in [&foo, &bar] {
+            package_json.ensure_dir().unwrap();
+            let name = package_json.parent().unwrap().file_name().unwrap();
+            package_json
+                .create_with_contents(format!("{{\"name\": \"{name}\"}}"))
+                .unwrap();
+        }
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"packageManager": "pnpm@7.0"}"#)
+            .unwrap();
+        repo_root
+            .join_component("pnpm-lock.yaml")
+            .create_with_contents("")
+            .unwrap();
+        let workspaces_path = repo_root.join_component("pnpm-workspace.yaml");
+        workspaces_path
+            .create_with_contents(r#"packages: ["packages/*"]"#)
+            .unwrap();
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_writer = CookieWriter::new(
+            watcher.cookie_dir(),
+            Duration::from_millis(100),
+            recv.clone(),
+        );
+
+        let package_watcher = PackageWatcher::new(repo_root.clone(), recv, cookie_writer).unwrap();
+
+        let data = package_watcher.discover_packages_blocking().await.unwrap();
+        assert_eq!(data.package_manager, PackageManager::Pnpm);
+        assert_eq!(
+            data.workspaces,
+            vec![WorkspaceData {
+                package_json: foo.clone(),
+                turbo_json: None,
+            }]
+        );
+
+        // widen the globs to also cover packages2
+        workspaces_path
+            .create_with_contents(r#"packages: ["packages/*", "packages2/*"]"#)
+            .unwrap();
+
+        let mut data = package_watcher.discover_packages_blocking().await.unwrap();
+        data.workspaces
+            .sort_by_key(|workspace| workspace.package_json.clone());
+        assert_eq!(
+            data.workspaces,
+            vec![
+                WorkspaceData {
+                    package_json: foo.clone(),
+                    turbo_json: None,
+                },
+                WorkspaceData {
+                    package_json: bar.clone(),
+                    turbo_json: None,
+                },
+            ]
+        );
+
+        // narrow the globs so that only packages2 is covered
+        workspaces_path
+            .create_with_contents(r#"packages: ["packages2/*"]"#)
+            .unwrap();
+
+        let data = package_watcher.discover_packages_blocking().await.unwrap();
+        assert_eq!(
+            data.workspaces,
+            vec![WorkspaceData {
+                package_json: bar,
+                turbo_json: None,
+            }]
+        );
+    }
+
     #[tokio::test]
     #[tracing_test::traced_test]
     async fn pnpm_invalid_states_test() {
@@ -0,0 +1,98 @@
+//! Requests from the backend to rescan part of the filesystem.
+//!
+//! Backends can drop events, e.g. when FSEvents reports that it must rescan
+//! subdirectories, or when the inotify queue overflows. Instead of
+//! subscribers treating this like any other error and throwing away all of
+//! their state, we tell them which directory to rescan. Rescans are broadcast
+//! as events with the `Rescan` flag set, and `Rescan::from_event` recovers
+//! them.
+
+use std::path::PathBuf;
+
+use notify::{event::Flag, Event, EventKind};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+/// Everything under `path` may have changed without us receiving events for
+/// it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rescan {
+    pub path: AbsoluteSystemPathBuf,
+}
+
+impl Rescan {
+    /// Returns the rescans requested by `event`, or `None` if it isn't a
+    /// rescan event.
+    pub fn from_event(event: &Event) -> Option<Vec<Rescan>> {
+        if !event.need_rescan() {
+            return None;
+        }
+        let rescans = event
+            .paths
+            .iter()
+            .filter_map(|path| AbsoluteSystemPathBuf::try_from(path.as_path()).ok())
+            .map(|path| Rescan { path })
+            .collect();
+        Some(rescans)
+    }
+}
+
+/// An event requesting a rescan of each of `paths`
+pub(crate) fn rescan_event(paths: impl IntoIterator<Item = PathBuf>) -> Event {
+    let event = Event::new(EventKind::Other).set_flag(Flag::Rescan);
+    paths.into_iter().fold(event, Event::add_path)
+}
+
+/// Some backends don't know which paths they dropped events for, so we make
+/// that explicit: everything we're watching needs to be rescanned.
+pub(crate) fn fill_rescan_paths<'a>(
+    event: &mut Event,
+    roots: impl IntoIterator<Item = &'a AbsoluteSystemPath>,
+) {
+    if event.need_rescan() && event.paths.is_empty() {
+        event.paths = roots
+            .into_iter()
+            .map(|root| root.as_std_path().to_owned())
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use notify::{
+        event::{CreateKind, Flag},
+        Event, EventKind,
+    };
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::{fill_rescan_paths, rescan_event, Rescan};
+
+    #[test]
+    fn test_rescan_from_event() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let dir = root.join_component("dir");
+
+        let event = rescan_event([dir.as_std_path().to_owned()]);
+        assert_eq!(
+            Rescan::from_event(&event),
+            Some(vec![Rescan { path: dir.clone() }])
+        );
+
+        let create =
+            Event::new(EventKind::Create(CreateKind::File)).add_path(dir.as_std_path().to_owned());
+        assert_eq!(Rescan::from_event(&create), None);
+
+        // A rescan that doesn't say what to rescan covers everything we watch
+        let mut overflow = Event::new(EventKind::Other).set_flag(Flag::Rescan);
+        fill_rescan_paths(&mut overflow, [&*root]);
+        assert_eq!(
+            Rescan::from_event(&overflow),
+            Some(vec![Rescan { path: root.clone() }])
+        );
+
+        // Other events are left alone
+        let mut other = Event::new(EventKind::Other);
+        fill_rescan_paths(&mut other, [&*root]);
+        assert!(other.paths.is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+//! Recovery from running out of filewatching watches.
+//!
+//! inotify limits how many directories a user can watch
+//! (`fs.inotify.max_user_watches`), and a large repo can exceed it. Rather than
+//! tearing down the watcher and losing the directories we did manage to watch,
+//! we report where we ran out, keep what we have, and periodically try to watch
+//! the rest again.
+
+use std::{
+    mem,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use notify::{ErrorKind, Event};
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::{rescan::rescan_event, NotifyError};
+
+/// How long to wait before trying again to watch directories that we ran out
+/// of watches for.
+pub(crate) const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+pub(crate) fn is_watch_limit(err: &notify::Error) -> bool {
+    match &err.kind {
+        ErrorKind::MaxFilesWatch => true,
+        ErrorKind::Io(err) => err.raw_os_error() == Some(libc::ENOSPC),
+        _ => false,
+    }
+}
+
+/// The current limit on watches, if the platform has one we can read
+fn current_limit() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string("/proc/sys/fs/inotify/max_user_watches")
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// Directories whose recursive watch was cut short by the limit on watches.
+#[derive(Debug, Default)]
+pub(crate) struct IncompleteWatches {
+    // the roots of the recursive watches that didn't complete
+    dirs: Vec<PathBuf>,
+    // errors that haven't been sent to subscribers yet
+    unreported: Vec<NotifyError>,
+    retry_at: Option<Instant>,
+    retrying: bool,
+}
+
+impl IncompleteWatches {
+    /// Records that watching `dir` recursively failed with `err`, a watch
+    /// limit error, and schedules a retry.
+    pub(crate) fn add(&mut self, dir: &Path, err: &notify::Error) {
+        if !self.dirs.iter().any(|existing| dir.starts_with(existing)) {
+            self.dirs.retain(|existing| !existing.starts_with(dir));
+            self.dirs.push(dir.to_owned());
+        }
+        // A retry that still fails has already been reported
+        if self.retrying {
+            return;
+        }
+        self.retry_at
+            .get_or_insert_with(|| Instant::now() + RETRY_INTERVAL);
+        let error = NotifyError::WatchLimit {
+            dir: err.paths.first().cloned().unwrap_or_else(|| dir.to_owned()),
+            limit: current_limit(),
+        };
+        warn!("{}", error);
+        self.unreported.push(error);
+    }
+
+    /// When to retry watching, if there is anything to retry
+    pub(crate) fn retry_at(&self) -> Option<Instant> {
+        self.retry_at
+    }
+
+    /// Takes the errors that haven't been sent to subscribers yet
+    pub(crate) fn take_unreported(&mut self) -> Vec<NotifyError> {
+        mem::take(&mut self.unreported)
+    }
+
+    /// Takes the directories to try watching again. Any that still fail should
+    /// be `add`ed again before calling `finish_retry`.
+    pub(crate) fn start_retry(&mut self) -> Vec<PathBuf> {
+        self.retry_at = None;
+        self.retrying = true;
+        mem::take(&mut self.dirs)
+    }
+
+    /// Schedules the next retry if needed, and returns an event asking
+    /// subscribers to rescan the directories that are now watched, since we
+    /// may have missed changes in them.
+    pub(crate) fn finish_retry(&mut self, retried: Vec<PathBuf>) -> Option<Event> {
+        self.retrying = false;
+        if !self.dirs.is_empty() {
+            self.retry_at = Some(Instant::now() + RETRY_INTERVAL);
+        }
+        let recovered = retried
+            .into_iter()
+            .filter(|dir| !self.dirs.iter().any(|failed| failed.starts_with(dir)))
+            .collect::<Vec<_>>();
+        (!recovered.is_empty()).then(|| rescan_event(recovered))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io, path::PathBuf};
+
+    use notify::ErrorKind;
+
+    use super::{is_watch_limit, IncompleteWatches};
+    use crate::NotifyError;
+
+    fn limit_error(path: &str) -> notify::Error {
+        notify::Error::new(ErrorKind::MaxFilesWatch).add_path(path.into())
+    }
+
+    #[test]
+    fn test_is_watch_limit() {
+        assert!(is_watch_limit(&limit_error("/repo")));
+        assert!(is_watch_limit(&notify::Error::io(
+            io::Error::from_raw_os_error(libc::ENOSPC)
+        )));
+        assert!(!is_watch_limit(&notify::Error::path_not_found()));
+    }
+
+    #[test]
+    fn test_incomplete_watches() {
+        let mut incomplete = IncompleteWatches::default();
+        assert!(incomplete.retry_at().is_none());
+
+        incomplete.add("/repo".as_ref(), &limit_error("/repo/a/b"));
+        // Already covered by the watch of /repo
+        incomplete.add("/repo/c".as_ref(), &limit_error("/repo/c"));
+        assert!(incomplete.retry_at().is_some());
+        let errors = incomplete.take_unreported();
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(
+            &errors[0],
+            NotifyError::WatchLimit { dir, .. } if dir == &PathBuf::from("/repo/a/b")
+        ));
+        assert!(incomplete.take_unreported().is_empty());
+
+        // Still at the limit
+        let dirs = incomplete.start_retry();
+        assert_eq!(dirs, vec![PathBuf::from("/repo")]);
+        incomplete.add("/repo".as_ref(), &limit_error("/repo/d"));
+        assert!(incomplete.finish_retry(dirs).is_none());
+        assert!(incomplete.take_unreported().is_empty());
+        assert!(incomplete.retry_at().is_some());
+
+        // Recovered
+        let dirs = incomplete.start_retry();
+        let rescan = incomplete.finish_retry(dirs).unwrap();
+        assert!(rescan.need_rescan());
+        assert_eq!(rescan.paths, vec![PathBuf::from("/repo")]);
+        assert!(incomplete.retry_at().is_none());
+    }
+}
@@ -0,0 +1,784 @@
+//! Watcher implementation for Windows' ReadDirectoryChangesW API
+//!
+//! This is a fork of the `ReadDirectoryChangesWatcher` in notify 6.1.1, with
+//! one difference: when the buffer that ReadDirectoryChangesW writes events
+//! into overflows, the events are discarded without any indication in
+//! notify's implementation. Its completion routine ignores the number of bytes
+//! written and decodes the empty buffer as an entry with an unknown action,
+//! so there is nothing a wrapper around its watcher could observe. Here the
+//! completion routine tells an overflow apart from other results, see
+//! [completion], and rescans what changed while events were being dropped,
+//! see [changes_since].
+//!
+//! Everything else is kept as close to notify's implementation as possible so
+//! that fixes can be ported between the two. When notify is upgraded, compare
+//! this module against its `windows.rs`, and replace it with notify's watcher
+//! once notify reports overflows itself.
+//!
+//! For more information see the [ReadDirectoryChangesW reference][ref].
+//!
+//! [ref]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-readdirectorychangesw
+
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsString,
+    io,
+    mem::{self, ManuallyDrop},
+    os::{
+        raw::c_void,
+        windows::ffi::{OsStrExt, OsStringExt},
+    },
+    path::{Path, PathBuf},
+    ptr, slice,
+    sync::{
+        mpsc::{self, Receiver, Sender, SyncSender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use notify::{
+    event::{CreateKind, ModifyKind, RemoveKind, RenameMode},
+    Config, Error, Event, EventHandler, EventKind, RecursiveMode, Result, Watcher, WatcherKind,
+};
+use tracing::warn;
+use walkdir::WalkDir;
+use windows_sys::Win32::{
+    Foundation::{
+        CloseHandle, ERROR_NOTIFY_ENUM_DIR, ERROR_OPERATION_ABORTED, HANDLE, INVALID_HANDLE_VALUE,
+        WAIT_OBJECT_0,
+    },
+    Storage::FileSystem::{
+        CreateFileW, ReadDirectoryChangesW, FILE_ACTION_ADDED, FILE_ACTION_MODIFIED,
+        FILE_ACTION_REMOVED, FILE_ACTION_RENAMED_NEW_NAME, FILE_ACTION_RENAMED_OLD_NAME,
+        FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OVERLAPPED, FILE_LIST_DIRECTORY,
+        FILE_NOTIFY_CHANGE_ATTRIBUTES, FILE_NOTIFY_CHANGE_CREATION, FILE_NOTIFY_CHANGE_DIR_NAME,
+        FILE_NOTIFY_CHANGE_FILE_NAME, FILE_NOTIFY_CHANGE_LAST_WRITE, FILE_NOTIFY_CHANGE_SECURITY,
+        FILE_NOTIFY_CHANGE_SIZE, FILE_NOTIFY_INFORMATION, FILE_SHARE_DELETE, FILE_SHARE_READ,
+        FILE_SHARE_WRITE, OPEN_EXISTING,
+    },
+    System::{
+        Threading::{CreateSemaphoreW, ReleaseSemaphore, WaitForSingleObjectEx, INFINITE},
+        IO::{CancelIo, OVERLAPPED},
+    },
+};
+
+use crate::rescan::rescan_event;
+
+const BUF_SIZE: u32 = 16384;
+
+// Allowance for filesystems with coarse timestamps when looking for what
+// changed while events were being dropped
+const MTIME_SLACK: Duration = Duration::from_secs(2);
+
+// ReadDirectoryChangesW requires the buffer to be DWORD-aligned
+#[repr(C, align(4))]
+struct Buffer([u8; BUF_SIZE as usize]);
+
+#[derive(Clone)]
+struct ReadData {
+    // directory that is being watched
+    dir: PathBuf,
+    // if a file is being watched, this is its full path
+    file: Option<PathBuf>,
+    complete_sem: HANDLE,
+    is_recursive: bool,
+}
+
+struct ReadDirectoryRequest {
+    event_handler: Arc<Mutex<dyn EventHandler>>,
+    buffer: Buffer,
+    handle: HANDLE,
+    data: ReadData,
+    // when this request was queued
+    queued_at: SystemTime,
+    // when the previous request was queued. If this request reports an
+    // overflow, the dropped events happened after this.
+    prev_queued_at: SystemTime,
+}
+
+enum Action {
+    Watch(PathBuf, RecursiveMode),
+    Unwatch(PathBuf),
+    Stop,
+    Configure(Config, SyncSender<Result<bool>>),
+}
+
+struct WatchState {
+    dir_handle: HANDLE,
+    complete_sem: HANDLE,
+}
+
+struct ReadDirectoryChangesServer {
+    rx: Receiver<Action>,
+    event_handler: Arc<Mutex<dyn EventHandler>>,
+    cmd_tx: Sender<Result<PathBuf>>,
+    watches: HashMap<PathBuf, WatchState>,
+    wakeup_sem: HANDLE,
+}
+
+impl ReadDirectoryChangesServer {
+    fn start(
+        event_handler: Arc<Mutex<dyn EventHandler>>,
+        cmd_tx: Sender<Result<PathBuf>>,
+        wakeup_sem: HANDLE,
+    ) -> Sender<Action> {
+        let (action_tx, action_rx) = mpsc::channel();
+        let _ = thread::Builder::new()
+            .name("turbo filewatch windows loop".to_string())
+            .spawn(move || {
+                let server = ReadDirectoryChangesServer {
+                    rx: action_rx,
+                    event_handler,
+                    cmd_tx,
+                    watches: HashMap::new(),
+                    wakeup_sem,
+                };
+                server.run();
+            });
+        action_tx
+    }
+
+    fn run(mut self) {
+        loop {
+            // process all available actions first
+            let mut stopped = false;
+
+            while let Ok(action) = self.rx.try_recv() {
+                match action {
+                    Action::Watch(path, recursive_mode) => {
+                        let res = self.add_watch(path, recursive_mode == RecursiveMode::Recursive);
+                        let _ = self.cmd_tx.send(res);
+                    }
+                    Action::Unwatch(path) => self.remove_watch(path),
+                    Action::Stop => {
+                        stopped = true;
+                        for ws in self.watches.values() {
+                            stop_watch(ws);
+                        }
+                        break;
+                    }
+                    Action::Configure(_config, tx) => {
+                        let _ = tx.send(Ok(false));
+                    }
+                }
+            }
+
+            if stopped {
+                break;
+            }
+
+            // wait with the alertable flag set so that the completion routine fires
+            unsafe {
+                WaitForSingleObjectEx(self.wakeup_sem, 100, 1);
+            }
+        }
+
+        // we have to clean this up, since the watcher may be long gone
+        unsafe {
+            CloseHandle(self.wakeup_sem);
+        }
+    }
+
+    fn add_watch(&mut self, path: PathBuf, is_recursive: bool) -> Result<PathBuf> {
+        // path must exist and be either a file or directory
+        if !path.is_dir() && !path.is_file() {
+            return Err(
+                Error::generic("Input watch path is neither a file nor a directory.")
+                    .add_path(path),
+            );
+        }
+
+        let (watching_file, dir_target) = if path.is_dir() {
+            (false, path.clone())
+        } else {
+            // emulate file watching by watching the parent directory
+            (true, path.parent().unwrap().to_path_buf())
+        };
+
+        let encoded_path: Vec<u16> = dir_target
+            .as_os_str()
+            .encode_wide()
+            .chain(Some(0))
+            .collect();
+        let handle = unsafe {
+            CreateFileW(
+                encoded_path.as_ptr(),
+                FILE_LIST_DIRECTORY,
+                FILE_SHARE_READ | FILE_SHARE_DELETE | FILE_SHARE_WRITE,
+                ptr::null(),
+                OPEN_EXISTING,
+                FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OVERLAPPED,
+                0,
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(if watching_file {
+                Error::generic(
+                    "You attempted to watch a single file, but parent directory could not be \
+                     opened.",
+                )
+                .add_path(path)
+            } else {
+                Error::path_not_found().add_path(path)
+            });
+        }
+
+        // every watcher gets its own semaphore to signal completion
+        let semaphore = unsafe { CreateSemaphoreW(ptr::null(), 0, 1, ptr::null()) };
+        if semaphore == 0 || semaphore == INVALID_HANDLE_VALUE {
+            unsafe {
+                CloseHandle(handle);
+            }
+            return Err(Error::generic("Failed to create semaphore for watch.").add_path(path));
+        }
+        let rd = ReadData {
+            dir: dir_target,
+            file: watching_file.then(|| path.clone()),
+            complete_sem: semaphore,
+            is_recursive,
+        };
+        let ws = WatchState {
+            dir_handle: handle,
+            complete_sem: semaphore,
+        };
+        // Watching a path again replaces its existing watch
+        if let Some(old_ws) = self.watches.insert(path.clone(), ws) {
+            stop_watch(&old_ws);
+        }
+        start_read(&rd, self.event_handler.clone(), handle, SystemTime::now());
+        Ok(path)
+    }
+
+    fn remove_watch(&mut self, path: PathBuf) {
+        if let Some(ws) = self.watches.remove(&path) {
+            stop_watch(&ws);
+        }
+    }
+}
+
+fn stop_watch(ws: &WatchState) {
+    unsafe {
+        let cio = CancelIo(ws.dir_handle);
+        let ch = CloseHandle(ws.dir_handle);
+        // have to wait for it, otherwise we leak the memory allocated for the read
+        // request
+        if cio != 0 && ch != 0 {
+            while WaitForSingleObjectEx(ws.complete_sem, INFINITE, 1) != WAIT_OBJECT_0 {
+                // drain the apc queue
+            }
+        }
+        CloseHandle(ws.complete_sem);
+    }
+}
+
+fn start_read(
+    rd: &ReadData,
+    event_handler: Arc<Mutex<dyn EventHandler>>,
+    handle: HANDLE,
+    prev_queued_at: SystemTime,
+) {
+    let request = Box::new(ReadDirectoryRequest {
+        event_handler,
+        handle,
+        buffer: Buffer([0u8; BUF_SIZE as usize]),
+        data: rd.clone(),
+        queued_at: SystemTime::now(),
+        prev_queued_at,
+    });
+
+    let flags = FILE_NOTIFY_CHANGE_FILE_NAME
+        | FILE_NOTIFY_CHANGE_DIR_NAME
+        | FILE_NOTIFY_CHANGE_ATTRIBUTES
+        | FILE_NOTIFY_CHANGE_SIZE
+        | FILE_NOTIFY_CHANGE_LAST_WRITE
+        | FILE_NOTIFY_CHANGE_CREATION
+        | FILE_NOTIFY_CHANGE_SECURITY;
+
+    let monitor_subdir = if request.data.file.is_none() && request.data.is_recursive {
+        1
+    } else {
+        0
+    };
+
+    unsafe {
+        let mut overlapped = ManuallyDrop::new(Box::new(mem::zeroed::<OVERLAPPED>()));
+        // When using callback based async requests, we are allowed to use the
+        // hEvent member for our own purposes
+        let request = Box::leak(request);
+        overlapped.hEvent = request as *mut _ as _;
+
+        // This is using an asynchronous call with a completion routine for
+        // receiving notifications
+        let ret = ReadDirectoryChangesW(
+            handle,
+            request.buffer.0.as_mut_ptr() as *mut c_void,
+            BUF_SIZE,
+            monitor_subdir,
+            flags,
+            &mut 0u32 as *mut u32, // not used for async reqs
+            &mut **overlapped as *mut OVERLAPPED,
+            Some(handle_event),
+        );
+
+        if ret == 0 {
+            // Because of the error, ownership of the `overlapped` alloc was not
+            // passed over to `ReadDirectoryChangesW`, so we can claim it and the
+            // request back.
+            let _overlapped = ManuallyDrop::into_inner(overlapped);
+            let request: Box<ReadDirectoryRequest> = Box::from_raw(request);
+            ReleaseSemaphore(request.data.complete_sem, 1, ptr::null_mut());
+        }
+    }
+}
+
+fn emit_event(event_handler: &Mutex<dyn EventHandler>, event: Result<Event>) {
+    if let Ok(mut guard) = event_handler.lock() {
+        guard.handle_event(event);
+    }
+}
+
+/// How a completed read is handled
+#[derive(Debug, PartialEq, Eq)]
+enum Completion {
+    /// The watch was stopped
+    Aborted,
+    /// The read failed with this error, and the watch is unlikely to recover
+    Failed(u32),
+    /// More changes happened than fit in the buffer, so all of them were
+    /// discarded
+    Overflow,
+    /// The buffer holds events
+    Events,
+}
+
+/// Classifies the result of a read. An overflow is reported either with
+/// ERROR_NOTIFY_ENUM_DIR or with a successful read that wrote nothing.
+fn completion(error_code: u32, bytes_written: u32) -> Completion {
+    match (error_code, bytes_written) {
+        (ERROR_OPERATION_ABORTED, _) => Completion::Aborted,
+        (ERROR_NOTIFY_ENUM_DIR, _) | (0, 0) => Completion::Overflow,
+        (0, _) => Completion::Events,
+        (error_code, _) => Completion::Failed(error_code),
+    }
+}
+
+/// Finds what changed under `dir` since `since`, to make up for dropped
+/// events. Creating, removing or renaming an entry updates the modification
+/// time of its directory, so those are covered by a rescan of the directory,
+/// which also covers everything beneath it. Files that were written to in
+/// directories that didn't change get a modify event.
+fn changes_since(dir: &Path, is_recursive: bool, since: SystemTime) -> io::Result<Vec<Event>> {
+    let mut walk = WalkDir::new(dir)
+        .max_depth(if is_recursive { usize::MAX } else { 1 })
+        .into_iter();
+    let mut changed_dirs = Vec::new();
+    let mut events = Vec::new();
+    while let Some(entry) = walk.next() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            // Removed since we listed its directory, which will be rescanned
+            Err(e) if e.io_error().map(|e| e.kind()) == Some(io::ErrorKind::NotFound) => continue,
+            Err(e) => return Err(e.into()),
+        };
+        // The metadata from listing a directory can be stale for
+        // subdirectories, so we ask for it again
+        let modified = match entry.path().symlink_metadata() {
+            Ok(metadata) => metadata.modified()?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        if modified < since {
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            // The rescan covers its contents. Directories at the maximum depth
+            // aren't descended into, so there is nothing to skip for those.
+            if is_recursive {
+                walk.skip_current_dir();
+            }
+            changed_dirs.push(entry.into_path());
+        } else {
+            events.push(Event::new(EventKind::Modify(ModifyKind::Any)).add_path(entry.into_path()));
+        }
+    }
+    if !changed_dirs.is_empty() {
+        events.push(rescan_event(changed_dirs).set_info("rescan: buffer overflow"));
+    }
+    Ok(events)
+}
+
+/// Rescans what a watch covers after its buffer overflowed. This walks the
+/// filesystem, so it's done off of the server thread. If we can't find what
+/// changed, e.g. because only attributes did, or because a file was written
+/// without updating its modification time, the whole watch is rescanned.
+fn rescan_after_overflow(request: &ReadDirectoryRequest) {
+    let data = request.data.clone();
+    let event_handler = request.event_handler.clone();
+    let since = request
+        .prev_queued_at
+        .checked_sub(MTIME_SLACK)
+        .unwrap_or(request.prev_queued_at);
+    let _ = thread::Builder::new()
+        .name("turbo filewatch windows rescan".to_string())
+        .spawn(move || {
+            let watched = data.file.as_ref().unwrap_or(&data.dir);
+            warn!(
+                "filewatching events were dropped for {}, rescanning",
+                watched.display()
+            );
+            let events = match &data.file {
+                // Only the one file could have changed, so rescanning the
+                // whole watch is as targeted as it gets
+                Some(_) => Ok(Vec::new()),
+                None => changes_since(&data.dir, data.is_recursive, since),
+            };
+            let events = match events {
+                Ok(events) if !events.is_empty() => events,
+                Ok(_) => vec![rescan_event([watched.clone()]).set_info("rescan: buffer overflow")],
+                Err(e) => {
+                    warn!("failed to rescan {}: {}", watched.display(), e);
+                    vec![rescan_event([watched.clone()]).set_info("rescan: buffer overflow")]
+                }
+            };
+            for event in events {
+                emit_event(&event_handler, Ok(event));
+            }
+        });
+}
+
+unsafe extern "system" fn handle_event(
+    error_code: u32,
+    bytes_written: u32,
+    overlapped: *mut OVERLAPPED,
+) {
+    let overlapped: Box<OVERLAPPED> = Box::from_raw(overlapped);
+    let request: Box<ReadDirectoryRequest> = Box::from_raw(overlapped.hEvent as *mut _);
+
+    let completion = completion(error_code, bytes_written);
+    match completion {
+        Completion::Aborted => {
+            // received when dir is unwatched or watcher is shutdown; return and
+            // let overlapped/request get drop-cleaned
+            ReleaseSemaphore(request.data.complete_sem, 1, ptr::null_mut());
+            return;
+        }
+        Completion::Failed(error_code) => {
+            // The buffer can't be trusted, so stop reading rather than queue
+            // another request
+            let error = Error::io(io::Error::from_raw_os_error(error_code as i32))
+                .add_path(request.data.dir.clone());
+            emit_event(&request.event_handler, Err(error));
+            ReleaseSemaphore(request.data.complete_sem, 1, ptr::null_mut());
+            return;
+        }
+        Completion::Overflow | Completion::Events => {}
+    }
+
+    // Get the next request queued up as soon as possible
+    start_read(
+        &request.data,
+        request.event_handler.clone(),
+        request.handle,
+        request.queued_at,
+    );
+
+    if completion == Completion::Overflow {
+        rescan_after_overflow(&request);
+        return;
+    }
+
+    // The FILE_NOTIFY_INFORMATION struct has a variable length due to the
+    // variable length string as its last member. Each struct contains an offset
+    // for getting the next entry in the buffer.
+    let mut cur_offset: *const u8 = request.buffer.0.as_ptr();
+    loop {
+        let cur_entry = cur_offset as *const FILE_NOTIFY_INFORMATION;
+        // filename length is size in bytes, so / 2
+        let len = (*cur_entry).FileNameLength as usize / 2;
+        let encoded_path: &[u16] =
+            slice::from_raw_parts(ptr::addr_of!((*cur_entry).FileName) as *const u16, len);
+        // prepend root to get a full path
+        let path = request
+            .data
+            .dir
+            .join(PathBuf::from(OsString::from_wide(encoded_path)));
+
+        // if we are watching a single file, ignore the event unless the path is
+        // exactly the watched file
+        let skip = match &request.data.file {
+            None => false,
+            Some(watch_path) => *watch_path != path,
+        };
+
+        if !skip {
+            let kind = match (*cur_entry).Action {
+                FILE_ACTION_RENAMED_OLD_NAME => {
+                    Some(EventKind::Modify(ModifyKind::Name(RenameMode::From)))
+                }
+                FILE_ACTION_RENAMED_NEW_NAME => {
+                    Some(EventKind::Modify(ModifyKind::Name(RenameMode::To)))
+                }
+                FILE_ACTION_ADDED => Some(EventKind::Create(CreateKind::Any)),
+                FILE_ACTION_REMOVED => Some(EventKind::Remove(RemoveKind::Any)),
+                FILE_ACTION_MODIFIED => Some(EventKind::Modify(ModifyKind::Any)),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                emit_event(&request.event_handler, Ok(Event::new(kind).add_path(path)));
+            }
+        }
+
+        if (*cur_entry).NextEntryOffset == 0 {
+            break;
+        }
+        cur_offset = cur_offset.offset((*cur_entry).NextEntryOffset as isize);
+    }
+}
+
+/// Watcher implementation based on ReadDirectoryChangesW
+#[derive(Debug)]
+pub struct ReadDirectoryChangesWatcher {
+    tx: Sender<Action>,
+    cmd_rx: Receiver<Result<PathBuf>>,
+    wakeup_sem: HANDLE,
+}
+
+impl ReadDirectoryChangesWatcher {
+    fn create(event_handler: Arc<Mutex<dyn EventHandler>>) -> Result<ReadDirectoryChangesWatcher> {
+        let (cmd_tx, cmd_rx) = mpsc::channel();
+
+        let wakeup_sem = unsafe { CreateSemaphoreW(ptr::null(), 0, 1, ptr::null()) };
+        if wakeup_sem == 0 || wakeup_sem == INVALID_HANDLE_VALUE {
+            return Err(Error::generic("Failed to create wakeup semaphore."));
+        }
+
+        let tx = ReadDirectoryChangesServer::start(event_handler, cmd_tx, wakeup_sem);
+
+        Ok(ReadDirectoryChangesWatcher {
+            tx,
+            cmd_rx,
+            wakeup_sem,
+        })
+    }
+
+    fn wakeup_server(&mut self) {
+        // breaks the server out of its wait state, so that if you add a watch you
+        // don't block for 100ms in watch() while the server sleeps.
+        unsafe {
+            ReleaseSemaphore(self.wakeup_sem, 1, ptr::null_mut());
+        }
+    }
+
+    fn send_action_require_ack(&mut self, action: Action, pb: &PathBuf) -> Result<()> {
+        self.tx
+            .send(action)
+            .map_err(|_| Error::generic("Error sending to internal channel"))?;
+
+        // wake 'em up, we don't want to wait around for the ack
+        self.wakeup_server();
+
+        let ack_pb = self
+            .cmd_rx
+            .recv()
+            .map_err(|_| Error::generic("Error receiving from command channel"))??;
+
+        if pb.as_path() != ack_pb.as_path() {
+            Err(Error::generic(&format!(
+                "Expected ack for {:?} but got ack for {:?}",
+                pb, ack_pb
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn absolute(path: &Path) -> Result<PathBuf> {
+        if path.is_absolute() {
+            Ok(path.to_owned())
+        } else {
+            let cwd = env::current_dir().map_err(Error::io)?;
+            Ok(cwd.join(path))
+        }
+    }
+}
+
+impl Watcher for ReadDirectoryChangesWatcher {
+    fn new<F: EventHandler>(event_handler: F, _config: Config) -> Result<Self> {
+        Self::create(Arc::new(Mutex::new(event_handler)))
+    }
+
+    fn watch(&mut self, path: &Path, recursive_mode: RecursiveMode) -> Result<()> {
+        let pb = Self::absolute(path)?;
+        // path must exist and be either a file or directory
+        if !pb.is_dir() && !pb.is_file() {
+            return Err(
+                Error::generic("Input watch path is neither a file nor a directory.").add_path(pb),
+            );
+        }
+        self.send_action_require_ack(Action::Watch(pb.clone(), recursive_mode), &pb)
+    }
+
+    fn unwatch(&mut self, path: &Path) -> Result<()> {
+        let pb = Self::absolute(path)?;
+        let res = self
+            .tx
+            .send(Action::Unwatch(pb))
+            .map_err(|_| Error::generic("Error sending to internal channel"));
+        self.wakeup_server();
+        res
+    }
+
+    fn configure(&mut self, config: Config) -> Result<bool> {
+        let (tx, rx) = mpsc::sync_channel(1);
+        self.tx
+            .send(Action::Configure(config, tx))
+            .map_err(|_| Error::generic("Error sending to internal channel"))?;
+        self.wakeup_server();
+        rx.recv()
+            .map_err(|_| Error::generic("Error receiving from command channel"))?
+    }
+
+    fn kind() -> WatcherKind {
+        WatcherKind::ReadDirectoryChangesWatcher
+    }
+}
+
+impl Drop for ReadDirectoryChangesWatcher {
+    fn drop(&mut self) {
+        let _ = self.tx.send(Action::Stop);
+        // better wake it up
+        self.wakeup_server();
+    }
+}
+
+// `ReadDirectoryChangesWatcher` is not Send/Sync because of the semaphore
+// Handle. It's safe to send it across threads, since the handle is only used to
+// wake up the server thread.
+unsafe impl Send for ReadDirectoryChangesWatcher {}
+// Because all methods that change the mutable state use `&mut self`, it's also
+// safe to share references.
+unsafe impl Sync for ReadDirectoryChangesWatcher {}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        sync::{mpsc, Arc, Mutex},
+        thread,
+        time::{Duration, Instant, SystemTime},
+    };
+
+    use notify::{event::ModifyKind, Config, EventKind, RecursiveMode, Watcher};
+    use test_case::test_case;
+    use windows_sys::Win32::Foundation::{
+        ERROR_ACCESS_DENIED, ERROR_NOTIFY_ENUM_DIR, ERROR_OPERATION_ABORTED,
+    };
+
+    use super::{changes_since, completion, Completion, ReadDirectoryChangesWatcher};
+
+    #[test_case(ERROR_OPERATION_ABORTED, 0, Completion::Aborted ; "aborted")]
+    #[test_case(ERROR_NOTIFY_ENUM_DIR, 0, Completion::Overflow ; "enum dir")]
+    #[test_case(ERROR_NOTIFY_ENUM_DIR, 64, Completion::Overflow ; "enum dir with bytes")]
+    #[test_case(0, 0, Completion::Overflow ; "empty buffer")]
+    #[test_case(0, 64, Completion::Events ; "events")]
+    #[test_case(ERROR_ACCESS_DENIED, 0, Completion::Failed(ERROR_ACCESS_DENIED) ; "failed")]
+    fn test_completion(error_code: u32, bytes_written: u32, expected: Completion) {
+        assert_eq!(completion(error_code, bytes_written), expected);
+    }
+
+    #[test]
+    fn test_changes_since() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_owned();
+        for sub in ["unchanged", "changed", "edited"] {
+            fs::create_dir(dir.join(sub)).unwrap();
+            fs::write(dir.join(sub).join("file"), "").unwrap();
+        }
+        let since = SystemTime::now();
+        thread::sleep(Duration::from_millis(50));
+        fs::write(dir.join("changed").join("new"), "").unwrap();
+        fs::write(dir.join("edited").join("file"), "contents").unwrap();
+
+        let events = changes_since(&dir, true, since).unwrap();
+        let (rescans, modifies): (Vec<_>, Vec<_>) =
+            events.into_iter().partition(|event| event.need_rescan());
+        // The new file is covered by the rescan of its directory
+        assert_eq!(rescans.len(), 1);
+        assert_eq!(rescans[0].paths, vec![dir.join("changed")]);
+        assert_eq!(modifies.len(), 1);
+        assert_eq!(modifies[0].kind, EventKind::Modify(ModifyKind::Any));
+        assert_eq!(modifies[0].paths, vec![dir.join("edited").join("file")]);
+
+        // Without a recursive watch, only the top level is checked
+        let events = changes_since(&dir, false, since).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].paths, vec![dir.join("changed")]);
+    }
+
+    #[test]
+    fn test_rewatch_replaces_watch() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_owned();
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = ReadDirectoryChangesWatcher::new(tx, Config::default()).unwrap();
+        watcher.watch(&dir, RecursiveMode::Recursive).unwrap();
+        watcher.watch(&dir, RecursiveMode::Recursive).unwrap();
+
+        let file = dir.join("file");
+        fs::write(&file, "contents").unwrap();
+        let event = rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+        assert_eq!(event.paths, vec![file.clone()]);
+        // The replaced watch doesn't report the creation a second time
+        while let Ok(event) = rx.recv_timeout(Duration::from_millis(500)) {
+            let event = event.unwrap();
+            assert!(
+                !matches!(event.kind, EventKind::Create(_)),
+                "duplicate create event {:?}",
+                event
+            );
+        }
+    }
+
+    #[test]
+    fn test_overflow_emits_rescan() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_owned();
+        let (tx, rx) = mpsc::channel();
+        // Blocking the handler keeps the server thread from queueing the next
+        // read, so that changes pile up until they overflow the buffer
+        let gate = Arc::new(Mutex::new(()));
+        let handler_gate = gate.clone();
+        let mut watcher = ReadDirectoryChangesWatcher::new(
+            move |res| {
+                let _open = handler_gate.lock().unwrap();
+                let _ = tx.send(res);
+            },
+            Config::default(),
+        )
+        .unwrap();
+        watcher.watch(&dir, RecursiveMode::Recursive).unwrap();
+
+        let closed = gate.lock().unwrap();
+        fs::write(dir.join("first"), "").unwrap();
+        thread::sleep(Duration::from_millis(200));
+        let long_name = "a".repeat(200);
+        for i in 0..2000 {
+            fs::write(dir.join(format!("{}-{}", long_name, i)), "").unwrap();
+        }
+        drop(closed);
+
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let rescan = loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let event = rx.recv_timeout(timeout).expect("no rescan event").unwrap();
+            if event.need_rescan() {
+                break event;
+            }
+        };
+        // Only the watched directory had entries added
+        assert_eq!(rescan.paths, vec![dir]);
+    }
+}
@@ -0,0 +1,234 @@
+//! Expansion of glob syntax that wax doesn't support directly.
+//!
+//! Extglob patterns are rewritten into wax's repetitions and alternatives,
+//! and brace expressions are expanded into separate patterns, since wax
+//! doesn't allow some patterns inside of alternatives, such as `**`.
+
+use crate::GlobError;
+
+/// Expands `pattern` into the patterns that together match the same paths.
+///
+/// Supports brace expansion, e.g. `src/{a,b}/**`, and the extglob patterns
+/// `?(a|b)`, `*(a|b)`, `+(a|b)`, and `@(a|b)`. Negated extglobs, `!(a|b)`,
+/// can't be expressed as a wax glob and are an error.
+pub fn expand_glob(pattern: &str) -> Result<Vec<String>, GlobError> {
+    let translated = translate_extglobs(pattern).map_err(|reason| GlobError {
+        raw_input: pattern.to_owned(),
+        reason,
+    })?;
+    let mut expanded = Vec::new();
+    for glob in expand_braces(&translated) {
+        if !expanded.contains(&glob) {
+            expanded.push(glob);
+        }
+    }
+    Ok(expanded)
+}
+
+// Returns the index of the `close` that matches the `open` at `start`, skipping
+// escaped characters and character classes
+fn find_closing(chars: &[char], start: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' => i = find_class_end(chars, i).unwrap_or(i),
+            c if c == open => depth += 1,
+            c if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// Returns the index of the `]` that ends the character class at `start`
+fn find_class_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+    // a leading `!` or `^` negates the class, and a `]` right after that is a
+    // literal
+    if matches!(chars.get(i), Some('!' | '^')) {
+        i += 1;
+    }
+    if chars.get(i) == Some(&']') {
+        i += 1;
+    }
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            ']' => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// Splits `chars` on the `separator`s that aren't nested inside of another
+// group, class, or repetition
+fn split_top_level(chars: &[char], separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' => {
+                parts.last_mut().expect("non-empty").push(c);
+                if let Some(&next) = chars.get(i + 1) {
+                    parts.last_mut().expect("non-empty").push(next);
+                }
+                i += 2;
+                continue;
+            }
+            '[' => {
+                if let Some(end) = find_class_end(chars, i) {
+                    parts.last_mut().expect("non-empty").extend(&chars[i..=end]);
+                    i = end + 1;
+                    continue;
+                }
+            }
+            '{' | '(' | '<' => depth += 1,
+            '}' | ')' | '>' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(String::new());
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        parts.last_mut().expect("non-empty").push(c);
+        i += 1;
+    }
+    parts
+}
+
+fn translate_extglobs(pattern: &str) -> Result<String, String> {
+    let chars = pattern.chars().collect::<Vec<_>>();
+    let mut translated = String::with_capacity(pattern.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' => {
+                translated.push(c);
+                if let Some(&next) = chars.get(i + 1) {
+                    translated.push(next);
+                }
+                i += 2;
+                continue;
+            }
+            '[' => {
+                if let Some(end) = find_class_end(&chars, i) {
+                    translated.extend(&chars[i..=end]);
+                    i = end + 1;
+                    continue;
+                }
+            }
+            '?' | '*' | '+' | '@' | '!' if chars.get(i + 1) == Some(&'(') => {
+                // an unclosed extglob is left as is, since parentheses are
+                // otherwise literals
+                if let Some(end) = find_closing(&chars, i + 1, '(', ')') {
+                    if c == '!' {
+                        return Err("negated extglob patterns are not supported".to_string());
+                    }
+                    let alternatives = split_top_level(&chars[i + 2..end], '|')
+                        .iter()
+                        .map(|alternative| translate_extglobs(alternative))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let group = match alternatives.as_slice() {
+                        [alternative] => alternative.clone(),
+                        alternatives => format!("{{{}}}", alternatives.join(",")),
+                    };
+                    match c {
+                        '@' => translated.push_str(&group),
+                        '?' => translated.push_str(&format!("<{group}:0,1>")),
+                        '*' => translated.push_str(&format!("<{group}:0,>")),
+                        '+' => translated.push_str(&format!("<{group}:1,>")),
+                        _ => unreachable!("checked above"),
+                    }
+                    i = end + 1;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        translated.push(c);
+        i += 1;
+    }
+    Ok(translated)
+}
+
+// Expands the first brace expression that isn't inside of a class or
+// repetition, recursing to expand the rest. A brace expression without a
+// comma, or with an empty alternative, is left for wax to handle.
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let chars = pattern.chars().collect::<Vec<_>>();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' => i = find_class_end(&chars, i).unwrap_or(i),
+            '<' => i = find_closing(&chars, i, '<', '>').unwrap_or(i),
+            '{' => {
+                let Some(end) = find_closing(&chars, i, '{', '}') else {
+                    break;
+                };
+                let alternatives = split_top_level(&chars[i + 1..end], ',');
+                if alternatives.len() > 1 && alternatives.iter().all(|a| !a.is_empty()) {
+                    let prefix = chars[..i].iter().collect::<String>();
+                    let suffix = chars[end + 1..].iter().collect::<String>();
+                    return alternatives
+                        .iter()
+                        .flat_map(|alternative| {
+                            expand_braces(&format!("{prefix}{alternative}{suffix}"))
+                        })
+                        .collect();
+                }
+                i = end;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    vec![pattern.to_owned()]
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::expand_glob;
+
+    #[test_case("src/**/*.ts", &["src/**/*.ts"] ; "no expansion")]
+    #[test_case("src/{a,b}/**", &["src/a/**", "src/b/**"] ; "braces")]
+    #[test_case("{src/**/*.ts,lib/**}", &["src/**/*.ts", "lib/**"] ; "braces with tree wildcards")]
+    #[test_case("{a,b{c,d}}.js", &["a.js", "bc.js", "bd.js"] ; "nested braces")]
+    #[test_case("{a,b}/{c,d}", &["a/c", "a/d", "b/c", "b/d"] ; "multiple braces")]
+    #[test_case("{a,a}", &["a"] ; "duplicate alternatives")]
+    #[test_case("a{,bc}", &["a{,bc}"] ; "empty alternative")]
+    #[test_case("{a/*}", &["{a/*}"] ; "single alternative")]
+    #[test_case(r"\{a,b\}", &[r"\{a,b\}"] ; "escaped braces")]
+    #[test_case("[{]a,b}", &["[{]a,b}"] ; "brace in class")]
+    #[test_case("dist/@(esm|cjs)/**", &["dist/esm/**", "dist/cjs/**"] ; "extglob one of")]
+    #[test_case("*.?(m)js", &["*.<m:0,1>js"] ; "extglob optional")]
+    #[test_case("+(a|b).txt", &["<{a,b}:1,>.txt"] ; "extglob one or more")]
+    #[test_case("*(a).txt", &["<a:0,>.txt"] ; "extglob zero or more")]
+    #[test_case("@(a|+(b|c))", &["a", "<{b,c}:1,>"] ; "nested extglob")]
+    #[test_case("file(1).txt", &["file(1).txt"] ; "literal parentheses")]
+    #[test_case("*(unclosed", &["*(unclosed"] ; "unclosed extglob")]
+    fn test_expand_glob(pattern: &str, expected: &[&str]) {
+        assert_eq!(expand_glob(pattern).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_negated_extglob() {
+        assert!(expand_glob("src/!(generated)/**").is_err());
+    }
+}
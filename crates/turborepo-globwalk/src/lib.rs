@@ -1,6 +1,8 @@
 #![feature(assert_matches)]
 #![deny(clippy::all)]
 
+mod expand;
+
 use std::{
     borrow::Cow,
     collections::HashSet,
@@ -18,6 +20,8 @@ use regex::Regex;
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, PathError};
 use wax::{walk::FileIterator, BuildError, Glob};
 
+pub use crate::expand::expand_glob;
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum WalkType {
     Files,
@@ -45,6 +49,8 @@ pub enum WalkError {
     InternalError { glob: String, error: String },
     #[error("IO Error: {0}")]
     IO(#[from] std::io::Error),
+    #[error(transparent)]
+    Glob(#[from] GlobError),
 }
 
 fn join_unix_like_paths(a: &str, b: &str) -> String {
@@ -299,6 +305,14 @@ pub fn globwalk(
     globwalk_internal(base_path, &include, &exclude, walk_type)
 }
 
+fn expand_globs(globs: &[String]) -> Result<Vec<String>, GlobError> {
+    let mut expanded = Vec::with_capacity(globs.len());
+    for glob in globs {
+        expanded.extend(expand_glob(glob)?);
+    }
+    Ok(expanded)
+}
+
 #[tracing::instrument]
 pub fn globwalk_internal(
     base_path: &AbsoluteSystemPath,
@@ -306,8 +320,10 @@ pub fn globwalk_internal(
     exclude: &[String],
     walk_type: WalkType,
 ) -> Result<HashSet<AbsoluteSystemPathBuf>, WalkError> {
+    let include = expand_globs(include)?;
+    let exclude = expand_globs(exclude)?;
     let (base_path_new, include_paths, exclude_paths) =
-        preprocess_paths_and_globs(base_path, include, exclude)?;
+        preprocess_paths_and_globs(base_path, &include, &exclude)?;
 
     let ex_patterns: Vec<_> = exclude_paths
         .into_iter()
@@ -587,6 +603,10 @@ mod test {
     #[test_case("**/*.txt", 1, 1 => matches None)]
     #[test_case("**/【*", 1, 1 => matches None ; "star with unicode")]
     #[test_case("b/**/f", 0, 0 => matches None)]
+    #[test_case("{abc/**,c}", 4, 4 => matches None ; "curly braces with doublestar match")]
+    #[test_case("@(abcd|abcde)", 2, 2 => matches None ; "extglob one of match")]
+    #[test_case("ab+(c|d)", 2, 2 => matches None ; "extglob one or more match")]
+    #[test_case("!(abc)", 0, 0 => matches Some(WalkError::Glob(_)) ; "negated extglob error")]
     fn glob_walk(
         pattern: &str,
         result_count: usize,
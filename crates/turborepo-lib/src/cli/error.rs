@@ -5,7 +5,7 @@ use thiserror::Error;
 use turborepo_repository::package_graph;
 
 use crate::{
-    commands::{bin, generate, prune},
+    commands::{bin, generate, prune, replay},
     daemon::DaemonError,
     rewrite_json::RewriteError,
     run,
@@ -40,6 +40,8 @@ pub enum Error {
     #[diagnostic(transparent)]
     Prune(#[from] prune::Error),
     #[error(transparent)]
+    Replay(#[from] replay::Error),
+    #[error(transparent)]
     PackageJson(#[from] turborepo_repository::package_json::Error),
     #[error(transparent)]
     PackageManager(#[from] turborepo_repository::package_manager::Error),
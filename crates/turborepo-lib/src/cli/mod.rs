@@ -1,11 +1,11 @@
-use std::{backtrace, backtrace::Backtrace, env, fmt, fmt::Display, io, mem, process};
+use std::{backtrace, backtrace::Backtrace, env, fmt, fmt::Display, mem, process};
 
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::{
     builder::NonEmptyStringValueParser, ArgAction, ArgGroup, CommandFactory, Parser, Subcommand,
     ValueEnum,
 };
-use clap_complete::{generate, Shell};
+use clap_complete::Shell;
 pub use error::Error;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error};
@@ -24,8 +24,8 @@ use turborepo_ui::UI;
 
 use crate::{
     commands::{
-        bin, daemon, generate, info, link, login, logout, prune, run, scan, telemetry, unlink,
-        CommandBase,
+        bin, completion, completion::CompletionKind, daemon, generate, info, link, login, logout,
+        prune, replay, run, scan, telemetry, unlink, CommandBase,
     },
     get_version,
     shim::TurboState,
@@ -433,6 +433,10 @@ pub enum Command {
     /// Generate the autocompletion script for the specified shell
     #[serde(skip)]
     Completion { shell: Shell },
+    /// Prints values used by the dynamic parts of the completion scripts
+    #[clap(hide = true)]
+    #[serde(skip)]
+    CompletionValues { kind: CompletionKind },
     /// Runs the Turborepo background daemon
     Daemon {
         /// Set the idle timeout for turbod
@@ -523,6 +527,17 @@ pub enum Command {
         #[clap(long = "out-dir", default_value_t = String::from(prune::DEFAULT_OUTPUT_DIR), value_parser)]
         output_dir: String,
     },
+    /// Re-run a single task from a run summary with the environment it
+    /// originally ran with
+    Replay {
+        /// Path to a run summary, or the id of a summary in .turbo/runs
+        summary: String,
+        /// The id of the task to replay, e.g. web#build
+        task: String,
+        /// Replay even if the task's inputs have changed since it ran
+        #[clap(long)]
+        force: bool,
+    },
 
     /// Run tasks across projects in your monorepo
     ///
@@ -719,6 +734,13 @@ pub struct RunArgs {
     #[clap(long)]
     pub no_cache: bool,
 
+    /// Guarantee that no network requests are made. The remote cache,
+    /// telemetry, analytics, the update check and run summary uploads are
+    /// disabled, and turbo errors out if an option that requires the
+    /// network is set.
+    #[clap(long)]
+    pub offline: bool,
+
     // clap does not have negation flags such as --daemon and --no-daemon
     // so we need to use a group to enforce that only one of them is set.
     // we set the long name as [no-]daemon with an alias of daemon such
@@ -830,6 +852,7 @@ impl RunArgs {
         track_usage!(telemetry, self.single_package, |val| val);
         track_usage!(telemetry, self.no_deps, |val| val);
         track_usage!(telemetry, self.no_cache, |val| val);
+        track_usage!(telemetry, self.offline, |val| val);
         track_usage!(telemetry, self.daemon, |val| val);
         track_usage!(telemetry, self.no_daemon, |val| val);
         track_usage!(telemetry, self.only, |val| val);
@@ -957,19 +980,31 @@ pub async fn run(
     // track telemetry handle to close at the end of the run
     let mut telemetry_handle: Option<TelemetryHandle> = None;
 
-    // initialize telemetry
-    match AnonAPIClient::new("https://telemetry.vercel.com", 250, version) {
-        Ok(anonymous_api_client) => {
-            let handle = init_telemetry(anonymous_api_client, ui);
-            match handle {
-                Ok(h) => telemetry_handle = Some(h),
-                Err(error) => {
-                    debug!("failed to start telemetry: {:?}", error)
+    // initialize telemetry, unless the run should not touch the network
+    let is_offline = match &cli_args.command {
+        Some(Command::Run(run_args)) => run_args.offline,
+        Some(_) => false,
+        None => cli_args
+            .run_args
+            .as_ref()
+            .is_some_and(|run_args| run_args.offline),
+    };
+    if is_offline {
+        debug!("running offline, skipping telemetry");
+    } else {
+        match AnonAPIClient::new("https://telemetry.vercel.com", 250, version) {
+            Ok(anonymous_api_client) => {
+                let handle = init_telemetry(anonymous_api_client, ui);
+                match handle {
+                    Ok(h) => telemetry_handle = Some(h),
+                    Err(error) => {
+                        debug!("failed to start telemetry: {:?}", error)
+                    }
                 }
             }
-        }
-        Err(error) => {
-            debug!("Failed to create AnonAPIClient: {:?}", error);
+            Err(error) => {
+                debug!("Failed to create AnonAPIClient: {:?}", error);
+            }
         }
     }
 
@@ -1236,11 +1271,28 @@ pub async fn run(
             prune::prune(&base, &scope, docker, &output_dir, event_child).await?;
             Ok(0)
         }
+        Command::Replay {
+            summary,
+            task,
+            force,
+        } => {
+            CommandEventBuilder::new("replay")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let base = CommandBase::new(cli_args.clone(), repo_root, version, ui);
+            let exit_code = replay::run(&base, summary, task, *force)?;
+            Ok(exit_code)
+        }
         Command::Completion { shell } => {
             CommandEventBuilder::new("completion")
                 .with_parent(&root_telemetry)
                 .track_call();
-            generate(*shell, &mut Args::command(), "turbo", &mut io::stdout());
+            print!("{}", completion::script(*shell, &mut Args::command()));
+            Ok(0)
+        }
+        Command::CompletionValues { kind } => {
+            let base = CommandBase::new(cli_args.clone(), repo_root, version, ui);
+            completion::values(&base, *kind).await?;
             Ok(0)
         }
     };
@@ -1320,7 +1372,8 @@ mod test {
     use anyhow::Result;
 
     use crate::cli::{
-        Args, Command, DryRunMode, EnvMode, LogOrder, LogPrefix, OutputLogsMode, RunArgs, Verbosity,
+        Args, Command, CompletionKind, DryRunMode, EnvMode, LogOrder, LogPrefix, OutputLogsMode,
+        RunArgs, Verbosity,
     };
 
     #[test_case::test_case(
@@ -1877,6 +1930,18 @@ mod test {
 		} ;
         "remote_only=false works"
 	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--offline"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                offline: true,
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+		} ;
+        "offline"
+	)]
     #[test_case::test_case(
 		&["turbo", "run", "build", "--scope", "foo", "--scope", "bar"],
         Args {
@@ -2393,4 +2458,17 @@ mod test {
         );
         assert!(Args::try_parse_from(["turbo", "build", "--preflight=true"]).is_err());
     }
+
+    #[test]
+    fn test_completion_values() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "completion-values", "tasks"])
+                .unwrap()
+                .command,
+            Some(Command::CompletionValues {
+                kind: CompletionKind::Tasks
+            })
+        );
+        assert!(Args::try_parse_from(["turbo", "completion-values", "nope"]).is_err());
+    }
 }
@@ -0,0 +1,331 @@
+//! Dynamic shell completions.
+//!
+//! The scripts generated by `clap_complete` only know about the static shape
+//! of the CLI. The snippets here are appended to them and call back into
+//! `turbo completion-values` to complete package names, task names and
+//! `--filter` syntax for the repository the user is currently in.
+use std::collections::BTreeSet;
+#[cfg(feature = "daemon-package-discovery")]
+use std::time::Duration;
+
+use clap::ValueEnum;
+use clap_complete::{generate, Shell};
+use serde::Serialize;
+use turbopath::{AbsoluteSystemPath, AnchoredSystemPath};
+#[cfg(feature = "daemon-package-discovery")]
+use turborepo_repository::discovery::FallbackPackageDiscovery;
+use turborepo_repository::{
+    discovery::{LocalPackageDiscoveryBuilder, PackageDiscovery, PackageDiscoveryBuilder},
+    package_graph,
+    package_json::PackageJson,
+};
+
+use crate::{cli, commands::CommandBase, turbo_json::TurboJson};
+#[cfg(feature = "daemon-package-discovery")]
+use crate::{daemon::DaemonConnector, run::package_discovery::DaemonPackageDiscovery};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, ValueEnum)]
+pub enum CompletionKind {
+    /// Names of all packages in the repository
+    Packages,
+    /// Task names from turbo.json and package.json scripts
+    Tasks,
+    /// Values for --filter, including the dependency/dependent forms
+    Filters,
+}
+
+const BASH_DYNAMIC: &str = r#"
+_turbo_dynamic() {
+    local cur prev
+    cur="${COMP_WORDS[COMP_CWORD]}"
+    prev="${COMP_WORDS[COMP_CWORD-1]}"
+    if [[ "${prev}" == "--filter" || "${prev}" == "-F" ]]; then
+        COMPREPLY=( $(compgen -W "$(turbo completion-values filters 2>/dev/null)" -- "${cur}") )
+        return 0
+    fi
+    if [[ "${COMP_WORDS[1]}" == "run" && "${cur}" != -* ]]; then
+        COMPREPLY=( $(compgen -W "$(turbo completion-values tasks 2>/dev/null)" -- "${cur}") )
+        return 0
+    fi
+    _turbo "$@"
+}
+
+complete -F _turbo_dynamic -o bashdefault -o default turbo
+"#;
+
+const ZSH_DYNAMIC: &str = r#"
+_turbo_dynamic() {
+    if [[ "${words[CURRENT-1]}" == "--filter" || "${words[CURRENT-1]}" == "-F" ]]; then
+        compadd -- ${(f)"$(turbo completion-values filters 2>/dev/null)"}
+        return
+    fi
+    if [[ "${words[2]}" == "run" && "${PREFIX}" != -* ]]; then
+        compadd -- ${(f)"$(turbo completion-values tasks 2>/dev/null)"}
+        return
+    fi
+    _turbo "$@"
+}
+
+compdef _turbo_dynamic turbo
+"#;
+
+const FISH_DYNAMIC: &str = r#"
+complete -c turbo -n "__fish_seen_subcommand_from run" -f -a "(turbo completion-values tasks 2>/dev/null)"
+complete -c turbo -l filter -s F -x -a "(turbo completion-values filters 2>/dev/null)"
+"#;
+
+// PowerShell only keeps one completer per command, so the static completer is
+// stored in a variable instead of being registered and the dynamic one falls
+// back to it.
+const POWERSHELL_STATIC_REGISTRATION: &str =
+    "Register-ArgumentCompleter -Native -CommandName 'turbo' -ScriptBlock {";
+const POWERSHELL_STATIC_COMPLETER: &str = "$global:__turbo_static_completer = {";
+
+const POWERSHELL_DYNAMIC: &str = r#"
+Register-ArgumentCompleter -Native -CommandName 'turbo' -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $elements = @($commandAst.CommandElements | ForEach-Object { $_.ToString() })
+    $previous = if ($wordToComplete) { $elements[-2] } else { $elements[-1] }
+    $kind = $null
+    if ($previous -eq '--filter' -or $previous -eq '-F') {
+        $kind = 'filters'
+    } elseif ($elements.Count -gt 1 -and $elements[1] -eq 'run' -and -not $wordToComplete.StartsWith('-')) {
+        $kind = 'tasks'
+    }
+    if ($kind) {
+        turbo completion-values $kind 2>$null |
+            Where-Object { $_ -like "$wordToComplete*" } |
+            ForEach-Object {
+                [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+            }
+        return
+    }
+    & $global:__turbo_static_completer $wordToComplete $commandAst $cursorPosition
+}
+"#;
+
+/// Returns the snippet that adds dynamic completions on top of the static
+/// script for `shell`. Shells without a snippet only get static completions.
+fn dynamic_script(shell: Shell) -> Option<&'static str> {
+    match shell {
+        Shell::Bash => Some(BASH_DYNAMIC),
+        Shell::Zsh => Some(ZSH_DYNAMIC),
+        Shell::Fish => Some(FISH_DYNAMIC),
+        Shell::PowerShell => Some(POWERSHELL_DYNAMIC),
+        _ => None,
+    }
+}
+
+/// Generates the completion script for `shell`, including dynamic completions
+/// where the shell supports them.
+pub fn script(shell: Shell, cmd: &mut clap::Command) -> String {
+    let mut buf = Vec::new();
+    generate(shell, cmd, "turbo", &mut buf);
+    let mut script = String::from_utf8(buf).expect("clap_complete generates utf8 scripts");
+
+    if shell == Shell::PowerShell {
+        if !script.contains(POWERSHELL_STATIC_REGISTRATION) {
+            // Registering the dynamic completer would replace the static one
+            return script;
+        }
+        script = script.replacen(
+            POWERSHELL_STATIC_REGISTRATION,
+            POWERSHELL_STATIC_COMPLETER,
+            1,
+        );
+    }
+    if let Some(dynamic) = dynamic_script(shell) {
+        script.push_str(dynamic);
+    }
+    script
+}
+
+pub async fn values(base: &CommandBase, kind: CompletionKind) -> Result<(), cli::Error> {
+    for value in completion_values(&base.repo_root, kind).await? {
+        println!("{value}");
+    }
+
+    Ok(())
+}
+
+async fn completion_values(
+    repo_root: &AbsoluteSystemPath,
+    kind: CompletionKind,
+) -> Result<Vec<String>, cli::Error> {
+    let root_package_json = PackageJson::load(&repo_root.join_component("package.json"))?;
+    let package_jsons = workspace_package_jsons(repo_root, &root_package_json).await?;
+
+    Ok(match kind {
+        CompletionKind::Tasks => task_names(repo_root, &root_package_json, &package_jsons)
+            .into_iter()
+            .collect(),
+        CompletionKind::Packages => package_names(&package_jsons).into_iter().collect(),
+        CompletionKind::Filters => package_names(&package_jsons)
+            .into_iter()
+            .flat_map(|name| [name.clone(), format!("{name}..."), format!("...{name}")])
+            .collect(),
+    })
+}
+
+/// Loads the package.json of every workspace, excluding the root.
+///
+/// Completions run on every tab press, so this only discovers the workspaces
+/// rather than building the package graph, and only asks the daemon if one is
+/// already running.
+async fn workspace_package_jsons(
+    repo_root: &AbsoluteSystemPath,
+    root_package_json: &PackageJson,
+) -> Result<Vec<PackageJson>, cli::Error> {
+    let local_discovery = LocalPackageDiscoveryBuilder::new(
+        repo_root.to_owned(),
+        None,
+        Some(root_package_json.clone()),
+    )
+    .build()?;
+
+    #[cfg(feature = "daemon-package-discovery")]
+    let discovery = {
+        let can_start_server = false;
+        let can_kill_server = false;
+        let connector = DaemonConnector::new(can_start_server, can_kill_server, repo_root);
+        match connector.connect().await {
+            Ok(daemon) => {
+                FallbackPackageDiscovery::new(
+                    DaemonPackageDiscovery::new(daemon),
+                    local_discovery,
+                    Duration::from_millis(10),
+                )
+                .discover_packages()
+                .await
+            }
+            Err(_) => local_discovery.discover_packages().await,
+        }
+    };
+    #[cfg(not(feature = "daemon-package-discovery"))]
+    let discovery = local_discovery.discover_packages().await;
+
+    let workspaces = discovery.map_err(package_graph::Error::from)?.workspaces;
+
+    // A package.json that fails to load would also fail the run, there's nothing
+    // to complete for it
+    Ok(workspaces
+        .iter()
+        .filter_map(|workspace| PackageJson::load(&workspace.package_json).ok())
+        .collect())
+}
+
+fn package_names(package_jsons: &[PackageJson]) -> BTreeSet<String> {
+    package_jsons
+        .iter()
+        .filter_map(|package_json| package_json.name.clone())
+        .collect()
+}
+
+fn task_names(
+    repo_root: &AbsoluteSystemPath,
+    root_package_json: &PackageJson,
+    package_jsons: &[PackageJson],
+) -> BTreeSet<String> {
+    let mut tasks = BTreeSet::new();
+
+    // A missing or invalid turbo.json shouldn't prevent completing scripts
+    if let Ok(turbo_json) = TurboJson::load(
+        repo_root,
+        AnchoredSystemPath::empty(),
+        root_package_json,
+        false,
+    ) {
+        tasks.extend(
+            turbo_json
+                .pipeline
+                .keys()
+                .map(|task_name| task_name.task().to_string()),
+        );
+    }
+
+    for package_json in std::iter::once(root_package_json).chain(package_jsons) {
+        tasks.extend(package_json.scripts.keys().cloned());
+    }
+
+    tasks
+}
+
+#[cfg(test)]
+mod test {
+    use clap::CommandFactory;
+    use clap_complete::Shell;
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::{completion_values, script, CompletionKind, POWERSHELL_STATIC_COMPLETER};
+    use crate::cli::Args;
+
+    fn setup_repo() -> (TempDir, AbsoluteSystemPathBuf) {
+        let tmp = TempDir::new().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(
+                r#"{"name": "root", "packageManager": "npm@10.2.4", "workspaces": ["packages/*"], "scripts": {"format": "prettier"}}"#,
+            )
+            .unwrap();
+        repo_root
+            .join_component("turbo.json")
+            .create_with_contents(r#"{"pipeline": {"build": {}, "web#deploy": {}}}"#)
+            .unwrap();
+        for (name, script) in [("web", "dev"), ("docs", "lint")] {
+            repo_root
+                .join_components(&["packages", name, "package.json"])
+                .ensure_dir()
+                .unwrap();
+            repo_root
+                .join_components(&["packages", name, "package.json"])
+                .create_with_contents(format!(
+                    r#"{{"name": "{name}", "scripts": {{"{script}": "true"}}}}"#
+                ))
+                .unwrap();
+        }
+        (tmp, repo_root)
+    }
+
+    #[tokio::test]
+    async fn test_package_values() {
+        let (_tmp, repo_root) = setup_repo();
+
+        let packages = completion_values(&repo_root, CompletionKind::Packages)
+            .await
+            .unwrap();
+        assert_eq!(packages, vec!["docs", "web"]);
+
+        let filters = completion_values(&repo_root, CompletionKind::Filters)
+            .await
+            .unwrap();
+        assert_eq!(
+            filters,
+            vec!["docs", "docs...", "...docs", "web", "web...", "...web"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_task_values() {
+        let (_tmp, repo_root) = setup_repo();
+
+        let tasks = completion_values(&repo_root, CompletionKind::Tasks)
+            .await
+            .unwrap();
+        assert_eq!(tasks, vec!["build", "deploy", "dev", "format", "lint"]);
+    }
+
+    #[test]
+    fn test_powershell_script() {
+        let script = script(Shell::PowerShell, &mut Args::command());
+        assert!(script.contains(POWERSHELL_STATIC_COMPLETER));
+        assert!(script.contains("turbo completion-values $kind"));
+        assert_eq!(
+            script
+                .matches("Register-ArgumentCompleter -Native -CommandName 'turbo'")
+                .count(),
+            1
+        );
+    }
+}
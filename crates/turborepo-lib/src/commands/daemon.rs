@@ -8,7 +8,7 @@ use time::{format_description, OffsetDateTime};
 use tokio::signal::ctrl_c;
 use tracing::{trace, warn};
 use turbopath::AbsoluteSystemPath;
-use turborepo_ui::{color, BOLD_GREEN, BOLD_RED, GREY};
+use turborepo_ui::{color, BOLD_GREEN, BOLD_RED, GREY, YELLOW};
 use which::which;
 
 use super::CommandBase;
@@ -100,6 +100,12 @@ pub async fn daemon_client(command: &DaemonCommand, base: &CommandBase) -> Resul
                 log_file: log_file.into(),
                 pid_file: paths.pid_file.to_owned(),
                 sock_file: paths.sock_file.to_owned(),
+                clock_skew: status.clock_skew.map(|clock_skew| ClockSkewStatus {
+                    message: clock_skew.to_string(),
+                    anomalies: clock_skew.anomalies,
+                    last_path: clock_skew.last_path,
+                    last_skew_ms: clock_skew.last_skew_msec,
+                }),
             };
 
             if *json {
@@ -121,6 +127,12 @@ pub async fn daemon_client(command: &DaemonCommand, base: &CommandBase) -> Resul
                     "socket file: {}",
                     color!(base.ui, GREY, "{}", status.sock_file)
                 );
+                if let Some(clock_skew) = &status.clock_skew {
+                    println!(
+                        "clock skew: {}",
+                        color!(base.ui, YELLOW, "{}", clock_skew.message)
+                    );
+                }
             }
         }
         DaemonCommand::Logs => {
@@ -312,4 +324,14 @@ pub struct DaemonStatus {
     pub log_file: Utf8PathBuf,
     pub pid_file: turbopath::AbsoluteSystemPathBuf,
     pub sock_file: turbopath::AbsoluteSystemPathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clock_skew: Option<ClockSkewStatus>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ClockSkewStatus {
+    pub message: String,
+    pub anomalies: u64,
+    pub last_path: String,
+    pub last_skew_ms: i64,
 }
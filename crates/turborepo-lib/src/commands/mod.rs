@@ -12,6 +12,7 @@ use crate::{
 };
 
 pub(crate) mod bin;
+pub(crate) mod completion;
 pub(crate) mod daemon;
 pub(crate) mod generate;
 pub(crate) mod info;
@@ -19,6 +20,7 @@ pub(crate) mod link;
 pub(crate) mod login;
 pub(crate) mod logout;
 pub(crate) mod prune;
+pub(crate) mod replay;
 pub(crate) mod run;
 pub(crate) mod scan;
 pub(crate) mod telemetry;
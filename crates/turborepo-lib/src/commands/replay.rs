@@ -0,0 +1,231 @@
+//! `turbo replay` re-executes a single task from a saved run summary with the
+//! exact environment it ran with, which makes it possible to chase down tasks
+//! that only fail some of the time.
+//!
+//! Inputs can't be restored from a summary, so instead they are re-hashed and
+//! compared against the ones recorded for the task. Replaying with different
+//! inputs would defeat the point, so this is an error unless `--force` is
+//! passed.
+
+use std::{collections::BTreeMap, io, process::Command};
+
+use serde::Deserialize;
+use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf, RelativeUnixPathBuf};
+use turborepo_repository::{package_json::PackageJson, package_manager::PackageManager};
+use turborepo_scm::SCM;
+use turborepo_ui::{cprintln, GREY, YELLOW};
+use which::which;
+
+use crate::{
+    commands::CommandBase,
+    config,
+    run::summary::{EnvSnapshot, EnvSnapshotError, EnvSnapshotKey},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("unable to read run summary {path}: {source}")]
+    ReadSummary {
+        path: AbsoluteSystemPathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("unable to parse run summary: {0}")]
+    ParseSummary(#[from] serde_json::Error),
+    #[error("task {0} not found in run summary")]
+    MissingTask(String),
+    #[error(
+        "run summary has no environment snapshot for {0}. Only tasks that were executed by a run \
+         with --summarize can be replayed."
+    )]
+    MissingSnapshot(String),
+    #[error("unable to open environment snapshot: {0}")]
+    Snapshot(#[from] EnvSnapshotError),
+    #[error(
+        "inputs of {task} have changed: {}. Use --force to replay anyway",
+        .files.join(", ")
+    )]
+    InputsChanged { task: String, files: Vec<String> },
+    #[error("unable to hash inputs: {0}")]
+    Scm(#[from] turborepo_scm::Error),
+    #[error(transparent)]
+    Path(#[from] turbopath::PathError),
+    #[error(transparent)]
+    Config(#[from] config::Error),
+    #[error(transparent)]
+    PackageJson(#[from] turborepo_repository::package_json::Error),
+    #[error(transparent)]
+    PackageManager(#[from] turborepo_repository::package_manager::Error),
+    #[error("unable to find {0}")]
+    Which(&'static str, #[source] which::Error),
+    #[error("unable to run {task}: {source}")]
+    Spawn {
+        task: String,
+        #[source]
+        source: io::Error,
+    },
+}
+
+// The parts of a saved run summary needed to replay a task
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RunSummary {
+    tasks: Vec<TaskSummary>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskSummary {
+    task_id: String,
+    task: String,
+    hash: String,
+    inputs: BTreeMap<RelativeUnixPathBuf, String>,
+    cli_arguments: Vec<String>,
+    // absent for single package runs, where tasks run in the repo root
+    directory: Option<String>,
+    resolved_task_definition: TaskDefinition,
+    environment_snapshot: Option<EnvSnapshot>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskDefinition {
+    inputs: Vec<String>,
+    dot_env: Option<Vec<RelativeUnixPathBuf>>,
+}
+
+/// Replays `task` from `summary`, which is either a path to a run summary or
+/// the id of one in `.turbo/runs`. Returns the exit code of the task.
+pub fn run(base: &CommandBase, summary: &str, task: &str, force: bool) -> Result<i32, Error> {
+    let repo_root = &base.repo_root;
+    let summary_path = summary_path(base, summary)?;
+    let contents = summary_path
+        .read_to_string()
+        .map_err(|source| Error::ReadSummary {
+            path: summary_path.clone(),
+            source,
+        })?;
+    let summary: RunSummary = serde_json::from_str(&contents)?;
+
+    let task_summary = summary
+        .tasks
+        .into_iter()
+        .find(|t| t.task_id == task)
+        .ok_or_else(|| Error::MissingTask(task.to_string()))?;
+    let snapshot = task_summary
+        .environment_snapshot
+        .as_ref()
+        .ok_or_else(|| Error::MissingSnapshot(task.to_string()))?;
+    let env = EnvSnapshotKey::load()?.open(&task_summary.hash, snapshot)?;
+
+    let package_path = match &task_summary.directory {
+        Some(directory) => AnchoredSystemPathBuf::from_raw(directory)?,
+        None => AnchoredSystemPathBuf::default(),
+    };
+
+    let changed = changed_inputs(base, &package_path, &task_summary)?;
+    if !changed.is_empty() {
+        if !force {
+            return Err(Error::InputsChanged {
+                task: task.to_string(),
+                files: changed,
+            });
+        }
+        cprintln!(
+            base.ui,
+            YELLOW,
+            "Inputs of {} have changed since it ran: {}",
+            task,
+            changed.join(", ")
+        );
+    }
+
+    let root_package_json =
+        PackageJson::load(&repo_root.join_component("package.json")).unwrap_or_default();
+    let package_manager = PackageManager::get_package_manager(repo_root, Some(&root_package_json))?;
+    let binary =
+        which(package_manager.command()).map_err(|e| Error::Which(package_manager.command(), e))?;
+
+    let mut args = vec!["run".to_string(), task_summary.task.clone()];
+    if !task_summary.cli_arguments.is_empty() {
+        args.extend(
+            package_manager
+                .arg_separator(&task_summary.cli_arguments)
+                .map(|s| s.to_string()),
+        );
+        args.extend(task_summary.cli_arguments.iter().cloned());
+    }
+
+    cprintln!(
+        base.ui,
+        GREY,
+        "Replaying {} ({}) with {} environment variables",
+        task,
+        task_summary.hash,
+        env.len()
+    );
+
+    let status = Command::new(binary)
+        .args(args)
+        .current_dir(repo_root.resolve(&package_path))
+        .env_clear()
+        .envs(env.iter())
+        .status()
+        .map_err(|source| Error::Spawn {
+            task: task.to_string(),
+            source,
+        })?;
+
+    Ok(status.code().unwrap_or(1))
+}
+
+fn summary_path(base: &CommandBase, summary: &str) -> Result<AbsoluteSystemPathBuf, Error> {
+    let path = AbsoluteSystemPathBuf::from_unknown(&AbsoluteSystemPathBuf::cwd()?, summary);
+    if path.exists() {
+        return Ok(path);
+    }
+    // not a file, so treat it as the id of a saved summary
+    Ok(base
+        .repo_root
+        .join_components(&[".turbo", "runs", &format!("{summary}.json")]))
+}
+
+/// Hashes the task's inputs the same way the run did and returns the files
+/// that were added, removed or modified since.
+fn changed_inputs(
+    base: &CommandBase,
+    package_path: &AnchoredSystemPathBuf,
+    task_summary: &TaskSummary,
+) -> Result<Vec<String>, Error> {
+    let config = base.config()?;
+    let scm = SCM::new(&base.repo_root)
+        .with_large_file_threshold(config.large_file_threshold())
+        .with_timeout(config.scm_timeout())
+        .with_reuse_index_hashes(config.reuse_index_hashes());
+
+    let definition = &task_summary.resolved_task_definition;
+    let mut current =
+        scm.get_package_file_hashes(&base.repo_root, package_path, &definition.inputs, None)?;
+    if let Some(dot_env) = definition.dot_env.as_ref().filter(|d| !d.is_empty()) {
+        current.extend(scm.hash_existing_of(
+            &base.repo_root.resolve(package_path),
+            dot_env.iter().map(|p| p.to_anchored_system_path_buf()),
+        )?);
+    }
+
+    let mut changed = task_summary
+        .inputs
+        .iter()
+        .filter(|(file, hash)| current.get(*file) != Some(*hash))
+        .map(|(file, _)| file.to_string())
+        .collect::<Vec<_>>();
+    changed.extend(
+        current
+            .keys()
+            .filter(|file| !task_summary.inputs.contains_key(*file))
+            .map(|file| file.to_string()),
+    );
+    changed.sort();
+
+    Ok(changed)
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, ffi::OsString, io};
+use std::{collections::HashMap, ffi::OsString, io, time::Duration};
 
 use convert_case::{Case, Casing};
 use miette::{Diagnostic, NamedSource, SourceSpan};
@@ -111,6 +111,13 @@ pub enum Error {
         #[source_code]
         text: NamedSource,
     },
+    #[error("root relative `outputs` cannot point outside of the repository")]
+    RootOutputOutsideRepo {
+        #[label("`..` found here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        text: NamedSource,
+    },
     #[error("No \"extends\" key found")]
     NoExtends {
         #[label("add extends key here")]
@@ -118,6 +125,14 @@ pub enum Error {
         #[source_code]
         text: NamedSource,
     },
+    #[error("Invalid `cacheTtl`: {reason}")]
+    InvalidCacheTtl {
+        reason: String,
+        #[label("invalid duration found here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        text: NamedSource,
+    },
     #[error("Tasks cannot be marked as interactive and cacheable")]
     InteractiveNoCacheable {
         #[label("marked interactive here")]
@@ -135,6 +150,14 @@ pub enum Error {
     InvalidRemoteCacheEnabled,
     #[error("TURBO_REMOTE_CACHE_TIMEOUT: error parsing timeout.")]
     InvalidRemoteCacheTimeout(#[source] std::num::ParseIntError),
+    #[error("TURBO_LARGE_FILE_THRESHOLD: error parsing size in bytes.")]
+    InvalidLargeFileThreshold(#[source] std::num::ParseIntError),
+    #[error("TURBO_SCM_TIMEOUT: error parsing timeout.")]
+    InvalidScmTimeout(#[source] std::num::ParseIntError),
+    #[error("TURBO_DISK_SPACE_RESERVE: error parsing size in bytes.")]
+    InvalidDiskSpaceReserve(#[source] std::num::ParseIntError),
+    #[error("TURBO_REMOTE_MISS_TTL: error parsing ttl.")]
+    InvalidRemoteMissTtl(#[source] std::num::ParseIntError),
     #[error("TURBO_PREFLIGHT should be either 1 or 0.")]
     InvalidPreflight,
     #[error(transparent)]
@@ -185,6 +208,11 @@ pub struct ConfigurationOptions {
     pub(crate) spaces_id: Option<String>,
     #[serde(rename = "experimentalUI")]
     pub(crate) experimental_ui: Option<bool>,
+    pub(crate) large_file_threshold: Option<u64>,
+    pub(crate) scm_timeout: Option<u64>,
+    pub(crate) reuse_index_hashes: Option<bool>,
+    pub(crate) disk_space_reserve: Option<u64>,
+    pub(crate) remote_miss_ttl: Option<u64>,
 }
 
 #[derive(Default)]
@@ -245,6 +273,26 @@ impl ConfigurationOptions {
     pub fn experimental_ui(&self) -> bool {
         self.experimental_ui.unwrap_or_default() && atty::is(atty::Stream::Stdout)
     }
+
+    pub fn large_file_threshold(&self) -> Option<u64> {
+        self.large_file_threshold
+    }
+
+    pub fn scm_timeout(&self) -> Option<Duration> {
+        self.scm_timeout.map(Duration::from_secs)
+    }
+
+    pub fn reuse_index_hashes(&self) -> bool {
+        self.reuse_index_hashes.unwrap_or_default()
+    }
+
+    pub fn disk_space_reserve(&self) -> Option<u64> {
+        self.disk_space_reserve
+    }
+
+    pub fn remote_miss_ttl(&self) -> Option<Duration> {
+        self.remote_miss_ttl.map(Duration::from_secs)
+    }
 }
 
 // Maps Some("") to None to emulate how Go handles empty strings
@@ -285,6 +333,11 @@ impl ResolvedConfigurationOptions for RawTurboJson {
             .and_then(|spaces| spaces.id)
             .map(|spaces_id| spaces_id.into());
         opts.experimental_ui = self.experimental_ui;
+        opts.large_file_threshold = self.large_file_threshold;
+        opts.scm_timeout = self.scm_timeout;
+        opts.reuse_index_hashes = self.reuse_index_hashes;
+        opts.disk_space_reserve = self.disk_space_reserve;
+        opts.remote_miss_ttl = self.remote_miss_ttl;
         Ok(opts)
     }
 }
@@ -314,6 +367,20 @@ fn get_env_var_config(
     turbo_mapping.insert(OsString::from("turbo_remote_cache_timeout"), "timeout");
     turbo_mapping.insert(OsString::from("turbo_experimental_ui"), "experimental_ui");
     turbo_mapping.insert(OsString::from("turbo_preflight"), "preflight");
+    turbo_mapping.insert(
+        OsString::from("turbo_large_file_threshold"),
+        "large_file_threshold",
+    );
+    turbo_mapping.insert(OsString::from("turbo_scm_timeout"), "scm_timeout");
+    turbo_mapping.insert(
+        OsString::from("turbo_reuse_index_hashes"),
+        "reuse_index_hashes",
+    );
+    turbo_mapping.insert(
+        OsString::from("turbo_disk_space_reserve"),
+        "disk_space_reserve",
+    );
+    turbo_mapping.insert(OsString::from("turbo_remote_miss_ttl"), "remote_miss_ttl");
 
     // We do not enable new config sources:
     // turbo_mapping.insert(String::from("turbo_signature"), "signature"); // new
@@ -383,6 +450,38 @@ fn get_env_var_config(
         None
     };
 
+    // Process largeFileThreshold
+    let large_file_threshold = output_map
+        .get("large_file_threshold")
+        .filter(|threshold| !threshold.is_empty())
+        .map(|threshold| threshold.parse::<u64>())
+        .transpose()
+        .map_err(Error::InvalidLargeFileThreshold)?;
+
+    // Process scmTimeout
+    let scm_timeout = output_map
+        .get("scm_timeout")
+        .filter(|timeout| !timeout.is_empty())
+        .map(|timeout| timeout.parse::<u64>())
+        .transpose()
+        .map_err(Error::InvalidScmTimeout)?;
+
+    // Process diskSpaceReserve
+    let disk_space_reserve = output_map
+        .get("disk_space_reserve")
+        .filter(|reserve| !reserve.is_empty())
+        .map(|reserve| reserve.parse::<u64>())
+        .transpose()
+        .map_err(Error::InvalidDiskSpaceReserve)?;
+
+    // Process remoteMissTtl
+    let remote_miss_ttl = output_map
+        .get("remote_miss_ttl")
+        .filter(|ttl| !ttl.is_empty())
+        .map(|ttl| ttl.parse::<u64>())
+        .transpose()
+        .map_err(Error::InvalidRemoteMissTtl)?;
+
     // Process experimentalUI
     let experimental_ui = output_map
         .get("experimental_ui")
@@ -392,6 +491,16 @@ fn get_env_var_config(
             _ => None,
         });
 
+    // Process reuseIndexHashes
+    let reuse_index_hashes =
+        output_map
+            .get("reuse_index_hashes")
+            .and_then(|val| match val.as_str() {
+                "true" | "1" => Some(true),
+                "false" | "0" => Some(false),
+                _ => None,
+            });
+
     // We currently don't pick up a Spaces ID via env var, we likely won't
     // continue using the Spaces name, we can add an env var when we have the
     // name we want to stick with.
@@ -409,9 +518,14 @@ fn get_env_var_config(
         preflight,
         enabled,
         experimental_ui,
+        reuse_index_hashes,
 
         // Processed numbers
         timeout,
+        large_file_threshold,
+        scm_timeout,
+        disk_space_reserve,
+        remote_miss_ttl,
         spaces_id,
     };
 
@@ -457,6 +571,11 @@ fn get_override_env_var_config(
         enabled: None,
         experimental_ui: None,
         timeout: None,
+        large_file_threshold: None,
+        scm_timeout: None,
+        reuse_index_hashes: None,
+        disk_space_reserve: None,
+        remote_miss_ttl: None,
         spaces_id: None,
     };
 
@@ -593,6 +712,11 @@ impl TurborepoConfigBuilder {
     create_builder!(with_preflight, preflight, Option<bool>);
     create_builder!(with_timeout, timeout, Option<u64>);
     create_builder!(with_experimental_ui, experimental_ui, Option<bool>);
+    create_builder!(with_large_file_threshold, large_file_threshold, Option<u64>);
+    create_builder!(with_scm_timeout, scm_timeout, Option<u64>);
+    create_builder!(with_reuse_index_hashes, reuse_index_hashes, Option<bool>);
+    create_builder!(with_disk_space_reserve, disk_space_reserve, Option<u64>);
+    create_builder!(with_remote_miss_ttl, remote_miss_ttl, Option<u64>);
 
     pub fn build(&self) -> Result<ConfigurationOptions, Error> {
         // Priority, from least significant to most significant:
@@ -682,6 +806,21 @@ impl TurborepoConfigBuilder {
                     if let Some(experimental_ui) = current_source_config.experimental_ui {
                         acc.experimental_ui = Some(experimental_ui);
                     }
+                    if let Some(threshold) = current_source_config.large_file_threshold {
+                        acc.large_file_threshold = Some(threshold);
+                    }
+                    if let Some(scm_timeout) = current_source_config.scm_timeout {
+                        acc.scm_timeout = Some(scm_timeout);
+                    }
+                    if let Some(reuse_index_hashes) = current_source_config.reuse_index_hashes {
+                        acc.reuse_index_hashes = Some(reuse_index_hashes);
+                    }
+                    if let Some(reserve) = current_source_config.disk_space_reserve {
+                        acc.disk_space_reserve = Some(reserve);
+                    }
+                    if let Some(ttl) = current_source_config.remote_miss_ttl {
+                        acc.remote_miss_ttl = Some(ttl);
+                    }
 
                     acc
                 })
@@ -739,6 +878,11 @@ mod test {
         );
         env.insert("turbo_experimental_ui".into(), "true".into());
         env.insert("turbo_preflight".into(), "true".into());
+        env.insert("turbo_large_file_threshold".into(), "1048576".into());
+        env.insert("turbo_scm_timeout".into(), "30".into());
+        env.insert("turbo_reuse_index_hashes".into(), "1".into());
+        env.insert("turbo_disk_space_reserve".into(), "1073741824".into());
+        env.insert("turbo_remote_miss_ttl".into(), "300".into());
 
         let config = get_env_var_config(&env).unwrap();
         assert!(config.preflight());
@@ -749,6 +893,11 @@ mod test {
         assert_eq!(turbo_token, config.token.unwrap());
         assert_eq!(turbo_remote_cache_timeout, config.timeout.unwrap());
         assert_eq!(Some(true), config.experimental_ui);
+        assert_eq!(Some(1048576), config.large_file_threshold());
+        assert_eq!(Some(Duration::from_secs(30)), config.scm_timeout());
+        assert!(config.reuse_index_hashes());
+        assert_eq!(Some(1073741824), config.disk_space_reserve());
+        assert_eq!(Some(Duration::from_secs(300)), config.remote_miss_ttl());
     }
 
     #[test]
@@ -761,6 +910,11 @@ mod test {
         env.insert("turbo_token".into(), "".into());
         env.insert("turbo_experimental_ui".into(), "".into());
         env.insert("turbo_preflight".into(), "".into());
+        env.insert("turbo_large_file_threshold".into(), "".into());
+        env.insert("turbo_scm_timeout".into(), "".into());
+        env.insert("turbo_reuse_index_hashes".into(), "".into());
+        env.insert("turbo_disk_space_reserve".into(), "".into());
+        env.insert("turbo_remote_miss_ttl".into(), "".into());
 
         let config = get_env_var_config(&env).unwrap();
         assert_eq!(config.api_url(), DEFAULT_API_URL);
@@ -770,6 +924,11 @@ mod test {
         assert_eq!(config.token(), None);
         assert!(!config.experimental_ui());
         assert!(!config.preflight());
+        assert_eq!(config.large_file_threshold(), None);
+        assert_eq!(config.scm_timeout(), None);
+        assert!(!config.reuse_index_hashes());
+        assert_eq!(config.disk_space_reserve(), None);
+        assert_eq!(config.remote_miss_ttl(), None);
     }
 
     #[test]
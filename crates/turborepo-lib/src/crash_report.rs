@@ -0,0 +1,28 @@
+//! State that is included in crash reports alongside the recent log events,
+//! so that reports from the field show what turbo was in the middle of.
+
+use turborepo_telemetry::events::generic::GenericEventBuilder;
+
+use crate::task_hash;
+
+/// Returns the state of each subsystem as `(name, state)` pairs.
+pub fn subsystem_states() -> Vec<(&'static str, String)> {
+    vec![(
+        "pending_file_hashes",
+        task_hash::pending_file_hashes().to_string(),
+    )]
+}
+
+/// Submits a crash through the telemetry sink. This does nothing unless
+/// telemetry is initialized and enabled.
+///
+/// Only the location of the panic and the subsystem states are sent. The
+/// panic message and log events can contain user data, so they only go in
+/// the local report.
+pub fn submit_crash(location: &str) {
+    let event = GenericEventBuilder::new();
+    event.track_crash(location);
+    for (subsystem, state) in subsystem_states() {
+        event.track_crash_state(subsystem, &state);
+    }
+}
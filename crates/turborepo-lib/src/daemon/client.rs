@@ -1,4 +1,4 @@
-use std::{io, time::Duration};
+use std::{collections::HashMap, io, time::Duration};
 
 use globwalk::ValidatedGlob;
 use thiserror::Error;
@@ -21,6 +21,8 @@ use crate::{
 pub struct DaemonClient<T> {
     client: proto::turbod_client::TurbodClient<tonic::transport::Channel>,
     connect_settings: T,
+    capabilities: Vec<proto::Capability>,
+    clock_skew: Option<proto::ClockSkew>,
 }
 
 impl DaemonClient<()> {
@@ -28,6 +30,8 @@ impl DaemonClient<()> {
         Self {
             client,
             connect_settings: (),
+            capabilities: Vec::new(),
+            clock_skew: None,
         }
     }
 
@@ -40,15 +44,17 @@ impl DaemonClient<()> {
         DaemonClient {
             client: self.client,
             connect_settings,
+            capabilities: self.capabilities,
+            clock_skew: self.clock_skew,
         }
     }
 }
 
 impl<T> DaemonClient<T> {
-    /// Interrogate the server for its version.
+    /// Interrogate the server for its version and capabilities.
     #[tracing::instrument(skip(self))]
     pub(super) async fn handshake(&mut self) -> Result<(), DaemonError> {
-        let _ret = self
+        let response = self
             .client
             .hello(proto::HelloRequest {
                 version: proto::VERSION.to_string(),
@@ -60,11 +66,36 @@ impl<T> DaemonClient<T> {
                 // todo(arlyon): add session id
                 ..Default::default()
             })
-            .await?;
+            .await?
+            .into_inner();
+
+        self.capabilities = if response.capabilities.is_empty() {
+            proto::LEGACY_CAPABILITIES.to_vec()
+        } else {
+            // unknown values come from a newer daemon, and we can't use them anyway
+            response
+                .capabilities
+                .into_iter()
+                .filter_map(|c| proto::Capability::try_from(c).ok())
+                .collect()
+        };
+        self.clock_skew = response.clock_skew;
 
         Ok(())
     }
 
+    /// Whether the connected daemon supports the given capability. Only
+    /// meaningful after a successful handshake.
+    pub fn supports(&self, capability: proto::Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    /// The clock skew the daemon reported during the handshake, if it has
+    /// seen files with modification times ahead of the system clock.
+    pub fn clock_skew(&self) -> Option<&proto::ClockSkew> {
+        self.clock_skew.as_ref()
+    }
+
     /// Stops the daemon and closes the connection, returning
     /// the connection settings that were used to connect.
     pub async fn stop(mut self) -> Result<T, DaemonError> {
@@ -73,11 +104,13 @@ impl<T> DaemonClient<T> {
         Ok(self.connect_settings)
     }
 
+    /// Gets the output globs that have changed since the outputs for `hash`
+    /// were written, along with the paths that changed them.
     pub async fn get_changed_outputs(
         &mut self,
         hash: String,
         output_globs: &[ValidatedGlob],
-    ) -> Result<Vec<String>, DaemonError> {
+    ) -> Result<proto::GetChangedOutputsResponse, DaemonError> {
         let output_globs = output_globs
             .iter()
             .map(|validated_glob| validated_glob.as_str().to_string())
@@ -86,8 +119,43 @@ impl<T> DaemonClient<T> {
             .client
             .get_changed_outputs(proto::GetChangedOutputsRequest { hash, output_globs })
             .await?
+            .into_inner())
+    }
+
+    /// Gets the changed output globs for each of several hashes in one
+    /// request, keyed by hash. Requires the `BatchedOutputWatching`
+    /// capability.
+    ///
+    /// The daemon responds with an entry for every requested hash, so a
+    /// missing entry is reported as an error rather than left to the caller.
+    pub async fn get_changed_outputs_batch(
+        &mut self,
+        requests: impl IntoIterator<Item = (String, &[ValidatedGlob])>,
+    ) -> Result<HashMap<String, proto::GetChangedOutputsResponse>, DaemonError> {
+        let requests: Vec<_> = requests
+            .into_iter()
+            .map(|(hash, output_globs)| proto::GetChangedOutputsRequest {
+                hash,
+                output_globs: output_globs
+                    .iter()
+                    .map(|validated_glob| validated_glob.as_str().to_string())
+                    .collect(),
+            })
+            .collect();
+        let hashes: Vec<_> = requests
+            .iter()
+            .map(|request| request.hash.clone())
+            .collect();
+        let responses = self
+            .client
+            .get_changed_outputs_batch(proto::GetChangedOutputsBatchRequest { requests })
+            .await?
             .into_inner()
-            .changed_output_globs)
+            .responses;
+        if hashes.iter().any(|hash| !responses.contains_key(hash)) {
+            return Err(DaemonError::MalformedResponse);
+        }
+        Ok(responses)
     }
 
     pub async fn notify_outputs_written(
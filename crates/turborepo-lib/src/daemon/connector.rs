@@ -576,6 +576,56 @@ mod test {
 
     struct DummyServer {
         shutdown: Mutex<Option<Sender<bool>>>,
+        /// The capabilities to advertise, or `None` to fail the handshake
+        /// with a version mismatch
+        capabilities: Option<Vec<i32>>,
+    }
+
+    /// Serves `server` over an in-memory stream, returning a client that is
+    /// connected to it along with the running server.
+    async fn serve(
+        server: DummyServer,
+    ) -> (
+        TurbodClient<tonic::transport::Channel>,
+        tokio::task::JoinHandle<Result<(), tonic::transport::Error>>,
+    ) {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+
+        // set up the server
+        let stream = async_stream::stream! {
+            while let Some(item) = rx.recv().await {
+                yield item;
+            }
+        };
+
+        let service = ServiceBuilder::new()
+            .layer(DefaultTimeoutLayer)
+            .service(proto::turbod_server::TurbodServer::new(server));
+
+        let server_fut = tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(stream),
+        );
+
+        let client = Endpoint::try_from("http://[::]:50051")
+            .expect("this is a valid uri")
+            .connect_with_connector(tower::service_fn(move |_| {
+                // when a connection is made, create a duplex stream and send it to the server
+                let tx = tx.clone();
+                async move {
+                    let (client, server) = tokio::io::duplex(1024);
+                    let server: Result<_, anyhow::Error> = Ok(server);
+                    let client: Result<_, anyhow::Error> = Ok(client);
+                    tx.send(server).await.unwrap();
+                    client
+                }
+            }))
+            .await
+            .map(TurbodClient::new)
+            .unwrap();
+
+        (client, server_fut)
     }
 
     #[tonic::async_trait]
@@ -600,10 +650,16 @@ mod test {
             request: tonic::Request<proto::HelloRequest>,
         ) -> tonic::Result<tonic::Response<proto::HelloResponse>> {
             let client_version = request.into_inner().version;
-            Err(tonic::Status::failed_precondition(format!(
-                "version mismatch. Client {} Server test-version",
-                client_version
-            )))
+            match &self.capabilities {
+                Some(capabilities) => Ok(tonic::Response::new(proto::HelloResponse {
+                    capabilities: capabilities.clone(),
+                    clock_skew: None,
+                })),
+                None => Err(tonic::Status::failed_precondition(format!(
+                    "version mismatch. Client {} Server test-version",
+                    client_version
+                ))),
+            }
         }
 
         async fn status(
@@ -627,6 +683,13 @@ mod test {
             unimplemented!()
         }
 
+        async fn get_changed_outputs_batch(
+            &self,
+            _req: tonic::Request<proto::GetChangedOutputsBatchRequest>,
+        ) -> tonic::Result<tonic::Response<proto::GetChangedOutputsBatchResponse>> {
+            unimplemented!()
+        }
+
         async fn discover_packages(
             &self,
             _req: tonic::Request<proto::DiscoverPackagesRequest>,
@@ -654,49 +717,16 @@ mod test {
     async fn handles_kill_live_server() {
         let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
 
-        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
-
-        // set up the server
-        let stream = async_stream::stream! {
-            while let Some(item) = rx.recv().await {
-                yield item;
-            }
-        };
-
-        let service = ServiceBuilder::new().layer(DefaultTimeoutLayer).service(
-            proto::turbod_server::TurbodServer::new(DummyServer {
-                shutdown: Mutex::new(Some(shutdown_tx)),
-            }),
-        );
-
-        let server_fut = tonic::transport::Server::builder()
-            .add_service(service)
-            .serve_with_incoming(stream);
-
         let tmp_dir = tempfile::tempdir().unwrap();
         let repo_root = AbsoluteSystemPathBuf::try_from(tmp_dir.path()).unwrap();
         let connector = DaemonConnector::new(false, false, &repo_root);
 
-        let mut client = Endpoint::try_from("http://[::]:50051")
-            .expect("this is a valid uri")
-            .connect_with_connector(tower::service_fn(move |_| {
-                // when a connection is made, create a duplex stream and send it to the server
-                let tx = tx.clone();
-                async move {
-                    let (client, server) = tokio::io::duplex(1024);
-                    let server: Result<_, anyhow::Error> = Ok(server);
-                    let client: Result<_, anyhow::Error> = Ok(client);
-                    tx.send(server).await.unwrap();
-                    client
-                }
-            }))
-            .await
-            .map(TurbodClient::new)
-            .unwrap();
-
-        // spawn the future for the server so that it responds to
-        // the hello request
-        let server_fut = tokio::spawn(server_fut);
+        // the server is spawned so that it responds to the hello request
+        let (mut client, server_fut) = serve(DummyServer {
+            shutdown: Mutex::new(Some(shutdown_tx)),
+            capabilities: None,
+        })
+        .await;
 
         let hello_resp: DaemonError = client
             .hello(proto::HelloRequest {
@@ -722,4 +752,41 @@ mod test {
             "shutdown should have been received"
         )
     }
+
+    #[tokio::test]
+    async fn handshake_reads_capabilities() {
+        let (client, _server_fut) = serve(DummyServer {
+            shutdown: Mutex::new(None),
+            // 99 stands in for a capability from a newer daemon
+            capabilities: Some(vec![
+                proto::Capability::OutputWatching as i32,
+                proto::Capability::BatchedOutputWatching as i32,
+                99,
+            ]),
+        })
+        .await;
+        let mut client = DaemonClient::new(client);
+        client.handshake().await.unwrap();
+
+        assert!(client.supports(proto::Capability::OutputWatching));
+        assert!(client.supports(proto::Capability::BatchedOutputWatching));
+        assert!(!client.supports(proto::Capability::PackageDiscovery));
+        assert!(!client.supports(proto::Capability::PackageChanges));
+    }
+
+    #[tokio::test]
+    async fn handshake_assumes_legacy_capabilities() {
+        let (client, _server_fut) = serve(DummyServer {
+            shutdown: Mutex::new(None),
+            capabilities: Some(vec![]),
+        })
+        .await;
+        let mut client = DaemonClient::new(client);
+        client.handshake().await.unwrap();
+
+        for capability in proto::LEGACY_CAPABILITIES {
+            assert!(client.supports(*capability), "{capability:?} is assumed");
+        }
+        assert!(!client.supports(proto::Capability::BatchedOutputWatching));
+    }
 }
@@ -0,0 +1,209 @@
+//! Daemon event log
+//!
+//! The daemon records the decisions it makes to
+//! `.turbo/daemon/<repo hash>-events.jsonl`, so that what the daemon did can
+//! be reconstructed after the fact without trace logging being enabled.
+//!
+//! # Format
+//! The file is append-only and contains one JSON object per line. Every
+//! object has a `timestamp` (RFC 3339, UTC) and an `event` field, plus the
+//! fields for that event:
+//!
+//! - `daemon_started`: `version`
+//! - `package_discovered`, `package_dropped`, `package_changed`,
+//!   `package_config_changed`: `package_json`, the repo-relative path to the
+//!   package's `package.json`
+//! - `package_renamed`: `package_json`, `previous_name` and `name`, either of
+//!   which is `null` if the `package.json` had no name
+//! - `query_served`: `method`, the RPC that was called, `duration_ms`, and
+//!   `ok`, whether the RPC succeeded
+//! - `discovery_diagnostic`: `message`, a problem found with the discovered
+//!   packages, such as a duplicate package name
+//!
+//! New fields may be added to existing events, and new events may be added,
+//! so readers should ignore what they don't recognize.
+//!
+//! Once the file would grow past `MAX_EVENT_LOG_BYTES`, it is moved to
+//! `<repo hash>-events.jsonl.1`, replacing any previous one, and a new file is
+//! started.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    sync::Mutex,
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::debug;
+use turbopath::AbsoluteSystemPathBuf;
+
+const MAX_EVENT_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum DaemonEvent<'a> {
+    DaemonStarted {
+        version: &'a str,
+    },
+    PackageDiscovered {
+        package_json: &'a str,
+    },
+    PackageDropped {
+        package_json: &'a str,
+    },
+    PackageChanged {
+        package_json: &'a str,
+    },
+    PackageConfigChanged {
+        package_json: &'a str,
+    },
+    PackageRenamed {
+        package_json: &'a str,
+        previous_name: Option<&'a str>,
+        name: Option<&'a str>,
+    },
+    QueryServed {
+        method: &'a str,
+        duration_ms: u64,
+        ok: bool,
+    },
+    DiscoveryDiagnostic {
+        message: &'a str,
+    },
+}
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    event: DaemonEvent<'a>,
+}
+
+pub struct EventLog {
+    path: AbsoluteSystemPathBuf,
+    rotated_path: AbsoluteSystemPathBuf,
+    max_bytes: u64,
+    file: Mutex<Option<File>>,
+}
+
+impl EventLog {
+    pub fn new(path: AbsoluteSystemPathBuf) -> Self {
+        Self::with_max_bytes(path, MAX_EVENT_LOG_BYTES)
+    }
+
+    fn with_max_bytes(path: AbsoluteSystemPathBuf, max_bytes: u64) -> Self {
+        let rotated_path = AbsoluteSystemPathBuf::new(format!("{path}.1"))
+            .expect("appending to an absolute path is still absolute");
+        Self {
+            path,
+            rotated_path,
+            max_bytes,
+            file: Mutex::new(None),
+        }
+    }
+
+    /// Appends an event to the log. Failing to write is logged, but otherwise
+    /// ignored, since the log is only a diagnostic aid.
+    pub fn record(&self, event: DaemonEvent) {
+        if let Err(e) = self.write(Entry {
+            timestamp: Utc::now(),
+            event,
+        }) {
+            debug!("failed to write daemon event log: {e}");
+        }
+    }
+
+    fn write(&self, entry: Entry) -> Result<(), io::Error> {
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().expect("event log lock poisoned");
+        if let Some(current) = file.as_ref() {
+            if current.metadata()?.len() + line.len() as u64 > self.max_bytes {
+                *file = None;
+                self.path.rename(&self.rotated_path)?;
+            }
+        }
+        if file.is_none() {
+            *file = Some(self.open()?);
+        }
+
+        file.as_mut()
+            .expect("event log file was just opened")
+            .write_all(&line)
+    }
+
+    fn open(&self) -> Result<File, io::Error> {
+        self.path.ensure_dir()?;
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        let file = self.path.open_with_options(options.clone())?;
+        // Rotate a log left behind by a previous daemon if it's already full
+        if file.metadata()?.len() >= self.max_bytes {
+            drop(file);
+            self.path.rename(&self.rotated_path)?;
+            return self.path.open_with_options(options);
+        }
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::{DaemonEvent, EventLog};
+
+    #[test]
+    fn test_event_log_format() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let path = repo_root.join_components(&[".turbo", "daemon", "events.jsonl"]);
+        let log = EventLog::new(path.clone());
+
+        log.record(DaemonEvent::PackageDiscovered {
+            package_json: "packages/a/package.json",
+        });
+        log.record(DaemonEvent::QueryServed {
+            method: "DiscoverPackages",
+            duration_ms: 3,
+            ok: true,
+        });
+
+        let contents = path.read_to_string().unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "package_discovered");
+        assert_eq!(lines[0]["package_json"], "packages/a/package.json");
+        assert!(lines[0]["timestamp"].is_string());
+        assert_eq!(lines[1]["event"], "query_served");
+        assert_eq!(lines[1]["method"], "DiscoverPackages");
+        assert_eq!(lines[1]["duration_ms"], 3);
+        assert_eq!(lines[1]["ok"], true);
+    }
+
+    #[test]
+    fn test_event_log_rotates() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let path = repo_root.join_component("events.jsonl");
+        let rotated_path = repo_root.join_component("events.jsonl.1");
+        // Small enough that every event starts a new file
+        let log = EventLog::with_max_bytes(path.clone(), 150);
+
+        for version in ["1.0.0", "1.0.1", "1.0.2"] {
+            log.record(DaemonEvent::DaemonStarted { version });
+        }
+
+        let contents = path.read_to_string().unwrap();
+        let rotated_contents = rotated_path.read_to_string().unwrap();
+        assert_eq!(contents.lines().count(), 1);
+        assert!(contents.contains("1.0.2"));
+        assert_eq!(rotated_contents.lines().count(), 1);
+        assert!(rotated_contents.contains("1.0.1"));
+    }
+}
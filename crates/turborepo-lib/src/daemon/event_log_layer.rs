@@ -0,0 +1,98 @@
+//! event log middleware
+//!
+//! This is middleware for tonic that records every RPC the daemon serves,
+//! along with how long it took, to the daemon's event log.
+
+use std::{future::Future, pin::Pin, sync::Arc, time::Instant};
+
+use tonic::{
+    codegen::http::{HeaderMap, Request, Response},
+    server::NamedService,
+    transport::Body,
+};
+use tower::{Layer, Service};
+
+use super::event_log::{DaemonEvent, EventLog};
+
+/// A layer that records a `query_served` event for each request.
+pub struct EventLogLayer(Arc<EventLog>);
+
+impl EventLogLayer {
+    pub fn new(event_log: Arc<EventLog>) -> Self {
+        Self(event_log)
+    }
+}
+
+impl<S> Layer<S> for EventLogLayer {
+    type Service = EventLogService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        EventLogService {
+            inner,
+            event_log: self.0.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EventLogService<S> {
+    inner: S,
+    event_log: Arc<EventLog>,
+}
+
+impl<S, ResBody> Service<Request<Body>> for EventLogService<S>
+where
+    S: Service<Request<Body>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // gRPC paths are `/<service>/<method>`
+        let method = req
+            .uri()
+            .path()
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_owned();
+        let event_log = self.event_log.clone();
+        let start = Instant::now();
+        let response = self.inner.call(req);
+
+        Box::pin(async move {
+            let response = response.await;
+            let ok = match &response {
+                Ok(response) => is_grpc_ok(response.headers()),
+                Err(_) => false,
+            };
+            event_log.record(DaemonEvent::QueryServed {
+                method: &method,
+                duration_ms: start.elapsed().as_millis() as u64,
+                ok,
+            });
+            response
+        })
+    }
+}
+
+// Failed RPCs are sent as trailers-only responses, so their status is in the
+// headers. Successful ones only send a status in the trailers.
+fn is_grpc_ok(headers: &HeaderMap) -> bool {
+    headers
+        .get("grpc-status")
+        .map_or(true, |status| status == "0")
+}
+
+impl<T: NamedService> NamedService for EventLogService<T> {
+    const NAME: &'static str = T::NAME;
+}
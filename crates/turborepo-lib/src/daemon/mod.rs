@@ -26,6 +26,8 @@ mod client;
 mod connector;
 mod default_timeout_layer;
 pub(crate) mod endpoint;
+mod event_log;
+mod event_log_layer;
 mod server;
 
 pub use client::{DaemonClient, DaemonError};
@@ -42,6 +44,8 @@ pub struct Paths {
     pub lsp_pid_file: AbsoluteSystemPathBuf,
     pub log_file: AbsoluteSystemPathBuf,
     pub log_folder: AbsoluteSystemPathBuf,
+    pub event_log_file: AbsoluteSystemPathBuf,
+    pub discovery_snapshot_file: AbsoluteSystemPathBuf,
 }
 
 fn repo_hash(repo_root: &AbsoluteSystemPath) -> String {
@@ -72,6 +76,10 @@ impl Paths {
         let repo_hash = repo_hash(repo_root);
         let daemon_root = daemon_file_root(&repo_hash);
         let (log_file, log_folder) = daemon_log_file_and_folder(repo_root, &repo_hash);
+        let event_log_file =
+            log_folder.join_component(format!("{}-events.jsonl", repo_hash).as_str());
+        let discovery_snapshot_file =
+            log_folder.join_component(format!("{}-discovery.json", repo_hash).as_str());
         Self {
             pid_file: daemon_root.join_component("turbod.pid"),
             lock_file: daemon_root.join_component("turbod.lock"),
@@ -79,6 +87,8 @@ impl Paths {
             lsp_pid_file: daemon_root.join_component("lsp.pid"),
             log_file,
             log_folder,
+            event_log_file,
+            discovery_snapshot_file,
         }
     }
 }
@@ -102,6 +112,38 @@ pub(crate) mod proto {
     /// - Bump the patch version if making backwards compatible bug fixes.
     pub const VERSION: &str = "1.11.0";
 
+    /// The capabilities this daemon advertises during the handshake.
+    pub const CAPABILITIES: &[Capability] = &[
+        Capability::OutputWatching,
+        Capability::PackageDiscovery,
+        Capability::PackageChanges,
+        Capability::BatchedOutputWatching,
+    ];
+
+    /// The capabilities assumed for a daemon that doesn't advertise any,
+    /// i.e. one that was built before capability negotiation existed. This
+    /// list must not grow: new capabilities are only ever advertised.
+    pub const LEGACY_CAPABILITIES: &[Capability] = &[
+        Capability::OutputWatching,
+        Capability::PackageDiscovery,
+        Capability::PackageChanges,
+    ];
+
+    impl std::fmt::Display for ClockSkew {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(
+                f,
+                "{} file(s) with modification times ahead of the system clock, most recently {} \
+                 ({} ahead)",
+                self.anomalies,
+                self.last_path,
+                humantime::format_duration(std::time::Duration::from_millis(
+                    self.last_skew_msec.unsigned_abs()
+                )),
+            )
+        }
+    }
+
     impl From<PackageManager> for turborepo_repository::package_manager::PackageManager {
         fn from(pm: PackageManager) -> Self {
             match pm {
@@ -18,7 +18,7 @@ use semver::Version;
 use thiserror::Error;
 use tokio::{
     select,
-    sync::{mpsc, oneshot},
+    sync::{broadcast, mpsc, oneshot, watch},
     task::JoinHandle,
 };
 use tokio_stream::wrappers::ReceiverStream;
@@ -27,18 +27,30 @@ use tower::ServiceBuilder;
 use tracing::{error, info, trace, warn};
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 use turborepo_filewatch::{
+    clock_skew::ClockSkewWatcher,
     cookies::CookieWriter,
-    globwatcher::{Error as GlobWatcherError, GlobError, GlobSet, GlobWatcher},
-    package_watcher::{PackageWatchError, PackageWatcher},
-    FileSystemWatcher, WatchError,
+    globwatcher::{ChangedGlobs, Error as GlobWatcherError, GlobError, GlobSet, GlobWatcher},
+    package_watcher::{
+        PackageChangeEvent as PackageWatcherEvent, PackageWatchError, PackageWatcher,
+        PackageWatcherOptions,
+    },
+    FileSystemWatcher, FileSystemWatcherOptions, NotifyError, WatchError, WatcherHealth,
+    DEFAULT_HEARTBEAT_INTERVAL,
+};
+use turborepo_repository::{
+    discovery::{DiscoveryDiagnostic, WorkspaceData},
+    package_manager,
 };
-use turborepo_repository::package_manager;
 
 use super::{bump_timeout::BumpTimeout, endpoint::SocketOpenError, proto};
 use crate::{
     daemon::{
-        bump_timeout_layer::BumpTimeoutLayer, default_timeout_layer::DefaultTimeoutLayer,
-        endpoint::listen_socket, Paths,
+        bump_timeout_layer::BumpTimeoutLayer,
+        default_timeout_layer::DefaultTimeoutLayer,
+        endpoint::listen_socket,
+        event_log::{DaemonEvent, EventLog},
+        event_log_layer::EventLogLayer,
+        Paths,
     },
     package_changes_watcher::{PackageChangeEvent, PackageChangesWatcher},
 };
@@ -63,6 +75,7 @@ pub struct FileWatching {
     pub glob_watcher: Arc<GlobWatcher>,
     pub package_watcher: Arc<PackageWatcher>,
     pub package_changes_watcher: Arc<PackageChangesWatcher>,
+    pub clock_skew_watcher: Arc<ClockSkewWatcher>,
 }
 
 #[derive(Debug, Error)]
@@ -97,8 +110,23 @@ impl FileWatching {
     /// waiting for the filewatcher to be ready. Using `OptionalWatch`,
     /// dependent services can wait for resources they need to become
     /// available, and the server can start up without waiting for them.
-    pub fn new(repo_root: AbsoluteSystemPathBuf) -> Result<FileWatching, WatchError> {
-        let watcher = Arc::new(FileSystemWatcher::new_with_default_cookie_dir(&repo_root)?);
+    pub fn new(
+        repo_root: AbsoluteSystemPathBuf,
+        discovery_snapshot_file: AbsoluteSystemPathBuf,
+    ) -> Result<FileWatching, WatchError> {
+        let watcher = Arc::new(FileSystemWatcher::new_with_options(
+            &repo_root,
+            FileSystemWatcher::default_cookie_dir(&repo_root),
+            FileSystemWatcherOptions {
+                // The daemon outlives any single run, so it needs to notice a
+                // backend that silently stopped delivering events
+                heartbeat_interval: Some(DEFAULT_HEARTBEAT_INTERVAL),
+                // Nothing in the daemon hashes metadata, and tools that touch
+                // files would otherwise cause needless rehashing
+                ignore_metadata_changes: true,
+                ..Default::default()
+            },
+        )?);
         let recv = watcher.watch();
 
         let cookie_writer = CookieWriter::new(
@@ -112,25 +140,47 @@ impl FileWatching {
             recv.clone(),
         ));
         let package_watcher = Arc::new(
-            PackageWatcher::new(repo_root.clone(), recv.clone(), cookie_writer)
-                .map_err(|e| WatchError::Setup(format!("{:?}", e)))?,
+            PackageWatcher::new_with_options(
+                repo_root.clone(),
+                recv.clone(),
+                cookie_writer,
+                PackageWatcherOptions {
+                    debounce: Duration::ZERO,
+                    quiescence: PACKAGE_WATCHER_QUIESCENCE,
+                    snapshot_path: Some(discovery_snapshot_file),
+                    ..Default::default()
+                },
+            )
+            .map_err(|e| WatchError::Setup(format!("{:?}", e)))?,
         );
 
         let package_changes_watcher =
             Arc::new(PackageChangesWatcher::new(repo_root.clone(), recv.clone()));
 
+        let clock_skew_watcher = Arc::new(ClockSkewWatcher::new(recv.clone()));
+
         Ok(FileWatching {
             watcher,
             glob_watcher,
             package_watcher,
             package_changes_watcher,
+            clock_skew_watcher,
         })
     }
 }
 
+/// How long the package watcher waits for changes to workspaces to stop
+/// before updating discovery, so that a `git checkout` is handled at once.
+const PACKAGE_WATCHER_QUIESCENCE: Duration = Duration::from_millis(50);
+
 /// Timeout for every RPC the server handles
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// Whether output globs are matched regardless of case. The default
+/// filesystems on macOS and Windows are case-insensitive, so a change to
+/// `Dist/index.js` is a change to the outputs in `dist/**`.
+const CASE_INSENSITIVE_OUTPUTS: bool = cfg!(any(target_os = "macos", target_os = "windows"));
+
 pub struct TurboGrpcService<S> {
     repo_root: AbsoluteSystemPathBuf,
     paths: Paths,
@@ -183,8 +233,18 @@ where
         // well as available to the gRPC server itself to handle the shutdown RPC.
         let (trigger_shutdown, mut shutdown_signal) = mpsc::channel::<()>(1);
 
-        let (service, exit_root_watch, watch_root_handle) =
-            TurboGrpcServiceInner::new(repo_root.clone(), trigger_shutdown, paths.log_file);
+        let event_log = Arc::new(EventLog::new(paths.event_log_file));
+        event_log.record(DaemonEvent::DaemonStarted {
+            version: crate::get_version(),
+        });
+
+        let (service, exit_root_watch, watch_root_handle) = TurboGrpcServiceInner::new(
+            repo_root.clone(),
+            trigger_shutdown,
+            paths.log_file,
+            paths.discovery_snapshot_file,
+            event_log.clone(),
+        );
 
         let running = Arc::new(AtomicBool::new(true));
         let (_pid_lock, stream) =
@@ -209,6 +269,7 @@ where
 
         let server_fut = {
             let service = ServiceBuilder::new()
+                .layer(EventLogLayer::new(event_log))
                 .layer(BumpTimeoutLayer::new(bump_timeout.clone()))
                 .layer(DefaultTimeoutLayer)
                 .service(crate::daemon::proto::turbod_server::TurbodServer::new(
@@ -262,17 +323,29 @@ impl TurboGrpcServiceInner {
         repo_root: AbsoluteSystemPathBuf,
         trigger_shutdown: mpsc::Sender<()>,
         log_file: AbsoluteSystemPathBuf,
+        discovery_snapshot_file: AbsoluteSystemPathBuf,
+        event_log: Arc<EventLog>,
     ) -> (
         Self,
         oneshot::Sender<()>,
         JoinHandle<Result<(), WatchError>>,
     ) {
-        let file_watching = FileWatching::new(repo_root.clone()).unwrap();
+        let file_watching = FileWatching::new(repo_root.clone(), discovery_snapshot_file).unwrap();
 
         tracing::debug!("initing package discovery");
         // Note that we're cloning the Arc, not the package watcher itself
         let package_watcher = Arc::clone(&file_watching.package_watcher);
 
+        tokio::task::spawn(log_package_changes(
+            repo_root.clone(),
+            package_watcher.subscribe_package_changes(),
+            event_log.clone(),
+        ));
+        tokio::task::spawn(report_discovery_diagnostics(
+            package_watcher.diagnostics(),
+            event_log,
+        ));
+
         // exit_root_watch delivers a signal to the root watch loop to exit.
         // In the event that the server shuts down via some other mechanism, this
         // cleans up root watching task.
@@ -303,6 +376,17 @@ impl TurboGrpcServiceInner {
         let _ = self.shutdown.send(()).await;
     }
 
+    fn clock_skew(&self) -> Option<proto::ClockSkew> {
+        self.file_watching
+            .clock_skew_watcher
+            .report()
+            .map(|report| proto::ClockSkew {
+                anomalies: report.anomalies,
+                last_path: report.last_path.to_string(),
+                last_skew_msec: report.last_skew_ms,
+            })
+    }
+
     async fn watch_globs(
         &self,
         hash: String,
@@ -310,7 +394,11 @@ impl TurboGrpcServiceInner {
         output_glob_exclusions: Vec<String>,
         time_saved: u64,
     ) -> Result<(), RpcError> {
-        let glob_set = GlobSet::from_raw(output_globs, output_glob_exclusions)?;
+        let glob_set = if CASE_INSENSITIVE_OUTPUTS {
+            GlobSet::from_raw_case_insensitive(output_globs, output_glob_exclusions)?
+        } else {
+            GlobSet::from_raw(output_globs, output_glob_exclusions)?
+        };
         self.file_watching
             .glob_watcher
             .watch_globs(hash.clone(), glob_set, REQUEST_TIMEOUT)
@@ -326,7 +414,7 @@ impl TurboGrpcServiceInner {
         &self,
         hash: String,
         candidates: HashSet<String>,
-    ) -> Result<(HashSet<String>, u64), RpcError> {
+    ) -> Result<(ChangedGlobs, u64), RpcError> {
         let time_saved = {
             let times_saved = self.times_saved.lock().expect("times saved lock poisoned");
             times_saved.get(hash.as_str()).copied().unwrap_or_default()
@@ -338,6 +426,121 @@ impl TurboGrpcServiceInner {
             .await?;
         Ok((changed_globs, time_saved))
     }
+
+    async fn get_changed_outputs_batch(
+        &self,
+        candidates: HashMap<String, HashSet<String>>,
+    ) -> Result<HashMap<String, (ChangedGlobs, u64)>, RpcError> {
+        let changed = self
+            .file_watching
+            .glob_watcher
+            .get_changed_globs_batch(candidates, REQUEST_TIMEOUT)
+            .await?;
+        let times_saved = self.times_saved.lock().expect("times saved lock poisoned");
+        Ok(changed
+            .into_iter()
+            .map(|(hash, changed_globs)| {
+                let time_saved = times_saved.get(hash.as_str()).copied().unwrap_or_default();
+                (hash, (changed_globs, time_saved))
+            })
+            .collect())
+    }
+}
+
+fn changed_outputs_response(
+    changed: ChangedGlobs,
+    time_saved: u64,
+) -> proto::GetChangedOutputsResponse {
+    proto::GetChangedOutputsResponse {
+        changed_output_globs: changed.globs.into_iter().collect(),
+        time_saved,
+        changed_output_paths: changed
+            .paths
+            .into_iter()
+            .map(|(path, output_globs)| proto::ChangedOutputPath {
+                path: path.to_string(),
+                output_globs: output_globs.into_iter().collect(),
+            })
+            .collect(),
+    }
+}
+
+/// Records changes to the set of discovered packages in the event log.
+async fn log_package_changes(
+    repo_root: AbsoluteSystemPathBuf,
+    mut package_changes: broadcast::Receiver<PackageWatcherEvent>,
+    event_log: Arc<EventLog>,
+) {
+    let package_json = |workspace: &WorkspaceData| {
+        repo_root.anchor(&workspace.package_json).map_or_else(
+            |_| workspace.package_json.to_string(),
+            |p| p.to_unix().to_string(),
+        )
+    };
+
+    loop {
+        let event = match package_changes.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                warn!("event log missed {count} package changes");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        match event {
+            PackageWatcherEvent::PackagesAdded(workspaces) => {
+                for workspace in &workspaces {
+                    event_log.record(DaemonEvent::PackageDiscovered {
+                        package_json: &package_json(workspace),
+                    });
+                }
+            }
+            PackageWatcherEvent::PackagesRemoved(workspaces) => {
+                for workspace in &workspaces {
+                    event_log.record(DaemonEvent::PackageDropped {
+                        package_json: &package_json(workspace),
+                    });
+                }
+            }
+            PackageWatcherEvent::PackageChanged(workspace) => {
+                event_log.record(DaemonEvent::PackageChanged {
+                    package_json: &package_json(&workspace),
+                });
+            }
+            PackageWatcherEvent::PackageConfigChanged(workspace) => {
+                event_log.record(DaemonEvent::PackageConfigChanged {
+                    package_json: &package_json(&workspace),
+                });
+            }
+            PackageWatcherEvent::PackageRenamed {
+                workspace,
+                previous_name,
+                name,
+            } => {
+                event_log.record(DaemonEvent::PackageRenamed {
+                    package_json: &package_json(&workspace),
+                    previous_name: previous_name.as_deref(),
+                    name: name.as_deref(),
+                });
+            }
+        }
+    }
+}
+
+/// Reports problems with the discovered packages, rather than leaving them to
+/// surface as confusing failures later on.
+async fn report_discovery_diagnostics(
+    mut diagnostics: watch::Receiver<Vec<DiscoveryDiagnostic>>,
+    event_log: Arc<EventLog>,
+) {
+    while diagnostics.changed().await.is_ok() {
+        let current = diagnostics.borrow_and_update().clone();
+        for diagnostic in current {
+            let message = diagnostic.to_string();
+            warn!("{message}");
+            event_log.record(DaemonEvent::DiscoveryDiagnostic { message: &message });
+        }
+    }
 }
 
 async fn watch_root(
@@ -355,6 +558,8 @@ async fn watch_root(
 
     tracing::debug!("watching root: {:?}", root);
 
+    let mut health = filewatching_access.watcher.subscribe_health();
+
     loop {
         // Ignore the outer layer of Result, if the sender has closed, filewatching has
         // gone away and we can return.
@@ -370,6 +575,8 @@ async fn watch_root(
                     // before triggering a shutdown
                     Ok(event) if event.paths.iter().any(|p| p == (&root as &AbsoluteSystemPath)) => !root.exists(),
                     Ok(_) => false,
+                    // Watching continues, and the missing watches are retried
+                    Err(NotifyError::WatchLimit { .. }) => false,
                     Err(_) => true
                 };
                 if should_trigger_shutdown {
@@ -380,6 +587,24 @@ async fn watch_root(
                     break;
                 }
             }
+            Ok(()) = health.changed() => {
+                let current = *health.borrow_and_update();
+                match current {
+                    // The watcher restarts a stalled backend itself, and asks subscribers to
+                    // rescan once it has, so there's nothing to do but wait
+                    WatcherHealth::Stalled { .. } => {
+                        warn!("filewatching is stalled, waiting for it to restart");
+                    }
+                    // A stopped watcher would leave us serving stale state indefinitely.
+                    // Shutting down means the next client starts a daemon with a fresh watcher.
+                    WatcherHealth::Stopped => {
+                        warn!("filewatching has stopped, triggering shutdown");
+                        let _ = trigger_shutdown.send(()).await;
+                        break;
+                    }
+                    WatcherHealth::Starting | WatcherHealth::Healthy { .. } => {}
+                }
+            }
         }
     }
 
@@ -410,7 +635,10 @@ impl proto::turbod_server::Turbod for TurboGrpcServiceInner {
         };
 
         if passes_version_check {
-            Ok(tonic::Response::new(proto::HelloResponse {}))
+            Ok(tonic::Response::new(proto::HelloResponse {
+                capabilities: proto::CAPABILITIES.iter().map(|c| *c as i32).collect(),
+                clock_skew: self.clock_skew(),
+            }))
         } else {
             Err(tonic::Status::failed_precondition(format!(
                 "version mismatch. Client {} Server {}",
@@ -439,6 +667,7 @@ impl proto::turbod_server::Turbod for TurboGrpcServiceInner {
             daemon_status: Some(proto::DaemonStatus {
                 uptime_msec: self.start_time.elapsed().as_millis() as u64,
                 log_file: self.log_file.to_string(),
+                clock_skew: self.clock_skew(),
             }),
         }))
     }
@@ -467,10 +696,32 @@ impl proto::turbod_server::Turbod for TurboGrpcServiceInner {
         let (changed, time_saved) = self
             .get_changed_outputs(inner.hash, HashSet::from_iter(inner.output_globs))
             .await?;
-        Ok(tonic::Response::new(proto::GetChangedOutputsResponse {
-            changed_output_globs: changed.into_iter().collect(),
-            time_saved,
-        }))
+        Ok(tonic::Response::new(changed_outputs_response(
+            changed, time_saved,
+        )))
+    }
+
+    async fn get_changed_outputs_batch(
+        &self,
+        request: tonic::Request<proto::GetChangedOutputsBatchRequest>,
+    ) -> Result<tonic::Response<proto::GetChangedOutputsBatchResponse>, tonic::Status> {
+        let candidates = request
+            .into_inner()
+            .requests
+            .into_iter()
+            .map(|request| (request.hash, HashSet::from_iter(request.output_globs)))
+            .collect();
+        let changed = self.get_changed_outputs_batch(candidates).await?;
+        Ok(tonic::Response::new(
+            proto::GetChangedOutputsBatchResponse {
+                responses: changed
+                    .into_iter()
+                    .map(|(hash, (changed, time_saved))| {
+                        (hash, changed_outputs_response(changed, time_saved))
+                    })
+                    .collect(),
+            },
+        ))
     }
 
     async fn discover_packages(
@@ -607,21 +858,26 @@ impl NamedService for TurboGrpcServiceInner {
 mod test {
     use std::{
         assert_matches::{self, assert_matches},
+        sync::Arc,
         time::{Duration, Instant},
     };
 
     use futures::FutureExt;
     use semver::Version;
     use test_case::test_case;
-    use tokio::sync::oneshot;
+    use tokio::sync::{mpsc, oneshot};
     use turbopath::AbsoluteSystemPathBuf;
     use turborepo_repository::{
         discovery::{DiscoveryResponse, PackageDiscovery},
         package_manager::PackageManager,
     };
 
-    use super::compare_versions;
-    use crate::daemon::{proto::VersionRange, CloseReason, Paths, TurboGrpcService};
+    use super::{compare_versions, TurboGrpcServiceInner};
+    use crate::daemon::{
+        event_log::EventLog,
+        proto::{self, turbod_server::Turbod, VersionRange},
+        CloseReason, Paths, TurboGrpcService,
+    };
 
     #[test_case("1.2.3", "1.2.3", VersionRange::Exact, true ; "exact match")]
     #[test_case("1.2.3", "1.2.3", VersionRange::Patch, true ; "patch match")]
@@ -661,6 +917,7 @@ mod test {
             Ok(DiscoveryResponse {
                 package_manager: PackageManager::Yarn,
                 workspaces: vec![],
+                ecosystem_workspaces: vec![],
             })
         }
 
@@ -826,4 +1083,81 @@ mod test {
             .expect("server exited");
         assert_matches!(close_reason, Ok(CloseReason::Shutdown));
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_changed_outputs_batch() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = AbsoluteSystemPathBuf::try_from(tempdir.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+
+        let repo_root = path.join_component("repo");
+        repo_root.create_dir_all().unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces": ["packages/*"]}"#)
+            .unwrap();
+        repo_root
+            .join_component("package-lock.json")
+            .create_with_contents("")
+            .unwrap();
+
+        let (trigger_shutdown, _shutdown_signal) = mpsc::channel(1);
+        let (service, _exit_root_watch, _watch_root_handle) = TurboGrpcServiceInner::new(
+            repo_root.clone(),
+            trigger_shutdown,
+            path.join_component("turbod.log"),
+            path.join_component("discovery.json"),
+            Arc::new(EventLog::new(path.join_component("events.log"))),
+        );
+        // give filewatching some time to bootstrap
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        for (hash, time_saved) in [("written", 10), ("untouched", 20)] {
+            let written = repo_root.join_components(&[hash, "dist", "out.js"]);
+            written.ensure_dir().unwrap();
+            written.create_with_contents("out").unwrap();
+            service
+                .notify_outputs_written(tonic::Request::new(proto::NotifyOutputsWrittenRequest {
+                    hash: hash.to_string(),
+                    output_globs: vec![format!("{hash}/dist/**")],
+                    output_exclusion_globs: vec![],
+                    time_saved,
+                }))
+                .await
+                .unwrap();
+        }
+        repo_root
+            .join_components(&["written", "dist", "out.js"])
+            .create_with_contents("changed")
+            .unwrap();
+
+        let request = |hash: &str| proto::GetChangedOutputsRequest {
+            hash: hash.to_string(),
+            output_globs: vec![format!("{hash}/dist/**")],
+        };
+        let responses = service
+            .get_changed_outputs_batch(tonic::Request::new(proto::GetChangedOutputsBatchRequest {
+                requests: vec![request("written"), request("untouched"), request("unknown")],
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .responses;
+
+        assert_eq!(responses.len(), 3);
+        assert_eq!(
+            responses["written"].changed_output_globs,
+            vec!["written/dist/**".to_string()]
+        );
+        assert_eq!(responses["written"].time_saved, 10);
+        assert!(responses["untouched"].changed_output_globs.is_empty());
+        assert_eq!(responses["untouched"].time_saved, 20);
+        // Outputs we were never told about have to be restored
+        assert_eq!(
+            responses["unknown"].changed_output_globs,
+            vec!["unknown/dist/**".to_string()]
+        );
+    }
 }
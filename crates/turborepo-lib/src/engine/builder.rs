@@ -563,6 +563,7 @@ mod test {
             Ok(turborepo_repository::discovery::DiscoveryResponse {
                 package_manager: PackageManager::Npm,
                 workspaces: vec![], // we don't care about this
+                ecosystem_workspaces: vec![],
             })
         }
 
@@ -414,6 +414,7 @@ mod test {
             Ok(DiscoveryResponse {
                 package_manager: turborepo_repository::package_manager::PackageManager::Pnpm,
                 workspaces,
+                ecosystem_workspaces: vec![],
             })
         }
 
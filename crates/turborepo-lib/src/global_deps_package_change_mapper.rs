@@ -82,6 +82,7 @@ mod tests {
             Ok(discovery::DiscoveryResponse {
                 package_manager: turborepo_repository::package_manager::PackageManager::Npm,
                 workspaces: vec![],
+                ecosystem_workspaces: vec![],
             })
         }
 
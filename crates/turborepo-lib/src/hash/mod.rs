@@ -6,7 +6,7 @@
 
 mod traits;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use capnp::message::{Builder, HeapAllocator};
 pub use traits::TurboHash;
@@ -75,6 +75,7 @@ pub struct GlobalHashable<'a> {
     pub env_mode: EnvMode,
     pub framework_inference: bool,
     pub dot_env: &'a [turbopath::RelativeUnixPathBuf],
+    pub root_package_json_fields: &'a BTreeMap<String, String>,
 }
 
 pub struct LockFilePackages(pub Vec<turborepo_lockfiles::Package>);
@@ -361,6 +362,19 @@ impl From<GlobalHashable<'_>> for Builder<HeapAllocator> {
             }
         }
 
+        // Only set when there are fields so that the field is truncated from the
+        // canonical message and doesn't change the hash of repos without them.
+        if !hashable.root_package_json_fields.is_empty() {
+            let mut entries = builder
+                .reborrow()
+                .init_root_package_json_fields(hashable.root_package_json_fields.len() as u32);
+            for (i, (key, value)) in hashable.root_package_json_fields.iter().enumerate() {
+                let mut entry = entries.reborrow().get(i as u32);
+                entry.set_key(key);
+                entry.set_value(value);
+            }
+        }
+
         // We're okay to unwrap here because we haven't hit the nesting
         // limit and the message will not have cycles.
         let size = builder
@@ -380,6 +394,8 @@ impl From<GlobalHashable<'_>> for Builder<HeapAllocator> {
 
 #[cfg(test)]
 mod test {
+    use std::collections::{BTreeMap, HashMap};
+
     use test_case::test_case;
     use turborepo_env::ResolvedEnvMode;
     use turborepo_lockfiles::Package;
@@ -433,11 +449,35 @@ mod test {
             framework_inference: true,
 
             dot_env: &[turbopath::RelativeUnixPathBuf::new("dotenv".to_string()).unwrap()],
+            root_package_json_fields: &BTreeMap::new(),
         };
 
         assert_eq!(global_hash.hash(), "c0ddf8138bd686e8");
     }
 
+    #[test]
+    fn global_hashable_root_package_json_fields() {
+        let global_file_hash_map = HashMap::new();
+        let hash = |root_package_json_fields: &BTreeMap<String, String>| {
+            GlobalHashable {
+                global_cache_key: "global_cache_key",
+                global_file_hash_map: &global_file_hash_map,
+                root_external_dependencies_hash: None,
+                env: &[],
+                resolved_env_vars: vec![],
+                pass_through_env: &[],
+                env_mode: EnvMode::Infer,
+                framework_inference: true,
+                dot_env: &[],
+                root_package_json_fields,
+            }
+            .hash()
+        };
+
+        let with_fields = [("engines.node".to_string(), ">=18".to_string())].into();
+        assert_ne!(hash(&BTreeMap::new()), hash(&with_fields));
+    }
+
     #[test_case(vec![], "459c029558afe716" ; "empty")]
     #[test_case(vec![Package {
         key: "key".to_string(),
@@ -17,6 +17,7 @@ mod child;
 mod cli;
 mod commands;
 mod config;
+mod crash_report;
 mod daemon;
 mod diagnostics;
 mod engine;
@@ -42,8 +43,10 @@ mod unescape;
 pub use crate::{
     child::spawn_child,
     cli::Args,
+    crash_report::{submit_crash, subsystem_states},
     daemon::{DaemonClient, DaemonConnector, Paths as DaemonPaths},
     run::package_discovery::DaemonPackageDiscovery,
+    tracing::recent_events,
 };
 
 pub fn get_version() -> &'static str {
@@ -1,4 +1,4 @@
-use std::backtrace;
+use std::{backtrace, time::Duration};
 
 use thiserror::Error;
 use turbopath::AnchoredSystemPathBuf;
@@ -28,6 +28,8 @@ pub enum Error {
     ConcurrencyOutOfBounds(#[backtrace] backtrace::Backtrace, String),
     #[error(transparent)]
     Path(#[from] turbopath::PathError),
+    #[error("--offline cannot be used with {0}, which requires network access")]
+    RequiresNetwork(&'static str),
 }
 
 #[derive(Debug)]
@@ -86,6 +88,14 @@ impl<'a> TryFrom<&'a Args> for Opts {
         let Some(Command::Run(run_args)) = &args.command else {
             return Err(Error::ExpectedRun);
         };
+        if run_args.offline {
+            if run_args.remote_only {
+                return Err(Error::RequiresNetwork("--remote-only"));
+            }
+            if run_args.experimental_space_id.is_some() {
+                return Err(Error::RequiresNetwork("--experimental-space-id"));
+            }
+        }
         let run_opts = RunOpts::try_from(run_args.as_ref())?;
         let cache_opts = CacheOpts::from(run_args.as_ref());
         let scope_opts = ScopeOpts::try_from(run_args.as_ref())?;
@@ -105,6 +115,7 @@ pub struct RunCacheOpts {
     pub(crate) skip_reads: bool,
     pub(crate) skip_writes: bool,
     pub(crate) task_output_mode_override: Option<OutputLogsMode>,
+    pub(crate) disk_space_reserve: Option<u64>,
 }
 
 impl<'a> From<&'a RunArgs> for RunCacheOpts {
@@ -113,6 +124,7 @@ impl<'a> From<&'a RunArgs> for RunCacheOpts {
             skip_reads: args.force.flatten().is_some_and(|f| f),
             skip_writes: args.no_cache,
             task_output_mode_override: args.output_logs,
+            disk_space_reserve: None,
         }
     }
 }
@@ -137,6 +149,7 @@ pub struct RunOpts {
     pub log_order: ResolvedLogOrder,
     pub summarize: Option<Option<bool>>,
     pub(crate) experimental_space_id: Option<String>,
+    pub(crate) offline: bool,
     pub is_github_actions: bool,
 }
 
@@ -214,6 +227,7 @@ impl<'a> TryFrom<&'a RunArgs> for RunOpts {
             log_order,
             summarize: args.summarize,
             experimental_space_id: args.experimental_space_id.clone(),
+            offline: args.offline,
             framework_inference: args.framework_inference,
             env_mode: args.env_mode,
             concurrency,
@@ -341,6 +355,11 @@ impl<'a> TryFrom<&'a RunArgs> for ScopeOpts {
     }
 }
 
+// How long a remote cache miss is trusted across invocations in CI, unless
+// `remoteMissTtl` is configured. This is meant to cover sequential `turbo run`s
+// in one job, not a whole pipeline.
+const REMOTE_MISS_TTL: Duration = Duration::from_secs(10 * 60);
+
 impl<'a> From<&'a RunArgs> for CacheOpts {
     fn from(run_args: &'a RunArgs) -> Self {
         CacheOpts {
@@ -348,6 +367,7 @@ impl<'a> From<&'a RunArgs> for CacheOpts {
             skip_filesystem: run_args.remote_only,
             remote_cache_read_only: run_args.remote_cache_read_only,
             workers: run_args.cache_workers,
+            remote_miss_ttl: turborepo_ci::is_ci().then_some(REMOTE_MISS_TTL),
             ..CacheOpts::default()
         }
     }
@@ -373,13 +393,15 @@ impl ScopeOpts {
 
 #[cfg(test)]
 mod test {
+    use clap::Parser;
     use test_case::test_case;
     use turborepo_cache::CacheOpts;
 
-    use super::{LegacyFilter, RunOpts};
+    use super::{Error, LegacyFilter, RunOpts};
     use crate::{
         cli::DryRunMode,
         opts::{Opts, RunCacheOpts, ScopeOpts},
+        Args,
     };
 
     #[test_case(LegacyFilter {
@@ -531,6 +553,7 @@ mod test {
             log_order: crate::opts::ResolvedLogOrder::Stream,
             summarize: None,
             experimental_space_id: None,
+            offline: false,
             is_github_actions: false,
         };
         let cache_opts = CacheOpts::default();
@@ -552,4 +575,21 @@ mod test {
         let synthesized = opts.synthesize_command();
         assert_eq!(synthesized, expected);
     }
+
+    #[test_case(&["--remote-only"], Some("--remote-only") ; "remote only")]
+    #[test_case(&["--experimental-space-id", "space"], Some("--experimental-space-id") ; "space id")]
+    #[test_case(&["--remote-cache-read-only"], None ; "remote cache read only")]
+    fn test_offline_requires_network(flags: &[&str], expected: Option<&str>) {
+        let args = Args::try_parse_from(
+            ["turbo", "run", "build", "--offline"]
+                .iter()
+                .chain(flags.iter()),
+        )
+        .unwrap();
+        match (Opts::try_from(&args), expected) {
+            (Err(Error::RequiresNetwork(flag)), Some(expected)) => assert_eq!(flag, expected),
+            (Ok(_), None) => (),
+            (result, _) => panic!("unexpected result: {result:?}"),
+        }
+    }
 }
@@ -1,10 +1,16 @@
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    path::MAIN_SEPARATOR,
+};
 
 use ignore::gitignore::Gitignore;
 use notify::Event;
 use tokio::sync::{broadcast, oneshot};
-use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf};
-use turborepo_filewatch::{NotifyError, OptionalWatch};
+use turbopath::{
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
+    PathRelation,
+};
+use turborepo_filewatch::{NotifyError, OptionalWatch, Rescan};
 use turborepo_repository::{
     change_mapper::{ChangeMapper, GlobalDepsPackageChangeMapper, PackageChanges},
     package_graph::{PackageGraph, PackageGraphBuilder, PackageName},
@@ -77,9 +83,75 @@ fn is_in_git_folder(path: &AnchoredSystemPath) -> bool {
 struct RepoState {
     root_turbo_json: Option<TurboJson>,
     pkg_dep_graph: PackageGraph,
+    // Package directories that are symlinks, keyed by the path they resolve to
+    symlinked_packages: Vec<(AbsoluteSystemPathBuf, AnchoredSystemPathBuf)>,
+    // Only set if the filesystem is case-insensitive. Maps lowercased package
+    // directories to the casing that was used during package discovery.
+    package_paths_by_lowercase: Option<HashMap<String, AnchoredSystemPathBuf>>,
 }
 
 impl RepoState {
+    /// Anchors a changed path at the repo root. The file watcher doesn't
+    /// follow symlinks, so changes to files in a symlinked package directory
+    /// are reported at the symlink's target and need to be mapped back to the
+    /// package's location to be attributed to it.
+    ///
+    /// On case-insensitive filesystems the watcher may also report a path with
+    /// different casing than we used during package discovery, so we compare
+    /// case-insensitively and restore the casing of the package directory.
+    fn anchor(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+        path: &AbsoluteSystemPath,
+    ) -> Option<AnchoredSystemPathBuf> {
+        let anchored = self
+            .symlinked_packages
+            .iter()
+            .find_map(|(target, package_path)| {
+                let relative = target.anchor(path).ok()?;
+                Some(package_path.join(&relative))
+            })
+            .or_else(|| repo_root.anchor(path).ok());
+        let Some(package_paths) = &self.package_paths_by_lowercase else {
+            return anchored;
+        };
+        let anchored = match anchored {
+            Some(anchored) => anchored,
+            None => anchor_ignoring_case(repo_root, path)?,
+        };
+        Some(restore_package_casing(package_paths, anchored))
+    }
+
+    /// The packages that overlap the rescanned directories, or `None` if the
+    /// whole repo needs rescanning
+    fn packages_to_rescan(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+        rescans: &[Rescan],
+    ) -> Option<HashSet<PackageName>> {
+        let mut packages = HashSet::new();
+        for rescan in rescans {
+            if rescan.path.relation_to_path(repo_root) == PathRelation::Parent {
+                return None;
+            }
+            let Some(dir) = self.anchor(repo_root, &rescan.path) else {
+                // outside of the repo
+                continue;
+            };
+            packages.extend(
+                self.pkg_dep_graph
+                    .packages()
+                    .filter(|(_, info)| {
+                        let package_path = info.package_path().as_path();
+                        package_path.starts_with(dir.as_path())
+                            || dir.as_path().starts_with(package_path)
+                    })
+                    .map(|(name, _)| name.clone()),
+            );
+        }
+        Some(packages)
+    }
+
     fn get_change_mapper(&self) -> Option<ChangeMapper<GlobalDepsPackageChangeMapper>> {
         let Ok(package_change_mapper) = GlobalDepsPackageChangeMapper::new(
             &self.pkg_dep_graph,
@@ -100,6 +172,77 @@ impl RepoState {
     }
 }
 
+// The root package.json must exist for us to get this far, so if we can also
+// find it with different casing, the filesystem is case-insensitive.
+fn is_case_insensitive(repo_root: &AbsoluteSystemPath) -> bool {
+    repo_root.join_component("PACKAGE.JSON").exists()
+}
+
+fn anchor_ignoring_case(
+    repo_root: &AbsoluteSystemPath,
+    path: &AbsoluteSystemPath,
+) -> Option<AnchoredSystemPathBuf> {
+    let (root, path) = (repo_root.as_str(), path.as_str());
+    let prefix = path.get(..root.len())?;
+    if !prefix.eq_ignore_ascii_case(root) {
+        return None;
+    }
+    let rest = &path[root.len()..];
+    if !rest.is_empty() && !rest.starts_with(MAIN_SEPARATOR) {
+        // e.g. `/repo-other` for a root of `/repo`
+        return None;
+    }
+    AnchoredSystemPathBuf::from_raw(rest.trim_start_matches(MAIN_SEPARATOR)).ok()
+}
+
+fn restore_package_casing(
+    package_paths: &HashMap<String, AnchoredSystemPathBuf>,
+    path: AnchoredSystemPathBuf,
+) -> AnchoredSystemPathBuf {
+    // ancestors are yielded from the longest to the shortest, so we find the
+    // most specific package
+    let restored = path.ancestors().find_map(|ancestor| {
+        let package_path = package_paths.get(&ancestor.as_str().to_ascii_lowercase())?;
+        let rest = path.as_str()[ancestor.as_str().len()..].trim_start_matches(MAIN_SEPARATOR);
+        if rest.is_empty() {
+            return Some(package_path.clone());
+        }
+        Some(package_path.join(AnchoredSystemPath::new(rest).ok()?))
+    });
+    restored.unwrap_or(path)
+}
+
+fn find_symlinked_packages(
+    repo_root: &AbsoluteSystemPath,
+    pkg_dep_graph: &PackageGraph,
+) -> Vec<(AbsoluteSystemPathBuf, AnchoredSystemPathBuf)> {
+    let Ok(real_repo_root) = repo_root.to_realpath() else {
+        return Vec::new();
+    };
+    pkg_dep_graph
+        .packages()
+        .filter_map(|(name, info)| {
+            let package_path = info.package_path();
+            let package_dir = repo_root.resolve(package_path);
+            if !package_dir.symlink_metadata().ok()?.is_symlink() {
+                return None;
+            }
+            let target = package_dir.to_realpath().ok()?;
+            let Ok(anchored_target) = real_repo_root.anchor(&target) else {
+                // The file watcher only covers the repo root
+                tracing::debug!(
+                    "{name} is a symlink to {target} outside of the repository, changes to it \
+                     won't be detected"
+                );
+                return None;
+            };
+            // File events are reported relative to the repo root as we were given it,
+            // which isn't necessarily canonical
+            Some((repo_root.resolve(&anchored_target), package_path.to_owned()))
+        })
+        .collect()
+}
+
 impl Subscriber {
     fn new(
         repo_root: AbsoluteSystemPathBuf,
@@ -137,9 +280,25 @@ impl Subscriber {
             return None;
         };
 
+        let symlinked_packages = find_symlinked_packages(&self.repo_root, &pkg_dep_graph);
+        let package_paths_by_lowercase = is_case_insensitive(&self.repo_root).then(|| {
+            pkg_dep_graph
+                .packages()
+                .map(|(_, info)| {
+                    let package_path = info.package_path();
+                    (
+                        package_path.as_str().to_ascii_lowercase(),
+                        package_path.to_owned(),
+                    )
+                })
+                .collect()
+        });
+
         Some(RepoState {
             root_turbo_json,
             pkg_dep_graph,
+            symlinked_packages,
+            package_paths_by_lowercase,
         })
     }
 
@@ -170,6 +329,26 @@ impl Subscriber {
 
             loop {
                 match file_events.recv().await {
+                    Ok(Ok(event)) if event.need_rescan() => {
+                        // We may have missed changes to any package under the rescanned
+                        // directories
+                        let rescans = Rescan::from_event(&event).unwrap_or_default();
+                        match repo_state.packages_to_rescan(&self.repo_root, &rescans) {
+                            Some(packages) => {
+                                tracing::debug!("rescanning packages: {:?}", packages);
+                                for name in packages {
+                                    let _ = self
+                                        .package_change_events_tx
+                                        .send(PackageChangeEvent::Package { name });
+                                }
+                            }
+                            None => {
+                                let _ = self
+                                    .package_change_events_tx
+                                    .send(PackageChangeEvent::Rediscover);
+                            }
+                        }
+                    }
                     Ok(Ok(Event { paths, .. })) => {
                         // No point in raising an error for an invalid .gitignore
                         // This is slightly incorrect because we should also search for the
@@ -181,7 +360,7 @@ impl Subscriber {
                             .into_iter()
                             .filter_map(|p| {
                                 let p = AbsoluteSystemPathBuf::try_from(p).ok()?;
-                                self.repo_root.anchor(p).ok()
+                                repo_state.anchor(&self.repo_root, &p)
                             })
                             .filter(|p| {
                                 // If in .gitignore or in .git, filter out
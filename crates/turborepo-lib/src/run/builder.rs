@@ -2,12 +2,12 @@ use std::{
     collections::HashSet,
     io::{ErrorKind, IsTerminal},
     sync::Arc,
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 
 use chrono::Local;
 use rayon::iter::ParallelBridge;
-use tracing::debug;
+use tracing::{debug, warn};
 use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath};
 use turborepo_analytics::{start_analytics, AnalyticsHandle, AnalyticsSender};
 use turborepo_api_client::{APIAuth, APIClient};
@@ -30,7 +30,6 @@ use turborepo_ui::{ColorSelector, UI};
 #[cfg(feature = "daemon-package-discovery")]
 use {
     crate::run::package_discovery::DaemonPackageDiscovery,
-    std::time::Duration,
     turborepo_repository::discovery::{
         Error as DiscoveryError, FallbackPackageDiscovery, LocalPackageDiscoveryBuilder,
         PackageDiscoveryBuilder,
@@ -59,6 +58,9 @@ pub struct RunBuilder {
     ui: UI,
     version: &'static str,
     experimental_ui: bool,
+    large_file_threshold: Option<u64>,
+    scm_timeout: Option<Duration>,
+    reuse_index_hashes: bool,
     api_client: APIClient,
 }
 
@@ -70,7 +72,7 @@ impl RunBuilder {
         let mut opts: Opts = base.args().try_into()?;
         let config = base.config()?;
         let is_linked = turborepo_api_client::is_linked(&api_auth);
-        if !is_linked {
+        if !is_linked || opts.run_opts.offline {
             opts.cache_opts.skip_remote = true;
         } else if let Some(enabled) = config.enabled {
             // We're linked, but if the user has explicitly enabled or disabled, use that
@@ -80,17 +82,29 @@ impl RunBuilder {
         // Note that we don't currently use the team_id value here. In the future, we
         // should probably verify that we only use the signature value when the
         // configured team_id matches the final resolved team_id.
+        opts.runcache_opts.disk_space_reserve = config.disk_space_reserve();
+        // A configured ttl also applies outside of CI, and 0 turns remembering
+        // misses off entirely
+        if let Some(remote_miss_ttl) = config.remote_miss_ttl() {
+            opts.cache_opts.remote_miss_ttl =
+                (!remote_miss_ttl.is_zero()).then_some(remote_miss_ttl);
+        }
         let unused_remote_cache_opts_team_id = config.team_id().map(|team_id| team_id.to_string());
         let signature = config.signature();
         opts.cache_opts.remote_cache_opts = Some(RemoteCacheOpts::new(
             unused_remote_cache_opts_team_id,
             signature,
         ));
-        if opts.run_opts.experimental_space_id.is_none() {
+        // A configured space is skipped rather than rejected when offline, only an
+        // explicit --experimental-space-id is an error
+        if opts.run_opts.experimental_space_id.is_none() && !opts.run_opts.offline {
             opts.run_opts.experimental_space_id = config.spaces_id().map(|s| s.to_owned());
         }
         let version = base.version();
         let experimental_ui = config.experimental_ui();
+        let large_file_threshold = config.large_file_threshold();
+        let scm_timeout = config.scm_timeout();
+        let reuse_index_hashes = config.reuse_index_hashes();
         let processes = ProcessManager::new(
             // We currently only use a pty if the following are met:
             // - we're attached to a tty
@@ -108,6 +122,9 @@ impl RunBuilder {
             ui,
             version,
             experimental_ui,
+            large_file_threshold,
+            scm_timeout,
+            reuse_index_hashes,
         })
     }
 
@@ -149,12 +166,23 @@ impl RunBuilder {
             self.connect_process_manager(subscriber);
         }
 
-        let (analytics_sender, analytics_handle) =
-            Self::initialize_analytics(self.api_auth.clone(), self.api_client.clone()).unzip();
+        let (analytics_sender, analytics_handle) = if self.opts.run_opts.offline {
+            (None, None)
+        } else {
+            Self::initialize_analytics(self.api_auth.clone(), self.api_client.clone()).unzip()
+        };
 
         let scm = {
             let repo_root = self.repo_root.clone();
-            tokio::task::spawn_blocking(move || SCM::new(&repo_root))
+            let large_file_threshold = self.large_file_threshold;
+            let scm_timeout = self.scm_timeout;
+            let reuse_index_hashes = self.reuse_index_hashes;
+            tokio::task::spawn_blocking(move || {
+                SCM::new(&repo_root)
+                    .with_large_file_threshold(large_file_threshold)
+                    .with_timeout(scm_timeout)
+                    .with_reuse_index_hashes(reuse_index_hashes)
+            })
         };
         let package_json_path = self.repo_root.join_component("package.json");
         let root_package_json = PackageJson::load(&package_json_path)?;
@@ -200,6 +228,12 @@ impl RunBuilder {
                     (Ok(client), _) => {
                         run_telemetry.track_daemon_init(DaemonInitStatus::Started);
                         debug!("running in daemon mode");
+                        if let Some(clock_skew) = client.clock_skew() {
+                            warn!(
+                                "The daemon found {clock_skew}. Changes to these files may not be \
+                                 detected reliably."
+                            );
+                        }
                         Some(client)
                     }
                     (Err(e), Some(true)) => {
@@ -15,7 +15,7 @@ use crate::{
     daemon::{DaemonClient, DaemonConnector},
     hash::{FileHashes, TurboHash},
     opts::RunCacheOpts,
-    run::task_id::TaskId,
+    run::{disk_space, task_id::TaskId},
     task_graph::{TaskDefinition, TaskOutputs},
 };
 
@@ -37,6 +37,8 @@ pub enum Error {
     Scm(#[from] turborepo_scm::Error),
     #[error(transparent)]
     Path(#[from] turbopath::PathError),
+    #[error(transparent)]
+    DiskSpace(#[from] disk_space::InsufficientDiskSpace),
 }
 
 pub struct RunCache {
@@ -44,6 +46,7 @@ pub struct RunCache {
     cache: AsyncCache,
     reads_disabled: bool,
     writes_disabled: bool,
+    disk_space: Option<disk_space::DiskSpace>,
     repo_root: AbsoluteSystemPathBuf,
     color_selector: ColorSelector,
     daemon_client: Option<DaemonClient<DaemonConnector>>,
@@ -70,6 +73,9 @@ impl RunCache {
             cache,
             reads_disabled: opts.skip_reads,
             writes_disabled: opts.skip_writes,
+            disk_space: opts
+                .disk_space_reserve
+                .map(|reserve| disk_space::DiskSpace::new(repo_root, reserve)),
             repo_root: repo_root.to_owned(),
             color_selector,
             daemon_client,
@@ -77,6 +83,14 @@ impl RunCache {
         }
     }
 
+    /// Checks that the reserve is free before anything is restored or run.
+    pub fn check_disk_space(&self) -> Result<(), disk_space::InsufficientDiskSpace> {
+        match &self.disk_space {
+            Some(disk_space) => disk_space.claim(0),
+            None => Ok(()),
+        }
+    }
+
     pub fn task_cache(
         self: &Arc<Self>,
         // TODO: Group these in a struct
@@ -107,6 +121,7 @@ impl RunCache {
             task_id,
             task_output_mode,
             caching_disabled,
+            cache_ttl: task_definition.cache_ttl,
             log_file_path,
             daemon_client: self.daemon_client.clone(),
             ui: self.ui,
@@ -126,6 +141,7 @@ pub struct TaskCache {
     hash: String,
     task_output_mode: OutputLogsMode,
     caching_disabled: bool,
+    cache_ttl: Option<Duration>,
     log_file_path: AbsoluteSystemPathBuf,
     daemon_client: Option<DaemonClient<DaemonConnector>>,
     ui: UI,
@@ -211,7 +227,7 @@ impl TaskCache {
                 .get_changed_outputs(self.hash.to_string(), &validated_inclusions)
                 .await
             {
-                Ok(changed_output_globs) => changed_output_globs.len(),
+                Ok(changed_outputs) => changed_outputs.changed_output_globs.len(),
                 Err(err) => {
                     telemetry.track_error(TrackedErrors::DaemonSkipOutputRestoreCheckFailed);
                     debug!(
@@ -229,13 +245,20 @@ impl TaskCache {
         let has_changed_outputs = changed_output_count > 0;
 
         let cache_status = if has_changed_outputs {
+            // Check before restoring anything so a full disk doesn't leave us with
+            // partially written outputs. The size of the artifact is only known if it's
+            // in the local cache, otherwise we can only check against the reserve.
+            if let Some(disk_space) = &self.run_cache.disk_space {
+                let size = self.run_cache.cache.uncompressed_size(&self.hash);
+                disk_space.claim(size.unwrap_or_default())?;
+            }
             // Note that we currently don't use the output globs when restoring, but we
             // could in the future to avoid doing unnecessary file I/O. We also
             // need to pass along the exclusion globs as well.
             let cache_status = self
                 .run_cache
                 .cache
-                .fetch(&self.run_cache.repo_root, &self.hash)
+                .fetch(&self.run_cache.repo_root, &self.hash, self.cache_ttl)
                 .await?;
 
             let Some((cache_hit_metadata, restored_files)) = cache_status else {
@@ -361,6 +384,7 @@ impl TaskCache {
                 self.hash.clone(),
                 relative_paths.clone(),
                 duration.as_millis() as u64,
+                self.cache_ttl,
             )
             .await?;
 
@@ -429,7 +453,7 @@ impl ConfigCache {
     pub async fn restore(
         &self,
     ) -> Result<Option<(CacheHitMetadata, Vec<AnchoredSystemPathBuf>)>, CacheError> {
-        self.cache.fetch(&self.repo_root, &self.hash).await
+        self.cache.fetch(&self.repo_root, &self.hash, None).await
     }
 
     pub async fn save(&self) -> Result<(), CacheError> {
@@ -442,6 +466,7 @@ impl ConfigCache {
                         self.hash.clone(),
                         vec![self.anchored_path.clone()],
                         0,
+                        None,
                     )
                     .await
             }
@@ -0,0 +1,154 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+};
+
+use sysinfo::{DiskExt, RefreshKind, System, SystemExt};
+use tracing::debug;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "only {available} bytes of disk space are available for {path}, but restoring {size} bytes \
+     requires {reserve} bytes to be kept free by diskSpaceReserve. Free up disk space or lower \
+     diskSpaceReserve (TURBO_DISK_SPACE_RESERVE) to continue."
+)]
+pub struct InsufficientDiskSpace {
+    path: AbsoluteSystemPathBuf,
+    available: u64,
+    size: u64,
+    reserve: u64,
+}
+
+/// The free space on the disk holding the repository over the course of a
+/// run. Listing disks is slow, so the disk is only sampled the first time it's
+/// needed, and the space taken by each restore is subtracted from that sample.
+pub struct DiskSpace {
+    path: AbsoluteSystemPathBuf,
+    reserve: u64,
+    // `None` if the disk couldn't be determined
+    available: OnceLock<Option<AtomicU64>>,
+}
+
+impl DiskSpace {
+    pub fn new(path: &AbsoluteSystemPath, reserve: u64) -> Self {
+        Self {
+            path: path.to_owned(),
+            reserve,
+            available: OnceLock::new(),
+        }
+    }
+
+    /// Claims `size` bytes for a restore, if that leaves at least the reserve
+    /// free. If the disk can't be determined the check is skipped.
+    pub fn claim(&self, size: u64) -> Result<(), InsufficientDiskSpace> {
+        let available = self.available.get_or_init(|| {
+            let available = available_space(&self.path);
+            if available.is_none() {
+                debug!(
+                    "unable to determine available disk space for {}, skipping checks",
+                    self.path
+                );
+            }
+            available.map(AtomicU64::new)
+        });
+        let Some(available) = available else {
+            return Ok(());
+        };
+        available
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |available| {
+                available
+                    .checked_sub(size)
+                    .filter(|left| *left >= self.reserve)
+            })
+            .map(|_| ())
+            .map_err(|available| InsufficientDiskSpace {
+                path: self.path.clone(),
+                available,
+                size,
+                reserve: self.reserve,
+            })
+    }
+}
+
+fn available_space(path: &AbsoluteSystemPath) -> Option<u64> {
+    let system = System::new_with_specifics(RefreshKind::new().with_disks_list());
+    available_space_on_disk(
+        system
+            .disks()
+            .iter()
+            .map(|disk| (disk.mount_point(), disk.available_space())),
+        path.as_std_path(),
+    )
+}
+
+// The disk holding a path is the one with the longest mount point that the
+// path is inside of.
+fn available_space_on_disk<'a>(
+    disks: impl Iterator<Item = (&'a Path, u64)>,
+    path: &Path,
+) -> Option<u64> {
+    disks
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.components().count())
+        .map(|(_, available)| available)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        path::Path,
+        sync::{atomic::AtomicU64, OnceLock},
+    };
+
+    use test_case::test_case;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::{available_space_on_disk, DiskSpace};
+
+    #[test_case("/repo", Some(100) ; "root disk")]
+    #[test_case("/home/user/repo", Some(200) ; "nested mount")]
+    #[test_case("/home/user/data/repo", Some(300) ; "most specific mount")]
+    #[test_case("/homework", Some(100) ; "mount prefix is not a path prefix")]
+    fn test_available_space_on_disk(path: &str, expected: Option<u64>) {
+        let disks = [
+            (Path::new("/"), 100),
+            (Path::new("/home"), 200),
+            (Path::new("/home/user/data"), 300),
+        ];
+        assert_eq!(
+            available_space_on_disk(disks.into_iter(), Path::new(path)),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_claim() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let disk_space = DiskSpace {
+            path,
+            reserve: 10,
+            available: OnceLock::from(Some(AtomicU64::new(100))),
+        };
+
+        assert!(disk_space.claim(50).is_ok());
+        // Would leave less than the reserve
+        assert!(disk_space.claim(50).is_err());
+        assert!(disk_space.claim(40).is_ok());
+        assert!(disk_space.claim(1).is_err());
+        assert!(disk_space.claim(0).is_ok());
+    }
+
+    #[test]
+    fn test_no_matching_disk() {
+        let disks = [(Path::new("/mnt"), 100)];
+        assert_eq!(
+            available_space_on_disk(disks.into_iter(), Path::new("/repo")),
+            None
+        );
+    }
+}
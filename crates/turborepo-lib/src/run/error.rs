@@ -7,7 +7,7 @@ use crate::{
     config, daemon, engine,
     engine::ValidateError,
     opts,
-    run::{global_hash, scope},
+    run::{disk_space, global_hash, scope},
     task_graph, task_hash,
 };
 
@@ -51,4 +51,6 @@ pub enum Error {
     SignalHandler(std::io::Error),
     #[error(transparent)]
     Daemon(#[from] daemon::DaemonError),
+    #[error(transparent)]
+    DiskSpace(#[from] disk_space::InsufficientDiskSpace),
 }
@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     str::FromStr,
 };
 
@@ -9,7 +9,10 @@ use tracing::debug;
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, RelativeUnixPathBuf};
 use turborepo_env::{get_global_hashable_env_vars, DetailedMap, EnvironmentVariableMap};
 use turborepo_lockfiles::Lockfile;
-use turborepo_repository::package_manager::{self, PackageManager};
+use turborepo_repository::{
+    package_json::PackageJson,
+    package_manager::{self, PackageManager},
+};
 use turborepo_scm::SCM;
 
 use crate::{
@@ -49,12 +52,17 @@ pub struct GlobalHashableInputs<'a> {
     pub framework_inference: bool,
     pub dot_env: Option<&'a [RelativeUnixPathBuf]>,
     pub env_at_execution_start: &'a EnvironmentVariableMap,
+    // Fields of the root package.json that affect every task, keyed by their
+    // path in the package.json e.g. `engines.node` or `scripts.lint`
+    pub root_package_json_fields: BTreeMap<String, String>,
 }
 
 #[allow(clippy::too_many_arguments)]
 pub fn get_global_hash_inputs<'a, L: ?Sized + Lockfile>(
     root_external_dependencies_hash: Option<&'a str>,
     root_path: &AbsoluteSystemPath,
+    root_package_json: &PackageJson,
+    root_scripts: &[&str],
     package_manager: &PackageManager,
     lockfile: Option<&L>,
     global_file_dependencies: &'a [String],
@@ -120,9 +128,46 @@ pub fn get_global_hash_inputs<'a, L: ?Sized + Lockfile>(
         framework_inference,
         dot_env,
         env_at_execution_start,
+        root_package_json_fields: root_package_json_fields(root_package_json, root_scripts),
     })
 }
 
+// The root package.json is only hashed as a whole if there isn't a lockfile,
+// otherwise only the root dependencies contribute to the global hash. Fields
+// like `engines` and `workspaces` change the environment every task runs in,
+// and root tasks run the root scripts, so we hash them individually.
+fn root_package_json_fields(
+    root_package_json: &PackageJson,
+    root_scripts: &[&str],
+) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+
+    if let Some(engines) = root_package_json
+        .other
+        .get("engines")
+        .and_then(|engines| engines.as_object())
+    {
+        fields.extend(engines.iter().map(|(engine, version)| {
+            let version = match version.as_str() {
+                Some(version) => version.to_string(),
+                None => version.to_string(),
+            };
+            (format!("engines.{engine}"), version)
+        }));
+    }
+
+    if let Some(workspaces) = root_package_json.other.get("workspaces") {
+        fields.insert("workspaces".to_string(), workspaces.to_string());
+    }
+
+    fields.extend(root_scripts.iter().filter_map(|script| {
+        let command = root_package_json.scripts.get(*script)?;
+        Some((format!("scripts.{script}"), command.clone()))
+    }));
+
+    fields
+}
+
 fn collect_global_deps(
     package_manager: &PackageManager,
     root_path: &AbsoluteSystemPath,
@@ -191,6 +236,7 @@ impl<'a> GlobalHashableInputs<'a> {
             env_mode: self.env_mode,
             framework_inference: self.framework_inference,
             dot_env: self.dot_env.unwrap_or_default(),
+            root_package_json_fields: &self.root_package_json_fields,
         };
 
         global_hashable.hash()
@@ -202,12 +248,35 @@ mod tests {
     use turbopath::AbsoluteSystemPathBuf;
     use turborepo_env::EnvironmentVariableMap;
     use turborepo_lockfiles::Lockfile;
-    use turborepo_repository::package_manager::PackageManager;
+    use turborepo_repository::{package_json::PackageJson, package_manager::PackageManager};
     use turborepo_scm::SCM;
 
-    use super::get_global_hash_inputs;
+    use super::{get_global_hash_inputs, root_package_json_fields};
     use crate::{cli::EnvMode, run::global_hash::collect_global_deps};
 
+    #[test]
+    fn test_root_package_json_fields() {
+        let root_package_json: PackageJson = serde_json::from_value(serde_json::json!({
+            "engines": { "node": ">=18" },
+            "workspaces": ["apps/*", "packages/*"],
+            "scripts": { "lint": "eslint .", "dev": "next dev" }
+        }))
+        .unwrap();
+
+        let fields = root_package_json_fields(&root_package_json, &["lint", "missing"]);
+        assert_eq!(
+            fields.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("engines.node".to_string(), ">=18".to_string()),
+                ("scripts.lint".to_string(), "eslint .".to_string()),
+                (
+                    "workspaces".to_string(),
+                    r#"["apps/*","packages/*"]"#.to_string()
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn test_absolute_path() {
         // We don't technically support absolute paths in global deps,
@@ -232,6 +301,8 @@ mod tests {
         let result = get_global_hash_inputs(
             None,
             &root,
+            &PackageJson::default(),
+            &[],
             &PackageManager::Pnpm,
             lockfile,
             &file_deps,
@@ -2,6 +2,7 @@
 
 pub mod builder;
 mod cache;
+mod disk_space;
 mod error;
 pub(crate) mod global_hash;
 mod graph_visualizer;
@@ -14,7 +15,7 @@ pub mod watch;
 
 use std::{collections::HashSet, io::Write, sync::Arc};
 
-pub use cache::{ConfigCache, RunCache, TaskCache};
+pub use cache::{ConfigCache, Error as RunCacheError, RunCache, TaskCache};
 use chrono::{DateTime, Local};
 use tracing::debug;
 use turbopath::AbsoluteSystemPathBuf;
@@ -22,7 +23,7 @@ use turborepo_analytics::AnalyticsHandle;
 use turborepo_api_client::{APIAuth, APIClient};
 use turborepo_ci::Vendor;
 use turborepo_env::EnvironmentVariableMap;
-use turborepo_repository::package_graph::{PackageGraph, PackageName};
+use turborepo_repository::package_graph::{PackageGraph, PackageName, ROOT_PKG_NAME};
 use turborepo_scm::SCM;
 use turborepo_telemetry::events::generic::GenericEventBuilder;
 use turborepo_ui::{cprint, cprintln, BOLD_GREY, GREY, UI};
@@ -137,6 +138,10 @@ impl Run {
             return Ok(0);
         }
 
+        if self.opts.run_opts.dry_run.is_none() {
+            self.run_cache.check_disk_space()?;
+        }
+
         let root_workspace = self
             .pkg_dep_graph
             .package_info(&PackageName::Root)
@@ -166,9 +171,20 @@ impl Run {
                 ),
             };
 
+            // Root tasks run the scripts of the root package.json
+            let root_scripts = self
+                .root_turbo_json
+                .pipeline
+                .keys()
+                .filter(|task_name| task_name.package() == Some(ROOT_PKG_NAME))
+                .map(|task_name| task_name.task())
+                .collect::<Vec<_>>();
+
             get_global_hash_inputs(
                 root_external_dependencies_hash.as_deref(),
                 &self.repo_root,
+                self.pkg_dep_graph.root_package_json(),
+                &root_scripts,
                 self.pkg_dep_graph.package_manager(),
                 self.pkg_dep_graph.lockfile(),
                 &self.root_turbo_json.global_deps,
@@ -223,6 +239,7 @@ impl Run {
             &self.repo_root,
             global_env,
             self.experimental_ui,
+            &self.root_turbo_json.redact,
         );
 
         if self.opts.run_opts.dry_run.is_some() {
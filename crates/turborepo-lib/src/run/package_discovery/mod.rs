@@ -40,6 +40,7 @@ impl<C: Clone + Send + Sync> PackageDiscovery for DaemonPackageDiscovery<C> {
             package_manager: PackageManager::try_from(response.package_manager)
                 .expect("valid")
                 .into(),
+            ecosystem_workspaces: vec![],
         })
     }
 
@@ -68,6 +69,7 @@ impl<C: Clone + Send + Sync> PackageDiscovery for DaemonPackageDiscovery<C> {
             package_manager: PackageManager::try_from(response.package_manager)
                 .expect("valid")
                 .into(),
+            ecosystem_workspaces: vec![],
         })
     }
 }
@@ -16,7 +16,7 @@ use wax::Program;
 use super::{
     change_detector::GitChangeDetector,
     simple_glob::{Match, SimpleGlob},
-    target_selector::{GitRange, InvalidSelectorError, TargetSelector},
+    target_selector::{GitRange, InvalidSelectorError, PackagePredicate, TargetSelector},
 };
 use crate::{
     global_deps_package_change_mapper, run::scope::change_detector::ScopeChangeDetector,
@@ -72,8 +72,9 @@ impl PackageInference {
     }
 
     pub fn apply(&self, selector: &mut TargetSelector) {
-        // if the name pattern is provided, do not attempt inference
-        if !selector.name_pattern.is_empty() {
+        // if the name pattern or a package predicate is provided, do not attempt
+        // inference
+        if !selector.name_pattern.is_empty() || selector.predicate.is_some() {
             return;
         };
 
@@ -379,12 +380,16 @@ impl<'a, T: GitChangeDetector> FilterResolver<'a, T> {
         }
 
         // if we have a filter, use it to filter the entry packages
-        let filtered_entry_packages = if !selector.name_pattern.is_empty() {
+        let mut filtered_entry_packages = if !selector.name_pattern.is_empty() {
             match_package_names(&selector.name_pattern, entry_packages)?
         } else {
             entry_packages
         };
 
+        if let Some(predicate) = &selector.predicate {
+            filtered_entry_packages.retain(|package| self.package_matches(package, predicate));
+        }
+
         let mut roots = HashSet::new();
         let mut matched = HashSet::new();
         let changed_packages = if let Some(git_range) = selector.git_range.as_ref() {
@@ -498,8 +503,22 @@ impl<'a, T: GitChangeDetector> FilterResolver<'a, T> {
             }
         }
 
-        // if neither a name pattern, parent dir, or from ref is provided, then
-        // the selector is invalid
+        if let Some(predicate) = &selector.predicate {
+            if !selector_valid {
+                // the root package has to be explicitly included
+                entry_packages = self
+                    .pkg_graph
+                    .packages()
+                    .filter(|(name, _)| !PackageName::Root.eq(name))
+                    .map(|(name, _)| name.to_owned())
+                    .collect();
+                selector_valid = true;
+            }
+            entry_packages.retain(|package| self.package_matches(package, predicate));
+        }
+
+        // if neither a name pattern, package predicate, parent dir, or from ref is
+        // provided, then the selector is invalid
         if !selector_valid {
             Err(ResolutionError::InvalidSelector(
                 InvalidSelectorError::InvalidSelector(selector.raw.clone()),
@@ -517,6 +536,12 @@ impl<'a, T: GitChangeDetector> FilterResolver<'a, T> {
             .changed_packages(&git_range.from_ref, git_range.to_ref.as_deref())
     }
 
+    fn package_matches(&self, package: &PackageName, predicate: &PackagePredicate) -> bool {
+        self.pkg_graph
+            .package_json(package)
+            .map_or(false, |package_json| predicate.matches(package_json))
+    }
+
     fn match_package_names_to_vertices(
         &self,
         name_pattern: &str,
@@ -614,7 +639,9 @@ mod test {
 
     use super::{FilterResolver, PackageInference, TargetSelector};
     use crate::run::scope::{
-        change_detector::GitChangeDetector, target_selector::GitRange, ResolutionError,
+        change_detector::GitChangeDetector,
+        target_selector::{GitRange, PackagePredicate},
+        ResolutionError,
     };
 
     fn get_name(name: &str) -> (Option<&str>, &str) {
@@ -646,6 +673,7 @@ mod test {
             Ok(turborepo_repository::discovery::DiscoveryResponse {
                 package_manager: PackageManager::Pnpm6,
                 workspaces: vec![], // we don't care about this
+                ecosystem_workspaces: vec![],
             })
         }
 
@@ -968,6 +996,47 @@ mod test {
         &["project-0"] ;
         "infer single package from subdirectory"
     )]
+    #[test_case(
+        vec![
+            TargetSelector {
+                include_dependencies: true,
+                predicate: Some(PackagePredicate::Field {
+                    name: "name".to_string(),
+                    value: "project-1".to_string(),
+                }),
+                ..Default::default()
+            }
+        ],
+        None,
+        &["project-1", "project-2", "project-4"] ;
+        "select package with dependencies by field"
+    )]
+    #[test_case(
+        vec![
+            TargetSelector {
+                exclude: true,
+                predicate: Some(PackagePredicate::Field {
+                    name: "name".to_string(),
+                    value: "project-1".to_string(),
+                }),
+                ..Default::default()
+            }
+        ],
+        None,
+        &["project-0", "project-2", "project-3", "project-4", "project-5", "project-6"] ;
+        "exclude package by field"
+    )]
+    #[test_case(
+        vec![
+            TargetSelector {
+                predicate: Some(PackagePredicate::HasScript("test".to_string())),
+                ..Default::default()
+            }
+        ],
+        None,
+        &[] ;
+        "select packages by missing script"
+    )]
     fn filter(
         selectors: Vec<TargetSelector>,
         package_inference: Option<PackageInference>,
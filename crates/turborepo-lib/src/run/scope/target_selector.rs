@@ -1,8 +1,10 @@
 use std::str::FromStr;
 
 use regex::Regex;
+use serde_json::Value;
 use thiserror::Error;
 use turbopath::AnchoredSystemPathBuf;
+use turborepo_repository::package_json::PackageJson;
 
 #[derive(Debug, PartialEq)]
 pub struct GitRange {
@@ -10,6 +12,93 @@ pub struct GitRange {
     pub to_ref: Option<String>,
 }
 
+/// A condition on a package's package.json, e.g. `field(private)=false` or
+/// `has-script(test)`
+#[derive(Debug, PartialEq)]
+pub enum PackagePredicate {
+    Field { name: String, value: String },
+    HasScript(String),
+}
+
+impl PackagePredicate {
+    fn parse(selector: &str) -> Result<Option<Self>, InvalidSelectorError> {
+        if let Some(script) = selector
+            .strip_prefix("has-script(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            if script.is_empty() {
+                return Err(InvalidSelectorError::InvalidPredicate(selector.to_string()));
+            }
+            return Ok(Some(PackagePredicate::HasScript(script.to_string())));
+        }
+
+        if let Some(rest) = selector.strip_prefix("field(") {
+            let Some((name, value)) = rest.split_once(")=") else {
+                return Err(InvalidSelectorError::InvalidPredicate(selector.to_string()));
+            };
+            if name.is_empty() {
+                return Err(InvalidSelectorError::InvalidPredicate(selector.to_string()));
+            }
+            // e.g. `field(private)=false...[main]`. Arrays are compared against
+            // their JSON representation, so they're allowed to end in a bracket.
+            if !value.starts_with('[')
+                && value.ends_with(']')
+                && value.rfind('[').map_or(false, |start| start > 0)
+            {
+                return Err(InvalidSelectorError::PredicateWithGitRange(
+                    selector.to_string(),
+                ));
+            }
+            return Ok(Some(PackagePredicate::Field {
+                name: name.to_string(),
+                value: value.to_string(),
+            }));
+        }
+
+        Ok(None)
+    }
+
+    /// Fields are compared against their string contents if they are strings
+    /// and against their JSON representation otherwise. A missing field only
+    /// matches `false`, as that's what a missing boolean field means.
+    pub fn matches(&self, package_json: &PackageJson) -> bool {
+        match self {
+            PackagePredicate::HasScript(script) => package_json.scripts.contains_key(script),
+            PackagePredicate::Field { name, value } => match field(package_json, name) {
+                Some(Value::String(field)) => field == *value,
+                Some(field) => field.to_string() == *value,
+                None => value == "false",
+            },
+        }
+    }
+}
+
+// Only the requested field is converted to JSON, not the whole package.json
+fn field(package_json: &PackageJson, name: &str) -> Option<Value> {
+    fn to_value(field: &impl serde::Serialize) -> Option<Value> {
+        serde_json::to_value(field).ok()
+    }
+
+    match name {
+        "name" => package_json.name.clone().map(Value::String),
+        "version" => package_json.version.clone().map(Value::String),
+        "packageManager" => package_json.package_manager.clone().map(Value::String),
+        "dependencies" => package_json.dependencies.as_ref().and_then(to_value),
+        "devDependencies" => package_json.dev_dependencies.as_ref().and_then(to_value),
+        "optionalDependencies" => package_json
+            .optional_dependencies
+            .as_ref()
+            .and_then(to_value),
+        "peerDependencies" => package_json.peer_dependencies.as_ref().and_then(to_value),
+        "turbo" => package_json.legacy_turbo_config.clone(),
+        "scripts" if package_json.scripts.is_empty() => None,
+        "scripts" => to_value(&package_json.scripts),
+        "resolutions" => package_json.resolutions.as_ref().and_then(to_value),
+        "pnpm" => package_json.pnpm.as_ref().and_then(to_value),
+        _ => package_json.other.get(name).cloned(),
+    }
+}
+
 #[derive(Debug, Default, PartialEq)]
 pub struct TargetSelector {
     pub include_dependencies: bool,
@@ -21,6 +110,7 @@ pub struct TargetSelector {
     pub parent_dir: AnchoredSystemPathBuf,
     pub name_pattern: String,
     pub git_range: Option<GitRange>,
+    pub predicate: Option<PackagePredicate>,
     pub raw: String,
 }
 
@@ -66,6 +156,18 @@ impl FromStr for TargetSelector {
             (false, selector)
         };
 
+        if let Some(predicate) = PackagePredicate::parse(selector)? {
+            return Ok(TargetSelector {
+                exclude,
+                exclude_self,
+                include_dependencies,
+                include_dependents,
+                predicate: Some(predicate),
+                raw: raw_selector.to_string(),
+                ..Default::default()
+            });
+        }
+
         // We explicitly allow empty git ranges so we can return a more targeted error
         // below
         let re = Regex::new(r"^(?P<name>[^.](?:[^{}\[\]]*[^{}\[\].])?)?(\{(?P<directory>[^}]*)})?(?P<commits>(?:\.{3})?\[[^\]]*\])?$").expect("valid");
@@ -187,6 +289,10 @@ pub enum InvalidSelectorError {
     EmptyPathSpecification,
     #[error("invalid git range selector: {0}")]
     InvalidGitRange(String),
+    #[error("invalid package predicate: {0}")]
+    InvalidPredicate(String),
+    #[error("package predicates can't be combined with a git range: {0}")]
+    PredicateWithGitRange(String),
 
     #[error("selector \"{0}\" must have a reference, directory, or name pattern")]
     InvalidSelector(String),
@@ -221,10 +327,12 @@ pub fn is_selector_by_location(
 mod test {
     use std::str::FromStr;
 
+    use serde_json::json;
     use test_case::test_case;
     use turbopath::AnchoredSystemPathBuf;
+    use turborepo_repository::package_json::PackageJson;
 
-    use super::TargetSelector;
+    use super::{PackagePredicate, TargetSelector};
     use crate::run::scope::target_selector::GitRange;
 
     #[test_case("foo", TargetSelector { name_pattern: "foo".to_string(), raw: "foo".to_string(), ..Default::default() }; "foo")]
@@ -250,6 +358,10 @@ mod test {
     #[test_case("foo...[master]", TargetSelector { raw: "foo...[master]".to_string(), git_range: Some(GitRange { from_ref: "master".to_string(), to_ref: None }), name_pattern: "foo".to_string(), match_dependencies: true, ..Default::default() }; "foo...[master]")]
     #[test_case("foo...[master]...", TargetSelector { raw: "foo...[master]...".to_string(), git_range: Some(GitRange { from_ref: "master".to_string(), to_ref: None }), name_pattern: "foo".to_string(), match_dependencies: true, include_dependencies: true, ..Default::default() }; "foo...[master] dot dot dot")]
     #[test_case("{foo}...[master]", TargetSelector { raw: "{foo}...[master]".to_string(), git_range: Some(GitRange { from_ref: "master".to_string(), to_ref: None }), parent_dir: AnchoredSystemPathBuf::try_from("foo").unwrap(), match_dependencies: true, ..Default::default() }; "curly brackets foo...[master]")]
+    #[test_case("has-script(test)", TargetSelector { raw: "has-script(test)".to_string(), predicate: Some(PackagePredicate::HasScript("test".to_string())), ..Default::default() }; "has script")]
+    #[test_case("field(private)=false", TargetSelector { raw: "field(private)=false".to_string(), predicate: Some(PackagePredicate::Field { name: "private".to_string(), value: "false".to_string() }), ..Default::default() }; "field")]
+    #[test_case("!field(private)=true", TargetSelector { raw: "!field(private)=true".to_string(), predicate: Some(PackagePredicate::Field { name: "private".to_string(), value: "true".to_string() }), exclude: true, ..Default::default() }; "excluded field")]
+    #[test_case("...has-script(build)", TargetSelector { raw: "...has-script(build)".to_string(), predicate: Some(PackagePredicate::HasScript("build".to_string())), include_dependents: true, ..Default::default() }; "dependents of has script")]
     fn parse_target_selector(raw_selector: &str, want: TargetSelector) {
         let result = TargetSelector::from_str(raw_selector);
 
@@ -273,6 +385,11 @@ mod test {
     #[test_case("[...some-ref]" ; "missing git range start")]
     #[test_case("[some-ref...]" ; "missing git range end")]
     #[test_case("[...]" ; "missing entire git range")]
+    #[test_case("has-script()" ; "empty script name")]
+    #[test_case("field(private)" ; "missing field value")]
+    #[test_case("field()=true" ; "empty field name")]
+    #[test_case("field(private)=false...[main]" ; "field with dependencies since ref")]
+    #[test_case("field(private)=false[main]" ; "field since ref")]
     fn parse_target_selector_invalid(raw_selector: &str) {
         let result = TargetSelector::from_str(raw_selector);
 
@@ -285,4 +402,50 @@ mod test {
             }
         }
     }
+
+    #[test_case(PackagePredicate::HasScript("test".to_string()), true ; "has script")]
+    #[test_case(PackagePredicate::HasScript("lint".to_string()), false ; "missing script")]
+    #[test_case(PackagePredicate::Field { name: "private".to_string(), value: "false".to_string() }, true ; "boolean field")]
+    #[test_case(PackagePredicate::Field { name: "version".to_string(), value: "1.0.0".to_string() }, true ; "string field")]
+    #[test_case(PackagePredicate::Field { name: "license".to_string(), value: "MIT".to_string() }, false ; "missing field")]
+    #[test_case(PackagePredicate::Field { name: "sideEffects".to_string(), value: "false".to_string() }, true ; "missing boolean field is false")]
+    #[test_case(PackagePredicate::Field { name: "sideEffects".to_string(), value: "true".to_string() }, false ; "missing boolean field isn't true")]
+    #[test_case(PackagePredicate::Field { name: "files".to_string(), value: "[\"dist\"]".to_string() }, true ; "array field")]
+    #[test_case(PackagePredicate::Field { name: "scripts".to_string(), value: "{\"test\":\"jest\"}".to_string() }, true ; "structured field")]
+    fn test_predicate_matches(predicate: PackagePredicate, expected: bool) {
+        let package_json: PackageJson = serde_json::from_value(json!({
+            "name": "foo",
+            "version": "1.0.0",
+            "private": false,
+            "files": ["dist"],
+            "scripts": { "test": "jest" }
+        }))
+        .unwrap();
+        assert_eq!(predicate.matches(&package_json), expected);
+    }
+
+    #[test]
+    fn test_missing_private_field_matches_false() {
+        let package_json: PackageJson = serde_json::from_value(json!({ "name": "foo" })).unwrap();
+        let TargetSelector { predicate, .. } =
+            TargetSelector::from_str("field(private)=false").unwrap();
+        assert!(predicate.unwrap().matches(&package_json));
+    }
+
+    #[test]
+    fn test_array_field_value_is_not_a_git_range() {
+        let TargetSelector {
+            predicate,
+            git_range,
+            ..
+        } = TargetSelector::from_str("field(files)=[\"dist\"]").unwrap();
+        assert_eq!(git_range, None);
+        assert_eq!(
+            predicate,
+            Some(PackagePredicate::Field {
+                name: "files".to_string(),
+                value: "[\"dist\"]".to_string()
+            })
+        );
+    }
 }
@@ -0,0 +1,304 @@
+//! Encrypted snapshots of the environment each task was executed with.
+//!
+//! The run summary otherwise only records the names of environment variables
+//! (and hashes of their values), so the snapshot is sealed with AES-256-GCM
+//! before it's written. The key is generated on first use and kept in the
+//! user's turbo config directory, which means a summary copied off the machine
+//! it was written on (e.g. an artifact uploaded from CI) can't be opened
+//! without the key as well.
+//!
+//! Snapshots are bound to the hash of the task they were taken for, so one
+//! task's environment can't be passed off as another's.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    process,
+};
+
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+use turborepo_auth::TURBO_TOKEN_DIR;
+use turborepo_dirs::config_dir;
+use turborepo_env::EnvironmentVariableMap;
+
+const KEY_FILE: &str = "env-snapshot.key";
+const KEY_LEN: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("unable to find a config directory for the environment snapshot key")]
+    NoConfigDir,
+    #[error(transparent)]
+    Path(#[from] turbopath::PathError),
+    #[error("failed to access environment snapshot key: {0}")]
+    Io(#[from] io::Error),
+    #[error("no environment snapshot key found at {0}")]
+    MissingKey(AbsoluteSystemPathBuf),
+    #[error("environment snapshot key at {0} is invalid")]
+    InvalidKey(AbsoluteSystemPathBuf),
+    #[error("failed to generate random bytes")]
+    Random,
+    #[error(
+        "environment snapshot was sealed with a different key, it was likely recorded on another \
+         machine"
+    )]
+    WrongKey,
+    #[error("environment snapshot could not be encrypted")]
+    Encrypt,
+    #[error("environment snapshot could not be decrypted")]
+    Decrypt,
+    #[error("environment snapshot is malformed")]
+    Malformed,
+}
+
+/// The sealed environment of a single task, as stored in the run summary.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvSnapshot {
+    // identifies the key used to seal the snapshot without revealing it
+    key_id: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+pub struct EnvSnapshotKey {
+    key: LessSafeKey,
+    id: String,
+}
+
+impl EnvSnapshotKey {
+    /// Loads the key from the user's config directory, generating one if this
+    /// is the first snapshot taken on this machine.
+    pub fn load_or_create() -> Result<Self, Error> {
+        Self::load_or_create_at(&Self::path()?)
+    }
+
+    /// Loads the key from the user's config directory.
+    pub fn load() -> Result<Self, Error> {
+        let path = Self::path()?;
+        match path.read_to_string() {
+            Ok(contents) => Self::from_hex(&path, &contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Err(Error::MissingKey(path)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn path() -> Result<AbsoluteSystemPathBuf, Error> {
+        let config_dir = config_dir()?.ok_or(Error::NoConfigDir)?;
+        Ok(config_dir.join_components(&[TURBO_TOKEN_DIR, KEY_FILE]))
+    }
+
+    fn load_or_create_at(path: &AbsoluteSystemPath) -> Result<Self, Error> {
+        match path.read_to_string() {
+            Ok(contents) => return Self::from_hex(path, &contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e.into()),
+        }
+
+        let rng = SystemRandom::new();
+        let mut bytes = [0; KEY_LEN];
+        rng.fill(&mut bytes).map_err(|_| Error::Random)?;
+        let contents = hex::encode(bytes);
+
+        // The key is written to a temporary file in the same directory and
+        // then linked into place, so the key file only ever appears with its
+        // full contents. Unlike a rename, the link fails if the key already
+        // exists, so a key that another process created, and may already have
+        // sealed snapshots with, is never replaced.
+        path.ensure_dir()?;
+        let mut suffix = [0; 4];
+        rng.fill(&mut suffix).map_err(|_| Error::Random)?;
+        let temp = path
+            .parent()
+            .expect("key path has a parent")
+            .join_component(&format!(
+                ".{KEY_FILE}.{}.{}",
+                process::id(),
+                hex::encode(suffix)
+            ));
+        let mut opts = OpenOptions::new();
+        opts.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            opts.mode(0o600);
+        }
+        let written = temp
+            .open_with_options(opts)
+            .and_then(|mut file| file.write_all(contents.as_bytes()));
+        let linked = written.and_then(|()| fs::hard_link(&temp, path));
+        let _ = temp.remove_file();
+        match linked {
+            Ok(()) => Self::from_hex(path, &contents),
+            // another turbo process created the key first
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                Self::from_hex(path, &path.read_to_string()?)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn from_hex(path: &AbsoluteSystemPath, contents: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidKey(path.to_owned());
+        let bytes = hex::decode(contents.trim()).map_err(|_| invalid())?;
+        if bytes.len() != KEY_LEN {
+            return Err(invalid());
+        }
+        let key = UnboundKey::new(&AES_256_GCM, &bytes).map_err(|_| invalid())?;
+        let id = hex::encode(&Sha256::digest(&bytes)[..8]);
+        Ok(Self {
+            key: LessSafeKey::new(key),
+            id,
+        })
+    }
+
+    /// Encrypts `env` for the task with hash `task_hash`.
+    pub fn seal(
+        &self,
+        task_hash: &str,
+        env: &EnvironmentVariableMap,
+    ) -> Result<EnvSnapshot, Error> {
+        let mut nonce = [0; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| Error::Random)?;
+
+        // sort the variables so that the plaintext doesn't depend on hash order
+        let sorted = env.iter().collect::<BTreeMap<_, _>>();
+        let mut in_out = serde_json::to_vec(&sorted).map_err(|_| Error::Malformed)?;
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(task_hash.as_bytes()),
+                &mut in_out,
+            )
+            .map_err(|_| Error::Encrypt)?;
+
+        Ok(EnvSnapshot {
+            key_id: self.id.clone(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(in_out),
+        })
+    }
+
+    /// Decrypts the environment that the task with hash `task_hash` was
+    /// executed with.
+    pub fn open(
+        &self,
+        task_hash: &str,
+        snapshot: &EnvSnapshot,
+    ) -> Result<EnvironmentVariableMap, Error> {
+        if snapshot.key_id != self.id {
+            return Err(Error::WrongKey);
+        }
+        let nonce = hex::decode(&snapshot.nonce)
+            .ok()
+            .and_then(|nonce| Nonce::try_assume_unique_for_key(&nonce).ok())
+            .ok_or(Error::Malformed)?;
+        let mut in_out = hex::decode(&snapshot.ciphertext).map_err(|_| Error::Malformed)?;
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::from(task_hash.as_bytes()), &mut in_out)
+            .map_err(|_| Error::Decrypt)?;
+        let env: HashMap<String, String> =
+            serde_json::from_slice(plaintext).map_err(|_| Error::Malformed)?;
+
+        Ok(env.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_env::EnvironmentVariableMap;
+
+    use super::{EnvSnapshotKey, Error};
+
+    fn env() -> EnvironmentVariableMap {
+        HashMap::from([
+            ("API_TOKEN".to_string(), "secret".to_string()),
+            ("NODE_ENV".to_string(), "production".to_string()),
+        ])
+        .into()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tmp = TempDir::new().unwrap();
+        let path = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .join_components(&["turborepo", "env-snapshot.key"]);
+        let key = EnvSnapshotKey::load_or_create_at(&path).unwrap();
+
+        let snapshot = key.seal("1c2d3e4f5a6b7c8d", &env()).unwrap();
+        assert!(!snapshot.ciphertext.contains(&hex::encode("secret")));
+
+        // reloading the key from disk must give us the same key
+        let key = EnvSnapshotKey::load_or_create_at(&path).unwrap();
+        let opened = key.open("1c2d3e4f5a6b7c8d", &snapshot).unwrap();
+        assert_eq!(opened.into_inner(), env().into_inner());
+    }
+
+    #[test]
+    fn test_concurrent_creation_agrees_on_key() {
+        let tmp = TempDir::new().unwrap();
+        let dir = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let path = dir.join_component("env-snapshot.key");
+
+        let ids = std::thread::scope(|scope| {
+            let handles = (0..8)
+                .map(|_| scope.spawn(|| EnvSnapshotKey::load_or_create_at(&path).unwrap().id))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert!(ids.iter().all(|id| *id == ids[0]));
+        assert_eq!(EnvSnapshotKey::load_or_create_at(&path).unwrap().id, ids[0]);
+
+        // the temporary files are cleaned up
+        let entries = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(entries, 1);
+    }
+
+    #[test]
+    fn test_bound_to_task_hash() {
+        let tmp = TempDir::new().unwrap();
+        let path = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .join_component("env-snapshot.key");
+        let key = EnvSnapshotKey::load_or_create_at(&path).unwrap();
+
+        let snapshot = key.seal("1c2d3e4f5a6b7c8d", &env()).unwrap();
+        assert!(matches!(
+            key.open("8d7c6b5a4f3e2d1c", &snapshot),
+            Err(Error::Decrypt)
+        ));
+    }
+
+    #[test]
+    fn test_wrong_key() {
+        let tmp = TempDir::new().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let key = EnvSnapshotKey::load_or_create_at(&root.join_component("a.key")).unwrap();
+        let other = EnvSnapshotKey::load_or_create_at(&root.join_component("b.key")).unwrap();
+
+        let snapshot = key.seal("1c2d3e4f5a6b7c8d", &env()).unwrap();
+        assert!(matches!(
+            other.open("1c2d3e4f5a6b7c8d", &snapshot),
+            Err(Error::WrongKey)
+        ));
+    }
+}
@@ -38,6 +38,8 @@ pub struct ExecutionSummary<'a> {
     cached: usize,
     // number of tasks that started
     attempted: usize,
+    // total size in bytes of the outputs of all tasks
+    output_bytes: u64,
     pub(crate) start_time: i64,
     pub(crate) end_time: i64,
     #[serde(skip)]
@@ -49,6 +51,7 @@ impl<'a> ExecutionSummary<'a> {
     pub fn new(
         command: String,
         state: SummaryState,
+        output_bytes: u64,
         package_inference_root: Option<&'a AnchoredSystemPath>,
         exit_code: i32,
         start_time: DateTime<Local>,
@@ -61,6 +64,7 @@ impl<'a> ExecutionSummary<'a> {
             failed: state.failed,
             cached: state.cached,
             attempted: state.attempted,
+            output_bytes,
             // We're either at some path in the repo, or at the root, which is an empty path
             repo_path: package_inference_root.unwrap_or_else(|| AnchoredSystemPath::empty()),
             start_time: start_time.timestamp_millis(),
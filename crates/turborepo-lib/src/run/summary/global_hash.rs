@@ -32,6 +32,8 @@ pub struct GlobalHashSummary<'a> {
     pub files: BTreeMap<RelativeUnixPathBuf, String>,
     pub hash_of_external_dependencies: &'a str,
     pub global_dot_env: Option<&'a [RelativeUnixPathBuf]>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub root_package_json: BTreeMap<String, String>,
     pub environment_variables: GlobalEnvVarSummary<'a>,
 }
 
@@ -48,6 +50,7 @@ impl<'a> TryFrom<GlobalHashableInputs<'a>> for GlobalHashSummary<'a> {
             pass_through_env,
             dot_env,
             env_at_execution_start,
+            root_package_json_fields,
             ..
         } = global_hashable_inputs;
 
@@ -82,6 +85,7 @@ impl<'a> TryFrom<GlobalHashableInputs<'a>> for GlobalHashSummary<'a> {
             },
 
             global_dot_env: dot_env,
+            root_package_json: root_package_json_fields,
         })
     }
 }
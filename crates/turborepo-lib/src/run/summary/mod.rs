@@ -5,6 +5,7 @@
 //! exactly what we want to display to the user.
 #[allow(dead_code)]
 mod duration;
+mod env_snapshot;
 mod execution;
 mod global_hash;
 mod scm;
@@ -15,6 +16,7 @@ use std::{collections::HashSet, io, io::Write};
 
 use chrono::{DateTime, Local};
 pub use duration::TurboDuration;
+pub use env_snapshot::{EnvSnapshot, EnvSnapshotKey, Error as EnvSnapshotError};
 pub use execution::{TaskExecutionSummary, TaskTracker};
 pub use global_hash::GlobalHashSummary;
 use itertools::Itertools;
@@ -46,6 +48,7 @@ use crate::{
         spaces::{SpaceRequest, SpacesClient, SpacesClientHandle},
         task::TaskSummary,
     },
+    task_graph::redact::Redactor,
     task_hash::TaskHashTracker,
 };
 
@@ -128,6 +131,10 @@ pub struct RunSummary<'a> {
     run_type: RunType,
     #[serde(skip)]
     spaces_client_handle: Option<SpacesClientHandle>,
+    // Masks values of environment variables matching `redact` in the written
+    // summary
+    #[serde(skip)]
+    redactor: Redactor,
 }
 
 /// We use this to track the run, so it's constructed before the run.
@@ -194,6 +201,7 @@ impl RunTracker {
         packages,
         global_hash_summary,
         task_factory,
+        redactor,
     ))]
     pub async fn to_summary<'a>(
         self,
@@ -206,6 +214,7 @@ impl RunTracker {
         global_hash_summary: GlobalHashSummary<'a>,
         global_env_mode: EnvMode,
         task_factory: TaskSummaryFactory<'a>,
+        redactor: Redactor,
     ) -> Result<RunSummary<'a>, Error> {
         let single_package = run_opts.single_package;
         let should_save = run_opts.summarize.flatten().is_some_and(|s| s);
@@ -224,9 +233,15 @@ impl RunTracker {
             .cloned()
             .map(|TaskState { task_id, execution }| task_factory.task_summary(task_id, execution))
             .collect::<Result<Vec<_>, task_factory::Error>>()?;
+        let output_bytes = tasks
+            .iter()
+            .filter_map(|task| task.shared.output_size.as_ref())
+            .map(|output_size| output_size.total_bytes)
+            .sum();
         let execution_summary = ExecutionSummary::new(
             self.synthesized_command.clone(),
             summary_state,
+            output_bytes,
             package_inference_root,
             exit_code,
             self.started_at,
@@ -250,6 +265,7 @@ impl RunTracker {
             should_save,
             run_type,
             spaces_client_handle: self.spaces_client_handle,
+            redactor,
         })
     }
 
@@ -261,7 +277,8 @@ impl RunTracker {
         global_hash_summary,
         engine,
         hash_tracker,
-        env_at_execution_start
+        env_at_execution_start,
+        redactor
     ))]
     #[allow(clippy::too_many_arguments)]
     pub async fn finish<'a>(
@@ -278,16 +295,30 @@ impl RunTracker {
         engine: &'a Engine,
         hash_tracker: TaskHashTracker,
         env_at_execution_start: &'a EnvironmentVariableMap,
+        redactor: Redactor,
     ) -> Result<(), Error> {
         let end_time = Local::now();
 
+        // Only summaries that get written to disk can be replayed
+        let should_snapshot_env =
+            run_opts.dry_run.is_none() && run_opts.summarize.flatten().is_some_and(|s| s);
+        let env_snapshot_key = should_snapshot_env
+            .then(|| {
+                EnvSnapshotKey::load_or_create()
+                    .inspect_err(|err| warn!("Unable to snapshot task environments: {}", err))
+                    .ok()
+            })
+            .flatten();
+
         let task_factory = TaskSummaryFactory::new(
+            repo_root,
             pkg_dep_graph,
             engine,
             hash_tracker,
             env_at_execution_start,
             run_opts,
             global_env_mode,
+            env_snapshot_key.as_ref(),
         );
 
         let run_summary: RunSummary = self
@@ -301,6 +332,7 @@ impl RunTracker {
                 global_hash_summary,
                 global_env_mode.into(),
                 task_factory,
+                redactor,
             )
             .await?;
 
@@ -505,6 +537,17 @@ impl<'a> RunSummary<'a> {
                 .unwrap_or_default()
                 .len()
         )?;
+        cwriteln!(
+            tab_writer,
+            ui,
+            GREY,
+            "  Root package.json Fields\t=\t{}",
+            self.global_hash_summary
+                .root_package_json
+                .iter()
+                .map(|(field, value)| format!("{field}={value}"))
+                .join(", ")
+        )?;
         cwriteln!(
             tab_writer,
             ui,
@@ -754,7 +797,7 @@ impl<'a> RunSummary<'a> {
         }?;
         // Go produces an extra newline at the end of the JSON
         rendered_json.push('\n');
-        Ok(rendered_json)
+        Ok(self.redactor.redact_json(&rendered_json))
     }
 
     fn normalize(&mut self) {
@@ -1,11 +1,11 @@
 use std::collections::BTreeMap;
 
 use serde::Serialize;
-use turbopath::{AnchoredSystemPathBuf, RelativeUnixPathBuf};
+use turbopath::{AbsoluteSystemPath, AnchoredSystemPathBuf, RelativeUnixPathBuf};
 use turborepo_cache::CacheHitMetadata;
 use turborepo_env::{DetailedMap, EnvironmentVariableMap};
 
-use super::{execution::TaskExecutionSummary, EnvMode};
+use super::{env_snapshot::EnvSnapshot, execution::TaskExecutionSummary, EnvMode};
 use crate::{
     cli::OutputLogsMode,
     run::task_id::TaskId,
@@ -78,12 +78,37 @@ pub(crate) struct SharedTaskSummary<T> {
     pub dependents: Vec<T>,
     pub resolved_task_definition: TaskSummaryTaskDefinition,
     pub expanded_outputs: Vec<AnchoredSystemPathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_size: Option<TaskOutputSize>,
     pub framework: String,
     pub env_mode: EnvMode,
     pub environment_variables: TaskEnvVarSummary,
     pub dot_env: Option<Vec<RelativeUnixPathBuf>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub execution: Option<TaskExecutionSummary>,
+    // the encrypted environment the task was executed with, only present in
+    // saved summaries of real runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment_snapshot: Option<EnvSnapshot>,
+}
+
+// Number of files listed in `largestFiles` for each task
+const LARGEST_OUTPUT_FILES: usize = 5;
+
+/// Disk usage of the files a task wrote to its declared outputs, measured
+/// after the task ran or was restored from cache.
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskOutputSize {
+    pub total_bytes: u64,
+    pub largest_files: Vec<OutputFileSize>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputFileSize {
+    pub path: AnchoredSystemPathBuf,
+    pub bytes: u64,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -200,6 +225,32 @@ impl TaskEnvVarSummary {
     }
 }
 
+impl TaskOutputSize {
+    pub fn new(repo_root: &AbsoluteSystemPath, outputs: &[AnchoredSystemPathBuf]) -> Self {
+        // Directories and files that have since been removed don't count
+        // towards the total
+        let mut files = outputs
+            .iter()
+            .filter_map(|path| {
+                let metadata = repo_root.resolve(path).symlink_metadata().ok()?;
+                metadata.is_file().then(|| OutputFileSize {
+                    path: path.clone(),
+                    bytes: metadata.len(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let total_bytes = files.iter().map(|file| file.bytes).sum();
+
+        files.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+        files.truncate(LARGEST_OUTPUT_FILES);
+
+        Self {
+            total_bytes,
+            largest_files: files,
+        }
+    }
+}
+
 impl From<TaskSummary> for SinglePackageTaskSummary {
     fn from(value: TaskSummary) -> Self {
         let TaskSummary {
@@ -226,6 +277,7 @@ impl From<SharedTaskSummary<TaskId<'static>>> for SharedTaskSummary<String> {
             excluded_outputs,
             log_file,
             expanded_outputs,
+            output_size,
             dependencies,
             dependents,
             resolved_task_definition,
@@ -234,6 +286,7 @@ impl From<SharedTaskSummary<TaskId<'static>>> for SharedTaskSummary<String> {
             env_mode,
             environment_variables,
             dot_env,
+            environment_snapshot,
             ..
         } = value;
         Self {
@@ -248,6 +301,7 @@ impl From<SharedTaskSummary<TaskId<'static>>> for SharedTaskSummary<String> {
             log_file,
             directory: None,
             expanded_outputs,
+            output_size,
             dependencies: dependencies
                 .into_iter()
                 .map(|task_id| task_id.task().to_string())
@@ -262,6 +316,7 @@ impl From<SharedTaskSummary<TaskId<'static>>> for SharedTaskSummary<String> {
             env_mode,
             environment_variables,
             dot_env,
+            environment_snapshot,
         }
     }
 }
@@ -275,6 +330,7 @@ impl From<TaskDefinition> for TaskSummaryTaskDefinition {
                     exclusions,
                 },
             cache,
+            cache_ttl: _,
             mut env,
             pass_through_env,
             dot_env,
@@ -385,4 +441,50 @@ mod test {
     fn test_serialization(value: impl serde::Serialize, expected: serde_json::Value) {
         assert_eq!(serde_json::to_value(value).unwrap(), expected);
     }
+
+    #[test]
+    fn test_output_size() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPath::from_std_path(tmpdir.path()).unwrap();
+        let dist = repo_root.join_component("dist");
+        dist.create_dir_all().unwrap();
+        let output = |name: &str| {
+            AnchoredSystemPathBuf::relative_path_between(repo_root, &dist.join_component(name))
+        };
+        for (name, len) in [("a.js", 10), ("b.js", 30), ("c.js", 20)] {
+            dist.join_component(name)
+                .create_with_contents("x".repeat(len))
+                .unwrap();
+        }
+
+        let outputs = vec![
+            AnchoredSystemPathBuf::relative_path_between(repo_root, &dist),
+            output("a.js"),
+            output("b.js"),
+            output("c.js"),
+            output("gone.js"),
+        ];
+        let size = TaskOutputSize::new(repo_root, &outputs);
+
+        assert_eq!(
+            size,
+            TaskOutputSize {
+                total_bytes: 60,
+                largest_files: vec![
+                    OutputFileSize {
+                        path: output("b.js"),
+                        bytes: 30
+                    },
+                    OutputFileSize {
+                        path: output("c.js"),
+                        bytes: 20
+                    },
+                    OutputFileSize {
+                        path: output("a.js"),
+                        bytes: 10
+                    },
+                ],
+            }
+        );
+    }
 }
@@ -1,11 +1,14 @@
 use std::collections::HashSet;
 
+use tracing::warn;
+use turbopath::AbsoluteSystemPath;
 use turborepo_env::EnvironmentVariableMap;
 use turborepo_repository::package_graph::{PackageGraph, PackageInfo, PackageName};
 
 use super::{
+    env_snapshot::EnvSnapshotKey,
     execution::TaskExecutionSummary,
-    task::{SharedTaskSummary, TaskEnvVarSummary},
+    task::{SharedTaskSummary, TaskEnvVarSummary, TaskOutputSize},
     EnvMode, SinglePackageTaskSummary, TaskSummary,
 };
 use crate::{
@@ -18,12 +21,14 @@ use crate::{
 };
 
 pub struct TaskSummaryFactory<'a> {
+    repo_root: &'a AbsoluteSystemPath,
     package_graph: &'a PackageGraph,
     engine: &'a Engine,
     hash_tracker: TaskHashTracker,
     env_at_start: &'a EnvironmentVariableMap,
     run_opts: &'a RunOpts,
     global_env_mode: cli::EnvMode,
+    env_snapshot_key: Option<&'a EnvSnapshotKey>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -36,20 +41,24 @@ pub enum Error {
 
 impl<'a> TaskSummaryFactory<'a> {
     pub fn new(
+        repo_root: &'a AbsoluteSystemPath,
         package_graph: &'a PackageGraph,
         engine: &'a Engine,
         hash_tracker: TaskHashTracker,
         env_at_start: &'a EnvironmentVariableMap,
         run_opts: &'a RunOpts,
         global_env_mode: cli::EnvMode,
+        env_snapshot_key: Option<&'a EnvSnapshotKey>,
     ) -> Self {
         Self {
+            repo_root,
             package_graph,
             engine,
             hash_tracker,
             env_at_start,
             run_opts,
             global_env_mode,
+            env_snapshot_key,
         }
     }
 
@@ -123,6 +132,8 @@ impl<'a> TaskSummaryFactory<'a> {
             .hash_tracker
             .expanded_outputs(task_id)
             .unwrap_or_default();
+        let output_size = (!expanded_outputs.is_empty())
+            .then(|| TaskOutputSize::new(self.repo_root, &expanded_outputs));
 
         let framework = self.hash_tracker.framework(task_id).unwrap_or_default();
         let hash = self
@@ -143,6 +154,15 @@ impl<'a> TaskSummaryFactory<'a> {
 
         let cache_summary = self.hash_tracker.cache_status(task_id).into();
 
+        let environment_snapshot = self
+            .env_snapshot_key
+            .zip(self.hash_tracker.execution_env(task_id))
+            .and_then(|(key, env)| {
+                key.seal(&hash, &env)
+                    .inspect_err(|err| warn!("unable to snapshot environment of {task_id}: {err}"))
+                    .ok()
+            });
+
         let (dependencies, dependents) = self.dependencies_and_dependents(task_id, display_task);
 
         let log_file = {
@@ -172,6 +192,7 @@ impl<'a> TaskSummaryFactory<'a> {
             directory: Some(workspace_info.package_path().to_string()),
             resolved_task_definition: task_definition.clone().into(),
             expanded_outputs,
+            output_size,
             framework,
             dependencies,
             dependents,
@@ -199,6 +220,7 @@ impl<'a> TaskSummaryFactory<'a> {
             .expect("invalid glob in task definition should have been caught earlier"),
             dot_env: task_definition.dot_env.clone(),
             execution,
+            environment_snapshot,
         })
     }
 
@@ -1,6 +1,7 @@
+pub(crate) mod redact;
 mod visitor;
 
-use std::str::FromStr;
+use std::{str::FromStr, time::Duration};
 
 use globwalk::{GlobError, ValidatedGlob};
 use serde::{Deserialize, Serialize};
@@ -14,6 +15,10 @@ use crate::{
     turbo_json::RawTaskDefinition,
 };
 
+// Output globs starting with this prefix are relative to the repository root
+// rather than to the package, e.g. "//dist/web/**"
+pub const ROOT_OUTPUT_PREFIX: &str = "//";
+
 // TaskOutputs represents the patterns for including and excluding files from
 // outputs
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
@@ -44,6 +49,10 @@ pub struct TaskDefinition {
     pub outputs: TaskOutputs,
     pub(crate) cache: bool,
 
+    // CacheTtl is a hint for how long the task's cached outputs should be kept.
+    // It is not part of the task hash.
+    pub(crate) cache_ttl: Option<Duration>,
+
     // This field is custom-marshalled from `env` and `depends_on``
     pub(crate) env: Vec<String>,
 
@@ -84,6 +93,7 @@ impl Default for TaskDefinition {
     fn default() -> Self {
         Self {
             cache: true,
+            cache_ttl: Default::default(),
             outputs: Default::default(),
             env: Default::default(),
             pass_through_env: Default::default(),
@@ -145,6 +155,9 @@ impl TaskDefinition {
         workspace_dir: &AnchoredSystemPath,
     ) -> TaskOutputs {
         let make_glob_repo_relative = |glob: &str| -> String {
+            if let Some(root_relative_glob) = glob.strip_prefix(ROOT_OUTPUT_PREFIX) {
+                return root_relative_glob.to_string();
+            }
             let mut repo_relative_glob = workspace_dir.to_string();
             repo_relative_glob.push(std::path::MAIN_SEPARATOR);
             repo_relative_glob.push_str(glob);
@@ -216,6 +229,40 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_root_relative_output_globs() {
+        let task_defn = TaskDefinition {
+            outputs: TaskOutputs {
+                inclusions: vec!["//dist/foo/**".to_string()],
+                exclusions: vec!["//dist/foo/cache/**".to_string()],
+            },
+            ..Default::default()
+        };
+
+        let task_id = TaskId::new("foo", "build");
+        let workspace_dir = AnchoredSystemPath::new(match cfg!(windows) {
+            true => "apps\\foo",
+            false => "apps/foo",
+        })
+        .unwrap();
+
+        let relative_outputs = task_defn.repo_relative_hashable_outputs(&task_id, workspace_dir);
+        let relative_prefix = match cfg!(windows) {
+            true => "apps\\foo\\",
+            false => "apps/foo/",
+        };
+        assert_eq!(
+            relative_outputs,
+            TaskOutputs {
+                inclusions: vec![
+                    "dist/foo/**".to_string(),
+                    format!("{relative_prefix}.turbo/turbo-build.log"),
+                ],
+                exclusions: vec!["dist/foo/cache/**".to_string()],
+            }
+        );
+    }
+
     #[test]
     fn test_escape_log_file() {
         let build_log = TaskDefinition::workspace_relative_log_file("build");
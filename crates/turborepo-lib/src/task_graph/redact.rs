@@ -0,0 +1,176 @@
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::Arc,
+};
+
+const REDACTED: &str = "***";
+
+/// Replaces any occurrence of the given secrets with `***`.
+#[derive(Clone, Default)]
+pub struct Redactor {
+    secrets: Arc<[String]>,
+}
+
+// The secrets themselves must never end up in debug output
+impl fmt::Debug for Redactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Redactor")
+            .field("secrets", &self.secrets.len())
+            .finish()
+    }
+}
+
+impl Redactor {
+    pub fn new(secrets: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let mut secrets: Vec<String> = secrets
+            .into_iter()
+            .map(Into::into)
+            .filter(|secret: &String| !secret.is_empty())
+            .collect();
+        // Prefer the longest match when one secret contains another
+        secrets.sort_by(|a, b| b.len().cmp(&a.len()));
+        Self {
+            secrets: secrets.into(),
+        }
+    }
+
+    pub fn redact(&self, mut bytes: &[u8]) -> Vec<u8> {
+        let mut redacted = Vec::with_capacity(bytes.len());
+        'outer: while !bytes.is_empty() {
+            for secret in self.secrets.iter() {
+                if bytes.starts_with(secret.as_bytes()) {
+                    redacted.extend_from_slice(REDACTED.as_bytes());
+                    bytes = &bytes[secret.len()..];
+                    continue 'outer;
+                }
+            }
+            redacted.push(bytes[0]);
+            bytes = &bytes[1..];
+        }
+        redacted
+    }
+
+    pub fn redact_str(&self, s: &str) -> String {
+        if self.secrets.is_empty() {
+            return s.to_string();
+        }
+        // Secrets always match on character boundaries, so the result is still
+        // valid UTF-8
+        String::from_utf8(self.redact(s.as_bytes())).expect("redaction preserves UTF-8")
+    }
+
+    /// Redacts serialized JSON, where secrets appear in their escaped form.
+    pub fn redact_json(&self, json: &str) -> String {
+        if self.secrets.is_empty() {
+            return json.to_string();
+        }
+        let escaped = Redactor::new(self.secrets.iter().map(|secret| {
+            let quoted = serde_json::Value::from(secret.as_str()).to_string();
+            quoted[1..quoted.len() - 1].to_string()
+        }));
+        escaped.redact_str(json)
+    }
+
+    pub fn writer<W: Write>(&self, inner: W) -> RedactingWriter<W> {
+        RedactingWriter {
+            inner,
+            redactor: self.clone(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the length of the longest suffix of `bytes` that is a proper
+    /// prefix of one of the secrets.
+    fn partial_secret_len(&self, bytes: &[u8]) -> usize {
+        self.secrets
+            .iter()
+            .filter_map(|secret| {
+                let secret = secret.as_bytes();
+                (1..secret.len().min(bytes.len() + 1))
+                    .rev()
+                    .find(|len| bytes.ends_with(&secret[..*len]))
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// A writer that redacts secrets before passing output on.
+///
+/// Output may arrive in arbitrary chunks, so if the end of a write could be
+/// the start of a secret we hold it back until the next write or a flush.
+pub struct RedactingWriter<W> {
+    inner: W,
+    redactor: Redactor,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.redactor.secrets.is_empty() {
+            return self.inner.write(buf);
+        }
+        self.pending.extend_from_slice(buf);
+        let held_back = self.redactor.partial_secret_len(&self.pending);
+        let ready = self.pending.len() - held_back;
+        let redacted = self.redactor.redact(&self.pending[..ready]);
+        self.inner.write_all(&redacted)?;
+        self.pending.drain(..ready);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            let redacted = self.redactor.redact(&self.pending);
+            self.inner.write_all(&redacted)?;
+            self.pending.clear();
+        }
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use test_case::test_case;
+
+    use super::Redactor;
+
+    #[test_case(&["hello world\n"], &["secret"], "hello world\n" ; "no secrets")]
+    #[test_case(&["token is secret\n"], &["secret"], "token is ***\n" ; "single write")]
+    #[test_case(&["token is sec", "ret\n"], &["secret"], "token is ***\n" ; "split across writes")]
+    #[test_case(&["sec", "tion\n"], &["secret"], "section\n" ; "partial match")]
+    #[test_case(&["a-b a"], &["a", "a-b"], "*** ***" ; "longest secret wins")]
+    #[test_case(&["ends with sec"], &["secret"], "ends with sec" ; "partial match at end")]
+    fn test_redacting_writer(writes: &[&str], secrets: &[&str], expected: &str) {
+        let mut out = Vec::new();
+        {
+            let mut writer = Redactor::new(secrets.iter().copied()).writer(&mut out);
+            for write in writes {
+                writer.write_all(write.as_bytes()).unwrap();
+            }
+            writer.flush().unwrap();
+        }
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_redact_str() {
+        let redactor = Redactor::new(["s3cr\"t"]);
+        assert_eq!(
+            redactor.redact_str("command echo s3cr\"t exited (1)"),
+            "command echo *** exited (1)"
+        );
+    }
+
+    #[test]
+    fn test_redact_json() {
+        let redactor = Redactor::new(["s3cr\"t"]);
+        assert_eq!(
+            redactor.redact_json(r#"{"error": "echo s3cr\"t"}"#),
+            r#"{"error": "echo ***"}"#
+        );
+    }
+}
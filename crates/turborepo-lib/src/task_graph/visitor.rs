@@ -28,6 +28,7 @@ use turborepo_ui::{
 };
 use which::which;
 
+use super::redact::Redactor;
 use crate::{
     cli::EnvMode,
     engine::{Engine, ExecutionOptions, StopExecution},
@@ -41,7 +42,7 @@ use crate::{
         },
         task_access::TaskAccess,
         task_id::TaskId,
-        RunCache, TaskCache,
+        RunCache, RunCacheError, TaskCache,
     },
     task_hash::{self, PackageInputsHashes, TaskHashTracker, TaskHashTrackerState, TaskHasher},
 };
@@ -63,6 +64,7 @@ pub struct Visitor<'a> {
     task_hasher: TaskHasher<'a>,
     ui: UI,
     experimental_ui: bool,
+    redact: &'a [String],
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -84,6 +86,8 @@ pub enum Error {
     TaskHash(#[from] task_hash::Error),
     #[error(transparent)]
     RunSummary(#[from] summary::Error),
+    #[error(transparent)]
+    Env(#[from] turborepo_env::Error),
 }
 
 impl<'a> Visitor<'a> {
@@ -106,6 +110,7 @@ impl<'a> Visitor<'a> {
         repo_root: &'a AbsoluteSystemPath,
         global_env: EnvironmentVariableMap,
         experimental_ui: bool,
+        redact: &'a [String],
     ) -> Self {
         let task_hasher = TaskHasher::new(
             package_inputs_hashes,
@@ -133,6 +138,7 @@ impl<'a> Visitor<'a> {
             ui,
             global_env,
             experimental_ui,
+            redact,
         }
     }
 
@@ -266,15 +272,26 @@ impl<'a> Visitor<'a> {
                         continue;
                     }
 
+                    // Record exactly what the task will see so that `turbo replay` can
+                    // reproduce it from the run summary
+                    let mut env_snapshot = execution_env.clone();
+                    env_snapshot.insert("TURBO_HASH".to_owned(), task_hash.clone());
+                    self.task_hasher
+                        .task_hash_tracker()
+                        .insert_execution_env(info.clone().into_owned(), env_snapshot);
+
                     let workspace_directory = self.repo_root.resolve(workspace_info.package_path());
 
                     let takes_input = task_definition.interactive || task_definition.persistent;
+                    let redactor =
+                        Redactor::new(execution_env.from_wildcards(self.redact)?.values().cloned());
                     let mut exec_context = factory.exec_context(
                         info.clone(),
                         task_hash,
                         task_cache,
                         workspace_directory,
                         execution_env,
+                        redactor,
                         takes_input,
                         self.task_access.clone(),
                     );
@@ -362,10 +379,17 @@ impl<'a> Visitor<'a> {
             repo_root,
             global_env_mode,
             task_hasher,
+            redact,
             ..
         } = self;
 
         let global_hash_summary = GlobalHashSummary::try_from(global_hash_inputs)?;
+        let redactor = Redactor::new(
+            env_at_execution_start
+                .from_wildcards(redact)?
+                .values()
+                .cloned(),
+        );
 
         Ok(self
             .run_tracker
@@ -382,6 +406,7 @@ impl<'a> Visitor<'a> {
                 engine,
                 task_hasher.task_hash_tracker(),
                 env_at_execution_start,
+                redactor,
             )
             .await?)
     }
@@ -560,6 +585,8 @@ enum TaskErrorCause {
     Spawn { msg: String },
     #[error("command {command} exited ({exit_code})")]
     Exit { command: String, exit_code: i32 },
+    #[error("unable to restore outputs: {msg}")]
+    Restore { msg: String },
 }
 
 impl TaskError {
@@ -570,12 +597,10 @@ impl TaskError {
         }
     }
 
-    fn from_spawn(task_id: String, err: std::io::Error) -> Self {
+    fn from_spawn(task_id: String, msg: String) -> Self {
         Self {
             task_id,
-            cause: TaskErrorCause::Spawn {
-                msg: err.to_string(),
-            },
+            cause: TaskErrorCause::Spawn { msg },
         }
     }
 
@@ -629,6 +654,7 @@ impl<'a> ExecContextFactory<'a> {
         task_cache: TaskCache,
         workspace_directory: AbsoluteSystemPathBuf,
         execution_env: EnvironmentVariableMap,
+        redactor: Redactor,
         takes_input: bool,
         task_access: TaskAccess,
     ) -> ExecContext {
@@ -652,6 +678,7 @@ impl<'a> ExecContextFactory<'a> {
             manager: self.manager.clone(),
             task_hash,
             execution_env,
+            redactor,
             continue_on_error: self.visitor.run_opts.continue_on_error,
             pass_through_args,
             errors: self.errors.clone(),
@@ -688,6 +715,9 @@ struct ExecContext {
     manager: ProcessManager,
     task_hash: String,
     execution_env: EnvironmentVariableMap,
+    // Masks values of environment variables matching `redact` in the task's
+    // output and errors
+    redactor: Redactor,
     continue_on_error: bool,
     pass_through_args: Option<Vec<String>>,
     errors: Arc<Mutex<Vec<TaskError>>>,
@@ -853,9 +883,27 @@ impl ExecContext {
                 return ExecOutcome::Success(SuccessOutcome::CacheHit);
             }
             Ok(None) => (),
+            // Running the task would write its outputs to a disk that's already too full
+            Err(e @ RunCacheError::DiskSpace(_)) => {
+                let message = self.redactor.redact_str(&e.to_string());
+                prefixed_ui.error(format!("unable to restore outputs: {message}"));
+                self.errors.lock().expect("lock poisoned").push(TaskError {
+                    task_id: self.task_id_for_display.clone(),
+                    cause: TaskErrorCause::Restore {
+                        msg: message.clone(),
+                    },
+                });
+                return ExecOutcome::Task {
+                    exit_code: None,
+                    message,
+                };
+            }
             Err(e) => {
                 telemetry.track_error(TrackedErrors::ErrorFetchingFromCache);
-                prefixed_ui.error(format!("error fetching from cache: {e}"));
+                prefixed_ui.error(format!(
+                    "error fetching from cache: {}",
+                    self.redactor.redact_str(&e.to_string())
+                ));
             }
         }
 
@@ -896,13 +944,16 @@ impl ExecContext {
             Some(Ok(child)) => child,
             // Turbo was unable to spawn a process
             Some(Err(e)) => {
+                let error_string = self.redactor.redact_str(&e.to_string());
                 // Note: we actually failed to spawn, but this matches the Go output
-                prefixed_ui.error(format!("command finished with error: {e}"));
-                let error_string = e.to_string();
+                prefixed_ui.error(format!("command finished with error: {error_string}"));
                 self.errors
                     .lock()
                     .expect("lock poisoned")
-                    .push(TaskError::from_spawn(self.task_id_for_display.clone(), e));
+                    .push(TaskError::from_spawn(
+                        self.task_id_for_display.clone(),
+                        error_string.clone(),
+                    ));
                 return ExecOutcome::Task {
                     exit_code: None,
                     message: error_string,
@@ -927,7 +978,7 @@ impl ExecContext {
         } else {
             Either::Right(prefixed_ui.output_prefixed_writer())
         }) {
-            Ok(w) => w,
+            Ok(w) => self.redactor.writer(w),
             Err(e) => {
                 telemetry.track_error(TrackedErrors::FailedToCaptureOutputs);
                 error!("failed to capture outputs for \"{}\": {e}", self.task_id);
@@ -988,7 +1039,8 @@ impl ExecContext {
                 {
                     error!("error reading logs: {e}");
                 }
-                let error = TaskErrorCause::from_execution(process.label().to_string(), code);
+                let error =
+                    TaskErrorCause::from_execution(self.redactor.redact_str(process.label()), code);
                 let message = error.to_string();
                 if self.continue_on_error {
                     prefixed_ui.warn("command finished with error, but continuing...");
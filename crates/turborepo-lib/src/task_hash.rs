@@ -1,6 +1,9 @@
 use std::{
     collections::{HashMap, HashSet},
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
 };
 
 use rayon::prelude::*;
@@ -25,6 +28,30 @@ use crate::{
     task_graph::TaskDefinition,
 };
 
+// The number of tasks whose input files are being hashed, included in crash
+// reports
+static PENDING_FILE_HASHES: AtomicUsize = AtomicUsize::new(0);
+
+pub fn pending_file_hashes() -> usize {
+    PENDING_FILE_HASHES.load(Ordering::Relaxed)
+}
+
+// Counts a task towards `PENDING_FILE_HASHES` until it's dropped
+struct PendingFileHash;
+
+impl PendingFileHash {
+    fn start() -> Self {
+        PENDING_FILE_HASHES.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for PendingFileHash {
+    fn drop(&mut self) {
+        PENDING_FILE_HASHES.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("missing pipeline entry {0}")]
@@ -86,6 +113,7 @@ impl PackageInputsHashes {
                 let TaskNode::Task(task_id) = task else {
                     return None;
                 };
+                let _pending = PendingFileHash::start();
 
                 let task_definition = match task_definitions
                     .get(task_id)
@@ -176,6 +204,8 @@ pub struct TaskHashTrackerState {
     package_task_cache: HashMap<TaskId<'static>, CacheHitMetadata>,
     #[serde(skip)]
     package_task_inputs_expanded_hashes: HashMap<TaskId<'static>, FileHashes>,
+    #[serde(skip)]
+    package_task_execution_env: HashMap<TaskId<'static>, EnvironmentVariableMap>,
 }
 
 /// Caches package-inputs hashes, and package-task hashes.
@@ -526,6 +556,16 @@ impl TaskHashTracker {
         state.package_task_cache.insert(task_id, cache_status);
     }
 
+    pub fn execution_env(&self, task_id: &TaskId) -> Option<EnvironmentVariableMap> {
+        let state = self.state.lock().expect("hash tracker mutex poisoned");
+        state.package_task_execution_env.get(task_id).cloned()
+    }
+
+    pub fn insert_execution_env(&self, task_id: TaskId<'static>, env: EnvironmentVariableMap) {
+        let mut state = self.state.lock().expect("hash tracker mutex poisoned");
+        state.package_task_execution_env.insert(task_id, env);
+    }
+
     pub fn get_expanded_inputs(&self, task_id: &TaskId) -> Option<FileHashes> {
         let state = self.state.lock().expect("hash tracker mutex poisoned");
         state
@@ -1,10 +1,13 @@
-use std::{io::Stderr, marker::PhantomData, path::Path, sync::Mutex};
+use std::{fmt::Write as _, io::Stderr, marker::PhantomData, path::Path, sync::Mutex};
 
-use chrono::Local;
+use chrono::{DateTime, Local};
+use concurrent_queue::{ConcurrentQueue, PushError};
+use lazy_static::lazy_static;
 use owo_colors::{
     colors::{Black, Default, Red, Yellow},
     Color, OwoColorize,
 };
+use regex::Regex;
 use tracing::{field::Visit, metadata::LevelFilter, trace, Event, Level, Subscriber};
 use tracing_appender::{non_blocking::NonBlocking, rolling::RollingFileAppender};
 use tracing_chrome::ChromeLayer;
@@ -70,6 +73,20 @@ type ChromeReload = reload::Layer<Option<ChromeLog>, DaemonLogLayered>;
 /// `ChromeLogLayered`, which forms the base for the next layer.
 type ChromeLogLayered = layer::Layered<ChromeReload, DaemonLogLayered>;
 
+/// The number of events that are kept in memory to be included in crash
+/// reports.
+const RECENT_EVENTS_CAPACITY: usize = 100;
+
+lazy_static! {
+    static ref RECENT_EVENTS: ConcurrentQueue<RecentEvent> =
+        ConcurrentQueue::bounded(RECENT_EVENTS_CAPACITY);
+    static ref SECRET: Regex = Regex::new(
+        r#"(?i)(token|secret|password|authorization|api[_-]?key)(["']?\s*[:=]\s*["']?(?:(?:bearer|basic)\s+)?)[^\s"',]+"#
+    )
+    .unwrap();
+    static ref BEARER: Regex = Regex::new(r"(?i)(bearer\s+)\S+").unwrap();
+}
+
 pub struct TurboSubscriber {
     daemon_update: Handle<Option<DaemonLog>, StdErrLogLayered>,
 
@@ -137,10 +154,13 @@ impl TurboSubscriber {
 
         let (chrome, chrome_update) = reload::Layer::new(Option::<ChromeLog>::None);
 
+        let recent_events = RecentEvents.with_filter(env_filter(LevelFilter::INFO));
+
         let registry = Registry::default()
             .with(stderr)
             .with(logrotate)
-            .with(chrome);
+            .with(chrome)
+            .with(recent_events);
 
         #[cfg(feature = "pprof")]
         let pprof_guard = pprof::ProfilerGuardBuilder::default()
@@ -234,6 +254,84 @@ impl Drop for TurboSubscriber {
     }
 }
 
+/// Takes the most recent log events, oldest first, with anything that looks
+/// like a credential redacted. Used to make crash reports actionable.
+pub fn recent_events() -> Vec<String> {
+    let mut events = Vec::with_capacity(RECENT_EVENTS.len());
+    while let Ok(event) = RECENT_EVENTS.pop() {
+        events.push(redact(&event.to_string()));
+    }
+    events
+}
+
+/// An event as it was logged. Formatting and redaction are left until a crash
+/// report is built, so that logging stays cheap.
+struct RecentEvent {
+    time: DateTime<Local>,
+    level: Level,
+    target: &'static str,
+    fields: String,
+}
+
+impl std::fmt::Display for RecentEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}] {}:{}",
+            self.time.format("%Y-%m-%dT%H:%M:%S.%3f%z"),
+            self.level,
+            self.target,
+            self.fields
+        )
+    }
+}
+
+/// A layer that keeps the last `RECENT_EVENTS_CAPACITY` events in memory.
+struct RecentEvents;
+
+impl<S: Subscriber> Layer<S> for RecentEvents {
+    fn on_event(&self, event: &Event<'_>, _ctx: layer::Context<'_, S>) {
+        let mut fields = String::new();
+        event.record(&mut FieldsVisitor { out: &mut fields });
+        let event = RecentEvent {
+            time: Local::now(),
+            level: *event.metadata().level(),
+            target: event.metadata().target(),
+            fields,
+        };
+
+        // Make room by dropping the oldest event. If another thread fills the
+        // queue in the meantime we lose this event, which is fine.
+        if let Err(PushError::Full(event)) = RECENT_EVENTS.push(event) {
+            let _ = RECENT_EVENTS.pop();
+            let _ = RECENT_EVENTS.push(event);
+        }
+    }
+}
+
+/// Writes all fields of an event as `name=value`, except for the message
+/// which is written as is.
+struct FieldsVisitor<'a> {
+    out: &'a mut String,
+}
+
+impl<'a> Visit for FieldsVisitor<'a> {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.out, " {:?}", value);
+        } else {
+            let _ = write!(self.out, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+fn redact(line: &str) -> String {
+    // The secret pattern keeps the scheme of an authorization header, and
+    // bearer tokens that aren't in one are caught afterwards.
+    let line = SECRET.replace_all(line, "$1$2<redacted>");
+    BEARER.replace_all(&line, "$1<redacted>").into_owned()
+}
+
 /// The formatter for TURBOREPO
 ///
 /// This is a port of the go formatter, which follows a few main rules:
@@ -351,3 +449,20 @@ fn write_message<FG: Color, BG: Color>(
     event.record(&mut visitor);
     writeln!(writer)
 }
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::redact;
+
+    #[test_case("fetching artifact abc123", "fetching artifact abc123" ; "no secrets")]
+    #[test_case("token=abc123 team=my-team", "token=<redacted> team=my-team" ; "token")]
+    #[test_case(r#"{"apiKey": "abc123"}"#, r#"{"apiKey": "<redacted>"}"# ; "json")]
+    #[test_case("Authorization: Bearer abc123", "Authorization: Bearer <redacted>" ; "bearer")]
+    #[test_case("authorization=basic abc123", "authorization=basic <redacted>" ; "basic")]
+    #[test_case("retrying with bearer abc123", "retrying with bearer <redacted>" ; "bare bearer")]
+    fn test_redact(line: &str, expected: &str) {
+        assert_eq!(redact(line), expected);
+    }
+}
@@ -21,7 +21,7 @@ use crate::{
         task_access::{TaskAccessTraceFile, TASK_ACCESS_CONFIG_PATH},
         task_id::{TaskId, TaskName},
     },
-    task_graph::{TaskDefinition, TaskOutputs},
+    task_graph::{TaskDefinition, TaskOutputs, ROOT_OUTPUT_PREFIX},
     unescape::UnescapedString,
 };
 
@@ -55,6 +55,7 @@ pub struct TurboJson {
     pub(crate) global_env: Vec<String>,
     pub(crate) global_pass_through_env: Option<Vec<String>>,
     pub(crate) pipeline: Pipeline,
+    pub(crate) redact: Vec<String>,
 }
 
 // Iterable is required to enumerate allowed keys
@@ -122,6 +123,9 @@ pub struct RawTurboJson {
     // .env files to consider, in order.
     #[serde(skip_serializing_if = "Option::is_none")]
     global_dot_env: Option<Vec<UnescapedString>>,
+    // Environment variables whose values are masked in task logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redact: Option<Vec<Spanned<UnescapedString>>>,
     // Pipeline is a map of Turbo pipeline entries which define the task graph
     // and cache behavior on a per task or per package-task basis.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -131,6 +135,26 @@ pub struct RawTurboJson {
     pub(crate) remote_cache: Option<RawRemoteCacheOptions>,
     #[serde(skip_serializing_if = "Option::is_none", rename = "experimentalUI")]
     pub experimental_ui: Option<bool>,
+    // Files larger than this many bytes are hashed by size and mtime instead of
+    // by content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub large_file_threshold: Option<u64>,
+    // Git commands used for hashing that take longer than this many seconds are
+    // killed, and files are hashed without git instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scm_timeout: Option<u64>,
+    // Reuse the hashes git already has for unchanged tracked files when hashing
+    // the files matched by a task's `inputs`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reuse_index_hashes: Option<bool>,
+    // Fail before restoring outputs or starting a run when fewer than this many
+    // bytes are free on the disk holding the repository.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_space_reserve: Option<u64>,
+    // How many seconds a remote cache miss is remembered for, including across
+    // runs, before the remote cache is asked for the same hash again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_miss_ttl: Option<u64>,
 }
 
 #[derive(Serialize, Default, Debug, PartialEq, Clone)]
@@ -167,6 +191,8 @@ pub struct RawTaskDefinition {
     #[serde(skip_serializing_if = "Spanned::is_none")]
     cache: Spanned<Option<bool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    cache_ttl: Option<Spanned<UnescapedString>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     depends_on: Option<Spanned<Vec<Spanned<UnescapedString>>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     dot_env: Option<Spanned<Vec<UnescapedString>>>,
@@ -206,6 +232,7 @@ impl RawTaskDefinition {
         {
             self.cache = other.cache;
         }
+        set_field!(self, other, cache_ttl);
         set_field!(self, other, depends_on);
         set_field!(self, other, inputs);
         set_field!(self, other, output_mode);
@@ -227,28 +254,39 @@ impl TryFrom<Vec<Spanned<UnescapedString>>> for TaskOutputs {
         let mut inclusions = Vec::new();
         let mut exclusions = Vec::new();
 
+        // Root relative outputs are allowed, but not if what follows the prefix is
+        // absolute itself
+        let is_absolute = |glob: &str| {
+            let glob = glob.strip_prefix(ROOT_OUTPUT_PREFIX).unwrap_or(glob);
+            Utf8Path::new(glob).is_absolute()
+        };
+        // nor if it climbs out of the repository with `..`
+        let escapes_root = |glob: &str| {
+            glob.strip_prefix(ROOT_OUTPUT_PREFIX)
+                .is_some_and(|glob| glob.split(['/', '\\']).any(|segment| segment == ".."))
+        };
+        let validate = |glob: &Spanned<UnescapedString>, value: &str| {
+            if is_absolute(value) {
+                let (span, text) = glob.span_and_text("turbo.json");
+                return Err(Error::AbsolutePathInConfig {
+                    field: "outputs",
+                    span,
+                    text,
+                });
+            }
+            if escapes_root(value) {
+                let (span, text) = glob.span_and_text("turbo.json");
+                return Err(Error::RootOutputOutsideRepo { span, text });
+            }
+            Ok(())
+        };
+
         for glob in outputs {
             if let Some(stripped_glob) = glob.value.strip_prefix('!') {
-                if Utf8Path::new(stripped_glob).is_absolute() {
-                    let (span, text) = glob.span_and_text("turbo.json");
-                    return Err(Error::AbsolutePathInConfig {
-                        field: "outputs",
-                        span,
-                        text,
-                    });
-                }
-
+                validate(&glob, stripped_glob)?;
                 exclusions.push(stripped_glob.to_string());
             } else {
-                if Utf8Path::new(&glob.value).is_absolute() {
-                    let (span, text) = glob.span_and_text("turbo.json");
-                    return Err(Error::AbsolutePathInConfig {
-                        field: "outputs",
-                        span,
-                        text,
-                    });
-                }
-
+                validate(&glob, &glob.value)?;
                 inclusions.push(glob.into_inner().into());
             }
         }
@@ -283,6 +321,20 @@ impl TryFrom<RawTaskDefinition> for TaskDefinition {
             }
         }
 
+        let cache_ttl = raw_task
+            .cache_ttl
+            .map(|cache_ttl| {
+                humantime::parse_duration(&cache_ttl.value).map_err(|err| {
+                    let (span, text) = cache_ttl.span_and_text("turbo.json");
+                    Error::InvalidCacheTtl {
+                        reason: err.to_string(),
+                        span,
+                        text,
+                    }
+                })
+            })
+            .transpose()?;
+
         let mut env_var_dependencies = HashSet::new();
         let mut topological_dependencies: Vec<Spanned<TaskName>> = Vec::new();
         let mut task_dependencies: Vec<Spanned<TaskName>> = Vec::new();
@@ -370,6 +422,7 @@ impl TryFrom<RawTaskDefinition> for TaskDefinition {
         Ok(TaskDefinition {
             outputs,
             cache,
+            cache_ttl,
             topological_dependencies,
             task_dependencies,
             env,
@@ -523,6 +576,17 @@ impl TryFrom<RawTurboJson> for TurboJson {
                 })
                 .transpose()?,
             pipeline: raw_turbo.pipeline.unwrap_or_default(),
+            redact: raw_turbo
+                .redact
+                .map(|env| -> Result<Vec<String>, Error> {
+                    let mut redact = HashSet::new();
+                    gather_env_vars(env, "redact", &mut redact)?;
+                    let mut redact: Vec<String> = redact.into_iter().collect();
+                    redact.sort();
+                    Ok(redact)
+                })
+                .transpose()?
+                .unwrap_or_default(),
             // copy these over, we don't need any changes here.
             extends: raw_turbo
                 .extends
@@ -746,7 +810,7 @@ fn gather_env_vars(
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
+    use std::{assert_matches::assert_matches, fs, time::Duration};
 
     use anyhow::Result;
     use biome_deserialize::json::deserialize_from_json_str;
@@ -761,6 +825,7 @@ mod tests {
     use super::{Pipeline, RawTurboJson, Spanned};
     use crate::{
         cli::OutputLogsMode,
+        config::Error,
         run::task_id::TaskName,
         task_graph::{TaskDefinition, TaskOutputs},
         turbo_json::{RawTaskDefinition, TurboJson},
@@ -786,6 +851,12 @@ mod tests {
             ..TurboJson::default()
         }
     )]
+    #[test_case(r#"{ "redact": ["*_TOKEN", "AWS_*"] }"#,
+        TurboJson {
+            redact: vec!["*_TOKEN".to_string(), "AWS_*".to_string()],
+            ..TurboJson::default()
+        }
+    ; "redact (sorted)")]
     #[test_case(r#"{ "//": "A comment"}"#, TurboJson::default() ; "faux comment")]
     fn test_get_root_turbo_no_synthesizing(
         turbo_json_content: &str,
@@ -945,6 +1016,7 @@ mod tests {
             pass_through_env: Some(vec![Spanned::<UnescapedString>::new("AWS_SECRET_KEY".into()).with_range(134..150)]),
             outputs: Some(vec![Spanned::<UnescapedString>::new("package/a/dist".into()).with_range(175..191)]),
             cache: Spanned::new(Some(false)).with_range(213..218),
+            cache_ttl: None,
             inputs: Some(vec![Spanned::<UnescapedString>::new("package/a/src/**".into()).with_range(241..259)]),
             output_mode: Some(Spanned::new(OutputLogsMode::Full).with_range(286..292)),
             persistent: Some(Spanned::new(true).with_range(318..322)),
@@ -958,6 +1030,7 @@ mod tests {
               exclusions: vec![],
           },
           cache: false,
+          cache_ttl: None,
           inputs: vec!["package/a/src/**".to_string()],
           output_mode: OutputLogsMode::Full,
           pass_through_env: Some(vec!["AWS_SECRET_KEY".to_string()]),
@@ -968,6 +1041,18 @@ mod tests {
         }
       ; "full"
     )]
+    #[test_case(
+        r#"{ "cacheTtl": "7d" }"#,
+        RawTaskDefinition {
+            cache_ttl: Some(Spanned::new("7d".into()).with_range(14..18)),
+            ..RawTaskDefinition::default()
+        },
+        TaskDefinition {
+            cache_ttl: Some(Duration::from_secs(7 * 24 * 60 * 60)),
+            ..Default::default()
+        }
+      ; "cache ttl"
+    )]
     #[test_case(
         r#"{
               "dependsOn": ["cli#build"],
@@ -987,6 +1072,7 @@ mod tests {
             pass_through_env: Some(vec![Spanned::<UnescapedString>::new("AWS_SECRET_KEY".into()).with_range(152..168)]),
             outputs: Some(vec![Spanned::<UnescapedString>::new("package\\a\\dist".into()).with_range(197..215)]),
             cache: Spanned::new(Some(false)).with_range(241..246),
+            cache_ttl: None,
             inputs: Some(vec![Spanned::<UnescapedString>::new("package\\a\\src\\**".into()).with_range(273..294)]),
             output_mode: Some(Spanned::new(OutputLogsMode::Full).with_range(325..331)),
             persistent: Some(Spanned::new(true).with_range(361..365)),
@@ -1000,6 +1086,7 @@ mod tests {
                 exclusions: vec![],
             },
             cache: false,
+            cache_ttl: None,
             inputs: vec!["package\\a\\src\\**".to_string()],
             output_mode: OutputLogsMode::Full,
             pass_through_env: Some(vec!["AWS_SECRET_KEY".to_string()]),
@@ -1047,6 +1134,14 @@ mod tests {
         }
         ; "with .next (windows)"
     )]
+    #[test_case(
+        r#"["//dist/web/**", "!//dist/web/cache/**"]"#,
+        TaskOutputs {
+            inclusions: vec!["//dist/web/**".to_string()],
+            exclusions: vec!["//dist/web/cache/**".to_string()]
+        }
+        ; "root relative"
+    )]
     fn test_deserialize_task_outputs(
         task_outputs_str: &str,
         expected_task_outputs: TaskOutputs,
@@ -1062,6 +1157,36 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(unix)]
+    #[test_case(r#"["/dist/**"]"# ; "absolute")]
+    #[test_case(r#"["///dist/**"]"# ; "absolute after root prefix")]
+    fn test_absolute_task_outputs(task_outputs_str: &str) -> Result<()> {
+        let raw_task_outputs: Vec<UnescapedString> = serde_json::from_str(task_outputs_str)?;
+        let raw_task_outputs = raw_task_outputs
+            .into_iter()
+            .map(Spanned::new)
+            .collect::<Vec<_>>();
+        let task_outputs: Result<TaskOutputs, _> = raw_task_outputs.try_into();
+        assert!(task_outputs.is_err());
+
+        Ok(())
+    }
+
+    #[test_case(r#"["//../dist/**"]"# ; "parent of root")]
+    #[test_case(r#"["//dist/../../web/**"]"# ; "nested parent of root")]
+    #[test_case(r#"["!//..\\cache\\**"]"# ; "excluded parent of root (windows)")]
+    fn test_root_task_outputs_outside_repo(task_outputs_str: &str) -> Result<()> {
+        let raw_task_outputs: Vec<UnescapedString> = serde_json::from_str(task_outputs_str)?;
+        let raw_task_outputs = raw_task_outputs
+            .into_iter()
+            .map(Spanned::new)
+            .collect::<Vec<_>>();
+        let task_outputs: Result<TaskOutputs, _> = raw_task_outputs.try_into();
+        assert_matches!(task_outputs, Err(Error::RootOutputOutsideRepo { .. }));
+
+        Ok(())
+    }
+
     #[test]
     fn test_turbo_task_pruning() {
         let json = RawTurboJson::parse_from_serde(json!({
@@ -213,6 +213,13 @@ impl DeserializationVisitor for RawTaskDefinitionVisitor {
                         result.cache = Spanned::new(Some(cache)).with_range(range);
                     }
                 }
+                "cacheTtl" => {
+                    if let Some(cache_ttl) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.cache_ttl = Some(Spanned::new(cache_ttl).with_range(range));
+                    }
+                }
                 "dependsOn" => {
                     if let Some(depends_on) = Vec::deserialize(&value, &key_text, diagnostics) {
                         result.depends_on = Some(Spanned::new(depends_on).with_range(range));
@@ -578,6 +585,11 @@ impl DeserializationVisitor for RawTurboJsonVisitor {
                         result.global_pass_through_env = Some(global_pass_through_env);
                     }
                 }
+                "redact" => {
+                    if let Some(redact) = Vec::deserialize(&value, &key_text, diagnostics) {
+                        result.redact = Some(redact);
+                    }
+                }
                 "globalDotEnv" => {
                     if let Some(global_dot_env) = Vec::deserialize(&value, &key_text, diagnostics) {
                         result.global_dot_env = Some(global_dot_env);
@@ -606,6 +618,33 @@ impl DeserializationVisitor for RawTurboJsonVisitor {
                         result.experimental_ui = Some(experimental_ui);
                     }
                 }
+                "largeFileThreshold" => {
+                    if let Some(threshold) = u64::deserialize(&value, &key_text, diagnostics) {
+                        result.large_file_threshold = Some(threshold);
+                    }
+                }
+                "scmTimeout" => {
+                    if let Some(timeout) = u64::deserialize(&value, &key_text, diagnostics) {
+                        result.scm_timeout = Some(timeout);
+                    }
+                }
+                "reuseIndexHashes" => {
+                    if let Some(reuse_index_hashes) =
+                        bool::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.reuse_index_hashes = Some(reuse_index_hashes);
+                    }
+                }
+                "diskSpaceReserve" => {
+                    if let Some(reserve) = u64::deserialize(&value, &key_text, diagnostics) {
+                        result.disk_space_reserve = Some(reserve);
+                    }
+                }
+                "remoteMissTtl" => {
+                    if let Some(ttl) = u64::deserialize(&value, &key_text, diagnostics) {
+                        result.remote_miss_ttl = Some(ttl);
+                    }
+                }
                 // Allow for faux-comments at the top level
                 "//" => {}
                 unknown_key => {
@@ -628,6 +667,7 @@ impl WithMetadata for RawTurboJson {
         self.global_dependencies.add_text(text.clone());
         self.global_env.add_text(text.clone());
         self.global_pass_through_env.add_text(text.clone());
+        self.redact.add_text(text.clone());
         self.pipeline.add_text(text);
     }
 
@@ -637,6 +677,7 @@ impl WithMetadata for RawTurboJson {
         self.global_dependencies.add_path(path.clone());
         self.global_env.add_path(path.clone());
         self.global_pass_through_env.add_path(path.clone());
+        self.redact.add_path(path.clone());
         self.pipeline.add_path(path);
     }
 }
@@ -659,6 +700,7 @@ impl WithMetadata for Pipeline {
 
 impl WithMetadata for RawTaskDefinition {
     fn add_text(&mut self, text: Arc<str>) {
+        self.cache_ttl.add_text(text.clone());
         self.depends_on.add_text(text.clone());
         if let Some(depends_on) = &mut self.depends_on {
             depends_on.value.add_text(text.clone());
@@ -674,6 +716,7 @@ impl WithMetadata for RawTaskDefinition {
     }
 
     fn add_path(&mut self, path: Arc<str>) {
+        self.cache_ttl.add_path(path.clone());
         self.depends_on.add_path(path.clone());
         if let Some(depends_on) = &mut self.depends_on {
             depends_on.value.add_path(path.clone());
@@ -13,6 +13,7 @@ mod yarn1;
 use std::{
     any::Any,
     collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
 };
 
 pub use berry::{Error as BerryError, *};
@@ -70,10 +71,14 @@ pub fn all_transitive_closures<L: Lockfile + ?Sized>(
     lockfile: &L,
     workspaces: HashMap<String, HashMap<String, String>>,
 ) -> Result<HashMap<String, HashSet<Package>>, Error> {
+    // Workspaces usually share most of their external dependencies so we only
+    // look up the dependencies of each package once across all workspaces.
+    let dependencies = DependencyCache::new(lockfile);
     workspaces
         .into_par_iter()
         .map(|(workspace, unresolved_deps)| {
-            let closure = transitive_closure(lockfile, &workspace, unresolved_deps)?;
+            let mut closure = HashSet::new();
+            transitive_closure_helper(&dependencies, &workspace, &unresolved_deps, &mut closure)?;
             Ok((workspace, closure))
         })
         .collect()
@@ -88,9 +93,9 @@ pub fn transitive_closure<L: Lockfile + ?Sized>(
 ) -> Result<HashSet<Package>, Error> {
     let mut transitive_deps = HashSet::new();
     transitive_closure_helper(
-        lockfile,
+        &DependencyCache::new(lockfile),
         workspace_path,
-        unresolved_deps,
+        &unresolved_deps,
         &mut transitive_deps,
     )?;
 
@@ -98,13 +103,15 @@ pub fn transitive_closure<L: Lockfile + ?Sized>(
 }
 
 fn transitive_closure_helper<L: Lockfile + ?Sized>(
-    lockfile: &L,
+    dependencies: &DependencyCache<L>,
     workspace_path: &str,
-    unresolved_deps: HashMap<String, impl AsRef<str>>,
+    unresolved_deps: &HashMap<String, String>,
     resolved_deps: &mut HashSet<Package>,
 ) -> Result<(), Error> {
     for (name, specifier) in unresolved_deps {
-        let pkg = lockfile.resolve_package(workspace_path, &name, specifier.as_ref())?;
+        let pkg = dependencies
+            .lockfile
+            .resolve_package(workspace_path, name, specifier)?;
 
         match pkg {
             None => {
@@ -114,10 +121,10 @@ fn transitive_closure_helper<L: Lockfile + ?Sized>(
                 continue;
             }
             Some(pkg) => {
-                let all_deps = lockfile.all_dependencies(&pkg.key)?;
+                let all_deps = dependencies.get(&pkg.key)?;
                 resolved_deps.insert(pkg);
                 if let Some(deps) = all_deps {
-                    transitive_closure_helper(lockfile, workspace_path, deps, resolved_deps)?;
+                    transitive_closure_helper(dependencies, workspace_path, &deps, resolved_deps)?;
                 }
             }
         }
@@ -126,6 +133,41 @@ fn transitive_closure_helper<L: Lockfile + ?Sized>(
     Ok(())
 }
 
+/// Memoizes [Lockfile::all_dependencies] so it can be shared between closure
+/// calculations running in parallel. Only the dependencies of a package are
+/// cached, not their resolution, as resolving a dependency can depend on the
+/// workspace it is resolved from.
+struct DependencyCache<'a, L: ?Sized> {
+    lockfile: &'a L,
+    dependencies: RwLock<HashMap<String, Option<Arc<HashMap<String, String>>>>>,
+}
+
+impl<'a, L: Lockfile + ?Sized> DependencyCache<'a, L> {
+    fn new(lockfile: &'a L) -> Self {
+        Self {
+            lockfile,
+            dependencies: RwLock::default(),
+        }
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Arc<HashMap<String, String>>>, Error> {
+        if let Some(deps) = self
+            .dependencies
+            .read()
+            .expect("dependency cache lock poisoned")
+            .get(key)
+        {
+            return Ok(deps.clone());
+        }
+        let deps = self.lockfile.all_dependencies(key)?.map(Arc::new);
+        self.dependencies
+            .write()
+            .expect("dependency cache lock poisoned")
+            .insert(key.to_string(), deps.clone());
+        Ok(deps)
+    }
+}
+
 impl Package {
     pub fn new(key: impl Into<String>, version: impl Into<String>) -> Self {
         let key = key.into();
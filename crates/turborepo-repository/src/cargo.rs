@@ -0,0 +1,219 @@
+//! Discovery of Cargo workspace members, for repos that contain Rust crates
+//! alongside their JavaScript packages.
+
+use std::{io, str::FromStr};
+
+use globwalk::ValidatedGlob;
+use serde::Deserialize;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+use crate::discovery::{self, DiscoveryBackend, Ecosystem, EcosystemWorkspace};
+
+pub const CARGO_TOML: &str = "Cargo.toml";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("unable to read {0}: {1}")]
+    Io(AbsoluteSystemPathBuf, #[source] io::Error),
+    #[error("unable to parse {0}: {1}")]
+    Toml(AbsoluteSystemPathBuf, #[source] toml::de::Error),
+    #[error("invalid workspace member {0}")]
+    Glob(String, #[source] globwalk::GlobError),
+    #[error(transparent)]
+    Walk(#[from] globwalk::WalkError),
+}
+
+impl From<Error> for discovery::Error {
+    fn from(value: Error) -> Self {
+        discovery::Error::Failed(Box::new(value))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Manifest {
+    package: Option<Package>,
+    workspace: Option<Workspace>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Package {
+    name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Workspace {
+    #[serde(default)]
+    members: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+impl Manifest {
+    fn load(path: &AbsoluteSystemPath) -> Result<Option<Self>, Error> {
+        match path.read_to_string() {
+            Ok(contents) => toml::from_str(&contents)
+                .map(Some)
+                .map_err(|e| Error::Toml(path.to_owned(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::Io(path.to_owned(), e)),
+        }
+    }
+}
+
+/// Discovers the members of the Cargo workspace defined by the `Cargo.toml`
+/// at the repo root. A root `Cargo.toml` without a `[workspace]` table is
+/// treated as a single crate.
+#[derive(Debug, Default)]
+pub struct CargoDiscovery;
+
+impl CargoDiscovery {
+    fn members(
+        repo_root: &AbsoluteSystemPath,
+        workspace: &Workspace,
+    ) -> Result<Vec<AbsoluteSystemPathBuf>, Error> {
+        let to_glob = |path: &String, suffix: &str| {
+            let glob = match path.trim_start_matches("./").trim_end_matches('/') {
+                "" | "." => suffix.to_string(),
+                dir => format!("{dir}/{suffix}"),
+            };
+            ValidatedGlob::from_str(&glob).map_err(|e| Error::Glob(path.clone(), e))
+        };
+        let inclusions = workspace
+            .members
+            .iter()
+            .map(|member| to_glob(member, CARGO_TOML))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Unlike members, excluded paths aren't globs, but they do exclude
+        // everything beneath them
+        let exclusions = workspace
+            .exclude
+            .iter()
+            .map(|path| to_glob(path, "**"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(globwalk::globwalk(
+            repo_root,
+            &inclusions,
+            &exclusions,
+            globwalk::WalkType::Files,
+        )?
+        .into_iter()
+        .collect())
+    }
+}
+
+impl DiscoveryBackend for CargoDiscovery {
+    fn ecosystem(&self) -> Ecosystem {
+        Ecosystem::Cargo
+    }
+
+    fn is_manifest(&self, path: &AbsoluteSystemPath) -> bool {
+        path.file_name() == Some(CARGO_TOML)
+    }
+
+    fn discover(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+    ) -> Result<Vec<EcosystemWorkspace>, discovery::Error> {
+        let root_manifest = repo_root.join_component(CARGO_TOML);
+        let Some(manifest) = Manifest::load(&root_manifest)? else {
+            return Ok(vec![]);
+        };
+
+        let mut workspaces = Vec::new();
+        if let Some(package) = manifest.package {
+            workspaces.push(EcosystemWorkspace {
+                ecosystem: Ecosystem::Cargo,
+                manifest: root_manifest.clone(),
+                name: Some(package.name),
+            });
+        }
+        for member in Self::members(repo_root, &manifest.workspace.unwrap_or_default())? {
+            // The root crate can list itself as a member
+            if member == root_manifest {
+                continue;
+            }
+            // A member that can't be read is still reported, but without a
+            // name, the same way a broken package.json is still a workspace
+            let name = match Manifest::load(&member) {
+                Ok(manifest) => manifest.and_then(|m| m.package).map(|p| p.name),
+                Err(e) => {
+                    tracing::warn!("{e}");
+                    None
+                }
+            };
+            workspaces.push(EcosystemWorkspace {
+                ecosystem: Ecosystem::Cargo,
+                manifest: member,
+                name,
+            });
+        }
+        workspaces.sort();
+        Ok(workspaces)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+
+    fn setup(files: &[(&str, &str)]) -> (tempfile::TempDir, AbsoluteSystemPathBuf) {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        for (path, contents) in files {
+            let path = repo_root.join_unix_path(turbopath::RelativeUnixPath::new(path).unwrap());
+            path.ensure_dir().unwrap();
+            path.create_with_contents(contents).unwrap();
+        }
+        (tmp, repo_root)
+    }
+
+    #[test_case(&[], &[] ; "no cargo toml")]
+    #[test_case(
+        &[("Cargo.toml", "[package]\nname = \"root\"\n")],
+        &[("Cargo.toml", Some("root"))]
+        ; "single crate"
+    )]
+    #[test_case(
+        &[
+            ("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/skipped\"]\n"),
+            ("crates/foo/Cargo.toml", "[package]\nname = \"foo\"\n"),
+            ("crates/bar/Cargo.toml", "[package]\nname = \"bar\"\n"),
+            ("crates/skipped/Cargo.toml", "[package]\nname = \"skipped\"\n"),
+            ("crates/not-a-crate/package.json", "{}"),
+        ],
+        &[("crates/bar/Cargo.toml", Some("bar")), ("crates/foo/Cargo.toml", Some("foo"))]
+        ; "virtual workspace"
+    )]
+    #[test_case(
+        &[
+            ("Cargo.toml", "[package]\nname = \"root\"\n\n[workspace]\nmembers = [\".\", \"./cli\"]\n"),
+            ("cli/Cargo.toml", "not toml ["),
+        ],
+        &[("Cargo.toml", Some("root")), ("cli/Cargo.toml", None)]
+        ; "root package with broken member"
+    )]
+    fn test_cargo_discovery(files: &[(&str, &str)], expected: &[(&str, Option<&str>)]) {
+        let (_tmp, repo_root) = setup(files);
+        let expected = expected
+            .iter()
+            .map(|(manifest, name)| EcosystemWorkspace {
+                ecosystem: Ecosystem::Cargo,
+                manifest: repo_root
+                    .join_unix_path(turbopath::RelativeUnixPath::new(manifest).unwrap()),
+                name: name.map(|name| name.to_string()),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(CargoDiscovery.discover(&repo_root).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_cargo_discovery_invalid_root() {
+        let (_tmp, repo_root) = setup(&[("Cargo.toml", "[workspace\n")]);
+        assert!(CargoDiscovery.discover(&repo_root).is_err());
+    }
+}
@@ -137,6 +137,7 @@ mod tests {
             Ok(discovery::DiscoveryResponse {
                 package_manager: crate::package_manager::PackageManager::Npm,
                 workspaces: vec![],
+                ecosystem_workspaces: vec![],
             })
         }
 
@@ -9,9 +9,15 @@
 //! these strategies will implement some sort of monad-style composition so that
 //! we can track areas of run that are performing sub-optimally.
 
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    sync::Arc,
+};
+
 use tokio::time::error::Elapsed;
 use tokio_stream::{iter, StreamExt};
-use turbopath::AbsoluteSystemPathBuf;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 
 use crate::{
     package_json::PackageJson,
@@ -28,6 +34,234 @@ pub struct WorkspaceData {
 pub struct DiscoveryResponse {
     pub workspaces: Vec<WorkspaceData>,
     pub package_manager: PackageManager,
+    /// Workspaces found by a `DiscoveryBackend` for an ecosystem other than
+    /// the JavaScript package manager's. These aren't part of the package
+    /// graph.
+    pub ecosystem_workspaces: Vec<EcosystemWorkspace>,
+}
+
+/// The ecosystem that a non-JavaScript workspace belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Ecosystem {
+    Cargo,
+}
+
+impl fmt::Display for Ecosystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ecosystem::Cargo => write!(f, "cargo"),
+        }
+    }
+}
+
+/// A workspace discovered by a `DiscoveryBackend`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EcosystemWorkspace {
+    pub ecosystem: Ecosystem,
+    /// The workspace's manifest, e.g. its `Cargo.toml`
+    pub manifest: AbsoluteSystemPathBuf,
+    pub name: Option<String>,
+}
+
+/// Discovers the workspaces of an ecosystem other than JavaScript, for repos
+/// that mix languages. Backends are synchronous since they're expected to
+/// read a handful of manifests.
+pub trait DiscoveryBackend: fmt::Debug + Send + Sync {
+    fn ecosystem(&self) -> Ecosystem;
+
+    /// Whether a change to `path` could change the discovered workspaces.
+    fn is_manifest(&self, path: &AbsoluteSystemPath) -> bool;
+
+    /// Discovers the workspaces in `repo_root`, sorted by manifest path.
+    fn discover(&self, repo_root: &AbsoluteSystemPath) -> Result<Vec<EcosystemWorkspace>, Error>;
+}
+
+/// Runs each of `backends`, sorting the combined workspaces. A backend that
+/// fails is logged and contributes no workspaces, so that a broken manifest
+/// in one ecosystem doesn't prevent discovering the others.
+pub fn discover_ecosystem_workspaces(
+    backends: &[Arc<dyn DiscoveryBackend>],
+    repo_root: &AbsoluteSystemPath,
+) -> Vec<EcosystemWorkspace> {
+    let mut workspaces = backends
+        .iter()
+        .flat_map(|backend| {
+            backend.discover(repo_root).unwrap_or_else(|e| {
+                tracing::warn!("failed to discover {} workspaces: {e}", backend.ecosystem());
+                vec![]
+            })
+        })
+        .collect::<Vec<_>>();
+    workspaces.sort();
+    workspaces
+}
+
+/// What to do with a workspace whose directory is inside another workspace's
+/// directory, e.g. example apps inside a package that are also matched by the
+/// workspace globs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NestedWorkspacePolicy {
+    /// Only keep the outermost workspace
+    #[default]
+    Exclude,
+    /// Treat nested workspaces as packages of their own
+    Include,
+}
+
+/// A problem found with the discovered workspaces that doesn't prevent
+/// discovery from succeeding.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiscoveryDiagnostic {
+    /// The workspace at `package_json` is inside the workspace at `parent`.
+    NestedWorkspace {
+        package_json: AbsoluteSystemPathBuf,
+        parent: AbsoluteSystemPathBuf,
+        excluded: bool,
+    },
+    /// The package.json at `package_json` couldn't be read or parsed.
+    InvalidPackageJson {
+        package_json: AbsoluteSystemPathBuf,
+        error: String,
+    },
+    /// The package.json at `package_json` has no `name` field.
+    MissingPackageName { package_json: AbsoluteSystemPathBuf },
+    /// More than one workspace is named `name`.
+    DuplicatePackageName {
+        name: String,
+        package_jsons: Vec<AbsoluteSystemPathBuf>,
+    },
+}
+
+impl fmt::Display for DiscoveryDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiscoveryDiagnostic::NestedWorkspace {
+                package_json,
+                parent,
+                excluded,
+            } => {
+                write!(f, "workspace {package_json} is nested inside {parent}")?;
+                if *excluded {
+                    write!(f, " and was excluded")?;
+                }
+                Ok(())
+            }
+            DiscoveryDiagnostic::InvalidPackageJson {
+                package_json,
+                error,
+            } => write!(f, "{package_json} is invalid: {error}"),
+            DiscoveryDiagnostic::MissingPackageName { package_json } => {
+                write!(f, "{package_json} is missing the \"name\" field")
+            }
+            DiscoveryDiagnostic::DuplicatePackageName {
+                name,
+                package_jsons,
+            } => {
+                write!(f, "multiple workspaces are named \"{name}\": ")?;
+                for (i, package_json) in package_jsons.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{package_json}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Reads the package.json of each workspace and reports the ones that can't be
+/// turned into a package: unreadable or unparseable files, missing names, and
+/// names used by more than one workspace.
+pub fn validate_workspaces(workspaces: &[WorkspaceData]) -> Vec<DiscoveryDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut names: BTreeMap<String, Vec<AbsoluteSystemPathBuf>> = BTreeMap::new();
+    for workspace in workspaces {
+        match PackageJson::load(&workspace.package_json) {
+            Ok(PackageJson {
+                name: Some(name), ..
+            }) => names
+                .entry(name)
+                .or_default()
+                .push(workspace.package_json.clone()),
+            Ok(_) => diagnostics.push(DiscoveryDiagnostic::MissingPackageName {
+                package_json: workspace.package_json.clone(),
+            }),
+            Err(e) => diagnostics.push(DiscoveryDiagnostic::InvalidPackageJson {
+                package_json: workspace.package_json.clone(),
+                error: e.to_string(),
+            }),
+        }
+    }
+    diagnostics.extend(
+        names
+            .into_iter()
+            .filter(|(_, package_jsons)| package_jsons.len() > 1)
+            .map(
+                |(name, package_jsons)| DiscoveryDiagnostic::DuplicatePackageName {
+                    name,
+                    package_jsons,
+                },
+            ),
+    );
+    diagnostics
+}
+
+/// Finds workspaces nested inside of other workspaces and applies `policy` to
+/// them, returning the workspaces to keep and a diagnostic for each nested
+/// workspace.
+pub fn apply_nested_workspace_policy(
+    workspaces: Vec<WorkspaceData>,
+    policy: NestedWorkspacePolicy,
+) -> (Vec<WorkspaceData>, Vec<DiscoveryDiagnostic>) {
+    let directories: HashSet<&AbsoluteSystemPath> = workspaces
+        .iter()
+        .map(|workspace| workspace.package_json.parent().expect("non-root"))
+        .collect();
+
+    let mut diagnostics = Vec::new();
+    let mut kept = Vec::new();
+    for workspace in &workspaces {
+        let directory = workspace.package_json.parent().expect("non-root");
+        let parent = directory
+            .ancestors()
+            .skip(1)
+            .find(|ancestor| directories.contains(ancestor));
+        if let Some(parent) = parent {
+            diagnostics.push(DiscoveryDiagnostic::NestedWorkspace {
+                package_json: workspace.package_json.clone(),
+                parent: parent.join_component("package.json"),
+                excluded: policy == NestedWorkspacePolicy::Exclude,
+            });
+            if policy == NestedWorkspacePolicy::Exclude {
+                continue;
+            }
+        }
+        kept.push(workspace.clone());
+    }
+
+    (kept, diagnostics)
+}
+
+/// Returns the workspace that `path` belongs to: the one whose directory is
+/// the closest ancestor of `path`, if any.
+pub fn owning_workspace(
+    workspaces: impl IntoIterator<Item = WorkspaceData>,
+    path: &AbsoluteSystemPath,
+) -> Option<WorkspaceData> {
+    let mut by_directory = workspaces
+        .into_iter()
+        .map(|workspace| {
+            let directory = workspace
+                .package_json
+                .parent()
+                .expect("non-root")
+                .to_owned();
+            (directory, workspace)
+        })
+        .collect::<HashMap<_, _>>();
+    path.ancestors()
+        .find_map(|ancestor| by_directory.remove(ancestor))
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -69,6 +303,8 @@ pub trait PackageDiscoveryBuilder {
 pub struct LocalPackageDiscovery {
     repo_root: AbsoluteSystemPathBuf,
     package_manager: PackageManager,
+    nested_workspace_policy: NestedWorkspacePolicy,
+    backends: Vec<Arc<dyn DiscoveryBackend>>,
 }
 
 impl LocalPackageDiscovery {
@@ -76,6 +312,8 @@ impl LocalPackageDiscovery {
         Self {
             repo_root,
             package_manager,
+            nested_workspace_policy: NestedWorkspacePolicy::default(),
+            backends: Vec::new(),
         }
     }
 }
@@ -84,6 +322,8 @@ pub struct LocalPackageDiscoveryBuilder {
     repo_root: AbsoluteSystemPathBuf,
     package_manager: Option<PackageManager>,
     package_json: Option<PackageJson>,
+    nested_workspace_policy: NestedWorkspacePolicy,
+    backends: Vec<Arc<dyn DiscoveryBackend>>,
 }
 
 impl LocalPackageDiscoveryBuilder {
@@ -96,8 +336,22 @@ impl LocalPackageDiscoveryBuilder {
             repo_root,
             package_manager,
             package_json,
+            nested_workspace_policy: NestedWorkspacePolicy::default(),
+            backends: Vec::new(),
         }
     }
+
+    pub fn with_nested_workspace_policy(mut self, policy: NestedWorkspacePolicy) -> Self {
+        self.nested_workspace_policy = policy;
+        self
+    }
+
+    /// Also discovers workspaces using `backend`, e.g. Cargo workspace members
+    /// in a repo that contains Rust crates.
+    pub fn with_backend(mut self, backend: Arc<dyn DiscoveryBackend>) -> Self {
+        self.backends.push(backend);
+        self
+    }
 }
 
 impl PackageDiscoveryBuilder for LocalPackageDiscoveryBuilder {
@@ -115,6 +369,8 @@ impl PackageDiscoveryBuilder for LocalPackageDiscoveryBuilder {
         Ok(LocalPackageDiscovery {
             repo_root: self.repo_root,
             package_manager,
+            nested_workspace_policy: self.nested_workspace_policy,
+            backends: self.backends,
         })
     }
 }
@@ -123,6 +379,7 @@ impl PackageDiscovery for LocalPackageDiscovery {
     async fn discover_packages(&self) -> Result<DiscoveryResponse, Error> {
         tracing::debug!("discovering packages using local strategy");
 
+        let ecosystem_workspaces = discover_ecosystem_workspaces(&self.backends, &self.repo_root);
         let package_paths = match self.package_manager.get_package_jsons(&self.repo_root) {
             Ok(packages) => packages,
             // if there is not a list of workspaces, it is not necessarily an error. just report no
@@ -131,6 +388,7 @@ impl PackageDiscovery for LocalPackageDiscovery {
                 return Ok(DiscoveryResponse {
                     workspaces: vec![],
                     package_manager: self.package_manager,
+                    ecosystem_workspaces,
                 })
             }
             Err(e) => return Err(Error::Failed(Box::new(e))),
@@ -153,9 +411,17 @@ impl PackageDiscovery for LocalPackageDiscovery {
             })
             .collect::<Result<Vec<_>, _>>()
             .await
-            .map(|workspaces| DiscoveryResponse {
-                workspaces,
-                package_manager: self.package_manager,
+            .map(|workspaces| {
+                let (workspaces, diagnostics) =
+                    apply_nested_workspace_policy(workspaces, self.nested_workspace_policy);
+                for diagnostic in diagnostics {
+                    tracing::warn!("{diagnostic}");
+                }
+                DiscoveryResponse {
+                    workspaces,
+                    package_manager: self.package_manager,
+                    ecosystem_workspaces,
+                }
             })
     }
 
@@ -186,6 +452,18 @@ impl<P: PackageDiscovery + Send + Sync, F: PackageDiscovery + Send + Sync>
     }
 }
 
+/// Packages that have already been discovered, e.g. by a file watcher, can be
+/// used directly to build a package graph.
+impl PackageDiscovery for DiscoveryResponse {
+    async fn discover_packages(&self) -> Result<DiscoveryResponse, Error> {
+        Ok(self.clone())
+    }
+
+    async fn discover_packages_blocking(&self) -> Result<DiscoveryResponse, Error> {
+        Ok(self.clone())
+    }
+}
+
 impl<T: PackageDiscovery> PackageDiscoveryBuilder for T {
     type Output = T;
     type Error = std::convert::Infallible;
@@ -320,6 +598,7 @@ mod fallback_tests {
                 Ok(DiscoveryResponse {
                     package_manager: PackageManager::Npm,
                     workspaces: vec![],
+                    ecosystem_workspaces: vec![],
                 })
             }
         }
@@ -395,6 +674,7 @@ mod caching_tests {
             Ok(DiscoveryResponse {
                 package_manager: PackageManager::Npm,
                 workspaces: vec![],
+                ecosystem_workspaces: vec![],
             })
         }
 
@@ -424,3 +704,135 @@ mod caching_tests {
         });
     }
 }
+
+#[cfg(test)]
+mod nested_workspace_tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    fn workspace(root: &AbsoluteSystemPath, dir: &str) -> WorkspaceData {
+        let mut components: Vec<_> = dir.split('/').collect();
+        components.push("package.json");
+        WorkspaceData {
+            package_json: root.join_components(&components),
+            turbo_json: None,
+        }
+    }
+
+    #[test_case("packages/ui/src/index.ts", Some("packages/ui") ; "file in workspace")]
+    #[test_case("packages/ui", Some("packages/ui") ; "workspace directory")]
+    #[test_case("packages/ui/examples/app/src/index.ts", Some("packages/ui/examples/app") ; "innermost workspace")]
+    #[test_case("packages/ui-kit-extra/index.ts", None ; "sibling with common prefix")]
+    #[test_case("scripts/build.js", None ; "outside of workspaces")]
+    fn test_owning_workspace(path: &str, expected: Option<&str>) {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let workspaces = ["packages/ui", "packages/ui/examples/app", "packages/ui-kit"]
+            .iter()
+            .map(|dir| workspace(&root, dir))
+            .collect::<Vec<_>>();
+        let path = root.join_components(&path.split('/').collect::<Vec<_>>());
+
+        assert_eq!(
+            owning_workspace(workspaces, &path),
+            expected.map(|dir| workspace(&root, dir))
+        );
+    }
+
+    #[test_case(NestedWorkspacePolicy::Exclude, &["packages/ui", "packages/ui-kit"] ; "exclude")]
+    #[test_case(
+        NestedWorkspacePolicy::Include,
+        &["packages/ui", "packages/ui/examples/app", "packages/ui/examples/app/nested", "packages/ui-kit"]
+        ; "include"
+    )]
+    fn test_nested_workspaces(policy: NestedWorkspacePolicy, expected: &[&str]) {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let workspaces = [
+            "packages/ui",
+            "packages/ui/examples/app",
+            "packages/ui/examples/app/nested",
+            "packages/ui-kit",
+        ]
+        .iter()
+        .map(|dir| workspace(&root, dir))
+        .collect();
+
+        let (kept, diagnostics) = apply_nested_workspace_policy(workspaces, policy);
+
+        let expected: Vec<_> = expected.iter().map(|dir| workspace(&root, dir)).collect();
+        assert_eq!(kept, expected);
+
+        let excluded = policy == NestedWorkspacePolicy::Exclude;
+        assert_eq!(
+            diagnostics,
+            vec![
+                DiscoveryDiagnostic::NestedWorkspace {
+                    package_json: workspace(&root, "packages/ui/examples/app").package_json,
+                    parent: workspace(&root, "packages/ui").package_json,
+                    excluded,
+                },
+                DiscoveryDiagnostic::NestedWorkspace {
+                    package_json: workspace(&root, "packages/ui/examples/app/nested").package_json,
+                    parent: workspace(&root, "packages/ui/examples/app").package_json,
+                    excluded,
+                },
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod validation_tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_workspaces() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let workspaces = [
+            ("a", r#"{"name": "shared"}"#),
+            ("b", r#"{"name": "shared"}"#),
+            ("c", r#"{"name": "c"}"#),
+            ("d", r#"{"version": "1.0.0"}"#),
+            ("e", r#"{"name": "e""#),
+        ]
+        .into_iter()
+        .map(|(dir, contents)| {
+            let package_json = root.join_components(&[dir, "package.json"]);
+            package_json.ensure_dir().unwrap();
+            package_json.create_with_contents(contents).unwrap();
+            WorkspaceData {
+                package_json,
+                turbo_json: None,
+            }
+        })
+        .collect::<Vec<_>>();
+
+        let diagnostics = validate_workspaces(&workspaces);
+
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(
+            diagnostics[0],
+            DiscoveryDiagnostic::MissingPackageName {
+                package_json: workspaces[3].package_json.clone(),
+            }
+        );
+        assert!(matches!(
+            &diagnostics[1],
+            DiscoveryDiagnostic::InvalidPackageJson { package_json, .. }
+                if *package_json == workspaces[4].package_json
+        ));
+        assert_eq!(
+            diagnostics[2],
+            DiscoveryDiagnostic::DuplicatePackageName {
+                name: "shared".to_string(),
+                package_jsons: vec![
+                    workspaces[0].package_json.clone(),
+                    workspaces[1].package_json.clone(),
+                ],
+            }
+        );
+    }
+}
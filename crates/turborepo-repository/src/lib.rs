@@ -1,6 +1,7 @@
 #![feature(assert_matches)]
 #![feature(error_generic_member_access)]
 
+pub mod cargo;
 pub mod change_mapper;
 pub mod discovery;
 pub mod inference;
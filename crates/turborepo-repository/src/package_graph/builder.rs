@@ -603,6 +603,7 @@ mod test {
             Ok(crate::discovery::DiscoveryResponse {
                 package_manager: crate::package_manager::PackageManager::Npm,
                 workspaces: vec![],
+                ecosystem_workspaces: vec![],
             })
         }
 
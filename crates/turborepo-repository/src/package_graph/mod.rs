@@ -162,6 +162,12 @@ impl PackageGraph {
         self.lockfile.as_deref()
     }
 
+    /// Gives up the graph for its lockfile, so that the lockfile can be reused
+    /// to build another graph without parsing it again.
+    pub fn into_lockfile(self) -> Option<Box<dyn Lockfile>> {
+        self.lockfile
+    }
+
     pub fn package_json(&self, package: &PackageName) -> Option<&PackageJson> {
         let entry = self.packages.get(package)?;
         Some(&entry.package_json)
@@ -462,6 +468,7 @@ mod test {
             Ok(crate::discovery::DiscoveryResponse {
                 package_manager: PackageManager::Npm,
                 workspaces: vec![],
+                ecosystem_workspaces: vec![],
             })
         }
 
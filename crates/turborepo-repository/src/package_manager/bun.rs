@@ -3,6 +3,9 @@ use turbopath::AbsoluteSystemPath;
 use crate::package_manager::{Error, PackageManager};
 
 pub const LOCKFILE: &str = "bun.lockb";
+// Text lockfile written by newer versions of bun. Only used for detection,
+// lockfile parsing still requires `bun.lockb`.
+pub const TEXT_LOCKFILE: &str = "bun.lock";
 
 pub struct BunDetector<'a> {
     repo_root: &'a AbsoluteSystemPath,
@@ -27,9 +30,11 @@ impl<'a> Iterator for BunDetector<'a> {
         }
 
         self.found = true;
-        let package_json = self.repo_root.join_component(LOCKFILE);
+        let has_lockfile = [LOCKFILE, TEXT_LOCKFILE]
+            .iter()
+            .any(|lockfile| self.repo_root.join_component(lockfile).exists());
 
-        if package_json.exists() {
+        if has_lockfile {
             Some(Ok(PackageManager::Bun))
         } else {
             None
@@ -43,17 +48,19 @@ mod tests {
 
     use anyhow::Result;
     use tempfile::tempdir;
+    use test_case::test_case;
     use turbopath::AbsoluteSystemPathBuf;
 
-    use super::LOCKFILE;
+    use super::{LOCKFILE, TEXT_LOCKFILE};
     use crate::package_manager::PackageManager;
 
-    #[test]
-    fn test_detect_bun() -> Result<()> {
+    #[test_case(LOCKFILE ; "binary lockfile")]
+    #[test_case(TEXT_LOCKFILE ; "text lockfile")]
+    fn test_detect_bun(lockfile: &str) -> Result<()> {
         let repo_root = tempdir()?;
         let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
 
-        let lockfile_path = repo_root.path().join(LOCKFILE);
+        let lockfile_path = repo_root.path().join(lockfile);
         File::create(lockfile_path)?;
         let package_manager = PackageManager::detect_package_manager(&repo_root_path)?;
         assert_eq!(package_manager, PackageManager::Bun);
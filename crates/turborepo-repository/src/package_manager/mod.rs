@@ -2,6 +2,7 @@ mod bun;
 mod npm;
 mod pnpm;
 mod yarn;
+mod yarnrc;
 
 use std::{
     backtrace,
@@ -22,6 +23,7 @@ use turborepo_lockfiles::Lockfile;
 use wax::{Any, Glob, Program};
 use which::which;
 
+pub use self::yarnrc::{YarnRc, YARNRC};
 use crate::{
     discovery,
     package_json::PackageJson,
@@ -63,7 +65,7 @@ impl From<Workspaces> for Vec<String> {
     }
 }
 
-#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
 #[serde(rename_all = "lowercase")]
 pub enum PackageManager {
     Berry,
@@ -154,8 +156,17 @@ impl WorkspaceGlobs {
             .iter()
             .map(glob_with_contextual_error)
             .collect::<Result<Vec<_>, _>>()?;
+        // Excluding a directory also excludes everything beneath it, matching how
+        // globwalk applies exclusions when discovering package.json files
         let exclusion_globs = raw_exclusions
             .iter()
+            .flat_map(|exclusion| {
+                let mut globs = vec![exclusion.clone()];
+                if !exclusion.ends_with("/**") {
+                    globs.push(format!("{}/**", exclusion.trim_end_matches('/')));
+                }
+                globs
+            })
             .map(glob_with_contextual_error)
             .collect::<Result<Vec<_>, _>>()?;
         let validated_exclusions = raw_exclusions
@@ -329,6 +340,18 @@ impl PackageManager {
                 .for_each(|inclusion| exclusions.push(format!("{inclusion}/node_modules/**")));
         }
 
+        // Berry's package cache can be moved out of `.yarn` by `.yarnrc.yml`
+        if *self == PackageManager::Berry {
+            let yarnrc = YarnRc::load(root_path)?;
+            exclusions.extend(
+                yarnrc
+                    .package_store_folders(root_path)
+                    .iter()
+                    .filter_map(|folder| root_path.anchor(folder).ok())
+                    .map(|folder| folder.to_unix().to_string()),
+            );
+        }
+
         let globs = WorkspaceGlobs::new(inclusions, exclusions)?;
         Ok(globs)
     }
@@ -878,6 +901,89 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_negated_workspace_globs() -> Result<(), Error> {
+        let tmp = tempdir()?;
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())?.to_realpath()?;
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces": ["packages/**", "!packages/legacy-*"]}"#)?;
+        let workspaces = [
+            (vec!["packages", "foo"], true),
+            (vec!["packages", "legacy-a"], false),
+            (vec!["packages", "legacy-a", "nested"], false),
+            (vec!["packages", "foo", "legacy-b"], true),
+        ];
+        for (components, _) in &workspaces {
+            let package_json = repo_root
+                .join_components(components)
+                .join_component("package.json");
+            package_json.ensure_dir()?;
+            package_json.create_with_contents("{}")?;
+        }
+
+        let discovered = PackageManager::Npm
+            .get_package_jsons(&repo_root)?
+            .collect::<HashSet<_>>();
+        let globs = PackageManager::Npm.get_workspace_globs(&repo_root)?;
+        for (components, expected) in workspaces {
+            let workspace = repo_root.join_components(&components);
+            assert_eq!(
+                globs.target_is_workspace(&repo_root, &workspace)?,
+                expected,
+                "{workspace}"
+            );
+            assert_eq!(
+                discovered.contains(&workspace.join_component("package.json")),
+                expected,
+                "{workspace}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_berry_package_store_excluded() -> Result<(), Error> {
+        let tmp = tempdir()?;
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())?.to_realpath()?;
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces": ["packages/*", "vendor/**"]}"#)?;
+        repo_root.join_component(YARNRC).create_with_contents(
+            "pnpUnpluggedFolder: ./vendor/unplugged
+",
+        )?;
+        let workspaces = [
+            (vec!["packages", "foo"], true),
+            (vec!["vendor", "lib"], true),
+            (vec!["vendor", "unplugged", "left-pad-npm-1.3.0"], false),
+        ];
+        for (components, _) in &workspaces {
+            let package_json = repo_root
+                .join_components(components)
+                .join_component("package.json");
+            package_json.ensure_dir()?;
+            package_json.create_with_contents("{}")?;
+        }
+
+        let discovered = PackageManager::Berry
+            .get_package_jsons(&repo_root)?
+            .collect::<HashSet<_>>();
+        for (components, expected) in workspaces {
+            let package_json = repo_root
+                .join_components(&components)
+                .join_component("package.json");
+            assert_eq!(
+                discovered.contains(&package_json),
+                expected,
+                "{package_json}"
+            );
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_nested_workspace_globs() -> Result<(), Error> {
         let top_level: PackageJsonWorkspaces =
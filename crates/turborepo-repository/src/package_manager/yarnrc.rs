@@ -0,0 +1,129 @@
+use std::io;
+
+use serde::Deserialize;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+use crate::package_manager::Error;
+
+pub const YARNRC: &str = ".yarnrc.yml";
+
+const DEFAULT_CACHE_FOLDER: &str = ".yarn/cache";
+const DEFAULT_UNPLUGGED_FOLDER: &str = ".yarn/unplugged";
+const DEFAULT_INSTALL_STATE_PATH: &str = ".yarn/install-state.gz";
+const PNP_FILES: &[&str] = &[".pnp.cjs", ".pnp.loader.mjs", ".pnp.data.json"];
+
+/// The settings from a Yarn Berry `.yarnrc.yml` that affect discovery.
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct YarnRc {
+    node_linker: Option<String>,
+    cache_folder: Option<String>,
+    pnp_unplugged_folder: Option<String>,
+    install_state_path: Option<String>,
+}
+
+impl YarnRc {
+    /// Reads the `.yarnrc.yml` in `repo_root`, using Yarn's defaults if it
+    /// doesn't exist.
+    pub fn load(repo_root: &AbsoluteSystemPath) -> Result<Self, Error> {
+        match repo_root.join_component(YARNRC).read_to_string() {
+            Ok(contents) => {
+                Ok(serde_yaml::from_str::<Option<Self>>(&contents)?.unwrap_or_default())
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Whether dependencies are installed with Plug'n'Play, which is Yarn's
+    /// default linker.
+    pub fn is_pnp(&self) -> bool {
+        self.node_linker
+            .as_deref()
+            .map_or(true, |linker| linker == "pnp")
+    }
+
+    /// The directories where Yarn stores package archives and unpacked
+    /// packages. These contain package.json files that are never workspaces.
+    pub fn package_store_folders(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+    ) -> Vec<AbsoluteSystemPathBuf> {
+        [
+            self.cache_folder.as_deref().unwrap_or(DEFAULT_CACHE_FOLDER),
+            self.pnp_unplugged_folder
+                .as_deref()
+                .unwrap_or(DEFAULT_UNPLUGGED_FOLDER),
+        ]
+        .into_iter()
+        .map(|folder| AbsoluteSystemPathBuf::from_unknown(repo_root, folder))
+        .collect()
+    }
+
+    /// The paths that `yarn install` writes to. A change to any of them means
+    /// the installed dependencies may have changed, much like a change to the
+    /// lockfile.
+    pub fn install_state_paths(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+    ) -> Vec<AbsoluteSystemPathBuf> {
+        let mut paths = self.package_store_folders(repo_root);
+        paths.push(AbsoluteSystemPathBuf::from_unknown(
+            repo_root,
+            self.install_state_path
+                .as_deref()
+                .unwrap_or(DEFAULT_INSTALL_STATE_PATH),
+        ));
+        if self.is_pnp() {
+            paths.extend(PNP_FILES.iter().map(|file| repo_root.join_component(file)));
+        }
+        paths
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+
+    #[test_case(None, true ; "no yarnrc")]
+    #[test_case(Some(""), true ; "empty yarnrc")]
+    #[test_case(Some("nodeLinker: pnp\n"), true ; "pnp")]
+    #[test_case(Some("nodeLinker: node-modules\n"), false ; "node modules")]
+    fn test_is_pnp(contents: Option<&str>, expected: bool) {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        if let Some(contents) = contents {
+            repo_root
+                .join_component(YARNRC)
+                .create_with_contents(contents)
+                .unwrap();
+        }
+        assert_eq!(YarnRc::load(&repo_root).unwrap().is_pnp(), expected);
+    }
+
+    #[test]
+    fn test_install_state_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        repo_root
+            .join_component(YARNRC)
+            .create_with_contents(
+                "yarnPath: .yarn/releases/yarn-4.0.2.cjs\ncacheFolder: ./yarn-cache\nnodeLinker: \
+                 node-modules\n",
+            )
+            .unwrap();
+
+        let yarnrc = YarnRc::load(&repo_root).unwrap();
+        assert_eq!(
+            yarnrc.install_state_paths(&repo_root),
+            vec![
+                repo_root.join_component("yarn-cache"),
+                repo_root.join_components(&[".yarn", "unplugged"]),
+                repo_root.join_components(&[".yarn", "install-state.gz"]),
+            ]
+        );
+    }
+}
@@ -10,14 +10,14 @@ impl SCM {
     pub fn get_current_branch(&self, path: &AbsoluteSystemPath) -> Result<String, Error> {
         match self {
             Self::Git(git) => git.get_current_branch(),
-            Self::Manual => Err(Error::GitRequired(path.to_owned())),
+            Self::Manual { .. } => Err(Error::GitRequired(path.to_owned())),
         }
     }
 
     pub fn get_current_sha(&self, path: &AbsoluteSystemPath) -> Result<String, Error> {
         match self {
             Self::Git(git) => git.get_current_sha(),
-            Self::Manual => Err(Error::GitRequired(path.to_owned())),
+            Self::Manual { .. } => Err(Error::GitRequired(path.to_owned())),
         }
     }
 
@@ -29,7 +29,7 @@ impl SCM {
     ) -> Result<HashSet<AnchoredSystemPathBuf>, Error> {
         match self {
             Self::Git(git) => git.changed_files(turbo_root, from_commit, to_commit),
-            Self::Manual => Err(Error::GitRequired(turbo_root.to_owned())),
+            Self::Manual { .. } => Err(Error::GitRequired(turbo_root.to_owned())),
         }
     }
 
@@ -40,7 +40,7 @@ impl SCM {
     ) -> Result<Vec<u8>, Error> {
         match self {
             Self::Git(git) => git.previous_content(from_commit, file_path),
-            Self::Manual => Err(Error::GitRequired(file_path.to_owned())),
+            Self::Manual { .. } => Err(Error::GitRequired(file_path.to_owned())),
         }
     }
 }
@@ -191,10 +191,17 @@ impl Git {
         if output.status.success() {
             Ok(output.stdout)
         } else {
-            Err(Error::Git(
-                String::from_utf8_lossy(&output.stderr).to_string(),
-                Backtrace::capture(),
-            ))
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            // In a partial clone the previous version of the file is usually
+            // missing locally and git fetches it on demand, which fails when
+            // the promisor remote isn't reachable.
+            if self.partial_clone && stderr.contains("promisor remote") {
+                return Err(Error::PartialCloneMissingObject(
+                    format!("{}:{}", from_commit, anchored_file_path),
+                    stderr,
+                ));
+            }
+            Err(Error::Git(stderr, Backtrace::capture()))
         }
     }
 }
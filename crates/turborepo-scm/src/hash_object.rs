@@ -1,7 +1,7 @@
 use tracing::Span;
 use turbopath::{AbsoluteSystemPath, AnchoredSystemPathBuf, RelativeUnixPathBuf};
 
-use crate::{package_deps::GitHashes, Error};
+use crate::{manual::large_file_hash, package_deps::GitHashes, Error};
 
 #[tracing::instrument(skip(git_root, hashes, to_hash))]
 pub(crate) fn hash_objects(
@@ -9,6 +9,7 @@ pub(crate) fn hash_objects(
     pkg_path: &AbsoluteSystemPath,
     to_hash: Vec<RelativeUnixPathBuf>,
     hashes: &mut GitHashes,
+    large_file_threshold: Option<u64>,
 ) -> Result<(), Error> {
     let parent = Span::current();
     for filename in to_hash {
@@ -16,11 +17,14 @@ pub(crate) fn hash_objects(
         let _enter = span.enter();
 
         let full_file_path = git_root.join_unix_path(filename);
+        let package_relative_path =
+            AnchoredSystemPathBuf::relative_path_between(pkg_path, &full_file_path).to_unix();
+        if let Some(hash) = large_file_hash(&full_file_path, large_file_threshold) {
+            hashes.insert(package_relative_path, hash);
+            continue;
+        }
         match git2::Oid::hash_file(git2::ObjectType::Blob, &full_file_path) {
             Ok(hash) => {
-                let package_relative_path =
-                    AnchoredSystemPathBuf::relative_path_between(pkg_path, &full_file_path)
-                        .to_unix();
                 hashes.insert(package_relative_path, hash.to_string());
             }
             Err(e) => {
@@ -96,7 +100,7 @@ mod test {
             let expected_hashes = GitHashes::from_iter(file_hashes);
             let mut hashes = GitHashes::new();
             let to_hash = expected_hashes.keys().map(|k| pkg_prefix.join(k)).collect();
-            hash_objects(&git_root, pkg_path, to_hash, &mut hashes).unwrap();
+            hash_objects(&git_root, pkg_path, to_hash, &mut hashes, None).unwrap();
             assert_eq!(hashes, expected_hashes);
         }
 
@@ -116,7 +120,7 @@ mod test {
                 .collect();
 
             let mut hashes = GitHashes::new();
-            let result = hash_objects(&git_root, pkg_path, to_hash, &mut hashes);
+            let result = hash_objects(&git_root, pkg_path, to_hash, &mut hashes, None);
             assert!(result.is_err());
         }
     }
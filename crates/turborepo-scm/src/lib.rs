@@ -11,6 +11,12 @@ use std::{
     backtrace::{self, Backtrace},
     io::Read,
     process::{Child, Command},
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
 };
 
 use bstr::io::BufReadExt;
@@ -44,6 +50,11 @@ pub enum Error {
         "git command failed due to unsupported git version. Upgrade to git 2.18 or newer: {0}"
     )]
     GitVersion(String),
+    #[error(
+        "{0} is not available in this partial clone and could not be fetched from the promisor \
+         remote: {1}"
+    )]
+    PartialCloneMissingObject(String, String),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error, #[backtrace] backtrace::Backtrace),
     #[error("path error: {0}")]
@@ -79,6 +90,21 @@ impl Error {
     pub(crate) fn git2_error_context(error: git2::Error, error_context: String) -> Self {
         Error::Git2(error, error_context, Backtrace::capture())
     }
+
+    /// Whether the error is likely caused by a transient condition, such as
+    /// another git process holding the index lock, and the operation is worth
+    /// retrying.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Git(message, _) => message.contains("index.lock"),
+            Error::Git2(error, _, _) => error.code() == git2::ErrorCode::Locked,
+            Error::Io(error, _) => matches!(
+                error.kind(),
+                std::io::ErrorKind::Interrupted | std::io::ErrorKind::WouldBlock
+            ),
+            _ => false,
+        }
+    }
 }
 
 fn read_git_error_to_string<R: Read>(stderr: &mut R) -> Option<String> {
@@ -117,8 +143,9 @@ pub(crate) fn wait_for_success<R: Read, T>(
         );
         return Err(Error::Git(err_text, Backtrace::capture()));
     }
-    // TODO: if we've successfully parsed the output, but the command is hanging for
-    // some reason, we will currently block forever.
+    // If we've successfully parsed the output, but the command is hanging for
+    // some reason, we will block here unless the output was read with
+    // `read_with_timeout`.
     let exit_status = child.wait()?;
     if exit_status.success() {
         return parse_result;
@@ -143,10 +170,64 @@ pub(crate) fn wait_for_success<R: Read, T>(
     Err(Error::Git(err_text, Backtrace::capture()))
 }
 
+/// Runs `read` on the output of `child`, killing `child` if it is still
+/// running after `timeout`, e.g. because git is waiting on a lock held by
+/// another process. Killing the child closes its output, so `read` returns and
+/// the failure is then reported by `wait_for_success`.
+pub(crate) fn read_with_timeout<T>(
+    child: Child,
+    timeout: Option<Duration>,
+    read: impl FnOnce() -> Result<T, Error>,
+) -> (Child, Result<T, Error>) {
+    let Some(timeout) = timeout else {
+        return (child, read());
+    };
+
+    let child = Arc::new(Mutex::new(child));
+    let (done_tx, done_rx) = mpsc::channel::<()>();
+    let watchdog = thread::spawn({
+        let child = child.clone();
+        move || match done_rx.recv_timeout(timeout) {
+            Err(RecvTimeoutError::Timeout) => {
+                let _ = child.lock().expect("git child lock poisoned").kill();
+                true
+            }
+            // Dropping the sender means the output was read in time
+            _ => false,
+        }
+    });
+
+    let result = read();
+    drop(done_tx);
+    let timed_out = watchdog.join().unwrap_or_default();
+    let child = Arc::into_inner(child)
+        .expect("watchdog has exited")
+        .into_inner()
+        .expect("git child lock poisoned");
+    if timed_out {
+        debug!("git timed out after {:?}", timeout);
+        return (
+            child,
+            Err(Error::git_error(format!("timed out after {:?}", timeout))),
+        );
+    }
+    (child, result)
+}
+
 #[derive(Debug)]
 pub struct Git {
     root: AbsoluteSystemPathBuf,
     bin: AbsoluteSystemPathBuf,
+    large_file_threshold: Option<u64>,
+    // Git commands that run longer than this are killed, and we fall back to
+    // hashing files manually
+    timeout: Option<Duration>,
+    // When hashing files matched by `inputs`, use the hashes git already has
+    // for files that are unchanged since HEAD instead of reading them from disk
+    reuse_index_hashes: bool,
+    // Whether the repository is a partial clone, which may be missing objects
+    // locally that git would have to fetch from the promisor remote
+    partial_clone: bool,
 }
 
 #[derive(Debug, Error)]
@@ -165,7 +246,15 @@ impl Git {
         let bin = Self::find_bin()?;
         let root =
             find_git_root(path_in_repo).map_err(|e| GitError::Root(path_in_repo.to_owned(), e))?;
-        Ok(Self { root, bin })
+        let partial_clone = is_partial_clone(&bin, &root);
+        Ok(Self {
+            root,
+            bin,
+            large_file_threshold: None,
+            timeout: None,
+            reuse_index_hashes: false,
+            partial_clone,
+        })
     }
 
     pub fn find_bin() -> Result<AbsoluteSystemPathBuf, which::Error> {
@@ -207,10 +296,31 @@ fn find_git_root(turbo_root: &AbsoluteSystemPath) -> Result<AbsoluteSystemPathBu
     }
 }
 
+/// Whether the repository at `git_root` has a promisor remote, i.e. was cloned
+/// with `--filter` and may be missing objects locally.
+fn is_partial_clone(bin: &AbsoluteSystemPath, git_root: &AbsoluteSystemPath) -> bool {
+    let Ok(output) = Command::new(bin.as_std_path())
+        .args([
+            "config",
+            "--get-regexp",
+            r"^(extensions\.partialclone|remote\..*\.promisor)$",
+        ])
+        .current_dir(git_root)
+        .output()
+    else {
+        return false;
+    };
+    // `git config` exits with 1 when nothing matches
+    output.status.success()
+        && String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .any(|line| !line.ends_with(" false"))
+}
+
 #[derive(Debug)]
 pub enum SCM {
     Git(Git),
-    Manual,
+    Manual { large_file_threshold: Option<u64> },
 }
 
 impl SCM {
@@ -218,12 +328,45 @@ impl SCM {
     pub fn new(path_in_repo: &AbsoluteSystemPath) -> SCM {
         Git::find(path_in_repo).map(SCM::Git).unwrap_or_else(|e| {
             debug!("{}, continuing with manual hashing", e);
-            SCM::Manual
+            SCM::Manual {
+                large_file_threshold: None,
+            }
         })
     }
 
+    /// Files larger than `threshold` bytes are hashed by their size and
+    /// modification time instead of their contents when hashing package inputs.
+    pub fn with_large_file_threshold(mut self, threshold: Option<u64>) -> Self {
+        match &mut self {
+            SCM::Git(git) => git.large_file_threshold = threshold,
+            SCM::Manual {
+                large_file_threshold,
+            } => *large_file_threshold = threshold,
+        }
+        self
+    }
+
+    /// Git commands used for hashing that run longer than `timeout` are
+    /// killed, and the affected files are hashed manually instead.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        if let SCM::Git(git) = &mut self {
+            git.timeout = timeout;
+        }
+        self
+    }
+
+    /// When hashing the files matched by a task's `inputs`, reuse the hashes
+    /// git already has for tracked files that are unchanged since HEAD and
+    /// only hash modified and untracked files from disk.
+    pub fn with_reuse_index_hashes(mut self, reuse_index_hashes: bool) -> Self {
+        if let SCM::Git(git) = &mut self {
+            git.reuse_index_hashes = reuse_index_hashes;
+        }
+        self
+    }
+
     pub fn is_manual(&self) -> bool {
-        matches!(self, SCM::Manual)
+        matches!(self, SCM::Manual { .. })
     }
 }
 
@@ -233,12 +376,13 @@ mod tests {
         assert_matches::assert_matches,
         io::Read,
         process::{Command, Stdio},
+        time::Duration,
     };
 
     use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 
     use super::find_git_root;
-    use crate::{wait_for_success, Error};
+    use crate::{read_with_timeout, wait_for_success, Error};
 
     fn tmp_dir() -> (tempfile::TempDir, AbsoluteSystemPathBuf) {
         let tmp_dir = tempfile::tempdir().unwrap();
@@ -344,4 +488,27 @@ mod tests {
         // message.
         assert!(err.to_string().contains("any error"));
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_with_timeout() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp_dir.path()).unwrap();
+        let mut cmd = Command::new("sleep")
+            .arg("30")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdout = cmd.stdout.take().unwrap();
+        let mut stderr = cmd.stderr.take().unwrap();
+
+        let (cmd, parse_result) = read_with_timeout(cmd, Some(Duration::from_millis(100)), || {
+            let mut output = Vec::new();
+            stdout.read_to_end(&mut output)?;
+            Ok(output)
+        });
+        let err = wait_for_success(cmd, &mut stderr, "sleep", &root, parse_result).unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+    }
 }
@@ -6,11 +6,14 @@ use std::{
 use nom::Finish;
 use turbopath::{AbsoluteSystemPathBuf, RelativeUnixPathBuf};
 
-use crate::{package_deps::GitHashes, wait_for_success, Error, Git};
+use crate::{package_deps::GitHashes, read_with_timeout, wait_for_success, Error, Git};
 
 impl Git {
     #[tracing::instrument(skip(self))]
     pub fn git_ls_tree(&self, root_path: &AbsoluteSystemPathBuf) -> Result<GitHashes, Error> {
+        if self.partial_clone {
+            return self.git_ls_files(root_path);
+        }
         let mut hashes = GitHashes::new();
         let mut git = Command::new(self.bin.as_std_path())
             .args(["ls-tree", "-r", "-z", "HEAD"])
@@ -21,16 +24,47 @@ impl Git {
 
         let stdout = git
             .stdout
-            .as_mut()
+            .take()
             .ok_or_else(|| Error::git_error("failed to get stdout for git ls-tree"))?;
         let mut stderr = git
             .stderr
             .take()
             .ok_or_else(|| Error::git_error("failed to get stderr for git ls-tree"))?;
-        let parse_result = read_ls_tree(stdout, &mut hashes);
+        let (git, parse_result) =
+            read_with_timeout(git, self.timeout, || read_ls_tree(stdout, &mut hashes));
         wait_for_success(git, &mut stderr, "git ls-tree", root_path, parse_result)?;
         Ok(hashes)
     }
+
+    /// Reads the object IDs recorded in the index rather than those in the
+    /// HEAD tree. Partial clones (e.g. `--filter=blob:none`) can be missing
+    /// objects locally, and reading them makes git fetch them from the
+    /// promisor remote one at a time, or fail outright when it's unreachable.
+    /// The index records the ID of every tracked file without needing any
+    /// objects, and files whose index entry differs from HEAD are reported by
+    /// `git status` and rehashed, so the result is the same as listing HEAD.
+    fn git_ls_files(&self, root_path: &AbsoluteSystemPathBuf) -> Result<GitHashes, Error> {
+        let mut hashes = GitHashes::new();
+        let mut git = Command::new(self.bin.as_std_path())
+            .args(["ls-files", "--stage", "-z"])
+            .current_dir(root_path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let stdout = git
+            .stdout
+            .take()
+            .ok_or_else(|| Error::git_error("failed to get stdout for git ls-files"))?;
+        let mut stderr = git
+            .stderr
+            .take()
+            .ok_or_else(|| Error::git_error("failed to get stderr for git ls-files"))?;
+        let (git, parse_result) =
+            read_with_timeout(git, self.timeout, || read_ls_files(stdout, &mut hashes));
+        wait_for_success(git, &mut stderr, "git ls-files", root_path, parse_result)?;
+        Ok(hashes)
+    }
 }
 
 fn read_ls_tree<R: Read>(reader: R, hashes: &mut GitHashes) -> Result<(), Error> {
@@ -75,13 +109,70 @@ fn nom_parse_ls_tree(i: &[u8]) -> nom::IResult<&[u8], LsTreeEntry<'_>> {
     Ok((i, LsTreeEntry { filename, hash }))
 }
 
+fn read_ls_files<R: Read>(reader: R, hashes: &mut GitHashes) -> Result<(), Error> {
+    let mut reader = BufReader::new(reader);
+    let mut buffer = Vec::new();
+    while reader.read_until(b'\0', &mut buffer)? != 0 {
+        let entry = parse_ls_files(&buffer)?;
+        // Conflicted files have an entry per stage instead of stage 0. Stage 2 is
+        // our side of the merge, i.e. what `git ls-tree HEAD` would report.
+        if matches!(entry.stage, b"0" | b"2") {
+            let hash = String::from_utf8(entry.hash.to_vec())?;
+            let path = RelativeUnixPathBuf::new(String::from_utf8(entry.filename.to_vec())?)?;
+            hashes.insert(path, hash);
+        }
+        buffer.clear();
+    }
+    Ok(())
+}
+
+struct LsFilesEntry<'a> {
+    filename: &'a [u8],
+    hash: &'a [u8],
+    stage: &'a [u8],
+}
+
+fn parse_ls_files(i: &[u8]) -> Result<LsFilesEntry<'_>, Error> {
+    let mut parser = nom::combinator::all_consuming(nom_parse_ls_files);
+    match parser(i).finish() {
+        Ok((_, entry)) => Ok(entry),
+        Err(e) => Err(Error::git_error(format!(
+            "failed to parse git-ls-files: {}",
+            String::from_utf8_lossy(e.input)
+        ))),
+    }
+}
+
+fn nom_parse_ls_files(i: &[u8]) -> nom::IResult<&[u8], LsFilesEntry<'_>> {
+    let (i, _) = nom::bytes::complete::is_not(" ")(i)?;
+    let (i, _) = nom::character::complete::space1(i)?;
+    let (i, hash) = nom::bytes::complete::take(40usize)(i)?;
+    let (i, _) = nom::character::complete::space1(i)?;
+    let (i, stage) = nom::character::complete::digit1(i)?;
+    let (i, _) = nom::bytes::complete::tag("\t")(i)?;
+    let (i, filename) = nom::bytes::complete::is_not("\0")(i)?;
+    // We explicitly support a missing terminator
+    let (i, _) = nom::combinator::opt(nom::bytes::complete::tag(&[b'\0']))(i)?;
+    Ok((
+        i,
+        LsFilesEntry {
+            filename,
+            hash,
+            stage,
+        },
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
 
     use turbopath::RelativeUnixPathBuf;
 
-    use crate::{ls_tree::read_ls_tree, package_deps::GitHashes};
+    use crate::{
+        ls_tree::{read_ls_files, read_ls_tree},
+        package_deps::GitHashes,
+    };
 
     fn to_hash_map(pairs: &[(&str, &str)]) -> GitHashes {
         HashMap::from_iter(
@@ -141,4 +232,41 @@ mod tests {
             assert_eq!(hashes, expected);
         }
     }
+
+    #[test]
+    fn test_ls_files() {
+        let tests: &[(&str, &[(&str, &str)])] = &[
+            (
+                "100644 e69de29bb2d1d6434b8b29ae775ad8c2e48c5391 0\tpackage.json\0",
+                &[("package.json", "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391")],
+            ),
+            (
+                // missing nul byte
+                "100644 e69de29bb2d1d6434b8b29ae775ad8c2e48c5391 0\tpackage.json",
+                &[("package.json", "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391")],
+            ),
+            (
+                // We aren't attempting to use octal escapes here, it just looks like it
+                #[allow(clippy::octal_escapes)]
+                "100644 33dbaf21275ca2a5f460249d941cbc27d5da3121 0\tfile with spaces\0160000 \
+                 7360f2d292aec95907cebdcbb412a6bf2bd10f8a 0\tsubmodule\0100644 \
+                 9ec2879b24ce2c817296eebe2cb3846f8e4751ea 1\tconflicted\0100644 \
+                 5759aadaea2cde55468a61e7104eb0a9d86c1d30 2\tconflicted\0100644 \
+                 33d0621ee2f4da4a2f6f6bdd51a42618d181e337 3\tconflicted\0",
+                &[
+                    (
+                        "file with spaces",
+                        "33dbaf21275ca2a5f460249d941cbc27d5da3121",
+                    ),
+                    ("submodule", "7360f2d292aec95907cebdcbb412a6bf2bd10f8a"),
+                    ("conflicted", "5759aadaea2cde55468a61e7104eb0a9d86c1d30"),
+                ],
+            ),
+        ];
+        for (input, expected) in tests {
+            let mut hashes = GitHashes::new();
+            read_ls_files(input.as_bytes(), &mut hashes).unwrap();
+            assert_eq!(hashes, to_hash_map(expected));
+        }
+    }
 }
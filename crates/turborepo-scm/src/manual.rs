@@ -1,4 +1,7 @@
-use std::io::{ErrorKind, Read};
+use std::{
+    io::{ErrorKind, Read},
+    time::UNIX_EPOCH,
+};
 
 use globwalk::fix_glob_pattern;
 use hex::ToHex;
@@ -28,6 +31,35 @@ fn git_like_hash_file(path: &AbsoluteSystemPath) -> Result<String, Error> {
     Ok(result.encode_hex::<String>())
 }
 
+/// Returns a hash derived from the size and modification time of `path` if it
+/// is larger than `large_file_threshold` bytes, so that its contents never
+/// need to be read. Returns `None` if the file should be hashed normally.
+pub(crate) fn large_file_hash(
+    path: &AbsoluteSystemPath,
+    large_file_threshold: Option<u64>,
+) -> Option<String> {
+    let threshold = large_file_threshold?;
+    // Any errors here are left for the regular content hashing to report
+    let metadata = path.symlink_metadata().ok()?;
+    if !metadata.is_file() || metadata.len() <= threshold {
+        return None;
+    }
+    let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    let mut hasher = Sha1::new();
+    hasher.update(format!("large-file {} {}", metadata.len(), mtime.as_nanos()).as_bytes());
+    Some(hasher.finalize().encode_hex::<String>())
+}
+
+fn hash_file(
+    path: &AbsoluteSystemPath,
+    large_file_threshold: Option<u64>,
+) -> Result<String, Error> {
+    match large_file_hash(path, large_file_threshold) {
+        Some(hash) => Ok(hash),
+        None => git_like_hash_file(path),
+    }
+}
+
 fn to_glob(input: &str) -> Result<Glob, Error> {
     let glob = fix_glob_pattern(input).into_unix();
     let g = Glob::new(glob.as_str()).map(|g| g.into_owned())?;
@@ -61,6 +93,7 @@ pub(crate) fn get_package_file_hashes_without_git<S: AsRef<str>>(
     package_path: &AnchoredSystemPath,
     inputs: &[S],
     include_default_files: bool,
+    large_file_threshold: Option<u64>,
 ) -> Result<GitHashes, Error> {
     let full_package_path = turbo_root.resolve(package_path);
     let mut hashes = GitHashes::new();
@@ -146,7 +179,7 @@ pub(crate) fn get_package_file_hashes_without_git<S: AsRef<str>>(
         if metadata.is_symlink() {
             continue;
         }
-        let hash = git_like_hash_file(path)?;
+        let hash = hash_file(path, large_file_threshold)?;
         hashes.insert(relative_path, hash);
     }
 
@@ -177,7 +210,7 @@ pub(crate) fn get_package_file_hashes_without_git<S: AsRef<str>>(
                 if exclude_pattern.is_match(relative_path.as_str()) {
                     // track excludes so we can exclude them to the hash map later
                     if !metadata.is_symlink() {
-                        let hash = git_like_hash_file(path)?;
+                        let hash = hash_file(path, large_file_threshold)?;
                         excluded_file_hashes.insert(relative_path.clone(), hash);
                     }
                 }
@@ -187,7 +220,7 @@ pub(crate) fn get_package_file_hashes_without_git<S: AsRef<str>>(
             if metadata.is_symlink() {
                 continue;
             }
-            let hash = git_like_hash_file(path)?;
+            let hash = hash_file(path, large_file_threshold)?;
             default_file_hashes.insert(relative_path, hash);
         }
     }
@@ -251,6 +284,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_large_file_hash() {
+        let (_tmp, turbo_root) = tmp_dir();
+        let file = turbo_root.join_component("large-file.bin");
+        file.create_with_contents("0123456789").unwrap();
+
+        assert_eq!(large_file_hash(&file, None), None);
+        assert_eq!(large_file_hash(&file, Some(10)), None);
+        let hash = large_file_hash(&file, Some(9)).unwrap();
+        assert_ne!(hash, git_like_hash_file(&file).unwrap());
+        assert_eq!(hash_file(&file, Some(9)).unwrap(), hash);
+        assert_eq!(
+            hash_file(&file, Some(10)).unwrap(),
+            git_like_hash_file(&file).unwrap()
+        );
+    }
+
     #[test]
     fn test_hash_symlink() {
         let (_tmp, turbo_root) = tmp_dir();
@@ -447,6 +497,7 @@ mod tests {
             &pkg_path,
             &["**/*file", "!some-dir/excluded-file"],
             false,
+            None,
         )
         .unwrap();
 
@@ -1,8 +1,15 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    thread,
+    time::Duration,
+};
 
 use globwalk::ValidatedGlob;
 use tracing::debug;
-use turbopath::{AbsoluteSystemPath, AnchoredSystemPath, PathError, RelativeUnixPathBuf};
+use turbopath::{
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, PathError, RelativeUnixPathBuf,
+};
 use turborepo_telemetry::events::task::{FileHashMethod, PackageTaskEventBuilder};
 
 use crate::{hash_object::hash_objects, Error, Git, SCM};
@@ -11,6 +18,27 @@ pub type GitHashes = HashMap<RelativeUnixPathBuf, String>;
 
 const INPUT_INCLUDE_DEFAULT_FILES: &str = "$TURBO_DEFAULT$";
 
+// Transient git failures are retried this many times, doubling the backoff
+// each time, before we fall back to hashing files manually.
+const RETRY_ATTEMPTS: u32 = 3;
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+fn retry_transient<T>(mut f: impl FnMut() -> Result<T, Error>) -> Result<T, Error> {
+    let mut backoff = INITIAL_RETRY_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        match f() {
+            Err(err) if attempt < RETRY_ATTEMPTS && err.is_retryable() => {
+                debug!("retrying in {:?} after transient error: {}", backoff, err);
+                thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 impl SCM {
     pub fn get_hashes_for_files(
         &self,
@@ -43,7 +71,9 @@ impl SCM {
             .any(|input| input.as_ref() == INPUT_INCLUDE_DEFAULT_FILES);
 
         match self {
-            SCM::Manual => {
+            SCM::Manual {
+                large_file_threshold,
+            } => {
                 if let Some(telemetry) = telemetry {
                     telemetry.track_file_hash_method(FileHashMethod::Manual);
                 }
@@ -52,15 +82,18 @@ impl SCM {
                     package_path,
                     inputs,
                     include_default_files,
+                    *large_file_threshold,
                 )
             }
             SCM::Git(git) => {
-                let result = git.get_package_file_hashes(
-                    turbo_root,
-                    package_path,
-                    inputs,
-                    include_default_files,
-                );
+                let result = retry_transient(|| {
+                    git.get_package_file_hashes(
+                        turbo_root,
+                        package_path,
+                        inputs,
+                        include_default_files,
+                    )
+                });
                 match result {
                     Ok(hashes) => {
                         if let Some(telemetry) = telemetry {
@@ -81,6 +114,7 @@ impl SCM {
                             package_path,
                             inputs,
                             include_default_files,
+                            git.large_file_threshold,
                         )
                     }
                 }
@@ -94,7 +128,7 @@ impl SCM {
         files: impl Iterator<Item = impl AsRef<AnchoredSystemPath>>,
     ) -> Result<GitHashes, Error> {
         match self {
-            SCM::Manual => crate::manual::hash_files(turbo_root, files, false),
+            SCM::Manual { .. } => crate::manual::hash_files(turbo_root, files, false),
             SCM::Git(git) => git.hash_files(turbo_root, files),
         }
     }
@@ -150,7 +184,13 @@ impl Git {
         let mut hashes = self.git_ls_tree(&full_pkg_path)?;
         // Note: to_hash is *git repo relative*
         let to_hash = self.append_git_status(&full_pkg_path, &pkg_prefix, &mut hashes)?;
-        hash_objects(&self.root, &full_pkg_path, to_hash, &mut hashes)?;
+        hash_objects(
+            &self.root,
+            &full_pkg_path,
+            to_hash,
+            &mut hashes,
+            self.large_file_threshold,
+        )?;
         Ok(hashes)
     }
 
@@ -169,7 +209,7 @@ impl Git {
             })
             .collect::<Result<Vec<_>, PathError>>()?;
         // Note: to_hash is *git repo relative*
-        hash_objects(&self.root, process_relative_to, to_hash, &mut hashes)?;
+        hash_objects(&self.root, process_relative_to, to_hash, &mut hashes, None)?;
         Ok(hashes)
     }
 
@@ -238,11 +278,64 @@ impl Git {
                 Ok(path)
             })
             .collect::<Result<Vec<_>, Error>>()?;
-        let mut hashes = GitHashes::new();
-        hash_objects(&self.root, &full_pkg_path, to_hash, &mut hashes)?;
+        let (mut hashes, to_hash) = if self.reuse_index_hashes {
+            self.partition_by_index_hashes(&full_pkg_path, to_hash)?
+        } else {
+            (GitHashes::new(), to_hash)
+        };
+        hash_objects(
+            &self.root,
+            &full_pkg_path,
+            to_hash,
+            &mut hashes,
+            self.large_file_threshold,
+        )?;
         Ok(hashes)
     }
 
+    /// Splits `files`, which are git root relative, into the hashes git already
+    /// has for the ones that are unchanged since HEAD, keyed relative to the
+    /// package, and the ones that still need to be hashed from disk.
+    fn partition_by_index_hashes(
+        &self,
+        full_pkg_path: &AbsoluteSystemPathBuf,
+        files: Vec<RelativeUnixPathBuf>,
+    ) -> Result<(GitHashes, Vec<RelativeUnixPathBuf>), Error> {
+        let pkg_prefix = self.root.anchor(full_pkg_path)?.to_unix();
+        let mut index_hashes = self.git_ls_tree(full_pkg_path)?;
+        let changed = self
+            .append_git_status(full_pkg_path, &pkg_prefix, &mut index_hashes)?
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        let mut hashes = GitHashes::new();
+        let mut to_hash = Vec::new();
+        for file in files {
+            let index_hash = if changed.contains(&file) {
+                None
+            } else {
+                // Files outside of the package aren't in the package's index hashes
+                file.strip_prefix(&pkg_prefix).ok().and_then(|path| {
+                    let hash = index_hashes.remove(&path)?;
+                    Some((path, hash))
+                })
+            };
+            match index_hash {
+                Some((path, hash)) => {
+                    hashes.insert(path, hash);
+                }
+                None => to_hash.push(file),
+            }
+        }
+        debug!(
+            "reused {} hashes from git, hashing {} files from disk",
+            hashes.len(),
+            to_hash.len()
+        );
+
+        Ok((hashes, to_hash))
+    }
+
     #[tracing::instrument(skip(self, turbo_root, inputs))]
     fn get_package_file_hashes_from_inputs_and_index<S: AsRef<str>>(
         &self,
@@ -298,6 +391,38 @@ mod tests {
     use super::*;
     use crate::manual::get_package_file_hashes_without_git;
 
+    #[test]
+    fn test_retry_transient() {
+        let mut attempts = 0;
+        let result = retry_transient(|| {
+            attempts += 1;
+            if attempts < 3 {
+                Err(Error::git_error(
+                    "fatal: Unable to create '.git/index.lock': File exists.",
+                ))
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_matches!(result, Ok(3));
+
+        let mut attempts = 0;
+        let result: Result<(), _> = retry_transient(|| {
+            attempts += 1;
+            Err(Error::git_error("fatal: not a git repository"))
+        });
+        assert_matches!(result, Err(Error::Git(_, _)));
+        assert_eq!(attempts, 1);
+
+        let mut attempts = 0;
+        let result: Result<(), _> = retry_transient(|| {
+            attempts += 1;
+            Err(Error::git_error("index.lock"))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, RETRY_ATTEMPTS + 1);
+    }
+
     fn tmp_dir() -> (tempfile::TempDir, AbsoluteSystemPathBuf) {
         let tmp_dir = tempfile::tempdir().unwrap();
         let dir = AbsoluteSystemPathBuf::try_from(tmp_dir.path())
@@ -344,12 +469,13 @@ mod tests {
         let mut hashes = GitHashes::new();
         // FIXME: This test verifies a bug: we don't hash symlinks.
         // TODO: update this test to point at get_package_file_hashes
-        hash_objects(&git_root, &git_root, to_hash, &mut hashes).unwrap();
+        hash_objects(&git_root, &git_root, to_hash, &mut hashes, None).unwrap();
         assert!(hashes.is_empty());
 
         let pkg_path = git_root.anchor(&git_root).unwrap();
         let manual_hashes =
-            get_package_file_hashes_without_git(&git_root, &pkg_path, &["l*"], false).unwrap();
+            get_package_file_hashes_without_git(&git_root, &pkg_path, &["l*"], false, None)
+                .unwrap();
         assert!(manual_hashes.is_empty());
     }
 
@@ -388,6 +514,113 @@ mod tests {
         assert_eq!(hashes, expected);
     }
 
+    #[test]
+    fn test_reuse_index_hashes() -> Result<(), Error> {
+        let (_repo_root_tmp, repo_root) = tmp_dir();
+        let my_pkg_dir = repo_root.join_component("my-pkg");
+        my_pkg_dir.create_dir_all()?;
+        my_pkg_dir
+            .join_component("committed-file")
+            .create_with_contents("committed bytes")?;
+        my_pkg_dir
+            .join_component("modified-file")
+            .create_with_contents("original bytes")?;
+        repo_root
+            .join_component("outside-file")
+            .create_with_contents("outside bytes")?;
+        setup_repository(&repo_root);
+        commit_all(&repo_root);
+
+        my_pkg_dir
+            .join_component("modified-file")
+            .create_with_contents("modified bytes")?;
+        my_pkg_dir
+            .join_component("uncommitted-file")
+            .create_with_contents("uncommitted bytes")?;
+
+        let pkg_path = repo_root.anchor(&my_pkg_dir)?;
+        let inputs = ["*-file", "../outside-file"];
+        let hash = |reuse_index_hashes| {
+            SCM::new(&repo_root)
+                .with_reuse_index_hashes(reuse_index_hashes)
+                .get_package_file_hashes(&repo_root, &pkg_path, &inputs, None)
+        };
+
+        let hashes = hash(true)?;
+        assert_eq!(hashes, hash(false)?);
+        assert_eq!(hashes.len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_partial_clone() -> Result<(), Error> {
+        let (_repo_root_tmp, tmp_root) = tmp_dir();
+        let origin = tmp_root.join_component("origin");
+        let my_pkg_dir = origin.join_component("my-pkg");
+        my_pkg_dir.create_dir_all()?;
+        my_pkg_dir
+            .join_component("committed-file")
+            .create_with_contents("committed bytes")?;
+        let lockfile = origin.join_component("package-lock.json");
+        lockfile.create_with_contents("{}")?;
+        setup_repository(&origin);
+        require_git_cmd(
+            &origin,
+            &["config", "--local", "uploadpack.allowFilter", "true"],
+        );
+        commit_all(&origin);
+        lockfile.create_with_contents(r#"{"lockfileVersion": 3}"#)?;
+        commit_all(&origin);
+
+        // Only blobs reachable from HEAD are fetched during checkout, the previous
+        // version of the lockfile is left on the remote
+        require_git_cmd(
+            &tmp_root,
+            &[
+                "clone",
+                "--no-local",
+                "--filter=blob:none",
+                origin.as_str(),
+                "clone",
+            ],
+        );
+        let repo_root = tmp_root.join_component("clone");
+        require_git_cmd(
+            &repo_root,
+            &[
+                "remote",
+                "set-url",
+                "origin",
+                tmp_root.join_component("gone").as_str(),
+            ],
+        );
+        repo_root
+            .join_components(&["my-pkg", "uncommitted-file"])
+            .create_with_contents("uncommitted bytes")?;
+
+        let scm = SCM::new(&repo_root);
+        let SCM::Git(git) = &scm else {
+            panic!("expected git, found {:?}", scm);
+        };
+        assert!(git.partial_clone);
+
+        let package_path = AnchoredSystemPathBuf::from_raw("my-pkg")?;
+        let hashes = git.get_package_file_hashes::<&str>(&repo_root, &package_path, &[], false)?;
+        let expected = to_hash_map(&[
+            ("committed-file", "3a29e62ea9ba15c4a4009d1f605d391cdd262033"),
+            (
+                "uncommitted-file",
+                "4e56ad89387e6379e4e91ddfe9872cf6a72c9976",
+            ),
+        ]);
+        assert_eq!(hashes, expected);
+
+        let previous_lockfile =
+            scm.previous_content("HEAD~1", &repo_root.join_component("package-lock.json"));
+        assert_matches!(previous_lockfile, Err(Error::PartialCloneMissingObject(..)));
+        Ok(())
+    }
+
     #[test]
     fn test_get_package_deps() -> Result<(), Error> {
         // Directory structure:
@@ -6,7 +6,7 @@ use std::{
 use nom::Finish;
 use turbopath::{AbsoluteSystemPath, RelativeUnixPathBuf};
 
-use crate::{package_deps::GitHashes, wait_for_success, Error, Git};
+use crate::{package_deps::GitHashes, read_with_timeout, wait_for_success, Error, Git};
 
 impl Git {
     #[tracing::instrument(skip(self, root_path, hashes))]
@@ -32,13 +32,15 @@ impl Git {
 
         let stdout = git
             .stdout
-            .as_mut()
+            .take()
             .ok_or_else(|| Error::git_error("failed to get stdout for git status"))?;
         let mut stderr = git
             .stderr
             .take()
             .ok_or_else(|| Error::git_error("failed to get stderr for git status"))?;
-        let parse_result = read_status(stdout, root_path, pkg_prefix, hashes);
+        let (git, parse_result) = read_with_timeout(git, self.timeout, || {
+            read_status(stdout, root_path, pkg_prefix, hashes)
+        });
         wait_for_success(git, &mut stderr, "git status", root_path, parse_result)
     }
 }
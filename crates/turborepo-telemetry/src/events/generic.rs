@@ -244,4 +244,23 @@ impl GenericEventBuilder {
         });
         self
     }
+
+    // crashes
+    pub fn track_crash(&self, location: &str) -> &Self {
+        self.track(Event {
+            key: "crash".to_string(),
+            value: location.to_string(),
+            is_sensitive: EventType::NonSensitive,
+        });
+        self
+    }
+
+    pub fn track_crash_state(&self, subsystem: &str, state: &str) -> &Self {
+        self.track(Event {
+            key: format!("crash_state:{}", subsystem),
+            value: state.to_string(),
+            is_sensitive: EventType::NonSensitive,
+        });
+        self
+    }
 }
@@ -1,5 +1,5 @@
 use human_panic::report::{Method, Report};
-use turborepo_lib::get_version;
+use turborepo_lib::{get_version, recent_events, submit_crash, subsystem_states};
 
 pub fn panic_handler(panic_info: &std::panic::PanicInfo) {
     let cause = panic_info
@@ -7,13 +7,35 @@ pub fn panic_handler(panic_info: &std::panic::PanicInfo) {
         .map(ToString::to_string)
         .unwrap_or_else(|| "Unknown".to_string());
 
-    let explanation = match panic_info.location() {
+    let mut explanation = match panic_info.location() {
         Some(location) => format!("file '{}' at line {}\n", location.file(), location.line()),
         None => "unknown.".to_string(),
     };
 
+    explanation.push_str("\nSubsystem state:\n");
+    for (subsystem, state) in subsystem_states() {
+        explanation.push_str(&format!("{subsystem}: {state}\n"));
+    }
+
+    let recent_events = recent_events();
+    if !recent_events.is_empty() {
+        explanation.push_str("\nRecent events:\n");
+        for event in recent_events {
+            explanation.push_str(&event);
+            explanation.push('\n');
+        }
+    }
+
     let report = Report::new("turbo", get_version(), Method::Panic, explanation, cause);
 
+    // This is best effort, the event is only delivered if turbo gets to shut
+    // down telemetry after the panic
+    submit_crash(
+        &panic_info
+            .location()
+            .map_or_else(|| "unknown".to_string(), ToString::to_string),
+    );
+
     let report_message = match report.persist() {
         Ok(f) => {
             format!(
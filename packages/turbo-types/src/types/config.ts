@@ -103,6 +103,16 @@ export interface RootSchema extends BaseSchema {
    */
   globalDotEnv?: null | Array<AnchoredUnixPath>;
 
+  /**
+   * Environment variables whose values are replaced with `***` in task logs,
+   * including logs that are written to the cache.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#redact
+   *
+   * @defaultValue []
+   */
+  redact?: Array<EnvWildcard>;
+
   /**
    * Configuration options that control how turbo interfaces with the remote cache.
    *
@@ -120,6 +130,52 @@ export interface RootSchema extends BaseSchema {
    * @defaultValue `{}`
    */
   experimentalUI?: boolean;
+
+  /**
+   * Files larger than this many bytes are hashed by their size and
+   * modification time instead of their contents.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#largefilethreshold
+   */
+  largeFileThreshold?: number;
+
+  /**
+   * The number of seconds a git command used for hashing may run before it
+   * is stopped and files are hashed without git instead.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#scmtimeout
+   */
+  scmTimeout?: number;
+
+  /**
+   * Reuse the hashes git already recorded for unchanged tracked files when
+   * hashing the files matched by a task's `inputs`, and only hash modified
+   * and untracked files from disk.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#reuseindexhashes
+   *
+   * @defaultValue false
+   */
+  reuseIndexHashes?: boolean;
+
+  /**
+   * Fail before starting a run or restoring outputs from the cache when fewer
+   * than this many bytes are free on the disk holding the repository.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#diskspacereserve
+   */
+  diskSpaceReserve?: number;
+
+  /**
+   * The number of seconds a hash the remote cache reported as missing is
+   * remembered, including across runs, before the remote cache is asked for
+   * it again. Set to 0 to always ask the remote cache.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#remotemissttl
+   *
+   * @defaultValue 600 in CI, otherwise 0
+   */
+  remoteMissTtl?: number;
 }
 
 export interface Pipeline {
@@ -212,6 +268,17 @@ export interface Pipeline {
    */
   cache?: boolean;
 
+  /**
+   * How long the cached outputs of the task should be kept, e.g. "7d" or "12h".
+   *
+   * The hint is sent to the remote cache along with the artifact, and
+   * artifacts in the local cache are removed once it has passed.
+   * Changing it does not change the task's hash.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#cachettl
+   */
+  cacheTtl?: string;
+
   /**
    * The set of glob patterns to consider as inputs to this task.
    *
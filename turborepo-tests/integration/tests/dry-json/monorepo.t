@@ -16,6 +16,10 @@ Setup
     },
     "hashOfExternalDependencies": "459c029558afe716",
     "globalDotEnv": null,
+    "rootPackageJson": {
+      "scripts.something": "turbo run build",
+      "workspaces": "[\"apps/**\",\"packages/**\"]"
+    },
     "environmentVariables": {
       "specified": {
         "env": [
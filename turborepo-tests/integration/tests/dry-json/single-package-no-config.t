@@ -17,6 +17,10 @@ Setup
       },
       "hashOfExternalDependencies": "",
       "globalDotEnv": null,
+      "rootPackageJson": {
+        "scripts.build": "echo building > foo.txt",
+        "scripts.test": "cat foo.txt"
+      },
       "environmentVariables": {
         "specified": {
           "env": [],
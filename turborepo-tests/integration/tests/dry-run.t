@@ -20,6 +20,7 @@ Setup
     External Dependencies Hash            = 459c029558afe716
     Global Cache Key                      = HEY STELLLLLLLAAAAAAAAAAAAA
     Global .env Files Considered          = 0
+    Root package.json Fields              = scripts.something=turbo run build, workspaces=["apps/**","packages/**"]
     Global Env Vars                       = SOME_ENV_VAR
     Global Env Vars Values                = 
     Inferred Global Env Vars Values       = 
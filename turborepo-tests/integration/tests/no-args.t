@@ -18,6 +18,7 @@ Make sure exit code is 2 when no args are passed
     login       Login to your Vercel account
     logout      Logout to your Vercel account
     prune       Prepare a subset of your monorepo
+    replay      Re-run a single task from a run summary with the environment it originally ran with
     run         Run tasks across projects in your monorepo
     unlink      Unlink the current directory from your Vercel organization and disable Remote Caching
   
@@ -77,6 +78,8 @@ Make sure exit code is 2 when no args are passed
             DEPRECATED: Exclude dependent task consumers from execution
         --no-cache
             Avoid saving task results to the cache. Useful for development/watch tasks
+        --offline
+            Guarantee that no network requests are made. The remote cache, telemetry, analytics, the update check and run summary uploads are disabled, and turbo errors out if an option that requires the network is set
         --daemon
             
         --no-daemon
@@ -21,6 +21,7 @@ Validate that there was a failed task and exitCode is 1 (which is what we get fr
     "failed": 1,
     "cached": 0,
     "attempted": 1,
+    "outputBytes": 0,
     "startTime": [0-9]+, (re)
     "endTime": [0-9]+, (re)
     "exitCode": 1
@@ -104,6 +105,7 @@ success should be 1, and attempted should be 2
     "failed": 1,
     "cached": 0,
     "attempted": 2,
+    "outputBytes": [0-9]+, (re)
     "startTime": [0-9]+, (re)
     "endTime": [0-9]+, (re)
     "exitCode": 1
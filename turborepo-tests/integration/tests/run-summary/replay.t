@@ -0,0 +1,40 @@
+Setup
+  $ . ${TESTDIR}/../../../helpers/setup_integration_test.sh single_package
+  $ export TURBO_CONFIG_DIR_PATH=$(mktemp -d -t turbo-XXXXXXXXXX)
+
+Summaries of real runs include an encrypted snapshot of each task's environment
+  $ SECRET_VALUE=hunter2 ${TURBO} run build --summarize > /dev/null
+  $ SUMMARY=$(/bin/ls .turbo/runs/*.json | head -n1)
+  $ cat $SUMMARY | jq '.tasks[0].environmentSnapshot | keys'
+  [
+    "ciphertext",
+    "keyId",
+    "nonce"
+  ]
+  $ grep -c hunter2 $SUMMARY
+  0
+  [1]
+
+Dry runs don't record one
+  $ ${TURBO} run build --dry=json | jq '.tasks[0].environmentSnapshot'
+  null
+
+Replay the task from the summary
+  $ rm foo.txt
+  $ ${TURBO} replay $SUMMARY build > /dev/null
+  $ cat foo.txt
+  building
+
+Unknown tasks are an error
+  $ ${TURBO} replay $SUMMARY lint
+    x task lint not found in run summary
+  
+  [1]
+
+Replaying refuses to run if the inputs changed
+  $ echo "changed" > newfile.txt
+  $ ${TURBO} replay $SUMMARY build
+    x inputs of build have changed: newfile.txt. Use --force to replay anyway
+  
+  [1]
+  $ ${TURBO} replay $SUMMARY build --force > /dev/null
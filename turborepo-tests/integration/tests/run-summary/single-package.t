@@ -23,6 +23,7 @@ Check
     "endTime",
     "exitCode",
     "failed",
+    "outputBytes",
     "repoPath",
     "startTime",
     "success"
@@ -74,6 +75,7 @@ Check
     "dependents",
     "dotEnv",
     "envMode",
+    "environmentSnapshot",
     "environmentVariables",
     "excludedOutputs",
     "execution",
@@ -83,6 +85,7 @@ Check
     "hashOfExternalDependencies",
     "inputs",
     "logFile",
+    "outputSize",
     "outputs",
     "resolvedTaskDefinition",
     "task",
@@ -102,6 +105,11 @@ Check
     ".turbo(\/|\\\\)turbo-build.log", (re)
     "foo.txt"
   ]
+  $ echo $TASK_SUMMARY | jq '.outputSize.largestFiles | map(.path) | sort'
+  [
+    ".turbo(\/|\\\\)turbo-build.log", (re)
+    "foo.txt"
+  ]
   $ echo $TASK_SUMMARY | jq '.cache'
   {
     "local": false,
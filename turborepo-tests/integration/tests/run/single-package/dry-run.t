@@ -9,6 +9,7 @@ Check
     External Dependencies Hash            = 
     Global Cache Key                      = HEY STELLLLLLLAAAAAAAAAAAAA
     Global .env Files Considered          = 0
+    Root package.json Fields              = scripts.build=echo building > foo.txt, scripts.test=cat foo.txt
     Global Env Vars                       = 
     Global Env Vars Values                = 
     Inferred Global Env Vars Values       = 
@@ -11,6 +11,7 @@ Check
     External Dependencies Hash            =\s* (re)
     Global Cache Key                      = HEY STELLLLLLLAAAAAAAAAAAAA\s* (re)
     Global .env Files Considered          = 0\s* (re)
+    Root package.json Fields              = scripts\.build=echo building > foo\.txt, scripts\.test=cat foo\.txt\s* (re)
     Global Env Vars                       =\s* (re)
     Global Env Vars Values                =\s* (re)
     Inferred Global Env Vars Values       =\s* (re)